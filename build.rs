@@ -1,5 +1,45 @@
-#[cfg(all(windows, feature = "windows-icon"))]
 fn main() {
+    build_info();
+    #[cfg(all(windows, feature = "windows-icon"))]
+    windows_resources();
+}
+
+// Commit and date shown in the About view. GIT_COMMIT and SOURCE_DATE_EPOCH override them, so a
+// release built from a source tarball or reproducibly still says where it came from
+fn build_info() {
+    let commit = std::env::var("GIT_COMMIT").ok().or_else(|| {
+        let out = std::process::Command::new("git").args(["rev-parse", "--short=12", "HEAD"]).output().ok()?;
+        out.status.success().then(|| String::from_utf8_lossy(&out.stdout).trim().to_string())
+    });
+    println!("cargo:rustc-env=RUST_HASH_GIT_COMMIT={}", commit.filter(|c| !c.is_empty()).unwrap_or_else(|| "unknown".to_string()));
+
+    let secs = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .or_else(|| std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).ok().map(|d| d.as_secs()))
+        .unwrap_or(0);
+    let (y, m, d) = civil_date((secs / 86_400) as i64);
+    println!("cargo:rustc-env=RUST_HASH_BUILD_DATE={:04}-{:02}-{:02}", y, m, d);
+    println!("cargo:rerun-if-env-changed=GIT_COMMIT");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+}
+
+// Days since 1970-01-01 to a UTC calendar date (Howard Hinnant's civil_from_days)
+fn civil_date(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    (y, m, d)
+}
+
+#[cfg(all(windows, feature = "windows-icon"))]
+fn windows_resources() {
     let version = std::env::var("APP_VERSION")
         .or_else(|_| std::env::var("CARGO_PKG_VERSION"))
         .unwrap_or_else(|_| "0.0.0".to_string());
@@ -38,9 +78,6 @@ fn main() {
     let _ = res.compile();
 }
 
-#[cfg(not(all(windows, feature = "windows-icon")))]
-fn main() {}
-
 #[cfg(all(windows, feature = "windows-icon"))]
 fn to_winver4(tag: &str) -> String {
     // Strip leading 'v' or 'V' if present and keep only digits and dots
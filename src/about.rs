@@ -0,0 +1,84 @@
+// What this build is and what it runs on, for the About view and for pasting into bug reports
+
+// Stamped by build.rs
+pub const COMMIT: &str = env!("RUST_HASH_GIT_COMMIT");
+pub const BUILD_DATE: &str = env!("RUST_HASH_BUILD_DATE");
+
+// Every digest is computed in Rust, in the process; nothing goes through OpenSSL or an OS crypto API
+pub const CRYPTO_BACKEND: &str = "RustCrypto (sha2, sha1, md-5) and the blake3 crate, pure Rust";
+
+// Cargo features this binary was built with
+pub fn features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "gpu") {
+        features.push("gpu");
+    }
+    if cfg!(feature = "windows-icon") {
+        features.push("windows-icon");
+    }
+    features
+}
+
+// The instructions SHA-256 and BLAKE3 pick at run time on this CPU, by the same checks the crates make
+pub fn acceleration() -> Vec<(&'static str, &'static str)> {
+    vec![("SHA-256", sha256_path()), ("BLAKE3", blake3_path())]
+}
+
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+fn sha256_path() -> &'static str {
+    if is_x86_feature_detected!("sha") && is_x86_feature_detected!("sse4.1") {
+        "SHA extensions"
+    } else {
+        "portable"
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+fn sha256_path() -> &'static str {
+    if std::arch::is_aarch64_feature_detected!("sha2") {
+        "ARMv8 SHA2 instructions"
+    } else {
+        "portable"
+    }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64")))]
+fn sha256_path() -> &'static str {
+    "portable"
+}
+
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+fn blake3_path() -> &'static str {
+    if cfg!(target_arch = "x86_64") && is_x86_feature_detected!("avx512f") && is_x86_feature_detected!("avx512vl") {
+        "AVX-512"
+    } else if is_x86_feature_detected!("avx2") {
+        "AVX2"
+    } else if is_x86_feature_detected!("sse4.1") {
+        "SSE4.1"
+    } else {
+        "SSE2"
+    }
+}
+
+// NEON is an opt-in feature of the blake3 crate, which this build leaves off
+#[cfg(not(any(target_arch = "x86_64", target_arch = "x86")))]
+fn blake3_path() -> &'static str {
+    "portable"
+}
+
+// Plain text for a bug report; no paths or file names, so it can be pasted anywhere
+pub fn diagnostics(version: &str) -> String {
+    let features = features();
+    let mut lines = vec![
+        format!("Rust Hash {}", version),
+        format!("Commit: {}", COMMIT),
+        format!("Built: {}", BUILD_DATE),
+        format!("Features: {}", if features.is_empty() { "none".to_string() } else { features.join(", ") }),
+        format!("Platform: {} {}", std::env::consts::OS, std::env::consts::ARCH),
+        format!("Crypto: {}", CRYPTO_BACKEND),
+    ];
+    lines.extend(acceleration().into_iter().map(|(algo, path)| format!("{}: {}", algo, path)));
+    lines.push(format!("Policy: {}", crate::policy::get().describe().unwrap_or_else(|| "none".to_string())));
+    lines.push(format!("Portable mode: {}", if crate::storage::is_portable() { "yes" } else { "no" }));
+    lines.join("\n")
+}
@@ -0,0 +1,64 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::history;
+use crate::storage;
+
+const ACKNOWLEDGED_FILE: &str = "acknowledged.json";
+
+// A verify failure someone looked at and accepted, e.g. a file that was changed on purpose.
+// It stays quiet only while the file is in the acknowledged state: the same wrong digest, or still missing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ack {
+    // The checksum list or log the entry came from
+    pub manifest: PathBuf,
+    pub path: PathBuf,
+    // Digest the file had when acknowledged; None for a missing file
+    pub digest: Option<String>,
+    pub acknowledged_at: i64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Acknowledged {
+    entries: Vec<Ack>,
+}
+
+impl Acknowledged {
+    // Missing or unreadable file means nothing is acknowledged yet
+    pub fn load() -> Self {
+        acknowledged_path()
+            .and_then(|p| fs::read(p).ok())
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = acknowledged_path().context("No data directory available for acknowledged failures")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+        }
+        let data = serde_json::to_vec_pretty(self)?;
+        crate::atomic::write(&path, &data)
+    }
+
+    pub fn for_manifest(&self, manifest: &Path) -> Vec<Ack> {
+        self.entries.iter().filter(|a| a.manifest == manifest).cloned().collect()
+    }
+
+    // Replaces an earlier acknowledgement of the same entry, so a file acknowledged twice keeps only its latest digest
+    pub fn add(&mut self, manifest: &Path, path: &Path, digest: Option<String>) {
+        self.entries.retain(|a| !(a.manifest == manifest && a.path == path));
+        self.entries.push(Ack { manifest: manifest.to_path_buf(), path: path.to_path_buf(), digest, acknowledged_at: history::now_timestamp() });
+    }
+
+    pub fn clear_manifest(&mut self, manifest: &Path) {
+        self.entries.retain(|a| a.manifest != manifest);
+    }
+}
+
+fn acknowledged_path() -> Option<PathBuf> {
+    storage::data_dir().map(|d| d.join(ACKNOWLEDGED_FILE))
+}
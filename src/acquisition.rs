@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+
+// Digest and size of one side of the pair
+#[derive(Debug, Clone)]
+pub struct Side {
+    pub hex: String,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub device: Side,
+    pub image: Side,
+}
+
+impl Outcome {
+    pub fn matches(&self) -> bool {
+        self.device.hex == self.image.hex && self.device.bytes == self.image.bytes
+    }
+}
+
+// Post-acquisition check: hashes the source device, then the image taken from it, on one worker thread
+pub struct AcquisitionRun {
+    pub device: PathBuf,
+    pub image: PathBuf,
+    pub device_size: Option<u64>,
+    pub image_size: Option<u64>,
+    pub result: Option<Result<Outcome, String>>,
+    progress: Arc<AtomicU64>,
+    on_image: Arc<AtomicBool>,
+    cancel: Arc<AtomicBool>,
+    rx: Option<Receiver<Result<Outcome, String>>>,
+}
+
+impl AcquisitionRun {
+    pub fn start(device: PathBuf, image: PathBuf, forensic: bool) -> AcquisitionRun {
+        let progress = Arc::new(AtomicU64::new(0));
+        let on_image = Arc::new(AtomicBool::new(false));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        let (d, i, p, o, c) = (device.clone(), image.clone(), progress.clone(), on_image.clone(), cancel.clone());
+        thread::spawn(move || {
+            // The device is evidence: open it the forensic way whatever the app setting is
+            let hash = |path: &PathBuf, forensic: bool| -> Result<Side, String> {
+                let opts = crate::HashOptions { forensic, ..Default::default() };
+                crate::compute_sha256_file_progress(&path.display().to_string(), p.clone(), c.clone(), None, opts)
+                    .map(|((hex, _, bytes, _), _)| Side { hex, bytes })
+                    .map_err(|e| format!("{}: {:#}", path.display(), e))
+            };
+            let result = hash(&d, true).and_then(|device| {
+                o.store(true, Ordering::Relaxed);
+                p.store(0, Ordering::Relaxed);
+                // An E01 container is compared by the media it holds, not its own bytes
+                let image = if crate::ewf::is_ewf(&i) {
+                    crate::ewf::hash_logical(&i, &p, &c).map(|l| Side { hex: l.sha256, bytes: l.bytes }).map_err(|e| format!("{}: {:#}", i.display(), e))
+                } else {
+                    hash(&i, forensic)
+                };
+                image.map(|image| Outcome { device, image })
+            });
+            let _ = tx.send(result);
+        });
+        let image_size = if crate::ewf::is_ewf(&image) { None } else { crate::expected_size(&image) };
+        let device_size = crate::expected_size(&device);
+        AcquisitionRun { device, image, device_size, image_size, result: None, progress, on_image, cancel, rx: Some(rx) }
+    }
+
+    pub fn poll(&mut self) -> bool {
+        match self.rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            Some(result) => {
+                self.result = Some(result);
+                self.rx = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // (hashing the image yet, bytes read of the current side, its size)
+    pub fn stage(&self) -> (bool, u64, Option<u64>) {
+        let on_image = self.on_image.load(Ordering::Relaxed);
+        (on_image, self.progress.load(Ordering::Relaxed), if on_image { self.image_size } else { self.device_size })
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,154 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use anyhow::{anyhow, Context, Result};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+
+const BUFFER_SIZE: usize = 2 * 1024 * 1024;
+
+// Digests the headless mode can write manifests with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Algorithm {
+    #[default]
+    Sha256,
+    Sha512,
+    Sha1,
+    Md5,
+    Blake3,
+}
+
+pub const ALL: [Algorithm; 5] = [Algorithm::Sha256, Algorithm::Sha512, Algorithm::Sha1, Algorithm::Md5, Algorithm::Blake3];
+
+impl Algorithm {
+    pub const NAMES: &'static str = "sha256, sha512, sha1, md5, blake3";
+
+    pub fn parse(name: &str) -> Option<Algorithm> {
+        match name.to_ascii_lowercase().replace('-', "").as_str() {
+            "sha256" => Some(Algorithm::Sha256),
+            "sha512" => Some(Algorithm::Sha512),
+            "sha1" => Some(Algorithm::Sha1),
+            "md5" => Some(Algorithm::Md5),
+            "blake3" | "b3" => Some(Algorithm::Blake3),
+            _ => None,
+        }
+    }
+
+    // As `--algo` and the settings file spell it
+    pub fn key(self) -> &'static str {
+        match self {
+            Algorithm::Sha256 => "sha256",
+            Algorithm::Sha512 => "sha512",
+            Algorithm::Sha1 => "sha1",
+            Algorithm::Md5 => "md5",
+            Algorithm::Blake3 => "blake3",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Algorithm::Sha256 => "SHA-256",
+            Algorithm::Sha512 => "SHA-512",
+            Algorithm::Sha1 => "SHA-1",
+            Algorithm::Md5 => "MD5",
+            Algorithm::Blake3 => "BLAKE3",
+        }
+    }
+
+    // Code in the multiformats table, for multihash output
+    pub fn multihash_code(self) -> u64 {
+        match self {
+            Algorithm::Sha256 => 0x12,
+            Algorithm::Sha512 => 0x13,
+            Algorithm::Sha1 => 0x11,
+            Algorithm::Md5 => 0xd5,
+            Algorithm::Blake3 => 0x1e,
+        }
+    }
+
+    pub fn hasher(self) -> Hasher {
+        match self {
+            Algorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            Algorithm::Sha512 => Hasher::Sha512(Sha512::new()),
+            Algorithm::Sha1 => Hasher::Sha1(Sha1::new()),
+            Algorithm::Md5 => Hasher::Md5(Md5::new()),
+            Algorithm::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+}
+
+impl std::fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+pub enum Hasher {
+    Sha256(Sha256),
+    Sha512(Sha512),
+    Sha1(Sha1),
+    Md5(Md5),
+    // Boxed: the BLAKE3 state is far larger than the others
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Sha256(h) => h.update(data),
+            Hasher::Sha512(h) => h.update(data),
+            Hasher::Sha1(h) => h.update(data),
+            Hasher::Md5(h) => h.update(data),
+            Hasher::Blake3(h) => {
+                h.update(data);
+            }
+        }
+    }
+
+    pub fn finish_hex(self) -> String {
+        match self {
+            Hasher::Sha256(h) => hex::encode(h.finalize()),
+            Hasher::Sha512(h) => hex::encode(h.finalize()),
+            Hasher::Sha1(h) => hex::encode(h.finalize()),
+            Hasher::Md5(h) => hex::encode(h.finalize()),
+            Hasher::Blake3(h) => h.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+// (lowercase hex digest, bytes read). Adds to `progress` as it reads, so several workers can share one counter.
+pub fn hash_file(path: &Path, algorithm: Algorithm, progress: &AtomicU64, cancel: &AtomicBool) -> Result<(String, u64)> {
+    let (mut digests, total) = hash_file_multi(path, &[algorithm], progress, cancel)?;
+    Ok((digests.remove(0), total))
+}
+
+// One digest per algorithm, in the same order, from a single read of the file
+pub fn hash_file_multi(path: &Path, algorithms: &[Algorithm], progress: &AtomicU64, cancel: &AtomicBool) -> Result<(Vec<String>, u64)> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+    hash_reader(&mut file, &path.display().to_string(), algorithms, progress, cancel)
+}
+
+// The same for any stream, e.g. a file piped from another machine; `name` is for error messages
+pub fn hash_reader(reader: &mut impl Read, name: &str, algorithms: &[Algorithm], progress: &AtomicU64, cancel: &AtomicBool) -> Result<(Vec<String>, u64)> {
+    let mut hashers: Vec<Hasher> = algorithms.iter().map(|a| a.hasher()).collect();
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut total = 0u64;
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(anyhow!(crate::errors::CANCELLED));
+        }
+        let n = reader.read(&mut buffer).with_context(|| format!("Failed to read {}", name))?;
+        if n == 0 {
+            break;
+        }
+        for hasher in hashers.iter_mut() {
+            hasher.update(&buffer[..n]);
+        }
+        total += n as u64;
+        progress.fetch_add(n as u64, Ordering::Relaxed);
+    }
+    Ok((hashers.into_iter().map(Hasher::finish_hex).collect(), total))
+}
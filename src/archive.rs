@@ -0,0 +1,216 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{anyhow, Context, Result};
+
+const BUFFER_SIZE: usize = 1024 * 1024;
+// Members named in the one-line summary; the rest are only counted
+const NAMED_ISSUES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Zip,
+    SevenZip,
+    Rar,
+}
+
+impl Kind {
+    pub fn of(path: &Path) -> Option<Kind> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "zip" => Some(Kind::Zip),
+            "7z" => Some(Kind::SevenZip),
+            "rar" => Some(Kind::Rar),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Kind::Zip => "ZIP",
+            Kind::SevenZip => "7z",
+            Kind::Rar => "RAR",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct MemberIssue {
+    pub name: String,
+    pub detail: String,
+}
+
+// Stored per-member CRC-32 values compared with the decompressed content
+#[derive(Debug)]
+pub struct ArchiveCheck {
+    pub kind: Kind,
+    // Unknown when an external tool did the test and does not report a count
+    pub members: Option<usize>,
+    // Encrypted members, or members stored without a CRC
+    pub unchecked: usize,
+    pub issues: Vec<MemberIssue>,
+}
+
+impl ArchiveCheck {
+    pub fn describe(&self) -> String {
+        let members = self.members.map(|n| format!("{} members", n)).unwrap_or_else(|| "members".to_string());
+        let mut out = if self.issues.is_empty() {
+            format!("{} CRCs: all {} match their stored CRC", self.kind.label(), members)
+        } else {
+            let names: Vec<String> = self.issues.iter().take(NAMED_ISSUES).map(|i| format!("{} ({})", i.name, i.detail)).collect();
+            let more = self.issues.len().saturating_sub(NAMED_ISSUES);
+            let mut s = format!("{} CRCs: {} of {} do NOT match: {}", self.kind.label(), self.issues.len(), members, names.join("; "));
+            if more > 0 {
+                s.push_str(&format!("; and {} more", more));
+            }
+            s
+        };
+        if self.unchecked > 0 {
+            out.push_str(&format!(" ({} encrypted or without a CRC, not checked)", self.unchecked));
+        }
+        out
+    }
+}
+
+// Decompresses every member and checks it against the CRC stored in the archive
+pub fn check(path: &Path, cancel: &AtomicBool) -> Result<ArchiveCheck> {
+    let kind = Kind::of(path).ok_or_else(|| anyhow!("Not a ZIP, 7z or RAR archive: {}", path.display()))?;
+    match kind {
+        Kind::Zip => check_zip(path, cancel),
+        Kind::SevenZip => check_7z(path, cancel),
+        Kind::Rar => check_external(path),
+    }
+}
+
+// CRC-32 of everything `reader` yields, and the read error that stopped it, if any.
+// The archive readers report a stored-CRC mismatch as an error after the last byte.
+fn crc_of(reader: &mut dyn Read, cancel: &AtomicBool) -> Result<(u32, u64, Option<io::Error>)> {
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut total = 0u64;
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(anyhow!(crate::errors::CANCELLED));
+        }
+        match reader.read(&mut buffer) {
+            Ok(0) => return Ok((hasher.finalize(), total, None)),
+            Ok(n) => {
+                hasher.update(&buffer[..n]);
+                total += n as u64;
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Ok((hasher.finalize(), total, Some(e))),
+        }
+    }
+}
+
+fn issue(name: &str, stored: u32, actual: u32, size: u64, expected_size: u64, error: Option<io::Error>) -> Option<MemberIssue> {
+    let detail = if size < expected_size {
+        // Could not decompress all of it: damaged data rather than a wrong CRC
+        format!("damaged after {} of {} bytes{}", size, expected_size, error.map(|e| format!(": {}", e)).unwrap_or_default())
+    } else if stored != actual {
+        format!("stored {:08X}, content {:08X}", stored, actual)
+    } else if let Some(e) = error {
+        e.to_string()
+    } else {
+        return None;
+    };
+    Some(MemberIssue { name: name.to_string(), detail })
+}
+
+fn check_zip(path: &Path, cancel: &AtomicBool) -> Result<ArchiveCheck> {
+    let file = File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+    let mut archive = zip::ZipArchive::new(file).with_context(|| format!("Not a readable ZIP archive: {}", path.display()))?;
+    let mut result = ArchiveCheck { kind: Kind::Zip, members: Some(0), unchecked: 0, issues: Vec::new() };
+    for i in 0..archive.len() {
+        let mut member = match archive.by_index(i) {
+            Ok(member) => member,
+            Err(zip::result::ZipError::UnsupportedArchive(_)) => {
+                // Encrypted, or a compression method this build does not decode
+                result.unchecked += 1;
+                continue;
+            }
+            Err(e) => {
+                result.issues.push(MemberIssue { name: format!("member #{}", i + 1), detail: e.to_string() });
+                continue;
+            }
+        };
+        if member.is_dir() {
+            continue;
+        }
+        result.members = result.members.map(|n| n + 1);
+        let (stored, size, name) = (member.crc32(), member.size(), member.name().to_string());
+        let (actual, read, error) = crc_of(&mut member, cancel)?;
+        result.issues.extend(issue(&name, stored, actual, read, size, error));
+    }
+    Ok(result)
+}
+
+fn check_7z(path: &Path, cancel: &AtomicBool) -> Result<ArchiveCheck> {
+    let mut reader = sevenz_rust::SevenZReader::open(path, sevenz_rust::Password::empty())
+        .map_err(|e| anyhow!("Not a readable 7z archive: {}: {}", path.display(), e))?;
+    let mut result = ArchiveCheck { kind: Kind::SevenZip, members: Some(0), unchecked: 0, issues: Vec::new() };
+    let mut cancelled = false;
+    let decoded = reader.for_each_entries(|entry, data| {
+        if entry.is_directory() {
+            return Ok(true);
+        }
+        result.members = result.members.map(|n| n + 1);
+        // Members of a solid block must be read in order, so even unchecked ones are drained
+        let Ok((actual, read, error)) = crc_of(data, cancel) else {
+            cancelled = true;
+            return Ok(false);
+        };
+        if entry.has_stream && !entry.has_crc {
+            result.unchecked += 1;
+            return Ok(true);
+        }
+        let stored = if entry.has_crc { entry.crc as u32 } else { 0 };
+        result.issues.extend(issue(entry.name(), stored, actual, read, entry.size(), error));
+        Ok(true)
+    });
+    if cancelled {
+        return Err(anyhow!(crate::errors::CANCELLED));
+    }
+    if let Err(e) = decoded {
+        // A damaged solid block stops decoding for everything after it
+        result.issues.push(MemberIssue { name: "archive".to_string(), detail: format!("decoding stopped: {}", e) });
+    }
+    Ok(result)
+}
+
+// No pure-Rust RAR decoder exists, so RAR archives are tested with UnRAR or 7-Zip when installed
+fn check_external(path: &Path) -> Result<ArchiveCheck> {
+    let tools: &[(&str, &[&str])] = &[
+        ("unrar", &["t", "-idp", "-p-", "-y"]),
+        (r"C:\Program Files\WinRAR\UnRAR.exe", &["t", "-idp", "-p-", "-y"]),
+        ("7z", &["t", "-p", "-y"]),
+        (r"C:\Program Files\7-Zip\7z.exe", &["t", "-p", "-y"]),
+    ];
+    for (program, args) in tools {
+        let Ok(output) = Command::new(program).args(*args).arg(path).output() else {
+            continue;
+        };
+        let text = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+        let mut result = ArchiveCheck { kind: Kind::Rar, members: None, unchecked: 0, issues: Vec::new() };
+        for line in text.lines().map(str::trim) {
+            let lower = line.to_lowercase();
+            // UnRAR: "<name> - checksum error"; 7-Zip: "ERROR: CRC Failed : <name>"
+            if let Some(name) = line.strip_suffix("- checksum error").or_else(|| line.strip_suffix("- CRC failed")) {
+                result.issues.push(MemberIssue { name: name.trim().to_string(), detail: "checksum error".to_string() });
+            } else if lower.starts_with("error:") && (lower.contains("crc failed") || lower.contains("data error")) {
+                let (kind, name) = line[6..].split_once(" : ").unwrap_or((&line[6..], ""));
+                result.issues.push(MemberIssue { name: name.trim().to_string(), detail: kind.trim().to_string() });
+            }
+        }
+        if !output.status.success() && result.issues.is_empty() {
+            let last = text.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("").trim();
+            return Err(anyhow!("{} could not test {}: {}", program, path.display(), last));
+        }
+        return Ok(result);
+    }
+    Err(anyhow!("Checking RAR archives needs UnRAR or 7-Zip installed"))
+}
@@ -0,0 +1,408 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{anyhow, bail, Context, Result};
+use flate2::read::MultiGzDecoder;
+use sha2::{Digest, Sha256};
+
+const BUFFER_SIZE: usize = 1024 * 1024;
+const BLOCK: usize = 512;
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Zip,
+    SevenZip,
+    Tar,
+    TarGz,
+}
+
+impl Format {
+    fn of(path: &Path) -> Option<Format> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            return Some(Format::TarGz);
+        }
+        match name.rsplit('.').next()? {
+            "zip" | "jar" => Some(Format::Zip),
+            "7z" => Some(Format::SevenZip),
+            "tar" => Some(Format::Tar),
+            _ => None,
+        }
+    }
+}
+
+// What a member restores as; directories are left out, since some tools store them and others do not
+enum Member {
+    // SHA-256 of the content, lowercase hex
+    File(String),
+    Symlink(String),
+}
+
+// Digest of an archive's logical content
+#[derive(Debug, Clone)]
+pub struct ContentDigest {
+    pub hex: String,
+    pub members: usize,
+}
+
+pub fn is_supported(path: &Path) -> bool {
+    Format::of(path).is_some()
+}
+
+// SHA-256 over the members sorted by path, so archives holding the same files hash the same whatever tool,
+// order, timestamps, owners or permissions they were made with, and a ZIP matches a tar of the same tree.
+// Per member, in byte order of the path:
+//   <path> NUL <sha256 hex of the content> NUL      regular files; hard links as the file they point to
+//   <path> NUL "-> " <link target> NUL             symbolic links (ZIP and tar)
+// Paths use `/`, without a leading `./` or `/`. When a tar holds a path twice, the later one counts, as
+// extraction would leave it
+pub fn content_digest(path: &Path, cancel: &AtomicBool) -> Result<ContentDigest> {
+    let format = Format::of(path).ok_or_else(|| anyhow!("Not a ZIP, 7z, tar or .tar.gz archive: {}", path.display()))?;
+    let members = match format {
+        Format::Zip => zip_members(path, cancel)?,
+        Format::SevenZip => sevenz_members(path, cancel)?,
+        Format::Tar | Format::TarGz => {
+            let file = File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+            let reader: Box<dyn Read> = if format == Format::TarGz {
+                Box::new(MultiGzDecoder::new(BufReader::with_capacity(BUFFER_SIZE, file)))
+            } else {
+                Box::new(BufReader::with_capacity(BUFFER_SIZE, file))
+            };
+            tar_members(reader, cancel).with_context(|| format!("Not a readable tar archive: {}", path.display()))?
+        }
+    };
+    let mut hasher = Sha256::new();
+    for (name, member) in &members {
+        hasher.update(name.as_bytes());
+        hasher.update([0]);
+        match member {
+            Member::File(hex) => hasher.update(hex.as_bytes()),
+            Member::Symlink(target) => hasher.update(format!("-> {}", target).as_bytes()),
+        }
+        hasher.update([0]);
+    }
+    Ok(ContentDigest { hex: hex::encode(hasher.finalize()), members: members.len() })
+}
+
+// `./a//b/` and `a\b` both become `a/b`; None for the archive root itself
+fn normalize(name: &str) -> Option<String> {
+    let parts: Vec<&str> = name.split(['/', '\\']).filter(|p| !p.is_empty() && *p != ".").collect();
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+fn sha256_of(reader: &mut dyn Read, cancel: &AtomicBool) -> Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            bail!(crate::errors::CANCELLED);
+        }
+        match reader.read(&mut buffer) {
+            Ok(0) => return Ok(hex::encode(hasher.finalize())),
+            Ok(n) => hasher.update(&buffer[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+fn zip_members(path: &Path, cancel: &AtomicBool) -> Result<BTreeMap<String, Member>> {
+    let file = File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+    let mut archive = zip::ZipArchive::new(file).with_context(|| format!("Not a readable ZIP archive: {}", path.display()))?;
+    let mut members = BTreeMap::new();
+    for i in 0..archive.len() {
+        let mut member = archive.by_index(i).with_context(|| format!("Cannot read member #{} of {}", i + 1, path.display()))?;
+        let Some(name) = normalize(member.name()).filter(|_| !member.is_dir()) else {
+            continue;
+        };
+        // Unix zip tools store a symlink as a member whose content is the target
+        let entry = if member.unix_mode().is_some_and(|m| m & S_IFMT == S_IFLNK) {
+            let mut target = Vec::new();
+            member.read_to_end(&mut target).with_context(|| format!("Cannot read {} in {}", name, path.display()))?;
+            Member::Symlink(String::from_utf8_lossy(&target).into_owned())
+        } else {
+            Member::File(sha256_of(&mut member, cancel).with_context(|| format!("Cannot read {} in {}", name, path.display()))?)
+        };
+        members.insert(name, entry);
+    }
+    Ok(members)
+}
+
+fn sevenz_members(path: &Path, cancel: &AtomicBool) -> Result<BTreeMap<String, Member>> {
+    let mut reader = sevenz_rust::SevenZReader::open(path, sevenz_rust::Password::empty())
+        .map_err(|e| anyhow!("Not a readable 7z archive: {}: {}", path.display(), e))?;
+    let mut members = BTreeMap::new();
+    let mut failed = None;
+    let decoded = reader.for_each_entries(|entry, data| {
+        // Members of a solid block must be read in order, so directories are drained too
+        match sha256_of(data, cancel) {
+            Ok(digest) => {
+                if let Some(name) = normalize(entry.name()).filter(|_| !entry.is_directory()) {
+                    members.insert(name, Member::File(digest));
+                }
+                Ok(true)
+            }
+            Err(e) => {
+                failed = Some(e);
+                Ok(false)
+            }
+        }
+    });
+    if let Some(e) = failed {
+        return Err(e);
+    }
+    decoded.map_err(|e| anyhow!("Cannot decode {}: {}", path.display(), e))?;
+    Ok(members)
+}
+
+// ustar with the GNU (`L`/`K`) and pax (`x`) long-name extensions
+fn tar_members(mut reader: Box<dyn Read>, cancel: &AtomicBool) -> Result<BTreeMap<String, Member>> {
+    let mut members = BTreeMap::new();
+    let (mut long_name, mut long_link) = (None::<String>, None::<String>);
+    let mut header = [0u8; BLOCK];
+    loop {
+        if !read_block(&mut reader, &mut header)? || header.iter().all(|b| *b == 0) {
+            break;
+        }
+        let stored: u64 = header[..148].iter().chain([b' '; 8].iter()).chain(header[156..].iter()).map(|b| *b as u64).sum();
+        if octal(&header[148..156]) != Some(stored) {
+            bail!("tar header checksum does not match");
+        }
+        let size = number(&header[124..136]).ok_or_else(|| anyhow!("bad size field in tar header"))?;
+        let typeflag = header[156];
+        let mut name = text(&header[..100]);
+        if &header[257..262] == b"ustar" && header[345] != 0 {
+            name = format!("{}/{}", text(&header[345..500]), name);
+        }
+        let mut link = text(&header[157..257]);
+        match typeflag {
+            b'L' | b'K' | b'x' => {
+                let data = read_data(&mut reader, size)?;
+                match typeflag {
+                    b'L' => long_name = Some(text(&data)),
+                    b'K' => long_link = Some(text(&data)),
+                    _ => {
+                        let (path, linkpath) = pax(&data);
+                        long_name = path.or(long_name);
+                        long_link = linkpath.or(long_link);
+                    }
+                }
+                continue;
+            }
+            _ => {}
+        }
+        name = long_name.take().unwrap_or(name);
+        link = long_link.take().unwrap_or(link);
+        let normalized = normalize(&name);
+        match (typeflag, normalized) {
+            (b'0' | 0 | b'7', Some(name)) => {
+                let digest = sha256_of(&mut (&mut reader).take(size), cancel)?;
+                skip(&mut reader, padding(size))?;
+                members.insert(name, Member::File(digest));
+            }
+            (b'1', Some(name)) => {
+                // Hard links carry no data; they restore as a copy of their target
+                if let Some(Member::File(digest)) = normalize(&link).and_then(|l| members.get(&l)) {
+                    let digest = digest.clone();
+                    members.insert(name, Member::File(digest));
+                }
+                skip(&mut reader, size + padding(size))?;
+            }
+            (b'2', Some(name)) => {
+                members.insert(name, Member::Symlink(link));
+                skip(&mut reader, size + padding(size))?;
+            }
+            // Directories, devices, FIFOs and global pax headers
+            _ => skip(&mut reader, size + padding(size))?,
+        }
+    }
+    Ok(members)
+}
+
+// False at a clean end of the stream (some tools leave out the closing zero blocks)
+fn read_block(reader: &mut dyn Read, block: &mut [u8; BLOCK]) -> Result<bool> {
+    let mut filled = 0;
+    while filled < BLOCK {
+        match reader.read(&mut block[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => bail!("tar archive ends inside a header"),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(true)
+}
+
+fn read_data(reader: &mut dyn Read, size: u64) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    reader.take(size).read_to_end(&mut data)?;
+    if data.len() as u64 != size {
+        bail!("tar archive ends inside a member");
+    }
+    skip(reader, padding(size))?;
+    Ok(data)
+}
+
+fn skip(reader: &mut dyn Read, len: u64) -> Result<()> {
+    if io::copy(&mut reader.take(len), &mut io::sink())? != len {
+        bail!("tar archive ends inside a member");
+    }
+    Ok(())
+}
+
+fn padding(size: u64) -> u64 {
+    (BLOCK as u64 - size % BLOCK as u64) % BLOCK as u64
+}
+
+fn text(field: &[u8]) -> String {
+    let end = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+fn octal(field: &[u8]) -> Option<u64> {
+    let digits = text(field);
+    let digits = digits.trim_matches([' ', '\0']);
+    if digits.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(digits, 8).ok()
+}
+
+// Octal, or GNU base-256 (high bit set) for sizes of 8 GiB and more
+fn number(field: &[u8]) -> Option<u64> {
+    if field[0] & 0x80 != 0 {
+        let mut n = (field[0] & 0x7f) as u64;
+        for b in &field[1..] {
+            n = n.checked_mul(256)? | *b as u64;
+        }
+        return Some(n);
+    }
+    octal(field)
+}
+
+// `path` and `linkpath` from pax records, each `<length> <key>=<value>\n`
+fn pax(data: &[u8]) -> (Option<String>, Option<String>) {
+    let (mut path, mut linkpath) = (None, None);
+    let mut rest = data;
+    while let Some(space) = rest.iter().position(|b| *b == b' ') {
+        let Some(len) = std::str::from_utf8(&rest[..space]).ok().and_then(|s| s.parse::<usize>().ok()) else { break };
+        if len <= space || len > rest.len() {
+            break;
+        }
+        let record = String::from_utf8_lossy(&rest[space + 1..len]);
+        if let Some((key, value)) = record.trim_end_matches('\n').split_once('=') {
+            match key {
+                "path" => path = Some(value.to_string()),
+                "linkpath" => linkpath = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        rest = &rest[len..];
+    }
+    (path, linkpath)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+
+    fn header(name: &str, size: usize, typeflag: u8, link: &str) -> Vec<u8> {
+        let mut h = vec![0u8; BLOCK];
+        h[..name.len()].copy_from_slice(name.as_bytes());
+        h[124..135].copy_from_slice(format!("{:011o}", size).as_bytes());
+        h[156] = typeflag;
+        h[157..157 + link.len()].copy_from_slice(link.as_bytes());
+        h[257..263].copy_from_slice(b"ustar\0");
+        let sum = h.iter().map(|b| *b as u64).sum::<u64>() + 8 * b' ' as u64;
+        h[148..156].copy_from_slice(format!("{:06o}\0 ", sum).as_bytes());
+        h
+    }
+
+    fn member(out: &mut Vec<u8>, name: &str, typeflag: u8, link: &str, data: &[u8]) {
+        out.extend(header(name, data.len(), typeflag, link));
+        out.extend_from_slice(data);
+        out.resize(out.len() + padding(data.len() as u64) as usize, 0);
+    }
+
+    fn digest(name: &str, data: &[u8]) -> Result<ContentDigest> {
+        let path = std::env::temp_dir().join(format!("rust-hash-archive-test-{}-{}", std::process::id(), name));
+        fs::write(&path, data).unwrap();
+        let digest = content_digest(&path, &AtomicBool::new(false));
+        let _ = fs::remove_file(&path);
+        digest
+    }
+
+    fn sha256(data: &[u8]) -> String {
+        hex::encode(Sha256::digest(data))
+    }
+
+    // The same tree as a tar: a long name through a GNU `L` record, a hard link, a symlink, a directory,
+    // and `a.txt` stored twice
+    fn tar() -> Vec<u8> {
+        let long = format!("{}/deep.txt", "d".repeat(120));
+        let mut tar = Vec::new();
+        member(&mut tar, "./a.txt", b'0', "", b"old");
+        member(&mut tar, "dir/", b'5', "", b"");
+        member(&mut tar, "././@LongLink", b'L', "", format!("{}\0", long).as_bytes());
+        member(&mut tar, "ignored", b'0', "", b"deep");
+        member(&mut tar, "dir/hard", b'1', "./a.txt", b"");
+        member(&mut tar, "link", b'2', "a.txt", b"");
+        member(&mut tar, "a.txt", b'0', "", b"hello");
+        tar.extend([0u8; 2 * BLOCK]);
+        tar
+    }
+
+    #[test]
+    fn tar_members_in_the_documented_serialization() {
+        let long = format!("{}/deep.txt", "d".repeat(120));
+        let expected = format!(
+            "a.txt\0{}\0{}\0{}\0dir/hard\0{}\0link\0-> a.txt\0",
+            sha256(b"hello"),
+            long,
+            sha256(b"deep"),
+            // The hard link was written when a.txt still held "old"
+            sha256(b"old")
+        );
+        let plain = digest("plain.tar", &tar()).unwrap();
+        assert_eq!((plain.hex.as_str(), plain.members), (sha256(expected.as_bytes()).as_str(), 4));
+
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(&tar()).unwrap();
+        assert_eq!(digest("packed.tar.gz", &gz.finish().unwrap()).unwrap().hex, plain.hex);
+    }
+
+    #[test]
+    fn zip_and_tar_of_the_same_files_agree() {
+        let mut tarred = Vec::new();
+        member(&mut tarred, "b/c.txt", b'0', "", b"second");
+        member(&mut tarred, "a.txt", b'0', "", b"first");
+        member(&mut tarred, "link", b'2', "a.txt", b"");
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default();
+        zip.add_directory("b/", options).unwrap();
+        zip.start_file("a.txt", options).unwrap();
+        zip.write_all(b"first").unwrap();
+        zip.add_symlink("link", "a.txt", options).unwrap();
+        zip.start_file("b\\c.txt", options.compression_method(zip::CompressionMethod::Stored)).unwrap();
+        zip.write_all(b"second").unwrap();
+        let zip = zip.finish().unwrap().into_inner();
+        let from_tar = digest("same.tar", &tarred).unwrap();
+        let from_zip = digest("same.zip", &zip).unwrap();
+        assert_eq!((from_zip.hex, from_zip.members), (from_tar.hex, 3));
+    }
+
+    #[test]
+    fn damaged_tar_headers_are_refused() {
+        let mut damaged = tar();
+        damaged[0] = b'X';
+        assert!(digest("damaged.tar", &damaged).is_err());
+    }
+}
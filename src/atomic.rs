@@ -0,0 +1,42 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+// Sibling of `target` on the same volume, so the final rename cannot turn into a copy
+fn temp_path(target: &Path) -> PathBuf {
+    let name = target.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    target.with_file_name(format!(".{}.{}.tmp", name, std::process::id()))
+}
+
+// Writes `data` to a temporary file next to `target`, flushes it to disk and renames it over
+// `target`. A crash leaves either the old file or the new one, never a truncated mix.
+pub fn write(target: &Path, data: &[u8]) -> Result<()> {
+    let temp = temp_path(target);
+    let written = (|| -> Result<()> {
+        let mut file = File::create(&temp).with_context(|| format!("Failed to create {}", temp.display()))?;
+        file.write_all(data).with_context(|| format!("Failed to write {}", temp.display()))?;
+        file.sync_all().with_context(|| format!("Failed to flush {}", temp.display()))?;
+        fs::rename(&temp, target).with_context(|| format!("Failed to replace {}", target.display()))
+    })();
+    if written.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    written
+}
+
+// Append done the same way: the existing content plus `data` replace the file in one rename
+pub fn append(target: &Path, data: &[u8]) -> Result<()> {
+    let mut all = match fs::read(target) {
+        Ok(existing) => existing,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", target.display())),
+    };
+    // Keep the old last line intact when it had no line break
+    if all.last().is_some_and(|b| *b != b'\n' && *b != 0) {
+        all.push(b'\n');
+    }
+    all.extend_from_slice(data);
+    write(target, &all)
+}
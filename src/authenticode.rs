@@ -0,0 +1,185 @@
+use sha2::{Digest, Sha256};
+
+// Headers further in than this are not a PE image anyone signs
+const MAX_HEAD: usize = 64 * 1024;
+const PE32: u16 = 0x10b;
+const PE32_PLUS: u16 = 0x20b;
+// Index of the certificate table among the optional header's data directories
+const SECURITY_DIRECTORY: u32 = 4;
+
+// Authenticode-style image hash of a PE file (EXE, DLL, SYS): SHA-256 over the whole file except the
+// optional header's CheckSum, the certificate table's directory entry and the certificate table itself,
+// which are the parts signing changes. A signed and an unsigned copy of the same build hash the same.
+// The headers are parsed from the stream, so it runs in the same read pass as the digest; anything that
+// is not a PE image gives no value
+pub struct AuthenticodeHasher {
+    head: Vec<u8>,
+    // File ranges left out of the hash, sorted; None until the headers have been read
+    skip: Option<Vec<(u64, u64)>>,
+    not_pe: bool,
+    sha: Sha256,
+    offset: u64,
+}
+
+impl Default for AuthenticodeHasher {
+    fn default() -> Self {
+        AuthenticodeHasher { head: Vec::new(), skip: None, not_pe: false, sha: Sha256::new(), offset: 0 }
+    }
+}
+
+impl AuthenticodeHasher {
+    pub fn update(&mut self, data: &[u8]) {
+        if self.not_pe {
+            return;
+        }
+        if self.skip.is_some() {
+            self.feed(data);
+            return;
+        }
+        self.head.extend_from_slice(data);
+        match excluded_ranges(&self.head) {
+            Ok(Some(skip)) => {
+                self.skip = Some(skip);
+                let head = std::mem::take(&mut self.head);
+                self.feed(&head);
+            }
+            Ok(None) if self.head.len() < MAX_HEAD => {}
+            _ => {
+                self.not_pe = true;
+                self.head = Vec::new();
+            }
+        }
+    }
+
+    // Lowercase hex; None for files that are not PE images
+    pub fn finish(self) -> Option<String> {
+        self.skip.filter(|_| !self.not_pe).map(|_| hex::encode(self.sha.finalize()))
+    }
+
+    fn feed(&mut self, data: &[u8]) {
+        let (start, end) = (self.offset, self.offset + data.len() as u64);
+        let mut pos = start;
+        for &(a, b) in self.skip.as_deref().unwrap_or_default() {
+            if b <= pos || a >= end {
+                continue;
+            }
+            if a > pos {
+                self.sha.update(&data[(pos - start) as usize..(a - start) as usize]);
+            }
+            pos = pos.max(b.min(end));
+        }
+        if pos < end {
+            self.sha.update(&data[(pos - start) as usize..]);
+        }
+        self.offset = end;
+    }
+}
+
+// Ok(None) while more of the file is needed, Err when it is not a PE image
+fn excluded_ranges(head: &[u8]) -> Result<Option<Vec<(u64, u64)>>, ()> {
+    let u16_at = |at: usize| head.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+    let u32_at = |at: usize| head.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+    if head.len() >= 2 && &head[..2] != b"MZ" {
+        return Err(());
+    }
+    let Some(pe) = u32_at(0x3c).map(|v| v as usize) else { return Ok(None) };
+    if pe > MAX_HEAD {
+        return Err(());
+    }
+    let optional = pe + 24;
+    let Some(magic) = u16_at(optional) else { return Ok(None) };
+    if head.get(pe..pe + 4) != Some(b"PE\0\0".as_slice()) {
+        return Err(());
+    }
+    let (count_at, directories) = match magic {
+        PE32 => (optional + 92, optional + 96),
+        PE32_PLUS => (optional + 108, optional + 112),
+        _ => return Err(()),
+    };
+    let Some(count) = u32_at(count_at) else { return Ok(None) };
+    let checksum = (optional as u64 + 64, optional as u64 + 68);
+    if count <= SECURITY_DIRECTORY {
+        return Ok(Some(vec![checksum]));
+    }
+    let entry = directories + SECURITY_DIRECTORY as usize * 8;
+    let (Some(address), Some(size)) = (u32_at(entry), u32_at(entry + 4)) else { return Ok(None) };
+    let mut skip = vec![checksum, (entry as u64, entry as u64 + 8)];
+    // For this directory the address is a file offset, not an RVA
+    if address > 0 && size > 0 {
+        skip.push((address as u64, address as u64 + size as u64));
+    }
+    skip.sort();
+    Ok(Some(skip))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PE: usize = 0x80;
+    const OPTIONAL: usize = PE + 24;
+
+    // A 1 KiB PE32+ image; `signature` sets the CheckSum and appends a certificate table
+    fn image(signature: Option<&[u8]>) -> Vec<u8> {
+        let mut data: Vec<u8> = (0..1024u32).map(|i| (i % 253) as u8).collect();
+        data[..2].copy_from_slice(b"MZ");
+        data[0x3c..0x40].copy_from_slice(&(PE as u32).to_le_bytes());
+        data[PE..PE + 4].copy_from_slice(b"PE\0\0");
+        data[OPTIONAL..OPTIONAL + 2].copy_from_slice(&PE32_PLUS.to_le_bytes());
+        data[OPTIONAL + 108..OPTIONAL + 112].copy_from_slice(&16u32.to_le_bytes());
+        let entry = OPTIONAL + 112 + 32;
+        data[OPTIONAL + 64..OPTIONAL + 68].fill(0);
+        data[entry..entry + 8].fill(0);
+        if let Some(table) = signature {
+            data[OPTIONAL + 64..OPTIONAL + 68].copy_from_slice(&0x1234u32.to_le_bytes());
+            data[entry..entry + 4].copy_from_slice(&1024u32.to_le_bytes());
+            data[entry + 4..entry + 8].copy_from_slice(&(table.len() as u32).to_le_bytes());
+            data.extend_from_slice(table);
+        }
+        data
+    }
+
+    fn hash(data: &[u8], piece: usize) -> Option<String> {
+        let mut hasher = AuthenticodeHasher::default();
+        for chunk in data.chunks(piece) {
+            hasher.update(chunk);
+        }
+        hasher.finish()
+    }
+
+    #[test]
+    fn signing_does_not_change_the_hash() {
+        let unsigned = image(None);
+        let entry = OPTIONAL + 112 + 32;
+        let expected = Sha256::new()
+            .chain_update(&unsigned[..OPTIONAL + 64])
+            .chain_update(&unsigned[OPTIONAL + 68..entry])
+            .chain_update(&unsigned[entry + 8..])
+            .finalize();
+        let signed = image(Some(&[0xC5; 200]));
+        assert_eq!(hash(&unsigned, 1 << 20), Some(hex::encode(expected)));
+        // Headers split across reads
+        assert_eq!(hash(&signed, 7), hash(&unsigned, 1 << 20));
+        let mut patched = unsigned.clone();
+        patched[600] ^= 1;
+        assert_ne!(hash(&patched, 1 << 20), hash(&unsigned, 1 << 20));
+    }
+
+    #[test]
+    fn without_a_security_directory_only_the_checksum_is_left_out() {
+        let mut data = image(None);
+        data[OPTIONAL..OPTIONAL + 2].copy_from_slice(&PE32.to_le_bytes());
+        data[OPTIONAL + 92..OPTIONAL + 96].copy_from_slice(&4u32.to_le_bytes());
+        let expected = Sha256::new().chain_update(&data[..OPTIONAL + 64]).chain_update(&data[OPTIONAL + 68..]).finalize();
+        assert_eq!(hash(&data, 100), Some(hex::encode(expected)));
+    }
+
+    #[test]
+    fn other_files_give_no_value() {
+        assert_eq!(hash(b"#!/bin/sh\necho hi\n", 4), None);
+        let mut data = image(None);
+        data[PE] = b'N';
+        assert_eq!(hash(&data, 64), None);
+        assert_eq!(hash(b"MZ", 1), None);
+    }
+}
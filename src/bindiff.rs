@@ -0,0 +1,98 @@
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{anyhow, Context, Result};
+
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+// Where two files differ, to tell truncation from corruption
+#[derive(Debug, Default)]
+pub struct DiffSummary {
+    pub len_a: u64,
+    pub len_b: u64,
+    pub first_difference: Option<u64>,
+    // Runs of differing bytes within the length both files share
+    pub regions: u64,
+    pub differing_bytes: u64,
+}
+
+impl DiffSummary {
+    pub fn describe(&self) -> String {
+        let Some(first) = self.first_difference else {
+            return "contents are identical".to_string();
+        };
+        let mut parts = vec![format!("first difference at offset {} (0x{:X})", first, first)];
+        if self.regions > 0 {
+            parts.push(format!("{} differing region(s), {} byte(s)", self.regions, self.differing_bytes));
+        }
+        if self.len_a != self.len_b {
+            let (shorter, by) = if self.len_b < self.len_a { ("second", self.len_a - self.len_b) } else { ("first", self.len_b - self.len_a) };
+            // Equal up to the shorter length means one file is simply cut off
+            let kind = if self.regions == 0 { "truncated" } else { "shorter" };
+            parts.push(format!("{} file {} by {} byte(s)", shorter, kind, by));
+        }
+        parts.join(", ")
+    }
+}
+
+// Streams both files side by side; stops early when `cancel` is set
+pub fn compare(a: &Path, b: &Path, cancel: &AtomicBool) -> Result<DiffSummary> {
+    let open = |p: &Path| File::open(p).with_context(|| format!("Failed to open file: {}", p.display()));
+    let mut ra = BufReader::with_capacity(BUFFER_SIZE, open(a)?);
+    let mut rb = BufReader::with_capacity(BUFFER_SIZE, open(b)?);
+    let mut buf_a = vec![0u8; BUFFER_SIZE];
+    let mut buf_b = vec![0u8; BUFFER_SIZE];
+    let mut summary = DiffSummary::default();
+    let mut offset: u64 = 0;
+    let mut in_region = false;
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(anyhow!(crate::errors::CANCELLED));
+        }
+        let na = read_full(&mut ra, &mut buf_a)?;
+        let nb = read_full(&mut rb, &mut buf_b)?;
+        let common = na.min(nb);
+        for (i, (x, y)) in buf_a[..common].iter().zip(&buf_b[..common]).enumerate() {
+            if x != y {
+                if !in_region {
+                    summary.regions += 1;
+                    in_region = true;
+                }
+                summary.differing_bytes += 1;
+                summary.first_difference.get_or_insert(offset + i as u64);
+            } else {
+                in_region = false;
+            }
+        }
+        offset += common as u64;
+        summary.len_a += na as u64;
+        summary.len_b += nb as u64;
+        if na != nb {
+            // One file ended; count what is left of the other
+            summary.first_difference.get_or_insert(offset);
+            let rest = |r: &mut BufReader<File>| std::io::copy(r, &mut std::io::sink());
+            summary.len_a += rest(&mut ra)?;
+            summary.len_b += rest(&mut rb)?;
+            break;
+        }
+        if na == 0 {
+            break;
+        }
+    }
+    Ok(summary)
+}
+
+// Fills `buf` unless EOF comes first, so both sides advance in equal steps
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = reader.read(&mut buf[filled..])?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
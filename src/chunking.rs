@@ -0,0 +1,101 @@
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use fastcdc::v2020::StreamCDC;
+use sha2::{Digest, Sha256};
+
+// FastCDC 2020 parameters; changing them changes every boundary, so they are written into the chunk list
+pub const MIN_CHUNK: u32 = 256 * 1024;
+pub const AVG_CHUNK: u32 = 1024 * 1024;
+pub const MAX_CHUNK: u32 = 4 * 1024 * 1024;
+
+const HEADER: &str = "# rust-hash chunk list";
+
+#[derive(Debug, Clone)]
+pub struct ChunkEntry {
+    pub offset: u64,
+    pub length: u64,
+    pub hex: String,
+}
+
+// Splits the stream into content-defined chunks, hashing each one and feeding the whole-file hasher in the same pass
+pub fn hash_chunks<R: Read>(reader: R, whole: &mut Sha256) -> Result<Vec<ChunkEntry>> {
+    let mut chunks = Vec::new();
+    for chunk in StreamCDC::new(reader, MIN_CHUNK, AVG_CHUNK, MAX_CHUNK) {
+        let chunk = chunk.map_err(std::io::Error::from)?;
+        whole.update(&chunk.data);
+        chunks.push(ChunkEntry {
+            offset: chunk.offset,
+            length: chunk.length as u64,
+            hex: hex::encode(Sha256::digest(&chunk.data)),
+        });
+    }
+    Ok(chunks)
+}
+
+pub fn chunk_list_text(path: &Path, bytes: u64, hex: &str, chunks: &[ChunkEntry]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{}", HEADER);
+    let _ = writeln!(out, "# file: {}", path.display());
+    let _ = writeln!(out, "# size: {}", bytes);
+    let _ = writeln!(out, "# sha256: {}", hex);
+    let _ = writeln!(out, "# chunking: fastcdc-2020 min={} avg={} max={}", MIN_CHUNK, AVG_CHUNK, MAX_CHUNK);
+    let _ = writeln!(out, "# offset length sha256");
+    for c in chunks {
+        let _ = writeln!(out, "{} {} {}", c.offset, c.length, c.hex);
+    }
+    out
+}
+
+pub fn write_chunk_list(target: &Path, path: &Path, bytes: u64, hex: &str, chunks: &[ChunkEntry]) -> Result<()> {
+    fs::write(target, chunk_list_text(path, bytes, hex, chunks))
+        .with_context(|| format!("Failed to write chunk list: {}", target.display()))
+}
+
+pub fn read_chunk_list(target: &Path) -> Result<Vec<ChunkEntry>> {
+    let data = fs::read_to_string(target).with_context(|| format!("Failed to read chunk list: {}", target.display()))?;
+    if !data.starts_with(HEADER) {
+        return Err(anyhow!("Not a rust-hash chunk list: {}", target.display()));
+    }
+    let mut chunks = Vec::new();
+    for (idx, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let parsed = (|| {
+            let offset = fields.next()?.parse().ok()?;
+            let length = fields.next()?.parse().ok()?;
+            let hex = fields.next()?.to_ascii_lowercase();
+            Some(ChunkEntry { offset, length, hex })
+        })();
+        match parsed {
+            Some(c) => chunks.push(c),
+            None => return Err(anyhow!("Malformed chunk list line {}: {}", idx + 1, line)),
+        }
+    }
+    Ok(chunks)
+}
+
+// How much of `current` is already present (by chunk digest) in `previous`
+pub fn compare_summary(current: &[ChunkEntry], previous: &[ChunkEntry]) -> String {
+    let known: HashSet<&str> = previous.iter().map(|c| c.hex.as_str()).collect();
+    let total_bytes: u64 = current.iter().map(|c| c.length).sum();
+    let (shared, shared_bytes) = current
+        .iter()
+        .filter(|c| known.contains(c.hex.as_str()))
+        .fold((0usize, 0u64), |(n, b), c| (n + 1, b + c.length));
+    let pct = if total_bytes > 0 { shared_bytes as f64 / total_bytes as f64 * 100.0 } else { 100.0 };
+    format!(
+        "{} of {} chunks unchanged ({:.1}% of bytes); {} new or modified chunks",
+        shared,
+        current.len(),
+        pct,
+        current.len() - shared
+    )
+}
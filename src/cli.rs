@@ -0,0 +1,947 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::algo::{self, Algorithm};
+use crate::atomic;
+use crate::config::Config;
+use crate::content_id::{self, Multibase};
+use crate::errors::HashError;
+use crate::gpu::{self, Gpu};
+use crate::history::{self, History};
+use crate::path_norm::{self, Form, Separator};
+use crate::post_actions;
+use crate::progress::{self, Snapshot};
+use crate::share_read;
+use crate::tree_digest;
+use crate::usage::Usage;
+use crate::xattrs;
+
+// First argument that runs a headless hash instead of opening the window: `--cli [options] <file>...`
+pub const CLI_FLAG: &str = "--cli";
+
+// How often `--progress json` reports while a file is being read
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+const USAGE: &str = "usage: rust-hash --cli [-r] [-j N] [--include PATTERN] [--exclude PATTERN] [--algo sha256|sha512|sha1|md5|blake3] [-o MANIFEST [--append|--overwrite|--merge]] [-z] [-y] [-n] [--skip-unreadable] [--base DIR] [--preset deb|rpm] [--multibase base32|base58btc] [--separator native|/|\\] [--normalize none|nfc|nfd] [--progress json] [--parallel-reads N|auto] [--gpu] [--xattrs] [--tree] <file or folder>...\n       rust-hash --cli --bench-gpu [COUNT] [SIZE]";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProgressFormat {
+    None,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Existing {
+    Append,
+    Overwrite,
+    // Update the lines of files hashed again, add new ones, keep the rest
+    Merge,
+}
+
+// Package file lists, to check a locally built package's files against an installed system
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Preset {
+    // DEBIAN/md5sums: `md5  usr/bin/tool`, relative to the package root
+    Deb,
+    // Like `rpm -q --qf '[%{FILENAMES} %{FILEDIGESTS}\n]'`: `/usr/bin/tool sha256`
+    Rpm,
+}
+
+struct Options {
+    progress: ProgressFormat,
+    recursive: bool,
+    jobs: usize,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    algorithm: Algorithm,
+    // Manifest to write instead of printing to stdout
+    output: Option<PathBuf>,
+    // What to do when the -o manifest exists; None asks on a terminal and refuses otherwise
+    existing: Option<Existing>,
+    // NUL-terminated lines with unescaped names, like `sha256sum --zero`
+    zero: bool,
+    preset: Option<Preset>,
+    // Set by -a/--algo, so a preset does not override it
+    algorithm_given: bool,
+    // Digests written as multibase multihashes instead of hex, for IPFS/libp2p tools
+    multibase: Option<Multibase>,
+    // Folder names are written relative to, for lists checked from another working folder; None writes them as given
+    base: Option<PathBuf>,
+    // How names are written, so one tree gives the same manifest on every OS
+    separator: Separator,
+    form: Form,
+    // Start long runs without asking
+    yes: bool,
+    // List what would be hashed, with sizes, without reading anything
+    dry_run: bool,
+    // Files that cannot be opened (locked, gone, no permission) are listed at the end instead of failing the run
+    skip_unreadable: bool,
+    parallel_reads: ParallelReads,
+    // Small files hashed in batches on the GPU, when there is one
+    gpu: bool,
+    // An `XATTRS-SHA256` line after each file's line, so verify also checks extended attributes or ADS
+    xattrs: bool,
+    // A TREE-SHA256 line for the one folder given, after its files; with --xattrs it covers their attributes too
+    tree: bool,
+    inputs: Vec<PathBuf>,
+}
+// --parallel-reads: several handles per file, each reading its own range, for BLAKE3 over high-latency links
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParallelReads {
+    Off,
+    // Only for files on an SMB or NFS share; local disks read fastest in order
+    Auto,
+    Readers(usize),
+}
+
+impl ParallelReads {
+    // Handles to read `path` with; None reads it the usual way
+    fn readers(self, path: &Path) -> Option<usize> {
+        match self {
+            ParallelReads::Off => None,
+            ParallelReads::Auto => share_read::is_network_share(path).then_some(share_read::AUTO_READERS),
+            ParallelReads::Readers(n) => Some(n),
+        }
+    }
+}
+
+// One line on stderr per tick; the final one has "finished": true
+#[derive(Serialize)]
+struct ProgressEvent {
+    event: &'static str,
+    path: Option<String>,
+    bytes: u64,
+    total: Option<u64>,
+    percent: Option<f64>,
+    files_remaining: usize,
+    eta_secs: Option<u64>,
+    finished: bool,
+}
+
+// Sent once after the walk, before anything is read
+#[derive(Serialize)]
+struct PlanEvent {
+    event: &'static str,
+    files: usize,
+    bytes: u64,
+    // From the throughput of recent hashes in the window's history; None without any
+    estimate_secs: Option<u64>,
+}
+
+// One line on stderr per file, so a wrapper knows the outcome without parsing stdout
+#[derive(Serialize)]
+struct FileEvent {
+    event: &'static str,
+    path: String,
+    algorithm: &'static str,
+    digest: Option<String>,
+    bytes: Option<u64>,
+    elapsed_ms: u64,
+    error: Option<String>,
+    // With --skip-unreadable: the file could not be opened and does not count as a failure
+    skipped: bool,
+}
+
+// `-j 8` and `-j8`, `--jobs 8` and `--jobs=8` all work
+fn value<'a>((short, long): (&str, &str), arg: &'a str, rest: &mut std::slice::Iter<'a, OsString>) -> Result<Option<&'a str>, String> {
+    let inline = arg.strip_prefix(long).and_then(|v| v.strip_prefix('=')).or_else(|| arg.strip_prefix(short).filter(|v| !v.is_empty()));
+    if let Some(v) = inline {
+        return Ok(Some(v));
+    }
+    if arg == short || arg == long {
+        return rest.next().and_then(|v| v.to_str()).map(Some).ok_or_else(|| format!("{} needs a value", arg));
+    }
+    Ok(None)
+}
+
+fn parse(args: &[OsString]) -> Result<Options, String> {
+    let mut options = Options {
+        progress: ProgressFormat::None,
+        recursive: false,
+        jobs: 1,
+        include: Vec::new(),
+        exclude: Vec::new(),
+        // SHA-256 unless asked otherwise, whatever the window's settings, so scripts get what they always got
+        algorithm: Algorithm::Sha256,
+        output: None,
+        zero: false,
+        existing: None,
+        base: None,
+        preset: None,
+        algorithm_given: false,
+        multibase: None,
+        separator: Separator::Native,
+        form: Form::AsIs,
+        yes: false,
+        dry_run: false,
+        skip_unreadable: false,
+        parallel_reads: ParallelReads::Off,
+        gpu: false,
+        xattrs: false,
+        tree: false,
+        inputs: Vec::new(),
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let Some(text) = arg.to_str() else {
+            options.inputs.push(PathBuf::from(arg));
+            continue;
+        };
+        if let Some(v) = value(("-j", "--jobs"), text, &mut args)? {
+            options.jobs = v.parse().ok().filter(|n| *n > 0).ok_or_else(|| format!("--jobs takes a number above 0, not {}", v))?;
+            continue;
+        }
+        if let Some(v) = value(("-a", "--algo"), text, &mut args)? {
+            options.algorithm = Algorithm::parse(v).ok_or_else(|| format!("unknown algorithm {} (use {})", v, Algorithm::NAMES))?;
+            options.algorithm_given = true;
+            continue;
+        }
+        if let Some(v) = value(("-o", "--output"), text, &mut args)? {
+            options.output = Some(PathBuf::from(v));
+            continue;
+        }
+        match text {
+            "--progress" => {
+                options.progress = match args.next().and_then(|v| v.to_str()) {
+                    Some("json") => ProgressFormat::Json,
+                    Some("none") => ProgressFormat::None,
+                    other => return Err(format!("--progress takes json or none, not {}", other.unwrap_or("nothing"))),
+                }
+            }
+            "--separator" => {
+                let v = args.next().and_then(|v| v.to_str()).unwrap_or("nothing");
+                options.separator = Separator::parse(v).ok_or_else(|| format!("--separator takes native, / or \\, not {}", v))?;
+            }
+            "--base" => options.base = Some(args.next().map(PathBuf::from).ok_or("--base needs a folder")?),
+            "--preset" => {
+                options.preset = match args.next().and_then(|v| v.to_str()) {
+                    Some("deb") => Some(Preset::Deb),
+                    Some("rpm") => Some(Preset::Rpm),
+                    other => return Err(format!("--preset takes deb or rpm, not {}", other.unwrap_or("nothing"))),
+                }
+            }
+            "--multibase" => {
+                let v = args.next().and_then(|v| v.to_str()).unwrap_or("nothing");
+                options.multibase = Some(Multibase::parse(v).ok_or_else(|| format!("--multibase takes base32 or base58btc, not {}", v))?);
+            }
+            "--parallel-reads" => {
+                options.parallel_reads = match args.next().and_then(|v| v.to_str()) {
+                    Some("auto") => ParallelReads::Auto,
+                    Some(v) => ParallelReads::Readers(v.parse().ok().filter(|n| *n > 0).ok_or_else(|| format!("--parallel-reads takes auto or a number above 0, not {}", v))?),
+                    None => return Err("--parallel-reads needs auto or a number".to_string()),
+                }
+            }
+            "--normalize" => {
+                let v = args.next().and_then(|v| v.to_str()).unwrap_or("nothing");
+                options.form = Form::parse(v).ok_or_else(|| format!("--normalize takes none, nfc or nfd, not {}", v))?;
+            }
+            "-r" | "--recursive" => options.recursive = true,
+            "-z" | "--zero" => options.zero = true,
+            "-y" | "--yes" => options.yes = true,
+            "-n" | "--dry-run" => options.dry_run = true,
+            "--skip-unreadable" => options.skip_unreadable = true,
+            "--gpu" => options.gpu = true,
+            "--xattrs" => options.xattrs = true,
+            "--tree" => options.tree = true,
+            "--append" => options.existing = Some(Existing::Append),
+            "--overwrite" => options.existing = Some(Existing::Overwrite),
+            "--merge" => options.existing = Some(Existing::Merge),
+            "--include" | "--exclude" => {
+                let pattern = args.next().and_then(|v| v.to_str()).ok_or_else(|| format!("{} needs a pattern", text))?.to_string();
+                if text == "--include" {
+                    options.include.push(pattern);
+                } else {
+                    options.exclude.push(pattern);
+                }
+            }
+            "--" => options.inputs.extend(args.by_ref().map(PathBuf::from)),
+            flag if flag.starts_with('-') && flag.len() > 1 => return Err(format!("unknown option {}", flag)),
+            _ => options.inputs.push(PathBuf::from(arg)),
+        }
+    }
+    if options.inputs.is_empty() {
+        return Err("no files given".to_string());
+    }
+    // A merged list keeps its names relative to its own folder, which is what verify resolves them against
+    if options.base.is_some() && options.existing == Some(Existing::Merge) {
+        return Err("--base cannot be combined with --merge".to_string());
+    }
+    if options.multibase.is_some() && options.existing == Some(Existing::Merge) {
+        return Err("--multibase cannot be combined with --merge".to_string());
+    }
+    if let Some(preset) = options.preset {
+        if options.existing == Some(Existing::Merge) {
+            return Err("--preset cannot be combined with --merge".to_string());
+        }
+        // Package lists name files from the package root; a single folder given is taken to be it
+        if options.base.is_none() {
+            match options.inputs.as_slice() {
+                [root] if root.is_dir() => options.base = Some(root.clone()),
+                _ => return Err("--preset needs --base ROOT (the package's root folder) unless a single folder is given".to_string()),
+            }
+        }
+        options.separator = Separator::Slash;
+        options.recursive = true;
+        if !options.algorithm_given {
+            options.algorithm = if preset == Preset::Deb { Algorithm::Md5 } else { Algorithm::Sha256 };
+        }
+    }
+    // Ranges read out of order can only be put together by a tree hash
+    if options.parallel_reads != ParallelReads::Off && options.algorithm != Algorithm::Blake3 {
+        return Err("--parallel-reads works with --algo blake3 only".to_string());
+    }
+    if options.gpu && options.algorithm != Algorithm::Sha256 {
+        return Err("--gpu works with SHA-256 only".to_string());
+    }
+    // Verify puts the attribute digest on the SHA-256 entry of the same file
+    if options.xattrs && (options.algorithm != Algorithm::Sha256 || options.preset.is_some() || options.multibase.is_some()) {
+        return Err("--xattrs works with plain SHA-256 lists only".to_string());
+    }
+    if options.xattrs && options.existing == Some(Existing::Merge) {
+        return Err("--xattrs cannot be combined with --merge".to_string());
+    }
+    // The root is over the whole of one folder and is built from SHA-256 digests
+    if options.tree {
+        match options.inputs.as_slice() {
+            [root] if root.is_dir() => options.recursive = true,
+            _ => return Err("--tree needs a single folder".to_string()),
+        }
+        if options.algorithm != Algorithm::Sha256 || !options.include.is_empty() || !options.exclude.is_empty() {
+            return Err("--tree works with SHA-256 over the whole folder only".to_string());
+        }
+    }
+    if let Some(refusal) = crate::policy::get().refusal(options.algorithm) {
+        return Err(refusal);
+    }
+    Ok(options)
+}
+
+// `*` and `?` wildcards; patterns with a `/` are matched against the path below the walked folder,
+// others against the file name. Case-insensitive on Windows, like its file system
+fn wildcard(pattern: &str, text: &str) -> bool {
+    let fold = |s: &str| if cfg!(windows) { s.to_lowercase() } else { s.to_string() };
+    let (p, t): (Vec<char>, Vec<char>) = (fold(pattern).chars().collect(), fold(text).chars().collect());
+    let (mut pi, mut ti, mut star, mut mark) = (0, 0, None, 0);
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some(pi);
+            mark = ti;
+            pi += 1;
+        } else if let Some(s) = star {
+            pi = s + 1;
+            mark += 1;
+            ti = mark;
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+fn wanted(options: &Options, relative: &Path) -> bool {
+    let name = relative.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let path = relative.to_string_lossy().replace('\\', "/");
+    let hit = |pattern: &String| wildcard(pattern, if pattern.contains('/') { &path } else { &name });
+    (options.include.is_empty() || options.include.iter().any(hit)) && !options.exclude.iter().any(hit)
+}
+
+// Regular files below `dir`; symlinks are not followed
+fn walk(options: &Options, root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to list folder: {}: {}", dir.display(), e))?;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let Ok(ft) = entry.file_type() else { continue };
+        let path = entry.path();
+        if ft.is_dir() {
+            walk(options, root, &path, files)?;
+        } else if ft.is_file() && wanted(options, path.strip_prefix(root).unwrap_or(&path)) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// Named files as given, folders expanded when -r is set; filters only apply inside folders.
+// A folder's files are sorted by their normalized path below it with `/` between names, not by
+// the order the file system lists them, so the same tree always gives the same manifest
+fn collect(options: &Options) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    for input in &options.inputs {
+        if input.is_dir() {
+            if !options.recursive {
+                return Err(format!("{} is a folder (use -r to hash what is inside)", input.display()));
+            }
+            let mut found = Vec::new();
+            walk(options, input, input, &mut found)?;
+            let mut keyed: Vec<_> = found.into_iter().map(|p| (path_norm::render(p.strip_prefix(input).unwrap_or(&p), Separator::Slash, options.form), p)).collect();
+            keyed.sort();
+            files.extend(keyed.into_iter().map(|(_, p)| p));
+        } else {
+            files.push(input.clone());
+        }
+    }
+    Ok(files)
+}
+
+// Overwrite (or merge into) a new manifest; for an existing one use the flag, or ask when a person is at the terminal
+fn existing_choice(target: &Path, flag: Option<Existing>) -> Result<Existing, String> {
+    // A merge into a list that does not exist yet starts it, with names written the way merges write them
+    if !target.exists() {
+        return Ok(if flag == Some(Existing::Merge) { Existing::Merge } else { Existing::Overwrite });
+    }
+    if let Some(choice) = flag {
+        return Ok(choice);
+    }
+    if !std::io::stdin().is_terminal() {
+        return Err(format!("{} exists; pass --append, --overwrite or --merge", target.display()));
+    }
+    eprint!("{} exists. [m]erge, [a]ppend, [o]verwrite or [c]ancel? ", target.display());
+    let mut answer = String::new();
+    let _ = std::io::stdin().read_line(&mut answer);
+    match answer.trim().to_lowercase().as_str() {
+        "m" | "merge" => Ok(Existing::Merge),
+        "a" | "append" => Ok(Existing::Append),
+        "o" | "overwrite" => Ok(Existing::Overwrite),
+        _ => Err("cancelled".to_string()),
+    }
+}
+
+// How `path` is written in the manifest and reported
+fn manifest_name(options: &Options, path: &Path) -> String {
+    match &options.base {
+        Some(base) => path_norm::render(&path_norm::relative_to(path, base), options.separator, options.form),
+        None => path_norm::render(path, options.separator, options.form),
+    }
+}
+
+// `size  name` for every file the walk and filters selected, in manifest order, then the totals on
+// stderr. Nothing is read and no manifest is written; files that cannot be opened make it exit 1
+fn dry_run(options: &Options, files: &[PathBuf]) -> i32 {
+    let (mut bytes, mut missing) = (0, 0);
+    let mut out = std::io::stdout().lock();
+    for path in files {
+        let name = manifest_name(options, path);
+        let size = match fs::metadata(path) {
+            Ok(m) if m.is_file() => {
+                bytes += m.len();
+                m.len().to_string()
+            }
+            _ => {
+                missing += 1;
+                "MISSING".to_string()
+            }
+        };
+        let _ = write!(out, "{}  {}{}", size, name, if options.zero { '\0' } else { '\n' });
+    }
+    let estimate = History::load().estimate(bytes);
+    if options.progress == ProgressFormat::Json {
+        emit(&PlanEvent { event: "plan", files: files.len(), bytes, estimate_secs: estimate.map(|d| d.as_secs()) });
+    } else {
+        let eta = estimate.map(|d| format!(", about {}", crate::human_duration(d))).unwrap_or_default();
+        eprintln!("rust-hash: dry run: {} files, {}{}, {} missing", files.len(), crate::human_bytes(bytes as f64), eta, missing);
+    }
+    if missing > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+// JSON lines on stderr are written whole under the lock so they never interleave
+fn emit<T: Serialize>(event: &T) {
+    if let Ok(line) = serde_json::to_string(event) {
+        let _ = writeln!(std::io::stderr().lock(), "{}", line);
+    }
+}
+
+fn progress_event(s: &Snapshot) -> ProgressEvent {
+    ProgressEvent {
+        event: "progress",
+        path: s.current.as_ref().map(|p| p.display().to_string()),
+        bytes: s.bytes,
+        total: s.total,
+        percent: s.percent().map(|p| (p * 10.0).round() / 10.0),
+        files_remaining: s.files_remaining,
+        eta_secs: s.eta.map(|d| d.as_secs()),
+        finished: s.finished,
+    }
+}
+
+// Hashes each file and prints `digest  path` lines (sha256sum/b3sum format, names escaped the
+// coreutils way) on stdout, or into the -o manifest.
+// Up to -j files are read at once; lines still come out in walk order.
+// Exit code: 0 all hashed, 1 some failed, 2 bad arguments.
+pub fn run(args: &[OsString]) -> i32 {
+    attach_console();
+    if args.first().is_some_and(|a| a == "--bench-gpu") {
+        return bench_gpu(&args[1..]);
+    }
+    let (options, files) = match parse(args).and_then(|o| collect(&o).map(|f| (o, f))) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("rust-hash: {}\n{}", e, USAGE);
+            return 2;
+        }
+    };
+    if options.dry_run {
+        return dry_run(&options, &files);
+    }
+    // Settled before any hashing, so a long run never ends at a question
+    let existing = match &options.output {
+        Some(target) => match existing_choice(target, options.existing) {
+            Ok(choice) => Some(choice),
+            Err(e) => {
+                eprintln!("rust-hash: {}", e);
+                return 2;
+            }
+        },
+        None => None,
+    };
+    // Merging matches lines by file and needs to tell digests from names, which the list editor does for SHA-256 only
+    if existing == Some(Existing::Merge) && options.algorithm != Algorithm::Sha256 {
+        eprintln!("rust-hash: --merge works with SHA-256 lists only");
+        return 2;
+    }
+    // Files that cannot be measured (missing, character devices) count as empty for the percentage
+    let total = files.iter().filter_map(|p| crate::expected_size(p)).sum();
+    let estimate = History::load().estimate(total);
+    if options.progress == ProgressFormat::Json {
+        emit(&PlanEvent { event: "plan", files: files.len(), bytes: total, estimate_secs: estimate.map(|d| d.as_secs()) });
+    } else if options.recursive {
+        let eta = estimate.map(|d| format!(", about {}", crate::human_duration(d))).unwrap_or_default();
+        eprintln!("rust-hash: {} files, {}{}", files.len(), crate::human_bytes(total as f64), eta);
+    }
+    // Only a person at a terminal is asked; scripts run as before
+    if estimate.is_some_and(|d| d > history::CONFIRM_OVER) && !options.yes && std::io::stdin().is_terminal() {
+        eprint!("This will take over {}. Start? [y/N] ", crate::human_duration(history::CONFIRM_OVER));
+        let mut answer = String::new();
+        let _ = std::io::stdin().read_line(&mut answer);
+        if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+            eprintln!("rust-hash: cancelled");
+            return 2;
+        }
+    }
+    // Only when turned on in Settings, and only into usage.json
+    let mut usage = Config::load().preferences.usage_metrics.then(Usage::load);
+    if let Some(usage) = &mut usage {
+        usage.operation("Command line");
+    }
+    let tracker = progress::Tracker::new(Arc::new(AtomicU64::new(0)), Some(total), files.len());
+    let watcher = (options.progress == ProgressFormat::Json).then(|| progress::watch(tracker.clone(), PROGRESS_INTERVAL, |s| emit(&progress_event(s))));
+    let cancel = Arc::new(AtomicBool::new(false));
+    let gpu = if options.gpu { open_gpu() } else { None };
+    // Small files go to the GPU in batches; the rest, and everything without a GPU, to the CPU workers
+    let (small, large): (Vec<_>, Vec<_>) =
+        files.iter().cloned().enumerate().partition(|(_, p)| gpu.is_some() && fs::metadata(p).is_ok_and(|m| m.is_file() && m.len() <= gpu::MAX_FILE));
+    let queue = Arc::new(Mutex::new(large.into_iter()));
+    let (tx, rx) = mpsc::channel();
+    if let Some(gpu) = gpu {
+        let (tracker, cancel, tx) = (tracker.clone(), cancel.clone(), tx.clone());
+        thread::spawn(move || gpu_worker(&gpu, small, &tracker, &cancel, &tx));
+    }
+    for _ in 0..options.jobs.min(files.len().max(1)) {
+        let (queue, tracker, cancel, tx, algorithm, parallel) = (queue.clone(), tracker.clone(), cancel.clone(), tx.clone(), options.algorithm, options.parallel_reads);
+        thread::spawn(move || loop {
+            let Some((index, path)) = queue.lock().unwrap_or_else(|e| e.into_inner()).next() else {
+                break;
+            };
+            tracker.start_file(&path);
+            let started = Instant::now();
+            let result = match parallel.readers(&path) {
+                Some(readers) => share_read::blake3(&path, readers, &tracker.counter(), &cancel),
+                None => algo::hash_file(&path, algorithm, &tracker.counter(), &cancel),
+            };
+            tracker.finish_file();
+            if tx.send((index, result, started.elapsed())).is_err() {
+                break;
+            }
+        });
+    }
+    drop(tx);
+    let mut failed = 0;
+    let mut skipped = Vec::new();
+    let mut manifest = String::new();
+    let mut digests = Vec::new();
+    let mut records = Vec::new();
+    let mut pending = BTreeMap::new();
+    let mut next = 0;
+    for (index, result, elapsed) in rx {
+        pending.insert(index, (result, elapsed));
+        while let Some((result, elapsed)) = pending.remove(&next) {
+            let elapsed_ms = elapsed.as_millis() as u64;
+            let path = &files[next];
+            let name = manifest_name(&options, path);
+            next += 1;
+            let event = match result {
+                Ok((hex, bytes)) => {
+                    let digest = match options.multibase {
+                        Some(base) => content_id::multihash(options.algorithm.multihash_code(), &hex).map_or_else(|| hex.clone(), |m| base.encode(&m)),
+                        None => hex.clone(),
+                    };
+                    let mut line = match options.preset {
+                        Some(Preset::Rpm) => post_actions::rpm_line(&digest, &name, options.zero),
+                        _ => post_actions::checksum_line(&digest, &name, false, options.zero),
+                    };
+                    // An unreadable attribute list fails the run, but the content line is still written
+                    let attributes = match options.xattrs.then(|| xattrs::digest(path)) {
+                        Some(Ok(attributes)) => {
+                            line.push_str(&xattrs::manifest_line(&attributes, &name, options.zero));
+                            Some(attributes)
+                        }
+                        Some(Err(e)) => {
+                            failed += 1;
+                            if watcher.is_none() {
+                                eprintln!("rust-hash: {:#}", e);
+                            }
+                            None
+                        }
+                        None => None,
+                    };
+                    if options.tree {
+                        let relative = path.strip_prefix(&options.inputs[0]).unwrap_or(path);
+                        records.push(tree_digest::Record { path: tree_digest::name(relative), content: Some(hex.clone()), attributes });
+                    }
+                    if let Some(usage) = &mut usage {
+                        usage.algorithm(options.algorithm);
+                    }
+                    if existing == Some(Existing::Merge) {
+                        digests.push((path.clone(), hex.clone()));
+                    } else if options.output.is_some() {
+                        manifest.push_str(&line);
+                    } else {
+                        let _ = std::io::stdout().lock().write_all(line.as_bytes());
+                    }
+                    FileEvent { event: "file", path: name, algorithm: options.algorithm.label(), digest: Some(hex), bytes: Some(bytes), elapsed_ms, error: None, skipped: false }
+                }
+                Err(e) => {
+                    let error = HashError::classify(&e, Some(path));
+                    let skip = options.skip_unreadable && error.is_unopenable();
+                    if let Some(usage) = &mut usage {
+                        usage.error(error.reason());
+                    }
+                    if skip {
+                        skipped.push(format!("{}: {}", name, error.reason()));
+                    } else {
+                        failed += 1;
+                    }
+                    let message = format!("{:#}", e);
+                    if watcher.is_none() && !skip {
+                        eprintln!("rust-hash: {}", message);
+                    }
+                    FileEvent { event: "file", path: name, algorithm: options.algorithm.label(), digest: None, bytes: None, elapsed_ms, error: Some(message), skipped: skip }
+                }
+            };
+            if watcher.is_some() {
+                emit(&event);
+            }
+        }
+    }
+    tracker.finish();
+    if let Some(Err(e)) = usage.as_mut().map(Usage::flush) {
+        eprintln!("rust-hash: {:#}", e);
+    }
+    if let Some(watcher) = watcher {
+        let _ = watcher.join();
+    }
+    if !skipped.is_empty() && options.progress == ProgressFormat::None {
+        eprintln!("rust-hash: skipped {} files that could not be opened:", skipped.len());
+        for line in &skipped {
+            eprintln!("  {}", line);
+        }
+    }
+    if let (Some(target), Some(existing)) = (&options.output, existing) {
+        let written = match existing {
+            Existing::Append => atomic::append(target, manifest.as_bytes()),
+            Existing::Overwrite => atomic::write(target, manifest.as_bytes()),
+            Existing::Merge => crate::manifest::merge_into(target, digests, options.zero).map(|summary| {
+                if options.progress == ProgressFormat::None {
+                    eprintln!("rust-hash: merged into {}: {}", target.display(), summary);
+                }
+            }),
+        };
+        if let Err(e) = written {
+            eprintln!("rust-hash: {:#}", e);
+            return 1;
+        }
+    }
+    if options.tree {
+        match tree_line(&options, records, failed + skipped.len()) {
+            Ok(line) => {
+                let _ = std::io::stdout().lock().write_all(line.as_bytes());
+            }
+            Err(e) => {
+                eprintln!("rust-hash: {:#}", e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+// The TREE-SHA256 line of the folder given, from its files' records; its folders are listed (and their
+// attributes read) here. Any file left out would give a root that does not describe the tree
+fn tree_line(options: &Options, mut records: Vec<tree_digest::Record>, unhashed: usize) -> anyhow::Result<String> {
+    if unhashed > 0 {
+        anyhow::bail!("No tree digest: {} files were not hashed", unhashed);
+    }
+    let root = &options.inputs[0];
+    for relative in tree_digest::folders(root)? {
+        let attributes = options.xattrs.then(|| xattrs::digest(&root.join(&relative))).transpose()?;
+        records.push(tree_digest::Record { path: tree_digest::name(&relative), content: None, attributes });
+    }
+    let hex = tree_digest::root(&records, options.xattrs)?;
+    let tag = if options.xattrs { tree_digest::TAG_XATTRS } else { tree_digest::TAG };
+    Ok(post_actions::tagged_line(tag, &hex, &manifest_name(options, root), options.zero))
+}
+
+type Hashed = (usize, anyhow::Result<(String, u64)>, Duration);
+
+fn open_gpu() -> Option<Gpu> {
+    let gpu = Gpu::open();
+    match &gpu {
+        Some(gpu) => eprintln!("rust-hash: hashing small files on {}", gpu.name()),
+        None if cfg!(feature = "gpu") => eprintln!("rust-hash: no usable GPU; hashing on the CPU"),
+        None => eprintln!("rust-hash: built without the gpu feature; hashing on the CPU"),
+    }
+    gpu
+}
+
+// Reads a batch of small files, hashes them in one dispatch and reports each like a CPU worker would.
+// A failed dispatch is not a failed file: that batch is hashed on the CPU instead
+fn gpu_worker(gpu: &Gpu, files: Vec<(usize, PathBuf)>, tracker: &progress::Tracker, cancel: &AtomicBool, tx: &mpsc::Sender<Hashed>) {
+    let counter = tracker.counter();
+    for batch in files.chunks(gpu::MAX_BATCH) {
+        if cancel.load(std::sync::atomic::Ordering::Relaxed) {
+            break;
+        }
+        let started = Instant::now();
+        let (mut indices, mut buffers) = (Vec::new(), Vec::new());
+        for (index, path) in batch {
+            tracker.start_file(path);
+            match fs::read(path).with_context(|| format!("Failed to read {}", path.display())) {
+                Ok(data) => {
+                    counter.fetch_add(data.len() as u64, std::sync::atomic::Ordering::Relaxed);
+                    indices.push(*index);
+                    buffers.push(data);
+                }
+                Err(e) => {
+                    tracker.finish_file();
+                    if tx.send((*index, Err(e), started.elapsed())).is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+        let hexes = gpu.sha256_batch(&buffers).unwrap_or_else(|e| {
+            eprintln!("rust-hash: {:#}; hashing this batch on the CPU", e);
+            buffers.iter().map(|b| hex::encode(Sha256::digest(b))).collect()
+        });
+        let each = started.elapsed() / indices.len().max(1) as u32;
+        for ((index, hex), buffer) in indices.into_iter().zip(hexes).zip(&buffers) {
+            tracker.finish_file();
+            if tx.send((index, Ok((hex, buffer.len() as u64)), each)).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+// `--bench-gpu [COUNT] [SIZE]`: SHA-256 of COUNT generated buffers of SIZE bytes on every CPU core and
+// on the GPU, with the time each took and whether they agree
+fn bench_gpu(args: &[OsString]) -> i32 {
+    let number = |i: usize, default: usize| args.get(i).and_then(|a| a.to_str()).and_then(|a| a.parse().ok()).unwrap_or(default);
+    let (count, size) = (number(0, 200_000), number(1, 4096));
+    let buffers: Vec<Vec<u8>> = (0..count).map(|i| (0..size).map(|j| (i.wrapping_mul(131) ^ j.wrapping_mul(31)) as u8).collect()).collect();
+    let rate = |elapsed: Duration| {
+        let secs = elapsed.as_secs_f64().max(1e-9);
+        format!("{:.0} ms, {}/s, {:.0} buffers/s", secs * 1000.0, crate::human_bytes((count * size) as f64 / secs), count as f64 / secs)
+    };
+    println!("{} buffers of {} bytes", count, size);
+    let cores = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let started = Instant::now();
+    let cpu: Vec<String> = thread::scope(|s| {
+        let parts: Vec<_> = buffers.chunks(count.div_ceil(cores).max(1)).map(|part| s.spawn(move || part.iter().map(|b| hex::encode(Sha256::digest(b))).collect::<Vec<_>>())).collect();
+        parts.into_iter().flat_map(|p| p.join().unwrap_or_default()).collect()
+    });
+    println!("CPU ({} threads): {}", cores, rate(started.elapsed()));
+    let Some(gpu) = open_gpu() else {
+        return 1;
+    };
+    let started = Instant::now();
+    match gpu.sha256_batch(&buffers) {
+        Ok(hexes) => {
+            println!("GPU {}: {} (upload and read-back included)", gpu.name(), rate(started.elapsed()));
+            if hexes == cpu {
+                println!("Digests agree");
+                0
+            } else {
+                println!("Digests DIFFER between CPU and GPU");
+                1
+            }
+        }
+        Err(e) => {
+            eprintln!("rust-hash: {:#}", e);
+            1
+        }
+    }
+}
+
+// Release builds use the GUI subsystem on Windows, which starts without a console; borrow the
+// one of the shell that launched us so stdout/stderr reach it
+#[cfg(windows)]
+fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<OsString> {
+        line.split_whitespace().map(OsString::from).collect()
+    }
+
+    fn parsed(line: &str) -> Options {
+        match parse(&args(line)) {
+            Ok(options) => options,
+            Err(e) => panic!("{}: {}", line, e),
+        }
+    }
+
+    fn refused(line: &str) -> String {
+        match parse(&args(line)) {
+            Ok(_) => panic!("{} was accepted", line),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn defaults() {
+        let options = parsed("f");
+        assert_eq!(options.algorithm, Algorithm::Sha256);
+        assert_eq!(options.inputs, vec![PathBuf::from("f")]);
+    }
+
+    #[test]
+    fn bad_arguments_are_refused() {
+        assert!(refused("").contains("no files"));
+        assert!(refused("--bogus f").contains("unknown option"));
+        assert!(refused("--progress xml f").contains("json"));
+    }
+
+    #[test]
+    fn values_inline_or_separate() {
+        for line in ["-j8 f", "-j 8 f", "--jobs=8 f", "--jobs 8 f"] {
+            assert_eq!(parsed(line).jobs, 8, "{}", line);
+        }
+        assert_eq!(parsed("--algo=blake3 f").algorithm, Algorithm::Blake3);
+        assert_eq!(parsed("-o out.sha256 f").output, Some(PathBuf::from("out.sha256")));
+        assert!(refused("-j 0 f").contains("--jobs"));
+        assert!(refused("--algo").contains("needs a value"));
+    }
+
+    #[test]
+    fn folders_and_filters() {
+        assert_eq!(parsed("f").jobs, 1);
+        assert!(!parsed("f").recursive);
+        let options = parsed("-r --include *.iso dir -- -odd");
+        assert!(options.recursive);
+        assert_eq!(options.include, vec!["*.iso".to_string()]);
+        assert_eq!(options.inputs, vec![PathBuf::from("dir"), PathBuf::from("-odd")]);
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(wildcard("*.iso", "disc.iso"));
+        assert!(wildcard("a?c*", "abcdef"));
+        assert!(!wildcard("*.iso", "disc.img"));
+    }
+
+    #[test]
+    fn zero_terminated_output() {
+        assert!(!parsed("f").zero);
+        assert!(parsed("-z f").zero && parsed("--zero f").zero);
+    }
+
+    #[test]
+    fn separator_and_unicode_form() {
+        let options = parsed("--separator / --normalize nfc f");
+        assert_eq!(options.separator, Separator::Slash);
+        assert_eq!(options.form, Form::Nfc);
+    }
+
+    #[test]
+    fn base_is_not_merged() {
+        assert_eq!(parsed("--base d f").base, Some(PathBuf::from("d")));
+        assert!(refused("--base d --merge f").contains("--merge"));
+    }
+
+    #[test]
+    fn multibase_is_not_merged() {
+        assert!(refused("--multibase base32 --merge f").contains("--merge"));
+    }
+
+    #[test]
+    fn presets_pick_their_algorithm() {
+        let deb = parsed("--preset deb --base root f");
+        assert_eq!(deb.algorithm, Algorithm::Md5);
+        assert!(deb.recursive);
+        assert_eq!(deb.separator, Separator::Slash);
+        assert_eq!(parsed("--preset deb --algo sha256 --base root f").algorithm, Algorithm::Sha256);
+        assert!(refused("--preset rpm a b").contains("--base"));
+    }
+
+    #[test]
+    fn parallel_reads_need_blake3() {
+        assert_eq!(parsed("--parallel-reads auto --algo blake3 f").parallel_reads, ParallelReads::Auto);
+        assert!(refused("--parallel-reads 4 f").contains("blake3"));
+    }
+
+    #[test]
+    fn gpu_needs_sha256() {
+        assert!(refused("--gpu --algo md5 f").contains("SHA-256"));
+    }
+
+    #[test]
+    fn xattrs_need_a_plain_sha256_list() {
+        assert!(!parsed("f").xattrs);
+        assert!(parsed("-r --xattrs f").xattrs);
+        assert!(refused("--xattrs --algo md5 f").contains("SHA-256"));
+        assert!(refused("--xattrs --merge f").contains("--merge"));
+    }
+
+    // The package root is a folder that exists wherever the tests run
+    #[test]
+    fn tree_covers_one_whole_folder() {
+        let options = parsed("--tree --xattrs src");
+        assert!(options.tree && options.recursive);
+        assert!(refused("--tree f").contains("single folder"));
+        assert!(refused("--tree src src").contains("single folder"));
+        assert!(refused("--tree --algo md5 src").contains("SHA-256"));
+        assert!(refused("--tree --exclude *.rs src").contains("whole folder"));
+    }
+}
@@ -0,0 +1,53 @@
+use base64::Engine;
+use md5::{Digest, Md5};
+
+// CRC-32C (Castagnoli), reflected, as Google Cloud Storage computes it
+const CRC32C_POLY: u32 = 0x82f6_3b78;
+
+static CRC32C_TABLE: once_cell::sync::Lazy<[u32; 256]> = once_cell::sync::Lazy::new(|| {
+    let mut table = [0u32; 256];
+    for (i, slot) in table.iter_mut().enumerate() {
+        let mut crc = i as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ CRC32C_POLY } else { crc >> 1 };
+        }
+        *slot = crc;
+    }
+    table
+});
+
+// What the storage providers report for an uploaded object, to compare with the local file
+#[derive(Debug, Clone)]
+pub struct CloudDigests {
+    // Azure Block Blob Content-MD5 and GCS md5Hash: base64 of the MD5
+    pub content_md5: String,
+    // GCS crc32c: base64 of the big-endian CRC-32C
+    pub crc32c: String,
+}
+
+// Both values in the same read pass as the SHA-256
+pub struct CloudHasher {
+    md5: Md5,
+    crc: u32,
+}
+
+impl Default for CloudHasher {
+    fn default() -> Self {
+        CloudHasher { md5: Md5::new(), crc: !0 }
+    }
+}
+
+impl CloudHasher {
+    pub fn update(&mut self, data: &[u8]) {
+        self.md5.update(data);
+        let table = &*CRC32C_TABLE;
+        for &b in data {
+            self.crc = table[((self.crc ^ b as u32) & 0xff) as usize] ^ (self.crc >> 8);
+        }
+    }
+
+    pub fn finish(self) -> CloudDigests {
+        let b64 = base64::engine::general_purpose::STANDARD;
+        CloudDigests { content_md5: b64.encode(self.md5.finalize()), crc32c: b64.encode((!self.crc).to_be_bytes()) }
+    }
+}
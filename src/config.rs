@@ -0,0 +1,214 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::email::EmailSettings;
+use crate::post_actions::PostActions;
+use crate::{segments, storage};
+
+const CONFIG_FILE: &str = "config.json";
+// Settings export placed next to the executable preconfigures a fresh install
+pub const DEPLOY_FILE: &str = "rust-hash.settings.json";
+const EXPORT_FORMAT: &str = "rust-hash-settings";
+const EXPORT_VERSION: u32 = 1;
+
+// Hashing options from the main window, restored on start
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    pub uppercase: bool,
+    // Hex digests shown in blocks of this many characters; 0 for none
+    pub hex_grouping: usize,
+    pub auto_hash: bool,
+    pub quick: bool,
+    pub chunks: bool,
+    pub signature: bool,
+    pub follow: bool,
+    pub zero_aware: bool,
+    pub forensic: bool,
+    pub binary_diff: bool,
+    pub archive_check: bool,
+    pub archive_digest: bool,
+    pub disc_contents: bool,
+    pub partitions: bool,
+    // Tree digests also cover extended attributes (Linux, macOS) or alternate data streams (Windows)
+    pub tree_xattrs: bool,
+    // Restore the digests last used in a file's folder when one is chosen
+    pub folder_choices: bool,
+    pub payload: bool,
+    pub segments: bool,
+    pub segment_size: u64,
+    pub ipfs_cid: bool,
+    pub cloud_checksums: bool,
+    pub authenticode: bool,
+    // Metadata digest and its fields, as `name,size,mtime,permissions`
+    pub metadata_digest: bool,
+    pub metadata_fields: String,
+    // How verify matches list entries written on another OS
+    pub match_separators: bool,
+    pub match_ignore_case: bool,
+    pub match_unicode: bool,
+    // Queue: files that cannot be opened are skipped and listed, not counted as failures
+    pub skip_unopenable: bool,
+    // Background jobs are cancelled as TIMED OUT after this many minutes; 0 for never
+    pub job_timeout_minutes: u64,
+    // Verify ends at the first entry that does not match
+    pub verify_stop_on_failure: bool,
+    // Save results asks for a folder and writes paths relative to it instead of in full
+    pub relative_results: bool,
+    // `standard`, `high-contrast` or `color-blind-safe`
+    pub palette: String,
+    // `normal`, `battery` (fewer updates on battery) or `reduced`
+    pub update_rate: String,
+    // Size of each read while hashing, in KiB
+    pub read_buffer_kib: u64,
+    // Hashes kept in the history; 0 keeps none
+    pub history_size: usize,
+    // Webhooks, SMTP email and hashing over SSH; off keeps everything on this machine
+    pub network: bool,
+    // Digest the window shows alongside SHA-256: sha256 (none), sha512, sha1, md5 or blake3
+    pub algorithm: String,
+    // Counts of operations, algorithms and errors in usage.json, shown on the Usage page and never sent
+    pub usage_metrics: bool,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Preferences {
+            uppercase: false,
+            hex_grouping: 0,
+            auto_hash: true,
+            quick: false,
+            chunks: false,
+            signature: false,
+            follow: false,
+            zero_aware: false,
+            forensic: false,
+            binary_diff: false,
+            archive_check: false,
+            archive_digest: false,
+            disc_contents: false,
+            partitions: false,
+            tree_xattrs: false,
+            folder_choices: false,
+            payload: false,
+            segments: false,
+            segment_size: segments::DEFAULT_SEGMENT_SIZE,
+            ipfs_cid: false,
+            cloud_checksums: false,
+            authenticode: false,
+            metadata_digest: false,
+            metadata_fields: "name,size,mtime,permissions".to_string(),
+            match_separators: true,
+            match_ignore_case: false,
+            match_unicode: true,
+            skip_unopenable: false,
+            job_timeout_minutes: 0,
+            verify_stop_on_failure: false,
+            relative_results: false,
+            palette: "standard".to_string(),
+            update_rate: "battery".to_string(),
+            read_buffer_kib: 2048,
+            history_size: 500,
+            network: true,
+            algorithm: "sha256".to_string(),
+            usage_metrics: false,
+        }
+    }
+}
+
+// User preferences persisted between runs; unknown or missing fields fall back to defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub preferences: Preferences,
+    pub post_actions: PostActions,
+    // Receives a JSON POST when a job or the queue finishes; empty = off
+    pub webhook_url: String,
+    pub email: EmailSettings,
+    // Known-hash set and blocklist loaded on start; empty = none
+    pub known_hashes: String,
+    pub blocklist: String,
+    // Branding for PDF reports
+    pub report_header: String,
+    pub report_logo: String,
+}
+
+impl Config {
+    // Missing or unreadable config is treated as defaults so the app always starts.
+    // Without a saved config, a deployment file next to the executable is used instead.
+    pub fn load() -> Self {
+        let path = config_path();
+        if let Some(data) = path.as_ref().and_then(|p| fs::read(p).ok()) {
+            return serde_json::from_slice(&data).unwrap_or_default();
+        }
+        deploy_path().and_then(|p| Config::import_from(&p).ok()).unwrap_or_default()
+    }
+
+    // One self-describing file with everything, for copying a setup to other machines. The SMTP login
+    // stays behind, since exports get mailed around and checked in
+    pub fn export_to(&self, target: &Path) -> Result<()> {
+        let mut config = self.clone();
+        config.email.username.clear();
+        config.email.password.clear();
+        let file = ExportFile { format: EXPORT_FORMAT.to_string(), version: EXPORT_VERSION, config };
+        let data = serde_json::to_vec_pretty(&file)?;
+        fs::write(target, data).with_context(|| format!("Failed to write settings export: {}", target.display()))
+    }
+
+    pub fn import_from(source: &Path) -> Result<Config> {
+        let data = fs::read(source).with_context(|| format!("Failed to read settings export: {}", source.display()))?;
+        let file: ExportFile = serde_json::from_slice(&data).with_context(|| format!("Not a settings export: {}", source.display()))?;
+        if file.format != EXPORT_FORMAT {
+            return Err(anyhow!("Not a rust-hash settings export: {}", source.display()));
+        }
+        if file.version > EXPORT_VERSION {
+            return Err(anyhow!("Settings export version {} is newer than this build supports", file.version));
+        }
+        Ok(file.config)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = config_path().context("No data directory available for settings")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+        }
+        let data = serde_json::to_vec_pretty(self)?;
+        fs::write(&path, data).with_context(|| format!("Failed to write settings: {}", path.display()))?;
+        Ok(())
+    }
+
+    // Webhook to post to; empty, which posts nothing, while network features are off
+    pub fn webhook(&self) -> &str {
+        if self.preferences.network && !crate::policy::get().offline {
+            &self.webhook_url
+        } else {
+            ""
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportFile {
+    format: String,
+    version: u32,
+    config: Config,
+}
+
+// Neither saved settings nor a deployment file: the first-run questions are asked
+pub fn is_first_run() -> bool {
+    let saved = config_path().is_some_and(|p| p.exists());
+    let deployed = deploy_path().is_some_and(|p| p.exists());
+    !saved && !deployed
+}
+
+fn deploy_path() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    Some(exe.parent()?.join(DEPLOY_FILE))
+}
+
+fn config_path() -> Option<PathBuf> {
+    storage::data_dir().map(|d| d.join(CONFIG_FILE))
+}
@@ -0,0 +1,370 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+
+use crate::tiger::TreeHasher;
+
+// Torrent clients use between 16 KiB and 16 MiB per piece and aim for a couple of thousand pieces
+const MIN_PIECE: u64 = 16 * 1024;
+const MAX_PIECE: u64 = 16 * 1024 * 1024;
+const TARGET_PIECES: u64 = 2000;
+
+// `ipfs add --cid-version=1` defaults: 256 KiB chunks stored as raw leaves, balanced DAG of at most 174 links per node
+const CID_CHUNK: usize = 256 * 1024;
+const CID_LINKS: usize = 174;
+const RAW: u8 = 0x55;
+const DAG_PB: u8 = 0x70;
+const SHA2_256: u8 = 0x12;
+
+// Multibase encodings IPFS and libp2p tools print multihashes in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multibase {
+    Base32,
+    Base58Btc,
+}
+
+impl Multibase {
+    pub fn parse(name: &str) -> Option<Multibase> {
+        match name.to_ascii_lowercase().as_str() {
+            "base32" | "b" => Some(Multibase::Base32),
+            "base58btc" | "base58" | "z" => Some(Multibase::Base58Btc),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Multibase::Base32 => "base32",
+            Multibase::Base58Btc => "base58btc",
+        }
+    }
+
+    // With the one-letter multibase prefix, so tools can tell the encoding
+    pub fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            Multibase::Base32 => format!("b{}", base32(bytes).to_lowercase()),
+            Multibase::Base58Btc => format!("z{}", base58(bytes)),
+        }
+    }
+}
+
+// `code` (see Algorithm::multihash_code) and length in front of the digest; None for a malformed hex digest
+pub fn multihash(code: u64, hex_digest: &str) -> Option<Vec<u8>> {
+    let digest = hex::decode(hex_digest).ok()?;
+    let mut out = Vec::new();
+    varint(&mut out, code);
+    varint(&mut out, digest.len() as u64);
+    out.extend_from_slice(&digest);
+    Some(out)
+}
+
+// RFC 6920 named-information URI for a SHA-256 digest, e.g. `ni:///sha-256;f4OxZX_x...`
+pub fn ni_uri(sha256_hex: &str) -> Option<String> {
+    let bytes = hex::decode(sha256_hex).ok().filter(|b| b.len() == 32)?;
+    Some(format!("ni:///sha-256;{}", base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)))
+}
+
+// A block of the DAG as its parent links to it
+struct Link {
+    cid: Vec<u8>,
+    // File bytes below it
+    file_size: u64,
+    // Encoded size of the block and everything below it
+    tsize: u64,
+}
+
+// Works out the IPFS CIDv1 a file gets when added with default settings, in the same read pass as the digest.
+// Memory stays small: only one chunk and the unfinished nodes of each level are kept
+pub struct CidBuilder {
+    chunk: Vec<u8>,
+    // Links waiting for their parent node; index 0 holds leaves
+    levels: Vec<Vec<Link>>,
+}
+
+impl Default for CidBuilder {
+    fn default() -> Self {
+        CidBuilder { chunk: Vec::with_capacity(CID_CHUNK), levels: vec![Vec::new()] }
+    }
+}
+
+impl CidBuilder {
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let take = (CID_CHUNK - self.chunk.len()).min(data.len());
+            self.chunk.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.chunk.len() == CID_CHUNK {
+                self.flush_chunk();
+            }
+        }
+    }
+
+    // `bafy...` for files of more than one chunk, `bafk...` (a single raw block) otherwise
+    pub fn finish(mut self) -> String {
+        // An empty file is one empty raw block
+        if !self.chunk.is_empty() || (self.levels.len() == 1 && self.levels[0].is_empty()) {
+            self.flush_chunk();
+        }
+        // Full nodes were built as they filled up; what is left is the partly filled right edge of the tree
+        let mut level = 0;
+        loop {
+            let top = level + 1 == self.levels.len();
+            let mut links = std::mem::take(&mut self.levels[level]);
+            if top && links.len() == 1 {
+                return cid_string(&links.remove(0).cid);
+            }
+            if !links.is_empty() {
+                let parent = node(links);
+                if top {
+                    return cid_string(&parent.cid);
+                }
+                self.push(level + 1, parent);
+            }
+            level += 1;
+        }
+    }
+
+    fn flush_chunk(&mut self) {
+        let size = self.chunk.len() as u64;
+        let leaf = Link { cid: cid_bytes(RAW, &Sha256::digest(&self.chunk)), file_size: size, tsize: size };
+        self.chunk.clear();
+        self.push(0, leaf);
+    }
+
+    fn push(&mut self, level: usize, link: Link) {
+        if self.levels.len() == level {
+            self.levels.push(Vec::new());
+        }
+        self.levels[level].push(link);
+        if self.levels[level].len() == CID_LINKS {
+            let links = std::mem::take(&mut self.levels[level]);
+            self.push(level + 1, node(links));
+        }
+    }
+}
+
+// dag-pb node holding a UnixFS file entry over `links`, encoded the way the IPFS implementations do:
+// links first, each with an empty name, then the UnixFS data (type, file size, size below each link)
+fn node(links: Vec<Link>) -> Link {
+    let file_size = links.iter().map(|l| l.file_size).sum();
+    let mut unixfs = vec![0x08, 2];
+    unixfs.push(0x18);
+    varint(&mut unixfs, file_size);
+    for link in &links {
+        unixfs.push(0x20);
+        varint(&mut unixfs, link.file_size);
+    }
+    let mut block = Vec::new();
+    for link in &links {
+        let mut pb = vec![0x0a];
+        varint(&mut pb, link.cid.len() as u64);
+        pb.extend_from_slice(&link.cid);
+        pb.extend_from_slice(&[0x12, 0]);
+        pb.push(0x18);
+        varint(&mut pb, link.tsize);
+        block.push(0x12);
+        varint(&mut block, pb.len() as u64);
+        block.extend_from_slice(&pb);
+    }
+    block.push(0x0a);
+    varint(&mut block, unixfs.len() as u64);
+    block.extend_from_slice(&unixfs);
+    let tsize = block.len() as u64 + links.iter().map(|l| l.tsize).sum::<u64>();
+    Link { cid: cid_bytes(DAG_PB, &Sha256::digest(&block)), file_size, tsize }
+}
+
+// CIDv1: version, content codec, then the multihash of the block
+fn cid_bytes(codec: u8, sha256: &[u8]) -> Vec<u8> {
+    let mut cid = vec![1, codec, SHA2_256, sha256.len() as u8];
+    cid.extend_from_slice(sha256);
+    cid
+}
+
+// Multibase base32, the default text form of CIDv1
+fn cid_string(cid: &[u8]) -> String {
+    Multibase::Base32.encode(cid)
+}
+
+// Unsigned LEB128, as protobuf and the multiformats use
+fn varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push((n as u8 & 0x7f) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+// Magnet link with the BitTorrent v1 info hash of a single-file torrent, the SHA-1 (as urn:sha1, the
+// Gnutella/DC++ form), the Tiger tree hash (urn:tree:tiger, what DC++ and Shareaza search by), size and
+// name. The info hash depends on the piece size, so it only matches a torrent made with the same one;
+// the piece size chosen here is the power of two most clients pick. A policy that limits the
+// algorithms leaves the Tiger tree hash out
+pub fn magnet(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+    let size = file.metadata().with_context(|| format!("Failed to read metadata: {}", path.display()))?.len();
+    let piece_length = piece_length(size);
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut whole = Sha1::new();
+    let mut tree = crate::policy::get().refusal_of("Tiger").is_none().then(TreeHasher::default);
+    let mut pieces = Vec::new();
+    let mut piece = vec![0u8; piece_length as usize];
+    loop {
+        let filled = fill(&mut file, &mut piece).with_context(|| format!("Failed to read file: {}", path.display()))?;
+        if filled == 0 {
+            break;
+        }
+        whole.update(&piece[..filled]);
+        if let Some(tree) = &mut tree {
+            tree.update(&piece[..filled]);
+        }
+        pieces.extend_from_slice(&Sha1::digest(&piece[..filled]));
+        if filled < piece.len() {
+            break;
+        }
+    }
+    // Bencoded info dictionary, keys in sorted order as the format requires
+    let mut info = format!("d6:lengthi{}e4:name{}:{}12:piece lengthi{}e6:pieces{}:", size, name.len(), name, piece_length, pieces.len()).into_bytes();
+    info.extend_from_slice(&pieces);
+    info.push(b'e');
+    let btih = hex::encode(Sha1::digest(&info));
+    let sha1 = base32(&whole.finalize());
+    let tth = tree.map(|t| format!("&xt=urn:tree:tiger:{}", base32(&t.finish()))).unwrap_or_default();
+    Ok(format!("magnet:?xt=urn:btih:{}&xt=urn:sha1:{}{}&xl={}&dn={}", btih, sha1, tth, size, percent_encode(&name)))
+}
+
+fn piece_length(size: u64) -> u64 {
+    let mut length = MIN_PIECE;
+    while size / length > TARGET_PIECES && length < MAX_PIECE {
+        length *= 2;
+    }
+    length
+}
+
+// Reads until `buf` is full or the file ends
+fn fill(file: &mut File, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match file.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+// RFC 4648 base32 without padding, as magnet links write SHA-1 and the Tiger tree hash
+pub fn base32(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+    let mut out = String::new();
+    let (mut buffer, mut bits) = (0u32, 0);
+    for &b in bytes {
+        buffer = (buffer << 8) | b as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+// Bitcoin alphabet; each leading zero byte becomes a '1'
+fn base58(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    let zeros = bytes.iter().take_while(|b| **b == 0).count();
+    // Base-58 digits, least significant first
+    let mut digits: Vec<u8> = Vec::new();
+    for &b in &bytes[zeros..] {
+        let mut carry = b as u32;
+        for d in digits.iter_mut() {
+            carry += (*d as u32) << 8;
+            *d = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let mut out = "1".repeat(zeros);
+    out.extend(digits.iter().rev().map(|d| ALPHABET[*d as usize] as char));
+    out
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    fn cid(data: &[u8]) -> String {
+        let mut builder = CidBuilder::default();
+        // Odd read sizes, like the hashing loop hands over
+        for piece in data.chunks(100_000) {
+            builder.update(piece);
+        }
+        builder.finish()
+    }
+
+    #[test]
+    fn ni_uri_is_url_safe_base64() {
+        assert_eq!(ni_uri(EMPTY_SHA256).unwrap(), "ni:///sha-256;47DEQpj8HBSa-_TImW-5JCeuQeRkm5NMpJWZG3hSuFU");
+        assert_eq!(ni_uri("abcd"), None);
+    }
+
+    #[test]
+    fn base32_has_no_padding() {
+        assert_eq!(base32(b"f"), "MY");
+        assert_eq!(base32(b"foobar"), "MZXW6YTBOI");
+    }
+
+    #[test]
+    fn single_chunk_is_a_raw_block() {
+        assert_eq!(cid(b""), "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku");
+        let chunk = vec![7u8; CID_CHUNK];
+        assert_eq!(cid(&chunk), cid_string(&cid_bytes(RAW, &Sha256::digest(&chunk))));
+    }
+
+    #[test]
+    fn larger_files_get_a_dag_pb_root() {
+        let data = vec![7u8; CID_CHUNK + 1];
+        let root = cid(&data);
+        assert!(root.starts_with("bafybei"), "{}", root);
+        let leaves = vec![
+            Link { cid: cid_bytes(RAW, &Sha256::digest(&data[..CID_CHUNK])), file_size: CID_CHUNK as u64, tsize: CID_CHUNK as u64 },
+            Link { cid: cid_bytes(RAW, &Sha256::digest(&data[CID_CHUNK..])), file_size: 1, tsize: 1 },
+        ];
+        assert_eq!(root, cid_string(&node(leaves).cid));
+    }
+
+    #[test]
+    fn multihash_and_multibase() {
+        let multihash = multihash(0x12, EMPTY_SHA256).unwrap();
+        assert_eq!(&multihash[..2], &[0x12, 0x20]);
+        assert_eq!(Multibase::Base58Btc.encode(&multihash), "zQmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n");
+        assert_eq!(Multibase::Base32.encode(&multihash), "bciqohmgeikmpyhautl57jsezn64sij5oihsgjg4tjssjlgi3pbjlqvi");
+        // Codes above 127 take two varint bytes
+        assert_eq!(&super::multihash(0xb220, EMPTY_SHA256).unwrap()[..3], &[0xa0, 0xe4, 0x02]);
+        assert_eq!(super::multihash(0x12, "not hex"), None);
+        assert_eq!(base58(&[0, 0, 1]), "112");
+    }
+}
@@ -0,0 +1,217 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+use anyhow::{anyhow, Context, Result};
+use sha2::{Digest, Sha256};
+
+const BUFFER_SIZE: usize = 2 * 1024 * 1024;
+
+// One copied file: the digest taken while copying, and the one read back from the destination
+#[derive(Debug, Clone)]
+pub struct CopiedFile {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    pub bytes: u64,
+    pub source_hex: Option<String>,
+    pub target_hex: Option<String>,
+    pub error: Option<String>,
+}
+
+impl CopiedFile {
+    pub fn verified(&self) -> bool {
+        self.error.is_none() && self.source_hex.is_some() && self.source_hex == self.target_hex
+    }
+
+    pub fn mismatch(&self) -> bool {
+        self.error.is_none() && self.source_hex.is_some() && self.target_hex.is_some() && self.source_hex != self.target_hex
+    }
+}
+
+// Files to copy with their destinations and the total size (for progress). A folder is
+// recreated under `dest_dir` with its own name; symlinks are not followed.
+pub fn plan(source: &Path, dest_dir: &Path) -> Result<(Vec<(PathBuf, PathBuf)>, u64)> {
+    let name = source.file_name().with_context(|| format!("No file name: {}", source.display()))?;
+    let mut pairs = Vec::new();
+    let meta = fs::symlink_metadata(source).with_context(|| format!("Failed to read metadata: {}", source.display()))?;
+    if meta.is_dir() {
+        collect(source, &dest_dir.join(name), &mut pairs)?;
+    } else if meta.is_file() {
+        pairs.push((source.to_path_buf(), dest_dir.join(name)));
+    } else {
+        return Err(anyhow!("Not a regular file or folder: {}", source.display()));
+    }
+    let total = pairs.iter().filter_map(|(s, _)| fs::metadata(s).ok()).map(|m| m.len()).sum();
+    Ok((pairs, total))
+}
+
+fn collect(dir: &Path, target: &Path, pairs: &mut Vec<(PathBuf, PathBuf)>) -> Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to list folder: {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .collect();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let Ok(ft) = entry.file_type() else { continue };
+        let path = entry.path();
+        if ft.is_dir() {
+            collect(&path, &target.join(entry.file_name()), pairs)?;
+        } else if ft.is_file() {
+            pairs.push((path, target.join(entry.file_name())));
+        }
+    }
+    Ok(())
+}
+
+// Copies one file, hashing the source in the same pass, then reads the destination back and hashes it.
+// Existing destination files are never overwritten.
+pub fn copy_one(source: &Path, target: &Path, progress: &AtomicU64, cancel: &AtomicBool) -> CopiedFile {
+    let mut item = CopiedFile {
+        source: source.to_path_buf(),
+        target: target.to_path_buf(),
+        bytes: 0,
+        source_hex: None,
+        target_hex: None,
+        error: None,
+    };
+    match copy_hashed(source, target, progress, cancel) {
+        Ok((hex, bytes)) => {
+            item.source_hex = Some(hex);
+            item.bytes = bytes;
+        }
+        Err(e) => {
+            item.error = Some(format!("{:#}", e));
+            return item;
+        }
+    }
+    match hash_file(target, cancel) {
+        Ok(hex) => item.target_hex = Some(hex),
+        Err(e) => item.error = Some(format!("Read-back failed: {:#}", e)),
+    }
+    item
+}
+
+fn copy_hashed(source: &Path, target: &Path, progress: &AtomicU64, cancel: &AtomicBool) -> Result<(String, u64)> {
+    let mut input = File::open(source).with_context(|| format!("Failed to open file: {}", source.display()))?;
+    if let Some(dir) = target.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+    }
+    let mut output = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(target)
+        .with_context(|| format!("Failed to create {} (it may already exist)", target.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut total = 0u64;
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            drop(output);
+            let _ = fs::remove_file(target);
+            return Err(anyhow!(crate::errors::CANCELLED));
+        }
+        let n = input.read(&mut buffer).with_context(|| format!("Failed to read {}", source.display()))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+        output.write_all(&buffer[..n]).with_context(|| format!("Failed to write {}", target.display()))?;
+        total += n as u64;
+        progress.fetch_add(n as u64, Ordering::Relaxed);
+    }
+    // Make sure the read-back sees what reached the disk, not only what the writer buffered
+    output.sync_all().with_context(|| format!("Failed to flush {}", target.display()))?;
+    Ok((hex::encode(hasher.finalize()), total))
+}
+
+fn hash_file(path: &Path, cancel: &AtomicBool) -> Result<String> {
+    let mut input = File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(anyhow!(crate::errors::CANCELLED));
+        }
+        let n = input.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+// A verified copy running on a worker thread; finished files arrive one by one
+pub struct CopyRun {
+    pub source: PathBuf,
+    pub dest: PathBuf,
+    pub total: u64,
+    pub files: usize,
+    pub done: Vec<CopiedFile>,
+    progress: Arc<AtomicU64>,
+    cancel: Arc<AtomicBool>,
+    rx: Option<Receiver<CopiedFile>>,
+}
+
+impl CopyRun {
+    pub fn start(source: PathBuf, dest: PathBuf) -> Result<CopyRun> {
+        let (pairs, total) = plan(&source, &dest)?;
+        let progress = Arc::new(AtomicU64::new(0));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        let files = pairs.len();
+        let (p, c) = (progress.clone(), cancel.clone());
+        thread::spawn(move || {
+            for (from, to) in pairs {
+                if c.load(Ordering::Relaxed) {
+                    break;
+                }
+                if tx.send(copy_one(&from, &to, &p, &c)).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(CopyRun { source, dest, total, files, done: Vec::new(), progress, cancel, rx: Some(rx) })
+    }
+
+    // Collects finished files; true the one time the run ends
+    pub fn poll(&mut self) -> bool {
+        let Some(rx) = &self.rx else {
+            return false;
+        };
+        loop {
+            match rx.try_recv() {
+                Ok(item) => self.done.push(item),
+                Err(TryRecvError::Empty) => return false,
+                Err(TryRecvError::Disconnected) => {
+                    self.rx = None;
+                    return true;
+                }
+            }
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.rx.is_some()
+    }
+
+    pub fn processed(&self) -> u64 {
+        self.progress.load(Ordering::Relaxed)
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn mismatches(&self) -> usize {
+        self.done.iter().filter(|f| f.mismatch()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.done.iter().filter(|f| f.error.is_some()).count()
+    }
+}
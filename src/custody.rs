@@ -0,0 +1,275 @@
+use std::fmt::Write as _;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write as _};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::storage;
+
+const LOG_FILE: &str = "custody.log";
+// The end of the log is read back this much at a time when appending
+const TAIL_BLOCK: u64 = 4096;
+// `prev` of the first entry
+const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// One line of the chain-of-custody log. `hash` covers every other field, including `prev`,
+// so editing, removing or reordering entries breaks the chain from that point on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub seq: u64,
+    // RFC 3339 local time
+    pub time: String,
+    pub user: String,
+    pub host: String,
+    // "hash", "hash-failed", "hash-cancelled", "forensic-on", "forensic-off" or "report-export"
+    pub action: String,
+    pub path: Option<String>,
+    pub sha256: Option<String>,
+    pub bytes: Option<u64>,
+    pub detail: Option<String>,
+    pub prev: String,
+    pub hash: String,
+}
+
+// What is being recorded; who, when and the chain fields are filled in by `append`
+#[derive(Debug, Default)]
+pub struct Record<'a> {
+    pub action: &'a str,
+    pub path: Option<&'a Path>,
+    pub sha256: Option<&'a str>,
+    pub bytes: Option<u64>,
+    pub detail: Option<&'a str>,
+}
+
+// Outcome of walking the chain from the first entry
+pub enum ChainStatus {
+    Intact { head: String },
+    Broken { line: usize, reason: String },
+}
+
+pub fn log_path() -> Option<PathBuf> {
+    storage::data_dir().map(|d| d.join(LOG_FILE))
+}
+
+// Appends one entry; the file is only ever opened for appending
+pub fn append(record: Record) -> Result<()> {
+    let path = log_path().context("No data directory available for the custody log")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+    }
+    let (seq, prev) = match last_entry(&path)? {
+        Some(Ok(last)) => (last.seq + 1, last.hash.clone()),
+        Some(Err(_)) => return Err(anyhow!("Custody log is damaged; not appending to {}", path.display())),
+        None => (1, GENESIS.to_string()),
+    };
+    let mut entry = Entry {
+        seq,
+        time: Local::now().to_rfc3339(),
+        user: current_user(),
+        host: host_name(),
+        action: record.action.to_string(),
+        path: record.path.map(|p| p.display().to_string()),
+        sha256: record.sha256.map(str::to_string),
+        bytes: record.bytes,
+        detail: record.detail.map(str::to_string),
+        prev,
+        hash: String::new(),
+    };
+    entry.hash = entry_hash(&entry)?;
+    let mut line = serde_json::to_string(&entry)?;
+    line.push('\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open custody log: {}", path.display()))?;
+    file.write_all(line.as_bytes()).with_context(|| format!("Failed to append to custody log: {}", path.display()))
+}
+
+pub fn verify(entries: &[Result<Entry, String>]) -> ChainStatus {
+    let mut prev = GENESIS.to_string();
+    for (i, entry) in entries.iter().enumerate() {
+        let line = i + 1;
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => return ChainStatus::Broken { line, reason: format!("unreadable entry ({})", e) },
+        };
+        if entry.seq != line as u64 {
+            return ChainStatus::Broken { line, reason: format!("sequence number {} where {} was expected", entry.seq, line) };
+        }
+        if entry.prev != prev {
+            return ChainStatus::Broken { line, reason: "does not link to the previous entry".to_string() };
+        }
+        match entry_hash(entry) {
+            Ok(hash) if hash == entry.hash => prev = hash,
+            _ => return ChainStatus::Broken { line, reason: "contents do not match the entry hash".to_string() },
+        }
+    }
+    ChainStatus::Intact { head: prev }
+}
+
+// Writes a readable report of the whole log and records the export in the log itself.
+// The inner result is the detached GnuPG signature, or why the report could not be signed.
+pub fn export_report(target: &Path) -> Result<Result<PathBuf>> {
+    let path = log_path().context("No data directory available for the custody log")?;
+    let entries = read_entries(&path)?;
+    let status = verify(&entries);
+
+    let mut out = String::new();
+    let _ = writeln!(out, "rust-hash chain-of-custody report");
+    let _ = writeln!(out, "Generated: {} by {}@{}", Local::now().to_rfc3339(), current_user(), host_name());
+    let _ = writeln!(out, "Log: {}", path.display());
+    let _ = writeln!(out, "Entries: {}", entries.len());
+    match &status {
+        ChainStatus::Intact { head } => {
+            let _ = writeln!(out, "Chain: intact, head {}", head);
+        }
+        ChainStatus::Broken { line, reason } => {
+            let _ = writeln!(out, "Chain: BROKEN at entry {}: {}", line, reason);
+        }
+    }
+    for entry in entries.iter().flatten() {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "#{} {} {}@{} {}", entry.seq, entry.time, entry.user, entry.host, entry.action);
+        if let Some(p) = &entry.path {
+            let _ = writeln!(out, "    File:    {}", p);
+        }
+        if let Some(hex) = &entry.sha256 {
+            let _ = writeln!(out, "    SHA-256: {}", hex);
+        }
+        if let Some(bytes) = entry.bytes {
+            let _ = writeln!(out, "    Bytes:   {}", bytes);
+        }
+        if let Some(detail) = &entry.detail {
+            let _ = writeln!(out, "    Detail:  {}", detail);
+        }
+        let _ = writeln!(out, "    Entry:   {}", entry.hash);
+    }
+    fs::write(target, out).with_context(|| format!("Failed to write custody report: {}", target.display()))?;
+    append(Record { action: "report-export", path: Some(target), ..Record::default() })?;
+    Ok(sign(target))
+}
+
+// Detached ASCII-armored signature with the user's default GnuPG key
+fn sign(report: &Path) -> Result<PathBuf> {
+    let mut signature = report.as_os_str().to_owned();
+    signature.push(".asc");
+    let signature = PathBuf::from(signature);
+    let status = Command::new("gpg")
+        .args(["--batch", "--yes", "--armor", "--detach-sign", "--output"])
+        .arg(&signature)
+        .arg(report)
+        .status()
+        .context("GnuPG (gpg) is not available")?;
+    if !status.success() {
+        return Err(anyhow!("gpg exited with {}", status));
+    }
+    Ok(signature)
+}
+
+fn read_entries(path: &Path) -> Result<Vec<Result<Entry, String>>> {
+    let data = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read custody log: {}", path.display())),
+    };
+    Ok(data
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| serde_json::from_str(l).map_err(|e| e.to_string()))
+        .collect())
+}
+
+// Only the end of the file is read, so appending stays quick however long the log grows
+fn last_entry(path: &Path) -> Result<Option<Result<Entry, String>>> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read custody log: {}", path.display())),
+    };
+    let mut end = file.metadata().with_context(|| format!("Failed to read custody log: {}", path.display()))?.len();
+    let mut tail = Vec::new();
+    // Back one block at a time until a line break comes before the last line's text
+    while end > 0 {
+        let start = end.saturating_sub(TAIL_BLOCK);
+        let mut block = vec![0; (end - start) as usize];
+        file.seek(SeekFrom::Start(start))
+            .and_then(|_| file.read_exact(&mut block))
+            .with_context(|| format!("Failed to read custody log: {}", path.display()))?;
+        block.extend_from_slice(&tail);
+        tail = block;
+        end = start;
+        if tail.iter().rposition(|b| !b.is_ascii_whitespace()).is_some_and(|last| tail[..last].contains(&b'\n')) {
+            break;
+        }
+    }
+    let text = String::from_utf8_lossy(&tail);
+    Ok(text.lines().rev().find(|l| !l.trim().is_empty()).map(|l| serde_json::from_str(l).map_err(|e| e.to_string())))
+}
+
+fn entry_hash(entry: &Entry) -> Result<String> {
+    let unsealed = Entry { hash: String::new(), ..entry.clone() };
+    let data = serde_json::to_vec(&unsealed)?;
+    Ok(hex::encode(Sha256::digest(&data)))
+}
+
+fn current_user() -> String {
+    std::env::var("USERNAME").or_else(|_| std::env::var("USER")).unwrap_or_else(|_| "unknown".to_string())
+}
+
+fn host_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok().map(|h| h.trim().to_string()))
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(seq: u64, detail: &str) -> Entry {
+        let mut entry = Entry {
+            seq,
+            time: String::new(),
+            user: "u".to_string(),
+            host: "h".to_string(),
+            action: "hash".to_string(),
+            path: None,
+            sha256: None,
+            bytes: None,
+            detail: Some(detail.to_string()),
+            prev: GENESIS.to_string(),
+            hash: String::new(),
+        };
+        entry.hash = entry_hash(&entry).unwrap();
+        entry
+    }
+
+    // Lines longer than a block and trailing blank lines, as `read_entries` would see them
+    #[test]
+    fn the_last_entry_comes_from_the_end_of_the_file() {
+        let path = std::env::temp_dir().join(format!("rust-hash-custody-test-{}", std::process::id()));
+        let long = "x".repeat(TAIL_BLOCK as usize * 2);
+        let lines: Vec<String> = [entry(1, "short"), entry(2, &long), entry(3, "é")].iter().map(|e| serde_json::to_string(e).unwrap()).collect();
+        let mut found = Vec::new();
+        for text in [lines[..2].join("\n"), format!("{}\n\n \n", lines.join("\n")), lines[0].clone()] {
+            fs::write(&path, &text).unwrap();
+            let whole = read_entries(&path).unwrap().last().map(|e| e.as_ref().unwrap().seq);
+            found.push((last_entry(&path).unwrap().map(|e| e.unwrap().seq), whole));
+        }
+        fs::write(&path, format!("{}\n{{broken\n", lines[0])).unwrap();
+        let broken = last_entry(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(found, [(Some(2), Some(2)), (Some(3), Some(3)), (Some(1), Some(1))]);
+        assert!(matches!(broken, Some(Err(_))));
+        assert!(last_entry(&path).unwrap().is_none());
+    }
+}
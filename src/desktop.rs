@@ -0,0 +1,157 @@
+use std::path::Path;
+
+#[cfg(not(windows))]
+use anyhow::Context;
+use anyhow::Result;
+use iced::window::raw_window_handle::WindowHandle;
+
+// Hands a path or URI (mailto:, https:) to the desktop's default handler. ShellExecute passes it on
+// untouched; `cmd /C start` would cut a mailto: at the first `&` and expand `%VAR%` in encoded text
+#[cfg(windows)]
+pub fn open(target: &str) -> Result<()> {
+    use windows_sys::Win32::UI::Shell::ShellExecuteW;
+    use windows_sys::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+    let wide = |s: &str| s.encode_utf16().chain(std::iter::once(0)).collect::<Vec<u16>>();
+    let (verb, file) = (wide("open"), wide(target));
+    let result = unsafe { ShellExecuteW(std::ptr::null_mut(), verb.as_ptr(), file.as_ptr(), std::ptr::null(), std::ptr::null(), SW_SHOWNORMAL) };
+    // Above 32 is success; anything else is an error code
+    match result as isize {
+        code if code > 32 => Ok(()),
+        code => Err(anyhow::anyhow!("Failed to open {} (error {})", target, code)),
+    }
+}
+
+#[cfg(not(windows))]
+pub fn open(target: &str) -> Result<()> {
+    use std::process::Command;
+
+    #[cfg(target_os = "macos")]
+    let mut cmd = {
+        let mut c = Command::new("open");
+        c.arg(target);
+        c
+    };
+    #[cfg(not(target_os = "macos"))]
+    let mut cmd = {
+        let mut c = Command::new("xdg-open");
+        c.arg(target);
+        c
+    };
+    cmd.spawn().with_context(|| format!("Failed to open {}", target))?;
+    Ok(())
+}
+
+// Hands a generated file to other apps. On Windows it is dragged out of the window while the mouse
+// button stays down; a click, or a drop back onto the window, puts it on the clipboard the way
+// Explorer's Copy does instead. The GUI toolkit offers no drag source on macOS and Linux, so there
+// its folder is opened and the file is dragged from the file manager. Returns a note for the user.
+// Runs from `window::run_with_handle`, on the event loop's thread rather than inside `update`, since
+// the drag loop is modal.
+#[cfg(windows)]
+pub fn share_file(path: &Path, window: &WindowHandle<'_>) -> Result<String> {
+    use iced::window::raw_window_handle::RawWindowHandle;
+
+    let RawWindowHandle::Win32(window) = window.as_raw() else {
+        return Err(anyhow::anyhow!("Failed to drag {}: no window", path.display()));
+    };
+    if drag_file(path, window.hwnd.get() as _)? {
+        return Ok(format!("{} dropped", path.display()));
+    }
+    copy_file(path)?;
+    Ok(format!("{} copied as a file; paste it into a folder, email or chat, or drag the button", path.display()))
+}
+
+#[cfg(not(windows))]
+pub fn share_file(path: &Path, _window: &WindowHandle<'_>) -> Result<String> {
+    let dir = path.parent().context("File has no parent folder")?;
+    open(&dir.display().to_string())?;
+    Ok(format!("{} is shown in the file manager; drag it from there", path.display()))
+}
+
+// Runs the shell's drag loop with `path` until the mouse button is released. Must be called on the
+// thread of `window` while the button is down. True when it was dropped on another window; a drop on
+// this one arrives as an ordinary dropped file, which the caller has to ignore
+#[cfg(windows)]
+fn drag_file(path: &Path, window: windows_sys::Win32::Foundation::HWND) -> Result<bool> {
+    use std::ffi::c_void;
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::core::GUID;
+    use windows_sys::Win32::Foundation::{DRAGDROP_S_DROP, POINT};
+    use windows_sys::Win32::System::Ole::{OleInitialize, OleUninitialize, DROPEFFECT_COPY, DROPEFFECT_NONE};
+    use windows_sys::Win32::UI::Shell::{ILClone, ILCreateFromPathW, ILFindLastID, ILFree, ILRemoveLastID, SHCreateDataObject, SHDoDragDrop};
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetAncestor, GetCursorPos, WindowFromPoint, GA_ROOT};
+
+    const IID_IDATAOBJECT: GUID = GUID::from_u128(0x0000010e_0000_0000_c000_000000000046);
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    unsafe {
+        let item = ILCreateFromPathW(wide.as_ptr());
+        if item.is_null() {
+            return Err(anyhow::anyhow!("Failed to drag {}", path.display()));
+        }
+        let folder = ILClone(item);
+        ILRemoveLastID(folder);
+        let child = ILFindLastID(item) as *const _;
+        let mut data: *mut c_void = std::ptr::null_mut();
+        let created = SHCreateDataObject(folder, 1, &child, std::ptr::null_mut(), &IID_IDATAOBJECT, &mut data);
+        ILFree(folder);
+        ILFree(item);
+        if created < 0 || data.is_null() {
+            return Err(anyhow::anyhow!("Failed to drag {} (error {:#x})", path.display(), created));
+        }
+        // The window set OLE up already; this only balances the count
+        let ole = OleInitialize(std::ptr::null());
+        let mut effect = DROPEFFECT_NONE;
+        let result = SHDoDragDrop(window, data, std::ptr::null_mut(), DROPEFFECT_COPY, &mut effect);
+        if ole >= 0 {
+            OleUninitialize();
+        }
+        // IUnknown::Release, the third entry of the data object's vtable
+        let vtable = *(data as *const *const unsafe extern "system" fn(*mut c_void) -> u32);
+        (*vtable.add(2))(data);
+        let mut cursor = POINT { x: 0, y: 0 };
+        let over_self = GetCursorPos(&mut cursor) != 0 && GetAncestor(WindowFromPoint(cursor), GA_ROOT) == window;
+        Ok(result == DRAGDROP_S_DROP && effect != DROPEFFECT_NONE && !over_self)
+    }
+}
+
+#[cfg(windows)]
+fn copy_file(path: &Path) -> Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Foundation::GlobalFree;
+    use windows_sys::Win32::System::DataExchange::{CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData};
+    use windows_sys::Win32::System::Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE, GMEM_ZEROINIT};
+    use windows_sys::Win32::System::Ole::CF_HDROP;
+    use windows_sys::Win32::UI::Shell::DROPFILES;
+
+    // DROPFILES header followed by a double-NUL terminated list of wide paths
+    let mut list: Vec<u16> = path.as_os_str().encode_wide().collect();
+    list.extend([0, 0]);
+    let header = std::mem::size_of::<DROPFILES>();
+    let size = header + list.len() * 2;
+    unsafe {
+        let mem = GlobalAlloc(GMEM_MOVEABLE | GMEM_ZEROINIT, size);
+        if mem.is_null() {
+            return Err(anyhow::anyhow!("Out of memory for the clipboard"));
+        }
+        let ptr = GlobalLock(mem) as *mut u8;
+        let drop_files = ptr as *mut DROPFILES;
+        (*drop_files).pFiles = header as u32;
+        (*drop_files).fWide = 1;
+        std::ptr::copy_nonoverlapping(list.as_ptr() as *const u8, ptr.add(header), list.len() * 2);
+        GlobalUnlock(mem);
+        if OpenClipboard(std::ptr::null_mut()) == 0 {
+            GlobalFree(mem);
+            return Err(anyhow::anyhow!("The clipboard is in use by another application"));
+        }
+        EmptyClipboard();
+        // On success the clipboard owns the memory
+        let placed = !SetClipboardData(CF_HDROP as u32, mem).is_null();
+        CloseClipboard();
+        if !placed {
+            GlobalFree(mem);
+            return Err(anyhow::anyhow!("Failed to put the file on the clipboard"));
+        }
+    }
+    Ok(())
+}
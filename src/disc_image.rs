@@ -0,0 +1,542 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+
+use anyhow::{anyhow, bail, Context, Result};
+use sha2::{Digest, Sha256};
+
+const SECTOR: u64 = 2048;
+const BUFFER_SIZE: usize = 1024 * 1024;
+// Guards against directory loops in damaged images
+const MAX_DEPTH: usize = 64;
+
+// A run of file data: bytes in the image, or zeros (unrecorded/sparse UDF extents)
+#[derive(Debug, Clone, Copy)]
+struct Extent {
+    offset: Option<u64>,
+    len: u64,
+}
+
+struct Node {
+    path: String,
+    extents: Vec<Extent>,
+}
+
+// A file inside the image with the SHA-256 of its content
+#[derive(Debug, Clone)]
+pub struct ImageFile {
+    // Relative, '/'-separated
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Contents {
+    pub filesystem: &'static str,
+    pub files: Vec<ImageFile>,
+}
+
+impl Contents {
+    pub fn total_bytes(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+
+    // sha256sum-style manifest of the contained files, usable with "Verify list/log..."
+    pub fn manifest(&self) -> String {
+        self.files.iter().map(|f| crate::post_actions::sha256sum_line(&f.sha256, &f.path)).collect()
+    }
+}
+
+pub fn is_disc_image(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()).is_some_and(|e| matches!(e.to_ascii_lowercase().as_str(), "iso" | "udf"))
+}
+
+// Hashes every file stored in an ISO 9660 (with Joliet names) or UDF image. UDF is preferred
+// when both are present, since it carries full names and files over 4 GiB.
+pub fn hash_contents(path: &Path, progress: &AtomicU64, cancel: &AtomicBool) -> Result<Contents> {
+    let file = File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+    let mut image = Image { len: file.metadata()?.len(), file };
+    let (filesystem, nodes) = match udf::list(&mut image) {
+        Ok(Some(nodes)) => ("UDF", nodes),
+        udf_result => match iso9660::list(&mut image)? {
+            Some(listing) => listing,
+            None => {
+                return Err(match udf_result {
+                    Err(e) => e.context("Unreadable UDF file system"),
+                    _ => anyhow!("No ISO 9660 or UDF file system found in {}", path.display()),
+                })
+            }
+        },
+    };
+    let mut files = Vec::with_capacity(nodes.len());
+    let mut done = 0u64;
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    for node in nodes {
+        let mut hasher = Sha256::new();
+        let mut size = 0u64;
+        for extent in &node.extents {
+            let mut left = extent.len;
+            if let Some(offset) = extent.offset {
+                image.file.seek(SeekFrom::Start(offset))?;
+            }
+            while left > 0 {
+                if cancel.load(Ordering::Relaxed) {
+                    bail!(crate::errors::CANCELLED);
+                }
+                let n = left.min(BUFFER_SIZE as u64) as usize;
+                match extent.offset {
+                    Some(_) => image.file.read_exact(&mut buffer[..n]).with_context(|| format!("Image ends inside {}", node.path))?,
+                    None => buffer[..n].fill(0),
+                }
+                hasher.update(&buffer[..n]);
+                left -= n as u64;
+                size += n as u64;
+                done += n as u64;
+                progress.store(done, Ordering::Relaxed);
+            }
+        }
+        files.push(ImageFile { path: node.path, size, sha256: hex::encode(hasher.finalize()) });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(Contents { filesystem, files })
+}
+
+struct Image {
+    file: File,
+    len: u64,
+}
+
+impl Image {
+    fn read(&mut self, offset: u64, len: usize) -> Result<Vec<u8>> {
+        if offset.saturating_add(len as u64) > self.len {
+            bail!("Structure at offset {} lies beyond the end of the image", offset);
+        }
+        let mut buf = vec![0u8; len];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn read_extents(&mut self, extents: &[Extent]) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        for extent in extents {
+            let len = usize::try_from(extent.len)?;
+            match extent.offset {
+                Some(offset) => out.extend(self.read(offset, len)?),
+                None => out.resize(out.len() + len, 0),
+            }
+        }
+        Ok(out)
+    }
+}
+
+fn u16le(b: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([b[at], b[at + 1]])
+}
+
+fn u32le(b: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(b[at..at + 4].try_into().unwrap())
+}
+
+fn join(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", parent, name)
+    }
+}
+
+mod iso9660 {
+    use super::*;
+
+    // Files of the Joliet tree when there is one, else of the primary (8.3-style) tree
+    pub(super) fn list(image: &mut Image) -> Result<Option<(&'static str, Vec<Node>)>> {
+        let mut primary = None;
+        let mut joliet = None;
+        for sector in 16..64 {
+            let Ok(vd) = image.read(sector * SECTOR, SECTOR as usize) else { break };
+            if &vd[1..6] != b"CD001" {
+                break;
+            }
+            match vd[0] {
+                1 => primary = Some(vd[156..190].to_vec()),
+                // Joliet: UCS-2 escape sequence in a supplementary descriptor
+                2 if vd[88] == b'%' && vd[89] == b'/' && matches!(vd[90], b'@' | b'C' | b'E') => joliet = Some(vd[156..190].to_vec()),
+                255 => break,
+                _ => {}
+            }
+        }
+        let (name, root, ucs2) = match (joliet, primary) {
+            (Some(root), _) => ("ISO 9660 (Joliet)", root, true),
+            (None, Some(root)) => ("ISO 9660", root, false),
+            (None, None) => return Ok(None),
+        };
+        let mut nodes = Vec::new();
+        let mut seen = HashSet::new();
+        walk(image, &root, "", ucs2, 0, &mut seen, &mut nodes)?;
+        Ok(Some((name, nodes)))
+    }
+
+    fn walk(image: &mut Image, dir: &[u8], path: &str, ucs2: bool, depth: usize, seen: &mut HashSet<u32>, out: &mut Vec<Node>) -> Result<()> {
+        let lba = u32le(dir, 2);
+        if depth > MAX_DEPTH || !seen.insert(lba) {
+            return Ok(());
+        }
+        let data = image.read(lba as u64 * SECTOR, u32le(dir, 10) as usize)?;
+        let mut pos = 0;
+        // Files over 4 GiB are split into several records with the multi-extent flag on all but the last
+        let mut continues = false;
+        while pos < data.len() {
+            let len = data[pos] as usize;
+            if len == 0 {
+                // Records never cross a sector; the rest of this one is padding
+                pos = (pos / SECTOR as usize + 1) * SECTOR as usize;
+                continue;
+            }
+            if len < 34 || pos + len > data.len() {
+                bail!("Damaged directory record in {}", if path.is_empty() { "/" } else { path });
+            }
+            let record = &data[pos..pos + len];
+            pos += len;
+            let name_len = record[32] as usize;
+            let raw = &record[33..(33 + name_len).min(len)];
+            // "\0" and "\1" are the directory itself and its parent
+            if name_len == 1 && raw[0] <= 1 {
+                continue;
+            }
+            let name = decode_name(raw, ucs2);
+            let flags = record[25];
+            let extent = Extent { offset: Some(u32le(record, 2) as u64 * SECTOR), len: u32le(record, 10) as u64 };
+            if flags & 0x02 != 0 {
+                walk(image, record, &join(path, &name), ucs2, depth + 1, seen, out)?;
+                continue;
+            }
+            let full = join(path, &name);
+            match out.last_mut() {
+                Some(last) if continues && last.path == full => last.extents.push(extent),
+                _ => out.push(Node { path: full, extents: vec![extent] }),
+            }
+            continues = flags & 0x80 != 0;
+        }
+        Ok(())
+    }
+
+    fn decode_name(raw: &[u8], ucs2: bool) -> String {
+        let name = if ucs2 {
+            let units: Vec<u16> = raw.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+            String::from_utf16_lossy(&units)
+        } else {
+            String::from_utf8_lossy(raw).into_owned()
+        };
+        // "NAME.EXT;1": drop the version, and the dot of names without an extension
+        let name = name.split(';').next().unwrap_or("").to_string();
+        name.strip_suffix('.').map(str::to_string).unwrap_or(name)
+    }
+}
+
+mod udf {
+    use super::*;
+
+    const TAG_AVDP: u16 = 2;
+    const TAG_PARTITION: u16 = 5;
+    const TAG_LOGICAL_VOLUME: u16 = 6;
+    const TAG_TERMINATOR: u16 = 8;
+    const TAG_FILE_SET: u16 = 256;
+    const TAG_FILE_ID: u16 = 257;
+    const TAG_FILE_ENTRY: u16 = 261;
+    const TAG_EXT_FILE_ENTRY: u16 = 266;
+    const TYPE_DIRECTORY: u8 = 4;
+
+    struct Volume {
+        block: u64,
+        // Start sector of each partition map's physical partition
+        partitions: Vec<Option<u64>>,
+    }
+
+    impl Volume {
+        fn offset(&self, partition: u16, lbn: u32) -> Result<u64> {
+            let start = self.partitions.get(partition as usize).copied().flatten().ok_or_else(|| {
+                anyhow!("UDF partition type not supported (virtual, sparable or metadata partitions, as on BD and UDF 2.5+)")
+            })?;
+            Ok(start * SECTOR + lbn as u64 * self.block)
+        }
+    }
+
+    // None when the image has no UDF anchor at sector 256
+    pub(super) fn list(image: &mut Image) -> Result<Option<Vec<Node>>> {
+        let Ok(anchor) = image.read(256 * SECTOR, 512) else { return Ok(None) };
+        if u16le(&anchor, 0) != TAG_AVDP {
+            return Ok(None);
+        }
+        let (vds_len, vds_loc) = (u32le(&anchor, 16) as u64, u32le(&anchor, 20) as u64);
+        let mut partition_starts = Vec::new();
+        let mut lvd = None;
+        for sector in vds_loc..vds_loc + vds_len.div_ceil(SECTOR).min(64) {
+            let d = image.read(sector * SECTOR, SECTOR as usize)?;
+            match u16le(&d, 0) {
+                TAG_PARTITION => partition_starts.push((u16le(&d, 22), u32le(&d, 188) as u64)),
+                TAG_LOGICAL_VOLUME => lvd = Some(d),
+                TAG_TERMINATOR => break,
+                _ => {}
+            }
+        }
+        let lvd = lvd.context("UDF logical volume descriptor missing")?;
+        let block = u32le(&lvd, 212) as u64;
+        // Map each partition reference to a physical partition; other map types stay unsupported
+        let mut partitions = Vec::new();
+        let mut pos = 440;
+        for _ in 0..u32le(&lvd, 268) {
+            let (kind, len) = (lvd[pos], lvd[pos + 1] as usize);
+            let start = (kind == 1).then(|| u16le(&lvd, pos + 4)).and_then(|n| partition_starts.iter().find(|(p, _)| *p == n)).map(|(_, s)| *s);
+            partitions.push(start);
+            pos += len.max(6);
+            if pos + 6 > lvd.len() {
+                break;
+            }
+        }
+        let volume = Volume { block: if block == 0 { SECTOR } else { block }, partitions };
+        // File set descriptor location from the logical volume contents use (long_ad)
+        let fsd = image.read(volume.offset(u16le(&lvd, 256), u32le(&lvd, 252))?, 512)?;
+        if u16le(&fsd, 0) != TAG_FILE_SET {
+            bail!("UDF file set descriptor missing");
+        }
+        let root = (u16le(&fsd, 408), u32le(&fsd, 404));
+        let mut nodes = Vec::new();
+        let mut seen = HashSet::new();
+        walk(image, &volume, root, "", 0, &mut seen, &mut nodes)?;
+        Ok(Some(nodes))
+    }
+
+    fn walk(image: &mut Image, volume: &Volume, icb: (u16, u32), path: &str, depth: usize, seen: &mut HashSet<u64>, out: &mut Vec<Node>) -> Result<()> {
+        let at = volume.offset(icb.0, icb.1)?;
+        if depth > MAX_DEPTH || !seen.insert(at) {
+            return Ok(());
+        }
+        let (_, extents) = file_entry(image, volume, icb)?;
+        let data = image.read_extents(&extents)?;
+        let mut pos = 0;
+        while pos + 38 <= data.len() {
+            if u16le(&data, pos) != TAG_FILE_ID {
+                bail!("Damaged UDF directory {}", if path.is_empty() { "/" } else { path });
+            }
+            let characteristics = data[pos + 18];
+            let name_len = data[pos + 19] as usize;
+            let child = (u16le(&data, pos + 28), u32le(&data, pos + 24));
+            let iu_len = u16le(&data, pos + 36) as usize;
+            let name_at = pos + 38 + iu_len;
+            let raw = data.get(name_at..name_at + name_len).unwrap_or(&[]);
+            pos += (38 + iu_len + name_len).div_ceil(4) * 4;
+            // Parent entries and deleted files are not part of the content
+            if characteristics & 0x08 != 0 || characteristics & 0x04 != 0 {
+                continue;
+            }
+            let full = join(path, &decode_name(raw));
+            if characteristics & 0x02 != 0 {
+                walk(image, volume, child, &full, depth + 1, seen, out)?;
+            } else {
+                let (file_type, extents) = file_entry(image, volume, child)?;
+                if file_type == TYPE_DIRECTORY {
+                    walk(image, volume, child, &full, depth + 1, seen, out)?;
+                } else {
+                    out.push(Node { path: full, extents });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // File type and data extents of a (extended) file entry
+    fn file_entry(image: &mut Image, volume: &Volume, icb: (u16, u32)) -> Result<(u8, Vec<Extent>)> {
+        let at = volume.offset(icb.0, icb.1)?;
+        let fe = image.read(at, volume.block as usize)?;
+        let (ea_at, ad_at) = match u16le(&fe, 0) {
+            TAG_FILE_ENTRY => (168, 176),
+            TAG_EXT_FILE_ENTRY => (208, 216),
+            tag => bail!("Expected a UDF file entry, found tag {}", tag),
+        };
+        let file_type = fe[27];
+        let info_len = u64::from_le_bytes(fe[56..64].try_into().unwrap());
+        let start = ad_at + u32le(&fe, ea_at) as usize;
+        let ad_len = u32le(&fe, ea_at + 4) as usize;
+        let ads = fe.get(start..start + ad_len).context("UDF allocation descriptors overflow the file entry")?;
+        let mut extents = Vec::new();
+        match u16le(&fe, 34) & 0x07 {
+            // Data embedded in the entry itself
+            3 => extents.push(Extent { offset: Some(at + start as u64), len: (ad_len as u64).min(info_len) }),
+            kind @ (0 | 1) => {
+                let step = if kind == 0 { 8 } else { 16 };
+                for ad in ads.chunks_exact(step) {
+                    let raw_len = u32le(ad, 0);
+                    let (len, ext_type) = ((raw_len & 0x3FFF_FFFF) as u64, raw_len >> 30);
+                    if len == 0 {
+                        break;
+                    }
+                    if ext_type == 3 {
+                        bail!("UDF allocation extent chains are not supported");
+                    }
+                    let partition = if kind == 0 { icb.0 } else { u16le(ad, 8) };
+                    let offset = if ext_type == 0 { Some(volume.offset(partition, u32le(ad, 4))?) } else { None };
+                    extents.push(Extent { offset, len });
+                }
+            }
+            _ => bail!("UDF extended allocation descriptors are not supported"),
+        }
+        // The last extent is rounded up to whole blocks; the information length is exact
+        let mut left = info_len;
+        for extent in &mut extents {
+            extent.len = extent.len.min(left);
+            left -= extent.len;
+        }
+        extents.retain(|e| e.len > 0);
+        Ok((file_type, extents))
+    }
+
+    // OSTA compressed Unicode: 8 = one byte per character, 16 = UCS-2 big-endian
+    fn decode_name(raw: &[u8]) -> String {
+        match raw.split_first() {
+            Some((16, rest)) => String::from_utf16_lossy(&rest.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect::<Vec<_>>()),
+            Some((_, rest)) => rest.iter().map(|&b| b as char).collect(),
+            None => String::new(),
+        }
+    }
+}
+
+// Hashing of an image's contents on a worker thread
+pub struct ContentsRun {
+    pub image: PathBuf,
+    pub result: Option<Result<Contents, String>>,
+    progress: Arc<AtomicU64>,
+    cancel: Arc<AtomicBool>,
+    rx: Option<Receiver<Result<Contents, String>>>,
+}
+
+impl ContentsRun {
+    pub fn start(image: PathBuf) -> ContentsRun {
+        let progress = Arc::new(AtomicU64::new(0));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        let (path, p, c) = (image.clone(), progress.clone(), cancel.clone());
+        thread::spawn(move || {
+            let _ = tx.send(hash_contents(&path, &p, &c).map_err(|e| format!("{:#}", e)));
+        });
+        ContentsRun { image, result: None, progress, cancel, rx: Some(rx) }
+    }
+
+    pub fn poll(&mut self) {
+        if let Some(result) = self.rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.result = Some(result);
+            self.rx = None;
+        }
+    }
+
+    pub fn processed(&self) -> u64 {
+        self.progress.load(Ordering::Relaxed)
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn record(lba: u32, len: u32, flags: u8, name: &[u8]) -> Vec<u8> {
+        let mut r = vec![0u8; 33 + name.len() + (name.len() + 1) % 2];
+        r[0] = r.len() as u8;
+        r[2..6].copy_from_slice(&lba.to_le_bytes());
+        r[10..14].copy_from_slice(&len.to_le_bytes());
+        r[25] = flags;
+        r[32] = name.len() as u8;
+        r[33..33 + name.len()].copy_from_slice(name);
+        r
+    }
+
+    fn directory(lba: u32, records: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = [record(lba, SECTOR as u32, 2, &[0]), record(lba, SECTOR as u32, 2, &[1])].concat();
+        for r in records {
+            data.extend_from_slice(r);
+        }
+        data.resize(SECTOR as usize, 0);
+        data
+    }
+
+    fn ucs2(name: &str) -> Vec<u8> {
+        name.encode_utf16().flat_map(u16::to_be_bytes).collect()
+    }
+
+    // A primary tree with 8.3 names and, when `joliet`, a Joliet tree with long names over the same data.
+    // The Joliet tree also splits one file over two extents
+    fn image(joliet: bool) -> Vec<u8> {
+        let mut sectors = vec![vec![0u8; SECTOR as usize]; 30];
+        let descriptor = |kind: u8, root: u32| {
+            let mut vd = vec![0u8; SECTOR as usize];
+            vd[0] = kind;
+            vd[1..6].copy_from_slice(b"CD001");
+            vd[156..190].copy_from_slice(&record(root, SECTOR as u32, 2, &[0]));
+            vd
+        };
+        sectors[16] = descriptor(1, 20);
+        let mut next = 17;
+        if joliet {
+            sectors[17] = descriptor(2, 22);
+            sectors[17][88..91].copy_from_slice(b"%/E");
+            next = 18;
+        }
+        sectors[next] = descriptor(255, 0);
+        sectors[next][156..190].fill(0);
+        sectors[20] = directory(20, &[record(24, 5, 0, b"README.TXT;1"), record(21, SECTOR as u32, 2, b"DOCS")]);
+        sectors[21] = directory(21, &[record(25, 3, 0, b"A.;1")]);
+        sectors[22] = directory(22, &[record(24, 5, 0, &ucs2("Read me.txt;1")), record(23, SECTOR as u32, 2, &ucs2("Docs"))]);
+        sectors[23] = directory(23, &[record(25, 3, 0, &ucs2("a")), record(26, SECTOR as u32, 0x80, &ucs2("split.bin")), record(27, 10, 0, &ucs2("split.bin"))]);
+        sectors[24][..5].copy_from_slice(b"hello");
+        sectors[25][..3].copy_from_slice(b"abc");
+        sectors[26].fill(b'x');
+        sectors[27][..10].copy_from_slice(b"0123456789");
+        sectors.concat()
+    }
+
+    fn contents(data: &[u8]) -> Contents {
+        let path = std::env::temp_dir().join(format!("rust-hash-iso-test-{}-{}.iso", std::process::id(), data.len()));
+        fs::write(&path, data).unwrap();
+        let contents = hash_contents(&path, &AtomicU64::new(0), &AtomicBool::new(false));
+        let _ = fs::remove_file(&path);
+        contents.unwrap()
+    }
+
+    fn listed(contents: &Contents) -> Vec<(&str, u64, String)> {
+        contents.files.iter().map(|f| (f.path.as_str(), f.size, f.sha256.clone())).collect()
+    }
+
+    fn sha256(data: &[u8]) -> String {
+        hex::encode(Sha256::digest(data))
+    }
+
+    #[test]
+    fn primary_tree_names_lose_their_version() {
+        let contents = contents(&image(false));
+        assert_eq!(contents.filesystem, "ISO 9660");
+        assert_eq!(listed(&contents), vec![("DOCS/A", 3, sha256(b"abc")), ("README.TXT", 5, sha256(b"hello"))]);
+    }
+
+    #[test]
+    fn joliet_names_win_and_extents_join() {
+        let mut split = vec![b'x'; SECTOR as usize];
+        split.extend_from_slice(b"0123456789");
+        let contents = contents(&image(true));
+        assert_eq!(contents.filesystem, "ISO 9660 (Joliet)");
+        assert_eq!(
+            listed(&contents),
+            vec![("Docs/a", 3, sha256(b"abc")), ("Docs/split.bin", SECTOR + 10, sha256(&split)), ("Read me.txt", 5, sha256(b"hello"))]
+        );
+        assert_eq!(contents.total_bytes(), SECTOR + 18);
+    }
+}
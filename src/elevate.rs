@@ -0,0 +1,103 @@
+use std::fs;
+use std::path::Path;
+#[cfg(windows)]
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+// First argument that turns the executable into the elevated hashing helper: `--elevated-hash <file> <reply>`
+pub const HELPER_FLAG: &str = "--elevated-hash";
+
+// What the helper writes back to the unelevated instance
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HelperReply {
+    pub hex: Option<String>,
+    pub base64: Option<String>,
+    #[serde(default)]
+    pub bytes: u64,
+    pub error: Option<String>,
+}
+
+pub fn write_reply(target: &Path, reply: &HelperReply) -> Result<()> {
+    let json = serde_json::to_string(reply)?;
+    fs::write(target, json).with_context(|| format!("Failed to write helper reply: {}", target.display()))
+}
+
+#[cfg(windows)]
+fn read_reply(target: &Path) -> Result<HelperReply> {
+    let data = fs::read_to_string(target).with_context(|| format!("Failed to read helper reply: {}", target.display()))?;
+    Ok(serde_json::from_str(&data)?)
+}
+
+// Hashes `path` in an elevated copy of this executable (UAC prompt) and waits for its reply.
+// The reply travels through a file in the scratch directory, which both sides can reach.
+#[cfg(windows)]
+pub fn hash_elevated(path: &Path, cancel: &AtomicBool) -> Result<(String, String, u64)> {
+    use std::os::windows::ffi::OsStrExt;
+    use std::sync::atomic::Ordering;
+    use windows_sys::Win32::Foundation::{CloseHandle, WAIT_TIMEOUT};
+    use windows_sys::Win32::System::Threading::{TerminateProcess, WaitForSingleObject};
+    use windows_sys::Win32::UI::Shell::{ShellExecuteExW, SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW};
+    use windows_sys::Win32::UI::WindowsAndMessaging::SW_HIDE;
+
+    fn wide(s: &std::ffi::OsStr) -> Vec<u16> {
+        s.encode_wide().chain(std::iter::once(0)).collect()
+    }
+
+    let exe = std::env::current_exe()?;
+    let reply_path = reply_path();
+    let verb = wide("runas".as_ref());
+    let file = wide(exe.as_os_str());
+    // Windows paths cannot contain quotes, so plain quoting is enough
+    let params = wide(format!("{} \"{}\" \"{}\"", HELPER_FLAG, path.display(), reply_path.display()).as_ref());
+
+    let mut info: SHELLEXECUTEINFOW = unsafe { std::mem::zeroed() };
+    info.cbSize = std::mem::size_of::<SHELLEXECUTEINFOW>() as u32;
+    info.fMask = SEE_MASK_NOCLOSEPROCESS;
+    info.lpVerb = verb.as_ptr();
+    info.lpFile = file.as_ptr();
+    info.lpParameters = params.as_ptr();
+    info.nShow = SW_HIDE;
+    if unsafe { ShellExecuteExW(&mut info) } == 0 || info.hProcess.is_null() {
+        return Err(anyhow!("Could not start the elevated helper (the prompt may have been declined)"));
+    }
+
+    // Poll so Cancel can still stop the helper
+    let cancelled = loop {
+        if cancel.load(Ordering::Relaxed) {
+            unsafe { TerminateProcess(info.hProcess, 1) };
+            break true;
+        }
+        if unsafe { WaitForSingleObject(info.hProcess, 200) } != WAIT_TIMEOUT {
+            break false;
+        }
+    };
+    unsafe { CloseHandle(info.hProcess) };
+
+    let reply = read_reply(&reply_path);
+    let _ = fs::remove_file(&reply_path);
+    if cancelled {
+        return Err(anyhow!(crate::errors::CANCELLED));
+    }
+    let reply = reply.context("The elevated helper exited without a result")?;
+    match (reply.hex, reply.base64, reply.error) {
+        (_, _, Some(err)) => Err(anyhow!("Elevated helper: {}", err)),
+        (Some(hex), Some(base64), None) => Ok((hex, base64, reply.bytes)),
+        _ => Err(anyhow!("The elevated helper returned an incomplete result")),
+    }
+}
+
+#[cfg(not(windows))]
+pub fn hash_elevated(_path: &Path, _cancel: &AtomicBool) -> Result<(String, String, u64)> {
+    Err(anyhow!("Running elevated is only supported on Windows"))
+}
+
+#[cfg(windows)]
+fn reply_path() -> PathBuf {
+    let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+    let dir = crate::storage::scratch_dir();
+    let _ = fs::create_dir_all(&dir);
+    dir.join(format!("rust-hash-elevated-{}-{}.json", std::process::id(), nanos))
+}
@@ -0,0 +1,122 @@
+use std::fmt::Write as _;
+use std::sync::mpsc::Sender;
+use std::thread;
+
+use anyhow::{anyhow, Context, Result};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message as Email, SmtpTransport, Transport};
+use serde::{Deserialize, Serialize};
+
+use crate::desktop;
+
+// Queue report by email, either sent directly over SMTP or drafted in the mail app via mailto:
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmailSettings {
+    pub enabled: bool,
+    pub failures_only: bool,
+    // Draft in the default mail app instead of sending over SMTP
+    pub use_mailto: bool,
+    pub smtp_server: String,
+    pub smtp_port: u16,
+    pub username: String,
+    // Held for this session only and never written to config.json; empty reads RUST_HASH_SMTP_PASSWORD
+    #[serde(skip)]
+    pub password: String,
+    pub from: String,
+    pub to: String,
+}
+
+impl Default for EmailSettings {
+    fn default() -> Self {
+        EmailSettings {
+            enabled: false,
+            failures_only: false,
+            use_mailto: false,
+            smtp_server: String::new(),
+            smtp_port: 587,
+            username: String::new(),
+            password: String::new(),
+            from: String::new(),
+            to: String::new(),
+        }
+    }
+}
+
+pub struct Report {
+    pub subject: String,
+    pub lines: Vec<String>,
+    pub failed: usize,
+}
+
+impl Report {
+    fn body(&self) -> String {
+        let mut body = String::new();
+        for line in &self.lines {
+            let _ = writeln!(body, "{}", line);
+        }
+        body
+    }
+}
+
+// Sends or drafts the report on a worker thread; failures are reported through `errors`
+pub fn send_report(settings: &EmailSettings, report: Report, errors: Sender<String>) {
+    if !settings.enabled || (settings.failures_only && report.failed == 0) {
+        return;
+    }
+    let settings = settings.clone();
+    thread::spawn(move || {
+        let sent = if settings.use_mailto { open_mailto(&settings, &report) } else { send_smtp(&settings, &report) };
+        if let Err(e) = sent {
+            let _ = errors.send(format!("Email report: {:#}", e));
+        }
+    });
+}
+
+fn send_smtp(settings: &EmailSettings, report: &Report) -> Result<()> {
+    if settings.smtp_server.trim().is_empty() || settings.to.trim().is_empty() {
+        return Err(anyhow!("SMTP server and recipient are required"));
+    }
+    let from = if settings.from.trim().is_empty() { settings.username.trim() } else { settings.from.trim() };
+    let email = Email::builder()
+        .from(from.parse().with_context(|| format!("Invalid sender address: {}", from))?)
+        .to(settings.to.trim().parse().with_context(|| format!("Invalid recipient address: {}", settings.to))?)
+        .subject(report.subject.clone())
+        .body(report.body())?;
+    let server = settings.smtp_server.trim();
+    // Port 465 speaks TLS from the first byte; everything else upgrades with STARTTLS
+    let builder = if settings.smtp_port == 465 { SmtpTransport::relay(server)? } else { SmtpTransport::starttls_relay(server)? };
+    let mut builder = builder.port(settings.smtp_port);
+    if !settings.username.trim().is_empty() {
+        let password = if settings.password.is_empty() {
+            std::env::var("RUST_HASH_SMTP_PASSWORD").unwrap_or_default()
+        } else {
+            settings.password.clone()
+        };
+        builder = builder.credentials(Credentials::new(settings.username.trim().to_string(), password));
+    }
+    builder.build().send(&email).with_context(|| format!("Failed to send via {}", server))?;
+    Ok(())
+}
+
+fn open_mailto(settings: &EmailSettings, report: &Report) -> Result<()> {
+    let uri = format!(
+        "mailto:{}?subject={}&body={}",
+        settings.to.trim(),
+        percent_encode(&report.subject),
+        percent_encode(&report.body())
+    );
+    desktop::open(&uri)
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-_.~".contains(&b) {
+            out.push(b as char);
+        } else {
+            let _ = write!(out, "%{:02X}", b);
+        }
+    }
+    out
+}
@@ -0,0 +1,198 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Sentinel the hashing workers use for a user-requested cancel
+pub const CANCELLED: &str = "CANCELLED";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    NotFound,
+    PermissionDenied,
+    // Another program holds the file open exclusively (an open Outlook PST, a running VM disk)
+    Locked,
+    DeviceRemoved,
+    PathTooLong,
+    IsDirectory,
+    SpecialFile,
+    Cancelled,
+    // Stopped by the job timeout; `detail` holds how long it was given
+    TimedOut,
+    Other,
+}
+
+// Follow-up the error view offers as a button
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorAction {
+    Retry,
+    Browse,
+    RunElevated,
+}
+
+impl ErrorAction {
+    pub fn label(self) -> &'static str {
+        match self {
+            ErrorAction::Retry => "Retry",
+            ErrorAction::Browse => "Browse...",
+            ErrorAction::RunElevated => "Retry elevated",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HashError {
+    pub kind: ErrorKind,
+    pub path: Option<PathBuf>,
+    // Full error chain, shown under the tailored message
+    pub detail: String,
+}
+
+impl HashError {
+    pub fn classify(err: &anyhow::Error, path: Option<&Path>) -> Self {
+        let detail = format!("{:#}", err);
+        let kind = if err.chain().any(|c| c.to_string() == CANCELLED) {
+            ErrorKind::Cancelled
+        } else if err.chain().any(|c| c.is::<crate::special::Unsupported>()) {
+            ErrorKind::SpecialFile
+        } else if path.is_some_and(|p| p.is_dir()) {
+            // Windows reports opening a directory as access denied, so check this before the io kind
+            ErrorKind::IsDirectory
+        } else {
+            err.chain()
+                .find_map(|c| c.downcast_ref::<io::Error>())
+                .map(classify_io)
+                .unwrap_or(ErrorKind::Other)
+        };
+        HashError { kind, path: path.map(Path::to_path_buf), detail }
+    }
+
+    pub fn cancelled() -> Self {
+        HashError { kind: ErrorKind::Cancelled, path: None, detail: CANCELLED.to_string() }
+    }
+
+    pub fn timed_out(path: &Path, after: String) -> Self {
+        HashError { kind: ErrorKind::TimedOut, path: Some(path.to_path_buf()), detail: after }
+    }
+
+    pub fn message(&self) -> String {
+        let path = self.path.as_ref().map(|p| p.display().to_string()).unwrap_or_else(|| "the file".to_string());
+        match self.kind {
+            ErrorKind::NotFound => format!("File not found: {}. Check the path or pick the file again.", path),
+            ErrorKind::PermissionDenied => format!(
+                "Permission denied: {}. The file may be locked by another program or need administrator rights.",
+                path
+            ),
+            ErrorKind::Locked => format!("{} is locked by another program. Close it and retry.", path),
+            ErrorKind::DeviceRemoved => format!(
+                "The device holding {} is no longer available (drive removed or network share disconnected). Reconnect it and retry.",
+                path
+            ),
+            ErrorKind::PathTooLong => format!("The path is too long for this system: {}. Move the file to a shorter path.", path),
+            ErrorKind::IsDirectory => format!("{} is a folder. Pick a file inside it.", path),
+            ErrorKind::SpecialFile => format!("{}. Pick a regular file.", self.detail),
+            ErrorKind::Cancelled => "Cancelled.".to_string(),
+            ErrorKind::TimedOut => format!(
+                "TIMED OUT after {}: {} was cancelled. A hung network share or failing drive can cause this.",
+                self.detail, path
+            ),
+            ErrorKind::Other => self.detail.clone(),
+        }
+    }
+
+    // The file could not be opened at all: gone, locked or not ours to read. Batches may skip these
+    pub fn is_unopenable(&self) -> bool {
+        matches!(self.kind, ErrorKind::NotFound | ErrorKind::PermissionDenied | ErrorKind::Locked)
+    }
+
+    // Short reason for skip lists
+    pub fn reason(&self) -> &'static str {
+        match self.kind {
+            ErrorKind::NotFound => "not found",
+            ErrorKind::PermissionDenied => "permission denied",
+            ErrorKind::Locked => "locked by another program",
+            ErrorKind::DeviceRemoved => "device removed",
+            ErrorKind::PathTooLong => "path too long",
+            ErrorKind::IsDirectory => "folder",
+            ErrorKind::SpecialFile => "special file",
+            ErrorKind::Cancelled => "cancelled",
+            ErrorKind::TimedOut => "timed out",
+            ErrorKind::Other => "error",
+        }
+    }
+
+    pub fn action(&self) -> Option<ErrorAction> {
+        match self.kind {
+            ErrorKind::NotFound | ErrorKind::PathTooLong | ErrorKind::IsDirectory | ErrorKind::SpecialFile => Some(ErrorAction::Browse),
+            ErrorKind::PermissionDenied if cfg!(windows) => Some(ErrorAction::RunElevated),
+            ErrorKind::PermissionDenied | ErrorKind::Locked | ErrorKind::DeviceRemoved | ErrorKind::TimedOut | ErrorKind::Other => {
+                Some(ErrorAction::Retry)
+            }
+            ErrorKind::Cancelled => None,
+        }
+    }
+}
+
+fn classify_io(e: &io::Error) -> ErrorKind {
+    match e.kind() {
+        io::ErrorKind::NotFound => return ErrorKind::NotFound,
+        io::ErrorKind::PermissionDenied => return ErrorKind::PermissionDenied,
+        io::ErrorKind::IsADirectory => return ErrorKind::IsDirectory,
+        io::ErrorKind::InvalidFilename => return ErrorKind::PathTooLong,
+        _ => {}
+    }
+    match e.raw_os_error() {
+        Some(code) if is_locked(code) => ErrorKind::Locked,
+        Some(code) if is_device_removed(code) => ErrorKind::DeviceRemoved,
+        Some(code) if is_path_too_long(code) => ErrorKind::PathTooLong,
+        _ => ErrorKind::Other,
+    }
+}
+
+#[cfg(windows)]
+fn is_locked(code: i32) -> bool {
+    // ERROR_SHARING_VIOLATION, ERROR_LOCK_VIOLATION
+    matches!(code, 32 | 33)
+}
+
+#[cfg(unix)]
+fn is_locked(code: i32) -> bool {
+    // Mandatory locks and files being executed or written elsewhere
+    matches!(code, libc::EBUSY | libc::ETXTBSY | libc::EAGAIN)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn is_locked(_code: i32) -> bool {
+    false
+}
+
+#[cfg(windows)]
+fn is_device_removed(code: i32) -> bool {
+    // ERROR_NOT_READY, ERROR_DEV_NOT_EXIST, ERROR_NETNAME_DELETED, ERROR_DEVICE_NOT_CONNECTED, ERROR_DEVICE_REMOVED
+    matches!(code, 21 | 55 | 64 | 1167 | 1617)
+}
+
+#[cfg(unix)]
+fn is_device_removed(code: i32) -> bool {
+    // ESTALE is what a vanished NFS export looks like
+    matches!(code, libc::EIO | libc::ENXIO | libc::ENODEV | libc::ESTALE)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn is_device_removed(_code: i32) -> bool {
+    false
+}
+
+#[cfg(windows)]
+fn is_path_too_long(code: i32) -> bool {
+    // ERROR_FILENAME_EXCED_RANGE
+    code == 206
+}
+
+#[cfg(unix)]
+fn is_path_too_long(code: i32) -> bool {
+    code == libc::ENAMETOOLONG
+}
+
+#[cfg(not(any(unix, windows)))]
+fn is_path_too_long(_code: i32) -> bool {
+    false
+}
@@ -0,0 +1,357 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+
+use anyhow::{anyhow, bail, Context, Result};
+use flate2::read::ZlibDecoder;
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::algo::Algorithm;
+
+const SIGNATURE: &[u8; 8] = b"EVF\x09\x0d\x0a\xff\x00";
+const EWF2_SIGNATURE: &[u8; 8] = b"EVF2\x0d\x0a\x81\x00";
+const FILE_HEADER_LEN: u64 = 13;
+const DESCRIPTOR_LEN: u64 = 76;
+
+pub fn is_ewf(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("e01"))
+}
+
+// Digests of the acquired media inside an E01 set, next to the ones stored at acquisition
+#[derive(Debug, Clone)]
+pub struct Logical {
+    pub segments: usize,
+    pub bytes: u64,
+    pub sha256: String,
+    // None when the policy rules the algorithm out; a stored hash of it then goes unchecked
+    pub md5: Option<String>,
+    pub sha1: Option<String>,
+    pub stored_md5: Option<String>,
+    pub stored_sha1: Option<String>,
+}
+
+impl Logical {
+    // None when the container holds no acquisition hash that could be compared
+    pub fn matches(&self) -> Option<bool> {
+        let pairs = [(&self.stored_md5, &self.md5), (&self.stored_sha1, &self.sha1)];
+        let compared: Vec<bool> = pairs.iter().filter_map(|(stored, computed)| Some(*stored.as_ref()? == *computed.as_ref()?)).collect();
+        (!compared.is_empty()).then(|| compared.iter().all(|m| *m))
+    }
+}
+
+// Where one chunk of media data lives
+struct Chunk {
+    segment: usize,
+    offset: u64,
+    // Stored length, including the Adler-32 that follows uncompressed chunks
+    stored: u64,
+    compressed: bool,
+}
+
+#[derive(Default)]
+struct Layout {
+    chunk_size: u64,
+    media_size: u64,
+    chunks: Vec<Chunk>,
+    stored_md5: Option<String>,
+    stored_sha1: Option<String>,
+}
+
+// E01, E02, ... E99, EAA, EAB, ... in the image's folder, until one is missing
+fn segment_paths(first: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![first.to_path_buf()];
+    let Some(ext) = first.extension().and_then(|e| e.to_str()).map(str::to_string) else {
+        return paths;
+    };
+    let lower = ext.starts_with('e');
+    for n in 2.. {
+        let suffix = if n < 100 {
+            format!("{:02}", n)
+        } else {
+            let i = n - 100;
+            if i >= 26 * 26 {
+                break;
+            }
+            let (a, b) = ((b'A' + (i / 26) as u8) as char, (b'A' + (i % 26) as u8) as char);
+            format!("{}{}", a, b)
+        };
+        let next = first.with_extension(format!("{}{}", if lower { 'e' } else { 'E' }, if lower { suffix.to_lowercase() } else { suffix }));
+        if !next.is_file() {
+            break;
+        }
+        paths.push(next);
+    }
+    paths
+}
+
+fn u32le(b: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(b[at..at + 4].try_into().unwrap())
+}
+
+fn u64le(b: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(b[at..at + 8].try_into().unwrap())
+}
+
+// Walks the section chain of every segment and collects the chunk table
+fn read_layout(files: &mut [File]) -> Result<Layout> {
+    let mut layout = Layout::default();
+    for (segment, file) in files.iter_mut().enumerate() {
+        let len = file.metadata()?.len();
+        let mut header = [0u8; 8];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut header)?;
+        if &header == EWF2_SIGNATURE {
+            bail!("Ex01 (EWF2) containers are not supported yet");
+        }
+        if &header != SIGNATURE {
+            bail!("Segment {} is not an EWF (E01) file", segment + 1);
+        }
+        let mut pos = FILE_HEADER_LEN;
+        // The media data of EnCase 2+ segments ends where its "sectors" section ends
+        let mut sectors_end = None;
+        while pos + DESCRIPTOR_LEN <= len {
+            let mut d = [0u8; DESCRIPTOR_LEN as usize];
+            file.seek(SeekFrom::Start(pos))?;
+            file.read_exact(&mut d)?;
+            let kind = String::from_utf8_lossy(&d[..16]).trim_end_matches('\0').to_string();
+            let (next, size) = (u64le(&d, 16), u64le(&d, 24));
+            let data_at = pos + DESCRIPTOR_LEN;
+            match kind.as_str() {
+                "volume" | "disk" if layout.chunk_size == 0 => {
+                    let v = read_at(file, data_at, 24)?;
+                    let (per_chunk, sector_size, sectors) = (u32le(&v, 8) as u64, u32le(&v, 12) as u64, u64le(&v, 16));
+                    layout.chunk_size = per_chunk * sector_size;
+                    layout.media_size = sectors * sector_size;
+                }
+                "sectors" => sectors_end = Some(pos + size),
+                "table" => {
+                    let head = read_at(file, data_at, 24)?;
+                    let (count, base) = (u32le(&head, 0) as usize, u64le(&head, 8));
+                    let raw = read_at(file, data_at + 24, count * 4)?;
+                    let offsets: Vec<(u64, bool)> = raw.chunks_exact(4).map(|e| u32le(e, 0)).map(|e| (base + (e & 0x7FFF_FFFF) as u64, e & 0x8000_0000 != 0)).collect();
+                    let last_end = sectors_end.unwrap_or(pos);
+                    for (i, &(offset, compressed)) in offsets.iter().enumerate() {
+                        let end = offsets.get(i + 1).map_or(last_end, |n| n.0);
+                        layout.chunks.push(Chunk { segment, offset, stored: end.saturating_sub(offset), compressed });
+                    }
+                }
+                "hash" => layout.stored_md5 = Some(hex::encode(read_at(file, data_at, 16)?)),
+                "digest" => {
+                    let d = read_at(file, data_at, 36)?;
+                    layout.stored_md5.get_or_insert(hex::encode(&d[..16]));
+                    if d[16..].iter().any(|&b| b != 0) {
+                        layout.stored_sha1 = Some(hex::encode(&d[16..]));
+                    }
+                }
+                "next" | "done" => break,
+                _ => {}
+            }
+            if next <= pos {
+                break;
+            }
+            pos = next;
+        }
+    }
+    if layout.chunk_size == 0 {
+        bail!("No volume section in the E01 set");
+    }
+    Ok(layout)
+}
+
+fn read_at(file: &mut File, offset: u64, len: usize) -> Result<Vec<u8>> {
+    let mut buf = vec![0u8; len];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut buf).with_context(|| format!("EWF structure at offset {} is cut off", offset))?;
+    Ok(buf)
+}
+
+// Decompresses the media stored in an E01 set (all segments) and hashes it
+pub fn hash_logical(first: &Path, progress: &AtomicU64, cancel: &AtomicBool) -> Result<Logical> {
+    let paths = segment_paths(first);
+    let mut files = paths.iter().map(|p| File::open(p).with_context(|| format!("Failed to open file: {}", p.display()))).collect::<Result<Vec<_>>>()?;
+    let layout = read_layout(&mut files)?;
+    let policy = crate::policy::get();
+    let mut sha256 = Sha256::new();
+    let mut md5 = policy.allows(Algorithm::Md5).then(Md5::new);
+    let mut sha1 = policy.allows(Algorithm::Sha1).then(Sha1::new);
+    let mut done = 0u64;
+    let mut data = Vec::with_capacity(layout.chunk_size as usize);
+    for (i, chunk) in layout.chunks.iter().enumerate() {
+        if cancel.load(Ordering::Relaxed) {
+            bail!(crate::errors::CANCELLED);
+        }
+        let want = layout.chunk_size.min(layout.media_size.saturating_sub(done));
+        if want == 0 {
+            break;
+        }
+        let raw = read_at(&mut files[chunk.segment], chunk.offset, chunk.stored.min(layout.chunk_size + 4) as usize)
+            .with_context(|| format!("Chunk {} in {}", i, paths[chunk.segment].display()))?;
+        data.clear();
+        if chunk.compressed {
+            ZlibDecoder::new(&raw[..]).read_to_end(&mut data).with_context(|| format!("Chunk {} does not decompress", i))?;
+        } else {
+            data.extend_from_slice(&raw[..raw.len().min(want as usize)]);
+        }
+        let take = (want as usize).min(data.len());
+        if take < want as usize {
+            bail!("Chunk {} holds {} bytes, expected {}", i, data.len(), want);
+        }
+        sha256.update(&data[..take]);
+        if let Some(md5) = &mut md5 {
+            md5.update(&data[..take]);
+        }
+        if let Some(sha1) = &mut sha1 {
+            sha1.update(&data[..take]);
+        }
+        done += take as u64;
+        progress.store(done, Ordering::Relaxed);
+    }
+    if done < layout.media_size {
+        return Err(anyhow!("The E01 set holds {} of {} media bytes; a segment may be missing", done, layout.media_size));
+    }
+    Ok(Logical {
+        segments: paths.len(),
+        bytes: done,
+        sha256: hex::encode(sha256.finalize()),
+        md5: md5.map(|h| hex::encode(h.finalize())),
+        sha1: sha1.map(|h| hex::encode(h.finalize())),
+        stored_md5: layout.stored_md5,
+        stored_sha1: layout.stored_sha1,
+    })
+}
+
+// Logical hashing of an E01 set on a worker thread
+pub struct EwfRun {
+    pub image: PathBuf,
+    pub media_size: Option<u64>,
+    pub result: Option<Result<Logical, String>>,
+    progress: Arc<AtomicU64>,
+    cancel: Arc<AtomicBool>,
+    rx: Option<Receiver<Result<Logical, String>>>,
+}
+
+impl EwfRun {
+    pub fn start(image: PathBuf) -> EwfRun {
+        let progress = Arc::new(AtomicU64::new(0));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        let (path, p, c) = (image.clone(), progress.clone(), cancel.clone());
+        thread::spawn(move || {
+            let _ = tx.send(hash_logical(&path, &p, &c).map_err(|e| format!("{:#}", e)));
+        });
+        let media_size = File::open(&image).ok().and_then(|f| read_layout(&mut [f]).ok()).map(|l| l.media_size);
+        EwfRun { image, media_size, result: None, progress, cancel, rx: Some(rx) }
+    }
+
+    pub fn poll(&mut self) {
+        if let Some(result) = self.rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.result = Some(result);
+            self.rx = None;
+        }
+    }
+
+    pub fn processed(&self) -> u64 {
+        self.progress.load(Ordering::Relaxed)
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+
+    fn section(out: &mut Vec<u8>, kind: &str, data: &[u8]) {
+        let size = DESCRIPTOR_LEN + data.len() as u64;
+        let mut descriptor = [0u8; DESCRIPTOR_LEN as usize];
+        descriptor[..kind.len()].copy_from_slice(kind.as_bytes());
+        descriptor[16..24].copy_from_slice(&(out.len() as u64 + size).to_le_bytes());
+        descriptor[24..32].copy_from_slice(&size.to_le_bytes());
+        out.extend_from_slice(&descriptor);
+        out.extend_from_slice(data);
+    }
+
+    // One segment with three 512-byte sectors in two chunks: a compressed one of two sectors and a stored
+    // one with its Adler-32
+    fn image(media: &[u8], stored_md5: &[u8]) -> Vec<u8> {
+        let mut e01 = SIGNATURE.to_vec();
+        e01.extend_from_slice(&[1, 1, 0, 0, 0]);
+        let mut volume = vec![0u8; 94];
+        volume[8..12].copy_from_slice(&2u32.to_le_bytes());
+        volume[12..16].copy_from_slice(&512u32.to_le_bytes());
+        volume[16..24].copy_from_slice(&3u64.to_le_bytes());
+        section(&mut e01, "volume", &volume);
+        let mut zlib = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        zlib.write_all(&media[..1024]).unwrap();
+        let compressed = zlib.finish().unwrap();
+        let first = e01.len() as u64 + DESCRIPTOR_LEN;
+        let second = first + compressed.len() as u64;
+        section(&mut e01, "sectors", &[&compressed[..], &media[1024..], &[0; 4]].concat());
+        let mut table = vec![0u8; 24];
+        table[..4].copy_from_slice(&2u32.to_le_bytes());
+        table.extend_from_slice(&(first as u32 | 0x8000_0000).to_le_bytes());
+        table.extend_from_slice(&(second as u32).to_le_bytes());
+        section(&mut e01, "table", &table);
+        section(&mut e01, "hash", &[stored_md5, &[0; 64]].concat());
+        section(&mut e01, "done", &[]);
+        e01
+    }
+
+    fn logical(e01: &[u8]) -> Result<Logical> {
+        let path = std::env::temp_dir().join(format!("rust-hash-ewf-test-{}-{}.E01", std::process::id(), e01.len()));
+        fs::write(&path, e01).unwrap();
+        let logical = hash_logical(&path, &AtomicU64::new(0), &AtomicBool::new(false));
+        let _ = fs::remove_file(&path);
+        logical
+    }
+
+    #[test]
+    fn media_is_read_through_the_chunk_table() {
+        let media: Vec<u8> = (0..1536u32).map(|i| (i % 251) as u8).collect();
+        let hashed = logical(&image(&media, &Md5::digest(&media))).unwrap();
+        assert_eq!((hashed.segments, hashed.bytes), (1, 1536));
+        assert_eq!(hashed.sha256, hex::encode(Sha256::digest(&media)));
+        assert_eq!(hashed.stored_md5, Some(hex::encode(Md5::digest(&media))));
+        assert_eq!(hashed.matches(), Some(true));
+        assert_eq!(logical(&image(&media, &[0xAA; 16])).unwrap().matches(), Some(false));
+    }
+
+    #[test]
+    fn only_hashes_present_on_both_sides_are_compared() {
+        let logical = |md5: Option<&str>, stored_md5: Option<&str>, sha1: Option<&str>, stored_sha1: Option<&str>| Logical {
+            segments: 1,
+            bytes: 0,
+            sha256: String::new(),
+            md5: md5.map(str::to_string),
+            sha1: sha1.map(str::to_string),
+            stored_md5: stored_md5.map(str::to_string),
+            stored_sha1: stored_sha1.map(str::to_string),
+        };
+        assert_eq!(logical(Some("a"), Some("a"), Some("b"), None).matches(), Some(true));
+        // MD5 ruled out by the policy: the stored SHA-1 still counts
+        assert_eq!(logical(None, Some("a"), Some("b"), Some("c")).matches(), Some(false));
+        assert_eq!(logical(None, Some("a"), Some("b"), None).matches(), None);
+    }
+
+    #[test]
+    fn segments_follow_the_extension_sequence() {
+        let dir = std::env::temp_dir().join(format!("rust-hash-ewf-segments-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for ext in ["E01", "E02", "E04"] {
+            fs::write(dir.join("disk").with_extension(ext), b"").unwrap();
+        }
+        let paths = segment_paths(&dir.join("disk.E01"));
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(paths, vec![dir.join("disk.E01"), dir.join("disk.E02")]);
+    }
+}
@@ -0,0 +1,108 @@
+use std::path::Path;
+
+// Bytes from the start of the file the type is told from
+pub const SNIFF_LEN: usize = 512;
+
+// What a file's first bytes say it is, whatever its name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileType {
+    pub mime: &'static str,
+    pub name: &'static str,
+}
+
+const LNK: &[u8] = b"L\0\0\0\x01\x14\x02\0";
+const INTERNET_SHORTCUT: &[u8] = b"[InternetShortcut]";
+
+// (offset, magic, MIME type, description); the first match wins
+const MAGIC: &[(usize, &[u8], &str, &str)] = &[
+    (0, b"%PDF-", "application/pdf", "PDF document"),
+    (0, b"PK\x03\x04", "application/zip", "ZIP archive"),
+    (0, b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed", "7-Zip archive"),
+    (0, b"Rar!\x1a\x07", "application/vnd.rar", "RAR archive"),
+    (0, b"\x1f\x8b", "application/gzip", "gzip data"),
+    (0, b"\xfd7zXZ\0", "application/x-xz", "xz data"),
+    (0, b"BZh", "application/x-bzip2", "bzip2 data"),
+    (0, b"\x28\xb5\x2f\xfd", "application/zstd", "Zstandard data"),
+    (257, b"ustar", "application/x-tar", "tar archive"),
+    (0, b"\x89PNG\r\n\x1a\n", "image/png", "PNG image"),
+    (0, b"\xff\xd8\xff", "image/jpeg", "JPEG image"),
+    (0, b"GIF8", "image/gif", "GIF image"),
+    (0, b"\x1a\x45\xdf\xa3", "video/x-matroska", "Matroska/WebM video"),
+    (4, b"ftyp", "video/mp4", "MP4/QuickTime media"),
+    (0, b"ID3", "audio/mpeg", "MP3 audio"),
+    (0, b"MZ", "application/vnd.microsoft.portable-executable", "Windows executable"),
+    (0, b"\x7fELF", "application/x-elf", "ELF executable"),
+    (0, b"\xcf\xfa\xed\xfe", "application/x-mach-binary", "Mach-O executable"),
+    (0, b"\xca\xfe\xba\xbe", "application/x-mach-binary", "Mach-O universal binary"),
+    (0, b"EVF\x09\x0d\x0a\xff\x00", "application/x-ewf", "EnCase evidence file"),
+    (0, b"vhdxfile", "application/x-vhdx", "VHDX disk image"),
+    (0, b"SQLite format 3\0", "application/vnd.sqlite3", "SQLite database"),
+    (0, b"bplist00", "application/x-bplist", "binary property list"),
+    (0, LNK, "application/x-ms-shortcut", "Windows shortcut"),
+    (0, INTERNET_SHORTCUT, "application/x-mswinurl", "Internet shortcut"),
+];
+
+pub fn sniff(head: &[u8]) -> Option<FileType> {
+    if head.is_empty() {
+        return None;
+    }
+    if let Some((_, _, mime, name)) = MAGIC.iter().find(|(at, magic, _, _)| head.get(*at..at + magic.len()) == Some(*magic)) {
+        return Some(FileType { mime, name });
+    }
+    if head.len() >= 12 && head.starts_with(b"RIFF") {
+        return match &head[8..12] {
+            b"WAVE" => Some(FileType { mime: "audio/wav", name: "WAV audio" }),
+            b"AVI " => Some(FileType { mime: "video/x-msvideo", name: "AVI video" }),
+            b"WEBP" => Some(FileType { mime: "image/webp", name: "WebP image" }),
+            _ => None,
+        };
+    }
+    // The sample may end inside a multi-byte character
+    let text = match std::str::from_utf8(head) {
+        Ok(s) => Some(s),
+        Err(e) if e.valid_up_to() + 4 > head.len() => std::str::from_utf8(&head[..e.valid_up_to()]).ok(),
+        Err(_) => None,
+    };
+    text.filter(|s| !s.chars().any(|c| c.is_control() && !c.is_whitespace())).map(|_| FileType { mime: "text/plain", name: "text" })
+}
+
+// Files whose digest is probably not what was meant, told from their content
+pub fn content_warning(head: &[u8]) -> Option<&'static str> {
+    if head.starts_with(LNK) {
+        Some("This is a Windows shortcut: the digest is of the .lnk file, not the file it points to")
+    } else if head.starts_with(INTERNET_SHORTCUT) {
+        Some("This is an Internet shortcut: the digest is of the link, not the page or download it points to")
+    } else {
+        None
+    }
+}
+
+// The same, told from the name and, on Windows, the attributes the sync clients set. Checked before the
+// file is opened, since opening an online-only file makes the sync client download it
+pub fn path_warning(path: &Path) -> Option<&'static str> {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let ext = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if name.starts_with('.') && ext == "icloud" {
+        return Some("This is an iCloud placeholder: the file is not downloaded, so the digest is of the stub");
+    }
+    if matches!(ext.as_str(), "gdoc" | "gsheet" | "gslides" | "gdraw" | "gform" | "gmap" | "gsite") {
+        return Some("This is a Google Drive link file: the digest is of the pointer, not the document");
+    }
+    if is_online_only(path) {
+        return Some("Online-only cloud file: the sync client had to download it to hash it");
+    }
+    None
+}
+
+#[cfg(windows)]
+fn is_online_only(path: &Path) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    use windows_sys::Win32::Storage::FileSystem::{FILE_ATTRIBUTE_OFFLINE, FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS, FILE_ATTRIBUTE_RECALL_ON_OPEN};
+
+    std::fs::metadata(path).is_ok_and(|m| m.file_attributes() & (FILE_ATTRIBUTE_OFFLINE | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS | FILE_ATTRIBUTE_RECALL_ON_OPEN) != 0)
+}
+
+#[cfg(not(windows))]
+fn is_online_only(_path: &Path) -> bool {
+    false
+}
@@ -0,0 +1,62 @@
+use crate::algo::Algorithm;
+use crate::config::Preferences;
+
+// The questions asked on first launch, one page each, before anything is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Algorithm,
+    AutoHash,
+    Network,
+    Shell,
+}
+
+const STEPS: [Step; 4] = [Step::Algorithm, Step::AutoHash, Step::Network, Step::Shell];
+
+// Answers so far; they reach the settings file only when the last page is done
+#[derive(Debug, Clone)]
+pub struct Wizard {
+    index: usize,
+    pub algorithm: Algorithm,
+    pub auto_hash: bool,
+    pub network: bool,
+    // Add "Hash with Rust Hash" to the file manager; it changes the host, so it starts off
+    pub shell: bool,
+}
+
+impl Wizard {
+    // Starts from the defaults, so Next through every page keeps them
+    pub fn new(p: &Preferences) -> Wizard {
+        Wizard {
+            index: 0,
+            algorithm: Algorithm::parse(&p.algorithm).unwrap_or_default(),
+            auto_hash: p.auto_hash,
+            network: p.network,
+            shell: false,
+        }
+    }
+
+    pub fn step(&self) -> Step {
+        STEPS[self.index]
+    }
+
+    // `2 of 4`
+    pub fn position(&self) -> String {
+        format!("{} of {}", self.index + 1, STEPS.len())
+    }
+
+    pub fn is_first(&self) -> bool {
+        self.index == 0
+    }
+
+    pub fn is_last(&self) -> bool {
+        self.index + 1 == STEPS.len()
+    }
+
+    pub fn back(&mut self) {
+        self.index = self.index.saturating_sub(1);
+    }
+
+    pub fn next(&mut self) {
+        self.index = (self.index + 1).min(STEPS.len() - 1);
+    }
+}
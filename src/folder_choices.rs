@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::history;
+use crate::storage;
+
+const CHOICES_FILE: &str = "folder_choices.json";
+// Folders beyond this are forgotten, least recently used first
+const MAX_FOLDERS: usize = 500;
+
+// The digests a folder's files were last hashed with, next to SHA-256
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Choice {
+    pub chunks: bool,
+    pub rsync_signature: bool,
+    pub segment_size: Option<u64>,
+    pub ipfs_cid: bool,
+    pub cloud: bool,
+    pub authenticode: bool,
+    // Metadata digest fields as `name,size,mtime,permissions`
+    pub metadata: Option<String>,
+    pub used_at: i64,
+}
+
+impl Choice {
+    // What the window should say it picked, e.g. `IPFS CID, Metadata digest`
+    pub fn describe(&self) -> String {
+        let names: Vec<&str> = [
+            (self.chunks, "FastCDC chunks"),
+            (self.rsync_signature, "rsync signature"),
+            (self.segment_size.is_some(), "Segment digests"),
+            (self.ipfs_cid, "IPFS CID"),
+            (self.cloud, "Azure/GCS checksums"),
+            (self.authenticode, "Authenticode"),
+            (self.metadata.is_some(), "Metadata digest"),
+        ]
+        .into_iter()
+        .filter_map(|(on, name)| on.then_some(name))
+        .collect();
+        if names.is_empty() {
+            "SHA-256 only".to_string()
+        } else {
+            format!("SHA-256, {}", names.join(", "))
+        }
+    }
+}
+
+// Choices by folder, kept in the data directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FolderChoices {
+    folders: BTreeMap<String, Choice>,
+}
+
+impl FolderChoices {
+    // Missing or unreadable file means nothing remembered yet
+    pub fn load() -> Self {
+        choices_path()
+            .and_then(|p| fs::read(p).ok())
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    // The choice for the file's own folder or, failing that, the nearest folder above it, so a remembered
+    // ISOs folder also covers its subfolders
+    pub fn lookup(&self, file: &Path) -> Option<(PathBuf, &Choice)> {
+        file.ancestors().skip(1).find_map(|dir| self.folders.get(&key(dir)).map(|c| (dir.to_path_buf(), c)))
+    }
+
+    pub fn remember(&mut self, file: &Path, mut choice: Choice) -> Result<()> {
+        let Some(dir) = file.parent().filter(|d| !d.as_os_str().is_empty()) else {
+            return Ok(());
+        };
+        choice.used_at = history::now_timestamp();
+        let key = key(dir);
+        if self.folders.get(&key).is_some_and(|c| Choice { used_at: choice.used_at, ..c.clone() } == choice) {
+            // Same digests as last time; not worth a write per file
+            return Ok(());
+        }
+        self.folders.insert(key, choice);
+        while self.folders.len() > MAX_FOLDERS {
+            let oldest = self.folders.iter().min_by_key(|(_, c)| c.used_at).map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                self.folders.remove(&oldest);
+            }
+        }
+        let path = choices_path().context("No data directory available for folder choices")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+        }
+        crate::atomic::write(&path, &serde_json::to_vec_pretty(self)?)
+    }
+}
+
+// Windows paths compare without regard to case
+fn key(dir: &Path) -> String {
+    let key = dir.to_string_lossy().trim_end_matches(['/', '\\']).to_string();
+    if cfg!(windows) {
+        key.to_lowercase()
+    } else {
+        key
+    }
+}
+
+fn choices_path() -> Option<PathBuf> {
+    storage::data_dir().map(|d| d.join(CHOICES_FILE))
+}
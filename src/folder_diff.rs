@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+use anyhow::{anyhow, Context, Result};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Same,
+    Different,
+    OnlyInA,
+    OnlyInB,
+    Failed(String),
+}
+
+// One relative path looked up in both trees
+#[derive(Debug, Clone)]
+pub struct Compared {
+    pub relative: PathBuf,
+    pub status: Status,
+    // SHA-256 of each side, when it was hashed
+    pub a_hex: Option<String>,
+    pub b_hex: Option<String>,
+    pub a_bytes: Option<u64>,
+    pub b_bytes: Option<u64>,
+}
+
+impl Compared {
+    pub fn label(&self) -> &'static str {
+        match self.status {
+            Status::Same => "Identical",
+            Status::Different => "DIFFERENT",
+            Status::OnlyInA => "ONLY IN A",
+            Status::OnlyInB => "ONLY IN B",
+            Status::Failed(_) => "FAILED",
+        }
+    }
+}
+
+// Regular files under `root` by path relative to it, with their sizes; symlinks are not followed
+pub fn tree(root: &Path) -> Result<BTreeMap<PathBuf, u64>> {
+    if !fs::metadata(root).with_context(|| format!("Failed to read metadata: {}", root.display()))?.is_dir() {
+        return Err(anyhow!("Not a folder: {}", root.display()));
+    }
+    let mut files = BTreeMap::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir).with_context(|| format!("Failed to list folder: {}", dir.display()))?.filter_map(|e| e.ok()) {
+            let Ok(ft) = entry.file_type() else { continue };
+            let path = entry.path();
+            if ft.is_dir() {
+                pending.push(path);
+            } else if ft.is_file() {
+                let len = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                if let Ok(relative) = path.strip_prefix(root) {
+                    files.insert(relative.to_path_buf(), len);
+                }
+            }
+        }
+    }
+    Ok(files)
+}
+
+// Content-level comparison of two folder trees on a worker thread, in path order
+pub struct CompareRun {
+    pub a: PathBuf,
+    pub b: PathBuf,
+    // Distinct relative paths across both trees
+    pub paths: usize,
+    pub done: Vec<Compared>,
+    progress: Arc<AtomicU64>,
+    cancel: Arc<AtomicBool>,
+    rx: Option<Receiver<Compared>>,
+}
+
+impl CompareRun {
+    pub fn start(a: PathBuf, b: PathBuf, forensic: bool) -> Result<CompareRun> {
+        let (left, mut right) = (tree(&a)?, tree(&b)?);
+        let mut plan: Vec<(PathBuf, Option<u64>, Option<u64>)> = left
+            .into_iter()
+            .map(|(path, len)| {
+                let other = right.remove(&path);
+                (path, Some(len), other)
+            })
+            .collect();
+        plan.extend(right.into_iter().map(|(path, len)| (path, None, Some(len))));
+        plan.sort_by(|x, y| x.0.cmp(&y.0));
+        let progress = Arc::new(AtomicU64::new(0));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        let paths = plan.len();
+        let (root_a, root_b, p, c) = (a.clone(), b.clone(), progress.clone(), cancel.clone());
+        thread::spawn(move || {
+            for entry in plan {
+                if c.load(Ordering::Relaxed) {
+                    break;
+                }
+                let item = compare((&root_a, &root_b), entry, &p, &c, forensic);
+                // A path interrupted by Cancel is left out rather than reported as failed
+                if c.load(Ordering::Relaxed) || tx.send(item).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(CompareRun { a, b, paths, done: Vec::new(), progress, cancel, rx: Some(rx) })
+    }
+
+    // Collects compared paths; true the one time the run ends
+    pub fn poll(&mut self) -> bool {
+        let Some(rx) = &self.rx else {
+            return false;
+        };
+        loop {
+            match rx.try_recv() {
+                Ok(item) => self.done.push(item),
+                Err(TryRecvError::Empty) => return false,
+                Err(TryRecvError::Disconnected) => {
+                    self.rx = None;
+                    return true;
+                }
+            }
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.rx.is_some()
+    }
+
+    // Bytes read of the file being hashed right now
+    pub fn current_bytes(&self) -> u64 {
+        self.progress.load(Ordering::Relaxed)
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn count(&self, status: &Status) -> usize {
+        self.done.iter().filter(|c| c.status == *status).count()
+    }
+
+    pub fn problems(&self) -> impl Iterator<Item = &Compared> {
+        self.done.iter().filter(|c| c.status != Status::Same)
+    }
+}
+
+fn compare(
+    (a, b): (&Path, &Path),
+    (relative, a_bytes, b_bytes): (PathBuf, Option<u64>, Option<u64>),
+    progress: &Arc<AtomicU64>,
+    cancel: &Arc<AtomicBool>,
+    forensic: bool,
+) -> Compared {
+    let mut item = Compared { relative, status: Status::Same, a_hex: None, b_hex: None, a_bytes, b_bytes };
+    match (a_bytes, b_bytes) {
+        (Some(_), None) => item.status = Status::OnlyInA,
+        (None, Some(_)) => item.status = Status::OnlyInB,
+        // Different sizes cannot hold the same content, so neither side needs reading
+        (Some(x), Some(y)) if x != y => item.status = Status::Different,
+        _ => {
+            let hash = |root: &Path| {
+                progress.store(0, Ordering::Relaxed);
+                let opts = crate::HashOptions { forensic, ..Default::default() };
+                crate::compute_sha256_file_progress(&root.join(&item.relative).display().to_string(), progress.clone(), cancel.clone(), None, opts)
+                    .map(|((hex, _, _, _), _)| hex)
+                    .map_err(|e| format!("{:#}", e))
+            };
+            match hash(a).and_then(|x| hash(b).map(|y| (x, y))) {
+                Ok((x, y)) => {
+                    item.status = if x == y { Status::Same } else { Status::Different };
+                    item.a_hex = Some(x);
+                    item.b_hex = Some(y);
+                }
+                Err(e) => item.status = Status::Failed(e),
+            }
+        }
+    }
+    item
+}
@@ -0,0 +1,47 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use crate::post_actions::PostActions;
+
+// Opens a file for reading without updating its last-access time where the OS allows it.
+// Returns the file and whether the access time is known to be left alone.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn open_read_only(path: &Path) -> io::Result<(File, bool)> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    // O_NOATIME is only allowed for the file's owner (or with CAP_FOWNER); otherwise open normally
+    match std::fs::OpenOptions::new().read(true).custom_flags(libc::O_NOATIME).open(path) {
+        Ok(file) => Ok((file, true)),
+        Err(e) if e.raw_os_error() == Some(libc::EPERM) => File::open(path).map(|f| (f, false)),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(windows)]
+pub fn open_read_only(path: &Path) -> io::Result<(File, bool)> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Foundation::{FILETIME, GENERIC_READ};
+    use windows_sys::Win32::Storage::FileSystem::{SetFileTime, FILE_WRITE_ATTRIBUTES};
+
+    // A last-access time of all ones tells the file system not to update it for this handle.
+    // Setting it needs attribute access, which read-only media or ACLs may refuse.
+    let file = match std::fs::OpenOptions::new().access_mode(GENERIC_READ | FILE_WRITE_ATTRIBUTES).open(path) {
+        Ok(file) => file,
+        Err(_) => return File::open(path).map(|f| (f, false)),
+    };
+    let keep = FILETIME { dwLowDateTime: u32::MAX, dwHighDateTime: u32::MAX };
+    let kept = unsafe { SetFileTime(file.as_raw_handle(), std::ptr::null(), &keep, std::ptr::null()) } != 0;
+    Ok((file, kept))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", windows)))]
+pub fn open_read_only(path: &Path) -> io::Result<(File, bool)> {
+    File::open(path).map(|f| (f, false))
+}
+
+// Post-job actions minus those that write next to (or move) the evidence file
+pub fn restrict(actions: &PostActions) -> PostActions {
+    PostActions { write_sidecar: false, move_to: String::new(), ..actions.clone() }
+}
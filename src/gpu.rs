@@ -0,0 +1,233 @@
+// SHA-256 of many small buffers at once on the GPU, one shader invocation per buffer. An experiment for
+// trees of millions of tiny files, where the per-file work and not the bytes keeps the CPU busy. Only
+// built with `--features gpu`; without it, or without a usable GPU, `Gpu::open` gives None and callers
+// hash on the CPU as before
+
+// Files up to this size are sent to the GPU; larger ones read faster than they upload
+pub const MAX_FILE: u64 = 64 * 1024;
+// Buffers and padded bytes per dispatch, within the limits every adapter supports
+pub const MAX_BATCH: usize = 16 * 1024;
+#[cfg(feature = "gpu")]
+const MAX_BATCH_BYTES: usize = 64 * 1024 * 1024;
+#[cfg(feature = "gpu")]
+const WORKGROUP: u32 = 64;
+
+#[cfg(feature = "gpu")]
+const SHADER: &str = r#"
+struct Message {
+    // First word of the padded message and its number of 64-byte blocks
+    offset: u32,
+    blocks: u32,
+}
+
+@group(0) @binding(0) var<storage, read> words: array<u32>;
+@group(0) @binding(1) var<storage, read> messages: array<Message>;
+@group(0) @binding(2) var<storage, read_write> digests: array<u32>;
+
+var<private> K: array<u32, 64> = array<u32, 64>(
+    0x428a2f98u, 0x71374491u, 0xb5c0fbcfu, 0xe9b5dba5u, 0x3956c25bu, 0x59f111f1u, 0x923f82a4u, 0xab1c5ed5u,
+    0xd807aa98u, 0x12835b01u, 0x243185beu, 0x550c7dc3u, 0x72be5d74u, 0x80deb1feu, 0x9bdc06a7u, 0xc19bf174u,
+    0xe49b69c1u, 0xefbe4786u, 0x0fc19dc6u, 0x240ca1ccu, 0x2de92c6fu, 0x4a7484aau, 0x5cb0a9dcu, 0x76f988dau,
+    0x983e5152u, 0xa831c66du, 0xb00327c8u, 0xbf597fc7u, 0xc6e00bf3u, 0xd5a79147u, 0x06ca6351u, 0x14292967u,
+    0x27b70a85u, 0x2e1b2138u, 0x4d2c6dfcu, 0x53380d13u, 0x650a7354u, 0x766a0abbu, 0x81c2c92eu, 0x92722c85u,
+    0xa2bfe8a1u, 0xa81a664bu, 0xc24b8b70u, 0xc76c51a3u, 0xd192e819u, 0xd6990624u, 0xf40e3585u, 0x106aa070u,
+    0x19a4c116u, 0x1e376c08u, 0x2748774cu, 0x34b0bcb5u, 0x391c0cb3u, 0x4ed8aa4au, 0x5b9cca4fu, 0x682e6ff3u,
+    0x748f82eeu, 0x78a5636fu, 0x84c87814u, 0x8cc70208u, 0x90befffau, 0xa4506cebu, 0xbef9a3f7u, 0xc67178f2u,
+);
+
+fn rotr(x: u32, n: u32) -> u32 {
+    return (x >> n) | (x << (32u - n));
+}
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if (index >= arrayLength(&messages)) {
+        return;
+    }
+    let message = messages[index];
+    var h = array<u32, 8>(0x6a09e667u, 0xbb67ae85u, 0x3c6ef372u, 0xa54ff53au, 0x510e527fu, 0x9b05688cu, 0x1f83d9abu, 0x5be0cd19u);
+    var w: array<u32, 64>;
+    for (var block = 0u; block < message.blocks; block++) {
+        let base = message.offset + block * 16u;
+        for (var t = 0u; t < 16u; t++) {
+            w[t] = words[base + t];
+        }
+        for (var t = 16u; t < 64u; t++) {
+            let s0 = rotr(w[t - 15u], 7u) ^ rotr(w[t - 15u], 18u) ^ (w[t - 15u] >> 3u);
+            let s1 = rotr(w[t - 2u], 17u) ^ rotr(w[t - 2u], 19u) ^ (w[t - 2u] >> 10u);
+            w[t] = w[t - 16u] + s0 + w[t - 7u] + s1;
+        }
+        var a = h[0]; var b = h[1]; var c = h[2]; var d = h[3];
+        var e = h[4]; var f = h[5]; var g = h[6]; var hh = h[7];
+        for (var t = 0u; t < 64u; t++) {
+            let t1 = hh + (rotr(e, 6u) ^ rotr(e, 11u) ^ rotr(e, 25u)) + ((e & f) ^ (~e & g)) + K[t] + w[t];
+            let t2 = (rotr(a, 2u) ^ rotr(a, 13u) ^ rotr(a, 22u)) + ((a & b) ^ (a & c) ^ (b & c));
+            hh = g; g = f; f = e; e = d + t1;
+            d = c; c = b; b = a; a = t1 + t2;
+        }
+        h[0] += a; h[1] += b; h[2] += c; h[3] += d;
+        h[4] += e; h[5] += f; h[6] += g; h[7] += hh;
+    }
+    for (var i = 0u; i < 8u; i++) {
+        digests[index * 8u + i] = h[i];
+    }
+}
+"#;
+
+#[cfg(feature = "gpu")]
+pub struct Gpu {
+    // Adapter name and backend, e.g. `NVIDIA GeForce RTX 3060 (Vulkan)`
+    pub name: String,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+}
+
+// Without the feature there is never a GPU to hash on
+#[cfg(not(feature = "gpu"))]
+pub enum Gpu {}
+
+#[cfg(not(feature = "gpu"))]
+impl Gpu {
+    pub fn open() -> Option<Gpu> {
+        None
+    }
+
+    pub fn name(&self) -> &str {
+        match *self {}
+    }
+
+    pub fn sha256_batch(&self, _buffers: &[Vec<u8>]) -> anyhow::Result<Vec<String>> {
+        match *self {}
+    }
+}
+
+#[cfg(feature = "gpu")]
+impl Gpu {
+    // None without an adapter, or with only a software one, which would just be a slower CPU
+    pub fn open() -> Option<Gpu> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            compatible_surface: None,
+        }))?;
+        let info = adapter.get_info();
+        if info.device_type == wgpu::DeviceType::Cpu {
+            return None;
+        }
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor { label: Some("rust-hash"), required_features: wgpu::Features::empty(), required_limits: wgpu::Limits::downlevel_defaults() },
+            None,
+        ))
+        .ok()?;
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor { label: Some("sha256"), source: wgpu::ShaderSource::Wgsl(SHADER.into()) });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor { label: Some("sha256"), layout: None, module: &module, entry_point: "main" });
+        Some(Gpu { name: format!("{} ({:?})", info.name, info.backend), device, queue, pipeline })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // Lowercase hex SHA-256 of each buffer, in order
+    pub fn sha256_batch(&self, buffers: &[Vec<u8>]) -> anyhow::Result<Vec<String>> {
+        let mut out = Vec::with_capacity(buffers.len());
+        let mut start = 0;
+        while start < buffers.len() {
+            // As many buffers as fit in one dispatch, and always at least one
+            let mut end = start + 1;
+            let mut bytes = padded_len(buffers[start].len());
+            while end < buffers.len() && end - start < MAX_BATCH && bytes + padded_len(buffers[end].len()) <= MAX_BATCH_BYTES {
+                bytes += padded_len(buffers[end].len());
+                end += 1;
+            }
+            out.extend(self.dispatch(&buffers[start..end])?);
+            start = end;
+        }
+        Ok(out)
+    }
+
+    fn dispatch(&self, buffers: &[Vec<u8>]) -> anyhow::Result<Vec<String>> {
+        use wgpu::util::DeviceExt;
+
+        let mut words: Vec<u32> = Vec::new();
+        let mut messages: Vec<u32> = Vec::with_capacity(buffers.len() * 2);
+        for buffer in buffers {
+            let padded = pad(buffer);
+            messages.push(words.len() as u32);
+            messages.push((padded.len() / 64) as u32);
+            words.extend(padded.chunks_exact(4).map(|w| u32::from_be_bytes([w[0], w[1], w[2], w[3]])));
+        }
+        let to_bytes = |v: &[u32]| v.iter().flat_map(|w| w.to_le_bytes()).collect::<Vec<u8>>();
+        let storage = |label: &str, contents: &[u8]| {
+            self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor { label: Some(label), contents, usage: wgpu::BufferUsages::STORAGE })
+        };
+        let words = storage("words", &to_bytes(&words));
+        let messages = storage("messages", &to_bytes(&messages));
+        let size = (buffers.len() * 32) as u64;
+        let digests = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("digests"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: words.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: messages.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: digests.as_entire_binding() },
+            ],
+        });
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None, timestamp_writes: None });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups((buffers.len() as u32).div_ceil(WORKGROUP), 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&digests, 0, &readback, 0, size);
+        self.queue.submit(Some(encoder.finish()));
+        let slice = readback.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |r| {
+            let _ = tx.send(r);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        rx.recv()?.map_err(|e| anyhow::anyhow!("The GPU result could not be read back: {}", e))?;
+        let data = slice.get_mapped_range();
+        let hexes = data
+            .chunks_exact(32)
+            .map(|digest| digest.chunks_exact(4).flat_map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]).to_be_bytes()).collect::<Vec<u8>>())
+            .map(hex::encode)
+            .collect();
+        drop(data);
+        readback.unmap();
+        Ok(hexes)
+    }
+}
+
+// Message length after SHA-256 padding: a 0x80 byte, zeros, and the length in bits, to whole 64-byte blocks
+#[cfg(feature = "gpu")]
+fn padded_len(len: usize) -> usize {
+    (len + 9).div_ceil(64) * 64
+}
+
+#[cfg(feature = "gpu")]
+fn pad(message: &[u8]) -> Vec<u8> {
+    let mut padded = Vec::with_capacity(padded_len(message.len()));
+    padded.extend_from_slice(message);
+    padded.push(0x80);
+    padded.resize(padded_len(message.len()) - 8, 0);
+    padded.extend_from_slice(&((message.len() as u64) * 8).to_be_bytes());
+    padded
+}
@@ -0,0 +1,379 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::algo::Algorithm;
+use crate::verify::Entry;
+
+// Files and expected digests read from a checksum list or a copy tool's log
+pub struct HashList {
+    // Which reader understood the file, for the status line
+    pub format: &'static str,
+    pub entries: Vec<Entry>,
+    // Lines that name a file but carry no SHA-256, and whose file has none on another line (MD5, xxHash, ... logs)
+    pub skipped: usize,
+}
+
+// Reads sha256sum/TeraCopy checksum files (`hash *name`, BSD `SHA256 (name) = hash`),
+// hashdeep lists, TeraCopy CSV reports and FastCopy logs. Relative paths are resolved against
+// the list's folder, or FastCopy's destination folder.
+pub fn load(path: &Path) -> Result<HashList> {
+    let data = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let text = decode(&data);
+    let base = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let first = text.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+    let list = if first.starts_with("%%%% HASHDEEP") {
+        parse_hashdeep(&text, &base)?
+    } else if text.contains("FastCopy") {
+        let dest = text.lines().find_map(|l| l.trim().strip_prefix("<DestDir>")).map(|d| PathBuf::from(d.trim()));
+        parse_lines(&text, &dest.unwrap_or(base), "FastCopy log")
+    } else if let Some(delim) = csv_delimiter(first) {
+        parse_csv(&text, delim, &base)?
+    } else {
+        parse_lines(&text, &base, "checksum list")
+    };
+    if list.entries.is_empty() {
+        return Err(anyhow!("No SHA-256 entries found in {} ({} lines without a SHA-256 digest)", path.display(), list.skipped));
+    }
+    Ok(list)
+}
+
+// TeraCopy writes UTF-16 logs on some versions
+fn decode(data: &[u8]) -> String {
+    if data.starts_with(&[0xFF, 0xFE]) {
+        let units: Vec<u16> = data[2..].chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+        return String::from_utf16_lossy(&units);
+    }
+    let data = data.strip_prefix(&[0xEF, 0xBB, 0xBF][..]).unwrap_or(data);
+    String::from_utf8_lossy(data).into_owned()
+}
+
+fn parse_lines(text: &str, base: &Path, format: &'static str) -> HashList {
+    let mut list = HashList { format, entries: Vec::new(), skipped: 0 };
+    // MD5, SHA-1, ... lines, checked along with the SHA-256 line of the same file
+    let mut others = Vec::new();
+    // `XATTRS-SHA256 (name) = hash` lines from `--cli -r --xattrs`, likewise
+    let mut attributes = Vec::new();
+    // `sha256sum --zero` output ends each record with NUL rather than a newline
+    let records: Vec<&str> = if text.contains('\0') { text.split('\0').collect() } else { text.lines().collect() };
+    for line in records {
+        let line = line.trim();
+        // coreutils marks a line whose name is escaped with a leading backslash, before the digest or the tag
+        let (line, escaped) = match line.strip_prefix('\\') {
+            Some(rest) if rest.starts_with(|c: char| c.is_ascii_alphanumeric()) => (rest, true),
+            _ => (line, false),
+        };
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+        // The root line of `--cli --tree` is about the folder, which verify has no file to check against
+        if [crate::tree_digest::TAG, crate::tree_digest::TAG_XATTRS].iter().any(|tag| line.strip_prefix(tag).is_some_and(|rest| rest.starts_with(" ("))) {
+            continue;
+        }
+        if let Some((hex, name)) = xattrs_line(line) {
+            let name = if escaped { unescape(&name) } else { name };
+            attributes.push((resolve(base, &name), hex));
+            continue;
+        }
+        let (algorithm, hex, name) = match tagged_line(line) {
+            Some(tagged) => tagged,
+            None => match split_hash_line(line) {
+                Some((hex, name)) if hex.len() == 64 => (Algorithm::Sha256, hex, name),
+                Some((hex, name)) => match untagged_algorithm(&hex) {
+                    Some(algorithm) => (algorithm, hex, name),
+                    None => {
+                        list.skipped += 1;
+                        continue;
+                    }
+                },
+                None => continue,
+            },
+        };
+        let name = if escaped { unescape(&name) } else { name };
+        match algorithm {
+            Algorithm::Sha256 => list.entries.push(Entry { path: resolve(base, &name), expected: hex, list: 0, also: Vec::new(), xattrs: None }),
+            other => others.push((resolve(base, &name), other, hex)),
+        }
+    }
+    list.skipped += attach(&mut list.entries, others);
+    list.skipped += attach_xattrs(&mut list.entries, attributes);
+    list
+}
+
+fn xattrs_line(line: &str) -> Option<(String, String)> {
+    let rest = line.strip_prefix(crate::xattrs::TAG)?.strip_prefix(" (")?;
+    let (name, hash) = rest.rsplit_once(") = ")?;
+    let hash = hash.trim();
+    (hash.len() == 64 && is_hex(hash)).then(|| (hash.to_lowercase(), name.to_string()))
+}
+
+// BSD style with any tag: `MD5 (name) = hash`, `SHA2-256 (name) = hash`
+fn tagged_line(line: &str) -> Option<(Algorithm, String, String)> {
+    let (tag, rest) = line.split_once(" (")?;
+    let algorithm = Algorithm::parse(&tag.replace("SHA2-", "SHA"))?;
+    let (name, hash) = rest.rsplit_once(") = ")?;
+    let hash = hash.trim();
+    is_hex(hash).then(|| (algorithm, hash.to_lowercase(), name.to_string()))
+}
+
+// Untagged digests by length; 64 is taken as SHA-256 before this is asked
+fn untagged_algorithm(hex: &str) -> Option<Algorithm> {
+    match hex.len() {
+        32 => Some(Algorithm::Md5),
+        40 => Some(Algorithm::Sha1),
+        128 => Some(Algorithm::Sha512),
+        _ => None,
+    }
+}
+
+// Puts each other-algorithm digest on the SHA-256 entry of its file, so the file is read once for all
+// of them; returns how many had no such entry
+fn attach(entries: &mut [Entry], others: Vec<(PathBuf, Algorithm, String)>) -> usize {
+    let index: HashMap<PathBuf, usize> = entries.iter().enumerate().map(|(i, e)| (e.path.clone(), i)).collect();
+    let mut unmatched = 0;
+    for (path, algorithm, hex) in others {
+        match index.get(&path) {
+            Some(&i) => entries[i].also.push((algorithm, hex)),
+            None => unmatched += 1,
+        }
+    }
+    unmatched
+}
+
+// Puts each attribute digest on the SHA-256 entry of its file; returns how many had no such entry
+fn attach_xattrs(entries: &mut [Entry], attributes: Vec<(PathBuf, String)>) -> usize {
+    let index: HashMap<PathBuf, usize> = entries.iter().enumerate().map(|(i, e)| (e.path.clone(), i)).collect();
+    let mut unmatched = 0;
+    for (path, hex) in attributes {
+        match index.get(&path) {
+            Some(&i) => entries[i].xattrs = Some(hex),
+            None => unmatched += 1,
+        }
+    }
+    unmatched
+}
+
+// hashdeep's audit format: a `%%%% size,md5,sha256,filename` line names the columns and `##` lines are
+// comments. The name is the last column and may contain commas of its own
+fn parse_hashdeep(text: &str, base: &Path) -> Result<HashList> {
+    let header = text.lines().find_map(|l| l.strip_prefix("%%%% size,")).context("hashdeep list has no column header")?;
+    let columns: Vec<String> = std::iter::once("size").chain(header.trim().split(',')).map(|c| c.trim().to_lowercase()).collect();
+    if !columns.iter().any(|c| c == "sha256") {
+        anyhow::bail!("hashdeep list has no sha256 column (hashdeep -c sha256 adds one)");
+    }
+    let mut list = HashList { format: "hashdeep list", entries: Vec::new(), skipped: 0 };
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with("%%%%") && !l.starts_with('#')) {
+        let fields: Vec<&str> = line.splitn(columns.len(), ',').collect();
+        if fields.len() < columns.len() {
+            list.skipped += 1;
+            continue;
+        }
+        let mut expected = None;
+        let mut also = Vec::new();
+        for (column, value) in columns.iter().zip(&fields) {
+            match Algorithm::parse(column) {
+                Some(Algorithm::Sha256) => expected = Some(value.to_lowercase()),
+                Some(algorithm) if is_hex(value) => also.push((algorithm, value.to_lowercase())),
+                // size, filename, and digests this app does not compute (tiger, whirlpool)
+                _ => {}
+            }
+        }
+        match expected.filter(|h| h.len() == 64 && is_hex(h)) {
+            Some(expected) => list.entries.push(Entry { path: resolve(base, fields[columns.len() - 1]), expected, list: 0, also, xattrs: None }),
+            None => list.skipped += 1,
+        }
+    }
+    Ok(list)
+}
+
+// (digest, file name) from one line; the digest may come before or after the name
+pub fn split_hash_line(line: &str) -> Option<(String, String)> {
+    // BSD style: SHA256 (name) = hash
+    if let Some(rest) = line.strip_prefix("SHA256 (").or_else(|| line.strip_prefix("SHA2-256 (")) {
+        let (name, hash) = rest.rsplit_once(") = ")?;
+        return is_hex(hash.trim()).then(|| (hash.trim().to_lowercase(), name.to_string()));
+    }
+    // A SHA-256 anywhere on the line wins over a short hex file name that happens to come first
+    let mut tokens = line.split_whitespace();
+    let token = tokens.clone().find(|t| clean_token(t).len() == 64 && is_hex(clean_token(t))).or_else(|| tokens.find(|t| is_digest(clean_token(t))))?;
+    let hex = clean_token(token).to_lowercase();
+    let name = line.replacen(token, "", 1);
+    let name = name.trim().trim_start_matches('*').trim_matches(|c: char| c == ':' || c == ',' || c == '"' || c == '=' || c.is_whitespace());
+    (!name.is_empty()).then(|| (hex, name.to_string()))
+}
+
+// Reverses coreutils' name escaping: `\\` -> `\`, `\n` -> LF, `\r` -> CR
+pub fn unescape(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn clean_token(t: &str) -> &str {
+    let t = t.trim_matches(|c: char| c == '*' || c == ':' || c == ',' || c == '"' || c == '[' || c == ']');
+    // "SHA256:abc..." style prefixes
+    t.split_once(':').map_or(t, |(prefix, rest)| if prefix.len() < 10 { rest } else { t })
+}
+
+// SHA-256, or the shorter digests other tools log (counted as skipped)
+fn is_digest(t: &str) -> bool {
+    matches!(t.len(), 8 | 16 | 32 | 40 | 64 | 128) && is_hex(t)
+}
+
+fn is_hex(t: &str) -> bool {
+    !t.is_empty() && t.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn csv_delimiter(header: &str) -> Option<char> {
+    let lower = header.to_lowercase();
+    if !(lower.contains("sha") || lower.contains("hash") || lower.contains("checksum")) {
+        return None;
+    }
+    [',', ';', '\t'].into_iter().find(|d| header.contains(*d))
+}
+
+fn parse_csv(text: &str, delim: char, base: &Path) -> Result<HashList> {
+    let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+    let header: Vec<String> = split_csv(lines.next().unwrap_or(""), delim).iter().map(|h| h.to_lowercase()).collect();
+    let find = |names: &[&str]| header.iter().position(|h| names.iter().any(|n| h.contains(n)));
+    let hash_col = find(&["sha-256", "sha256", "hash", "checksum"]).context("CSV has no hash column")?;
+    // The copies are what needs re-verifying, so the destination wins over the source
+    let path_col = find(&["target", "destination", "dest"]).or_else(|| find(&["source", "file", "path", "name"])).context("CSV has no file column")?;
+    let mut list = HashList { format: "CSV report", entries: Vec::new(), skipped: 0 };
+    for line in lines {
+        let fields = split_csv(line, delim);
+        let (Some(hash), Some(name)) = (fields.get(hash_col), fields.get(path_col)) else {
+            continue;
+        };
+        let hash = clean_token(hash.trim());
+        if hash.len() == 64 && is_hex(hash) && !name.trim().is_empty() {
+            list.entries.push(Entry { path: resolve(base, name.trim()), expected: hash.to_lowercase(), list: 0, also: Vec::new(), xattrs: None });
+        } else {
+            list.skipped += 1;
+        }
+    }
+    Ok(list)
+}
+
+// Quote-aware field split; "" inside quotes is a literal quote
+fn split_csv(line: &str, delim: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            c if c == delim && !in_quotes => fields.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    fields.push(current);
+    fields
+}
+
+fn resolve(base: &Path, name: &str) -> PathBuf {
+    let path = PathBuf::from(name);
+    if path.is_absolute() {
+        path
+    } else {
+        base.join(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::post_actions::checksum_line;
+
+    const A: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    const B: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    fn parse(text: &str) -> HashList {
+        parse_lines(text, Path::new("base"), "checksum list")
+    }
+
+    #[test]
+    fn hashdeep_columns() {
+        let text = format!("%%%% HASHDEEP-1.0\n%%%% size,md5,sha256,filename\n## comment\n3,900150983cd24fb0d6963f7d28e17f72,{},dir/a,b.txt\n", A);
+        let list = parse_hashdeep(&text, Path::new("base")).unwrap();
+        assert_eq!(list.entries[0].path, Path::new("base").join("dir/a,b.txt"));
+        assert_eq!(list.entries[0].expected, A);
+    }
+
+    #[test]
+    fn csv_reports_prefer_the_destination() {
+        let text = format!("Source,Target,SHA-256\n\"C:\\src\\a\",\"out, new\",{}\n", A);
+        let list = parse_csv(&text, csv_delimiter("Source,Target,SHA-256").unwrap(), Path::new("base")).unwrap();
+        assert_eq!(list.entries[0].path, Path::new("base").join("out, new"));
+    }
+
+    #[test]
+    fn written_lines_read_back() {
+        let odd = "odd\\name\nwith newline";
+        let text = [checksum_line(A, "a.txt", false, false), checksum_line(B, odd, true, false), format!("SHA256 (c d.iso) = {}\n", A.to_uppercase())].concat();
+        let list = parse(&text);
+        let read: Vec<(PathBuf, &str)> = list.entries.iter().map(|e| (e.path.clone(), e.expected.as_str())).collect();
+        assert_eq!(read, vec![(Path::new("base").join("a.txt"), A), (Path::new("base").join(odd), B), (Path::new("base").join("c d.iso"), A)]);
+        assert_eq!(list.skipped, 0);
+    }
+
+    #[test]
+    fn zero_terminated_names_are_not_unescaped() {
+        let text = [checksum_line(A, "back\\slash", false, true), checksum_line(B, "new\nline", false, true)].concat();
+        let names: Vec<PathBuf> = parse(&text).entries.into_iter().map(|e| e.path).collect();
+        assert_eq!(names, vec![Path::new("base").join("back\\slash"), Path::new("base").join("new\nline")]);
+    }
+
+    #[test]
+    fn other_digests_join_the_sha256_entry() {
+        let text = format!("{}MD5 (a.txt) = 900150983cd24fb0d6963f7d28e17f72\nMD5 (gone) = 900150983cd24fb0d6963f7d28e17f72\n", checksum_line(A, "a.txt", false, false));
+        let list = parse(&text);
+        assert_eq!(list.entries.len(), 1);
+        assert_eq!(list.entries[0].also, vec![(Algorithm::Md5, "900150983cd24fb0d6963f7d28e17f72".to_string())]);
+    }
+
+    #[test]
+    fn attributes_join_the_sha256_entry() {
+        let text = [checksum_line(A, "a.txt", false, false), crate::xattrs::manifest_line(B, "a.txt", false), crate::xattrs::manifest_line(B, "gone", false)].concat();
+        let list = parse(&text);
+        assert_eq!(list.entries.len(), 1);
+        assert_eq!(list.entries[0].xattrs.as_deref(), Some(B));
+        // The attribute line of a file with no SHA-256 line
+        assert_eq!(list.skipped, 1);
+        let root = crate::post_actions::tagged_line(crate::tree_digest::TAG_XATTRS, A, "dir", false);
+        let list = parse(&[text, root].concat());
+        assert_eq!((list.entries.len(), list.skipped), (1, 1));
+    }
+
+    #[test]
+    fn escaped_attribute_names_read_back() {
+        let text = [checksum_line(A, "x\ny", false, false), crate::xattrs::manifest_line(B, "x\ny", false)].concat();
+        assert_eq!(parse(&text).entries[0].xattrs.as_deref(), Some(B));
+    }
+
+    #[test]
+    fn hashdeep_keeps_every_column() {
+        let text = format!("%%%% HASHDEEP-1.0\n%%%% size,md5,sha256,filename\n3,900150983cd24fb0d6963f7d28e17f72,{},a\n", A);
+        let list = parse_hashdeep(&text, Path::new("base")).unwrap();
+        assert_eq!(list.entries[0].also, vec![(Algorithm::Md5, "900150983cd24fb0d6963f7d28e17f72".to_string())]);
+    }
+}
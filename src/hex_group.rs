@@ -0,0 +1,31 @@
+// How a hex digest is shown: in blocks separated by spaces, which is easier to compare by eye against a
+// printed checksum. Only the display changes; copied and saved digests stay ungrouped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HexGrouping(pub usize);
+
+pub const NONE: HexGrouping = HexGrouping(0);
+
+pub const GROUPINGS: [HexGrouping; 3] = [NONE, HexGrouping(4), HexGrouping(8)];
+
+impl HexGrouping {
+    // Anything but 4 or 8 from the settings file means no grouping
+    pub fn from_setting(size: usize) -> HexGrouping {
+        GROUPINGS.into_iter().find(|g| g.0 == size).unwrap_or(NONE)
+    }
+
+    pub fn apply(self, hex: &str) -> String {
+        if self.0 == 0 || !hex.is_ascii() {
+            return hex.to_string();
+        }
+        hex.as_bytes().chunks(self.0).map(|c| std::str::from_utf8(c).unwrap_or_default()).collect::<Vec<_>>().join(" ")
+    }
+}
+
+impl std::fmt::Display for HexGrouping {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            0 => write!(f, "Hex ungrouped"),
+            n => write!(f, "Hex in blocks of {}", n),
+        }
+    }
+}
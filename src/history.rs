@@ -0,0 +1,188 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+const HISTORY_FILE: &str = "history.json";
+const MAX_ENTRIES: usize = 500;
+// Files smaller than this are mostly open/close overhead and would skew the throughput figure
+const MIN_CALIBRATION_BYTES: u64 = 16 * 1024 * 1024;
+// Batches estimated to take longer than this ask before they start
+pub const CONFIRM_OVER: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub path: PathBuf,
+    pub hex: String,
+    pub bytes: u64,
+    // Unix timestamp (seconds) of when the hash finished
+    pub hashed_at: i64,
+    // Quick triage digests are only ever compared with other quick digests
+    #[serde(default)]
+    pub quick: bool,
+    // How long the hash took; 0 in entries written before this was recorded
+    #[serde(default)]
+    pub elapsed_ms: u64,
+}
+
+impl HistoryEntry {
+    pub fn hashed_at_display(&self) -> String {
+        format_timestamp(self.hashed_at)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    entries: Vec<HistoryEntry>,
+    // From the settings; None until they are applied
+    #[serde(skip)]
+    limit: Option<Limit>,
+}
+
+// How many hashes the history keeps; 0 keeps none, which also turns off "unchanged since" comparisons
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limit(pub usize);
+
+pub const LIMITS: [Limit; 5] = [Limit(0), Limit(100), Limit(MAX_ENTRIES), Limit(2000), Limit(10_000)];
+
+impl Limit {
+    pub fn from_setting(entries: usize) -> Limit {
+        LIMITS.into_iter().find(|l| l.0 == entries).unwrap_or_default()
+    }
+}
+
+impl Default for Limit {
+    fn default() -> Self {
+        Limit(MAX_ENTRIES)
+    }
+}
+
+impl std::fmt::Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            0 => write!(f, "Keep no history"),
+            n => write!(f, "Keep the last {} hashes", n),
+        }
+    }
+}
+
+impl History {
+    // Missing or unreadable history is treated as empty so the app always starts
+    pub fn load() -> Self {
+        if crate::policy::get().disable_history {
+            return History::default();
+        }
+        history_path()
+            .and_then(|p| fs::read(p).ok())
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if crate::policy::get().disable_history {
+            return Ok(());
+        }
+        let path = history_path().context("No data directory available for history")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+        }
+        let data = serde_json::to_vec_pretty(self)?;
+        fs::write(&path, data).with_context(|| format!("Failed to write history: {}", path.display()))?;
+        Ok(())
+    }
+
+    // Most recent entry for the given file and mode, if it was hashed before
+    pub fn latest_for(&self, path: &Path, quick: bool) -> Option<&HistoryEntry> {
+        self.entries.iter().rev().find(|e| e.path == path && e.quick == quick)
+    }
+
+    // Distinct files, most recently hashed first
+    pub fn recent_files(&self, max: usize) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = Vec::new();
+        for entry in self.entries.iter().rev() {
+            if files.len() == max {
+                break;
+            }
+            if !files.contains(&entry.path) {
+                files.push(entry.path.clone());
+            }
+        }
+        files
+    }
+
+    // Average bytes per second over recent full hashes of reasonably large files; None until there are some
+    pub fn throughput(&self) -> Option<f64> {
+        let timed = self.entries.iter().filter(|e| !e.quick && e.elapsed_ms > 0 && e.bytes >= MIN_CALIBRATION_BYTES);
+        let (bytes, ms) = timed.fold((0u64, 0u64), |(b, m), e| (b + e.bytes, m + e.elapsed_ms));
+        (ms > 0).then(|| bytes as f64 * 1000.0 / ms as f64)
+    }
+
+    // How long hashing `bytes` should take at the calibrated throughput
+    pub fn estimate(&self, bytes: u64) -> Option<Duration> {
+        self.throughput().map(|rate| Duration::from_secs_f64(bytes as f64 / rate))
+    }
+
+    pub fn record(&mut self, entry: HistoryEntry) {
+        if crate::policy::get().disable_history {
+            return;
+        }
+        self.entries.push(entry);
+        self.trim();
+    }
+
+    // Drops the oldest entries beyond the new limit right away, not only at the next hash
+    pub fn set_limit(&mut self, limit: Limit) {
+        self.limit = Some(limit);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        let max = self.limit.unwrap_or_default().0;
+        if self.entries.len() > max {
+            let excess = self.entries.len() - max;
+            self.entries.drain(..excess);
+        }
+    }
+}
+
+pub fn now_timestamp() -> i64 {
+    Local::now().timestamp()
+}
+
+pub fn format_timestamp(ts: i64) -> String {
+    match Local.timestamp_opt(ts, 0).single() {
+        Some(dt) => dt.format("%Y-%m-%d %H:%M").to_string(),
+        None => "unknown time".to_string(),
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    storage::data_dir().map(|d| d.join(HISTORY_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, hex: &str, hashed_at: i64, quick: bool) -> HistoryEntry {
+        HistoryEntry { path: PathBuf::from(path), hex: hex.to_string(), bytes: 3, hashed_at, quick, elapsed_ms: 0 }
+    }
+
+    // "Hash again" compares with the newest earlier digest of the same file, full with full and quick with quick
+    #[test]
+    fn latest_for_takes_the_newest_of_the_same_mode() {
+        let history = History {
+            entries: vec![entry("a", "01", 1, false), entry("a", "02", 2, true), entry("b", "03", 3, false), entry("a", "04", 4, false)],
+            limit: None,
+        };
+        assert_eq!(history.latest_for(Path::new("a"), false).map(|e| e.hex.as_str()), Some("04"));
+        assert_eq!(history.latest_for(Path::new("a"), true).map(|e| e.hex.as_str()), Some("02"));
+        assert!(history.latest_for(Path::new("b"), true).is_none());
+        assert!(history.latest_for(Path::new("c"), false).is_none());
+    }
+}
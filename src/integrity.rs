@@ -0,0 +1,94 @@
+use base64::Engine;
+
+use crate::algo::Algorithm;
+
+// A digest pasted to compare the file with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expected {
+    pub algorithm: Algorithm,
+    // Lowercase hex
+    pub hex: String,
+}
+
+// Reads what people paste from lockfiles and download pages:
+// - npm/SRI `integrity` values, `sha512-<base64>`, several separated by spaces (options after `?` are ignored)
+// - NuGet `contentHash`, the bare base64 of the package's SHA-512
+// - plain hex: MD5, SHA-1, SHA-256 or SHA-512 by length
+// Nothing is returned when the text is none of these
+pub fn parse(input: &str) -> Vec<Expected> {
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let mut found = Vec::new();
+    for token in input.split_whitespace() {
+        let token = token.split('?').next().unwrap_or(token);
+        let parsed = match token.split_once('-') {
+            Some((alg, value)) => sri_algorithm(alg).and_then(|a| Some((a, b64.decode(value).ok()?))),
+            None if is_hex(token) => hex_algorithm(token.len()).and_then(|a| Some((a, hex::decode(token).ok()?))),
+            None => b64.decode(token).ok().filter(|d| d.len() == 64).map(|d| (Algorithm::Sha512, d)),
+        };
+        if let Some((algorithm, digest)) = parsed.filter(|(a, d)| d.len() == digest_len(*a)) {
+            found.push(Expected { algorithm, hex: hex::encode(digest) });
+        }
+    }
+    found
+}
+
+// The values to check against: per SRI, only those of the strongest algorithm given
+pub fn strongest(expected: &[Expected]) -> Vec<&Expected> {
+    let best = expected.iter().map(|e| strength(e.algorithm)).max();
+    expected.iter().filter(|e| Some(strength(e.algorithm)) == best).collect()
+}
+
+// `sha512-<base64>`, as npm writes to package-lock.json
+pub fn sri(algorithm: Algorithm, hex: &str) -> Option<String> {
+    let prefix = match algorithm {
+        Algorithm::Sha512 => "sha512",
+        Algorithm::Sha256 => "sha256",
+        Algorithm::Sha1 => "sha1",
+        _ => return None,
+    };
+    let digest = hex::decode(hex).ok()?;
+    Some(format!("{}-{}", prefix, base64::engine::general_purpose::STANDARD.encode(digest)))
+}
+
+fn sri_algorithm(name: &str) -> Option<Algorithm> {
+    match name.to_ascii_lowercase().as_str() {
+        "sha512" => Some(Algorithm::Sha512),
+        "sha256" => Some(Algorithm::Sha256),
+        // Old npm lockfiles still carry these
+        "sha1" => Some(Algorithm::Sha1),
+        "md5" => Some(Algorithm::Md5),
+        _ => None,
+    }
+}
+
+fn hex_algorithm(len: usize) -> Option<Algorithm> {
+    match len {
+        32 => Some(Algorithm::Md5),
+        40 => Some(Algorithm::Sha1),
+        64 => Some(Algorithm::Sha256),
+        128 => Some(Algorithm::Sha512),
+        _ => None,
+    }
+}
+
+fn digest_len(algorithm: Algorithm) -> usize {
+    match algorithm {
+        Algorithm::Md5 => 16,
+        Algorithm::Sha1 => 20,
+        Algorithm::Sha256 | Algorithm::Blake3 => 32,
+        Algorithm::Sha512 => 64,
+    }
+}
+
+fn strength(algorithm: Algorithm) -> u8 {
+    match algorithm {
+        Algorithm::Md5 => 0,
+        Algorithm::Sha1 => 1,
+        Algorithm::Sha256 | Algorithm::Blake3 => 2,
+        Algorithm::Sha512 => 3,
+    }
+}
+
+fn is_hex(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
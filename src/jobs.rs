@@ -0,0 +1,260 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::errors::HashError;
+use crate::share_read;
+use crate::{Comparison, HashOptions, HashResult, WorkerHandles, WorkerMessage};
+
+// Queued files up to this size go to one shared worker in batches: for thousands of tiny files a thread
+// and a Tick each cost far more than the reading
+pub const SMALL_FILE: u64 = 1024 * 1024;
+// Small files handed over per Tick
+pub const SMALL_BATCH: usize = 256;
+
+// A small file for the shared worker, with the handles its job polls
+pub type SmallFile = (String, HashOptions, WorkerHandles);
+
+// Files handed to the shared worker and not started yet
+type SmallQueue = Arc<Mutex<VecDeque<SmallFile>>>;
+
+// The thread that hashes small queued files one after another, kept between batches. Batches wait in a
+// queue shared with it rather than in a channel, so files it has not started can be taken back
+#[derive(Default)]
+pub struct SmallFiles {
+    worker: Option<(Sender<()>, SmallQueue)>,
+}
+
+impl SmallFiles {
+    pub fn submit(&mut self, batch: Vec<SmallFile>) {
+        if batch.is_empty() {
+            return;
+        }
+        let (tx, queue) = self.worker.get_or_insert_with(spawn_small_worker);
+        lock(queue).extend(batch);
+        // The worker is gone only if it panicked; a new one takes over its queue
+        if tx.send(()).is_err() {
+            let files = lock(queue).drain(..).collect();
+            self.worker = None;
+            self.submit(files);
+        }
+    }
+
+    // Files the worker has not started, in queue order; the one it is on keeps going
+    pub fn take_back(&mut self) -> Vec<SmallFile> {
+        self.worker.as_ref().map(|(_, queue)| lock(queue).drain(..).collect()).unwrap_or_default()
+    }
+
+    // A read that hangs holds up every file behind it, so the next batch gets a thread of its own
+    pub fn abandon(&mut self) {
+        self.worker = None;
+    }
+}
+
+fn spawn_small_worker() -> (Sender<()>, SmallQueue) {
+    let (tx, rx) = mpsc::channel::<()>();
+    let queue = SmallQueue::default();
+    let shared = queue.clone();
+    thread::spawn(move || loop {
+        let next = lock(&shared).pop_front();
+        // Idle until the next batch; the channel only wakes the thread
+        let Some((path, opts, w)) = next else {
+            if rx.recv().is_err() {
+                break;
+            }
+            continue;
+        };
+        // Cancelled or timed out while waiting its turn; nobody reads the result
+        if w.cancel.load(Ordering::Relaxed) {
+            continue;
+        }
+        let result = crate::hash_file_now(&path, opts, w.progress, w.cancel, w.stop);
+        let _ = w.tx.send((w.token, result));
+    });
+    (tx, queue)
+}
+
+fn lock(queue: &SmallQueue) -> std::sync::MutexGuard<'_, VecDeque<SmallFile>> {
+    queue.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// A hash moved out of the main view so it keeps running while other files are hashed,
+// or a queued file waiting for its turn
+pub struct BackgroundJob {
+    pub id: u64,
+    pub path: String,
+    pub quick: bool,
+    // Queued jobs run one at a time in list order; `waiting` is true until one starts
+    pub queued: bool,
+    pub waiting: bool,
+    opts: HashOptions,
+    pub total: Option<u64>,
+    pub processed: u64,
+    progress: Arc<AtomicU64>,
+    cancel: Arc<AtomicBool>,
+    rx: Option<Receiver<WorkerMessage>>,
+    pub outcome: Option<std::result::Result<HashResult, HashError>>,
+    pub comparison: Option<Comparison>,
+    started: Option<Instant>,
+}
+
+// Longest a background job may run before it is cancelled as TIMED OUT, so a hung network mount does not
+// hold up an unattended queue; 0 for none
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct JobTimeout(pub u64);
+
+pub const TIMEOUTS: [JobTimeout; 6] = [JobTimeout(0), JobTimeout(15), JobTimeout(30), JobTimeout(60), JobTimeout(240), JobTimeout(720)];
+
+impl JobTimeout {
+    pub fn limit(self) -> Option<Duration> {
+        (self.0 > 0).then(|| Duration::from_secs(self.0 * 60))
+    }
+
+    // `15 min`, `4 h`
+    fn length(self) -> String {
+        if self.0.is_multiple_of(60) {
+            format!("{} h", self.0 / 60)
+        } else {
+            format!("{} min", self.0)
+        }
+    }
+}
+
+impl std::fmt::Display for JobTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            0 => write!(f, "No job timeout"),
+            _ => write!(f, "Job timeout {}", self.length()),
+        }
+    }
+}
+
+impl BackgroundJob {
+    pub fn new(
+        id: u64,
+        path: String,
+        quick: bool,
+        total: Option<u64>,
+        progress: Arc<AtomicU64>,
+        cancel: Arc<AtomicBool>,
+        rx: Receiver<WorkerMessage>,
+    ) -> Self {
+        let processed = progress.load(Ordering::Relaxed);
+        BackgroundJob {
+            id,
+            path,
+            quick,
+            queued: false,
+            waiting: false,
+            opts: HashOptions::default(),
+            total,
+            processed,
+            progress,
+            cancel,
+            rx: Some(rx),
+            outcome: None,
+            comparison: None,
+            started: Some(Instant::now()),
+        }
+    }
+
+    pub fn queued(id: u64, path: String, opts: HashOptions) -> Self {
+        BackgroundJob {
+            id,
+            path,
+            quick: opts.quick,
+            queued: true,
+            waiting: true,
+            opts,
+            total: None,
+            processed: 0,
+            progress: Arc::new(AtomicU64::new(0)),
+            cancel: Arc::new(AtomicBool::new(false)),
+            rx: None,
+            outcome: None,
+            comparison: None,
+            started: None,
+        }
+    }
+
+    // Whether the file can go to the shared small-file worker. Files on network shares always get a worker
+    // of their own, since a read there can hang
+    pub fn is_small(&self) -> bool {
+        let path = std::path::Path::new(&self.path);
+        !self.opts.follow && crate::expected_size(path).is_some_and(|len| len <= SMALL_FILE) && path.is_file() && !share_read::is_network_share(path)
+    }
+
+    // Starts the hash on a thread of its own
+    pub fn start(&mut self) {
+        let w = self.begin();
+        crate::spawn_file_hash(self.path.clone(), self.opts, w);
+    }
+
+    // Marks the job running and returns it for a `SmallFiles` batch
+    pub fn start_small(&mut self) -> SmallFile {
+        (self.path.clone(), self.opts, self.begin())
+    }
+
+    // Waiting again, for a file taken back from the shared worker before it started
+    pub fn requeue(&mut self) {
+        self.waiting = true;
+        self.total = None;
+        self.started = None;
+        self.rx = None;
+    }
+
+    fn begin(&mut self) -> WorkerHandles {
+        let size = crate::expected_size(std::path::Path::new(&self.path));
+        self.total = size.map(|len| if self.quick { crate::quick_sample_len(len) } else { len });
+        self.waiting = false;
+        self.started = Some(Instant::now());
+        let (mut w, rx) = WorkerHandles::new(self.id);
+        w.progress = self.progress.clone();
+        w.cancel = self.cancel.clone();
+        self.rx = Some(rx);
+        w
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.rx.is_some()
+    }
+
+    // Refreshes progress; true the one time the result arrives, or when the job runs past `timeout` and is
+    // cancelled. A worker stuck in a read may linger, but its result is no longer waited for
+    pub fn poll(&mut self, timeout: JobTimeout) -> bool {
+        self.processed = self.progress.load(Ordering::Relaxed);
+        if let (Some(limit), Some(started)) = (timeout.limit(), self.started) {
+            if self.is_running() && started.elapsed() >= limit {
+                self.cancel.store(true, Ordering::Relaxed);
+                self.rx = None;
+                self.outcome = Some(Err(HashError::timed_out(std::path::Path::new(&self.path), timeout.length())));
+                return true;
+            }
+        }
+        let Some(Ok((_, result))) = self.rx.as_ref().map(|rx| rx.try_recv()) else {
+            return false;
+        };
+        self.rx = None;
+        self.outcome = Some(result);
+        true
+    }
+
+    pub fn cancel(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+        self.waiting = false;
+        self.rx = None;
+        self.outcome = Some(Err(HashError::cancelled()));
+    }
+
+    // Bytes per second since the job started
+    pub fn rate(&self) -> Option<f64> {
+        self.started.map(|at| self.processed as f64 / at.elapsed().as_secs_f64().max(0.001))
+    }
+
+    pub fn percent(&self) -> Option<f64> {
+        self.total.filter(|t| *t > 0).map(|t| (self.processed as f64 / t as f64 * 100.0).clamp(0.0, 100.0))
+    }
+}
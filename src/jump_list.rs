@@ -0,0 +1,244 @@
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+
+// Arguments of the Jump List tasks; main starts the window at the task instead of hashing a file
+pub const VERIFY_LIST_FLAG: &str = "--verify-list";
+pub const TEXT_HASH_FLAG: &str = "--text-hash";
+// Files under the "Recently hashed" heading
+pub const MAX_RECENT: usize = 8;
+
+// The taskbar Jump List: recently hashed files and the common starting points, two clicks from the
+// taskbar. Rebuilt on a thread of its own, since the shell can take a moment; updates that arrive while
+// one is being written are folded into the next. Windows only
+pub struct JumpList {
+    tx: Sender<Vec<PathBuf>>,
+}
+
+impl JumpList {
+    #[cfg(windows)]
+    pub fn start() -> Option<JumpList> {
+        let (tx, rx) = std::sync::mpsc::channel::<Vec<PathBuf>>();
+        std::thread::spawn(move || {
+            use windows_sys::Win32::System::Com::{CoInitializeEx, CoUninitialize, COINIT_APARTMENTTHREADED};
+
+            let initialized = unsafe { CoInitializeEx(std::ptr::null(), COINIT_APARTMENTTHREADED as u32) } >= 0;
+            while let Ok(mut recent) = rx.recv() {
+                while let Ok(newer) = rx.try_recv() {
+                    recent = newer;
+                }
+                // An outdated list is all a failure costs, so it is not reported
+                let _ = unsafe { shell::build(&recent) };
+            }
+            if initialized {
+                unsafe { CoUninitialize() };
+            }
+        });
+        Some(JumpList { tx })
+    }
+
+    #[cfg(not(windows))]
+    pub fn start() -> Option<JumpList> {
+        None
+    }
+
+    // Newest first
+    pub fn update(&self, recent: Vec<PathBuf>) {
+        let _ = self.tx.send(recent);
+    }
+}
+
+// ICustomDestinationList and friends. windows-sys has no COM interfaces, so their methods are called
+// through the object's function table by position, in the order the Windows SDK headers declare them
+#[cfg(windows)]
+mod shell {
+    use std::ffi::c_void;
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+
+    use anyhow::{anyhow, Result};
+    use windows_sys::core::{GUID, HRESULT};
+    use windows_sys::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
+    use windows_sys::Win32::UI::Shell::{DestinationList, EnumerableObjectCollection, ShellLink};
+
+    const IID_CUSTOM_DESTINATION_LIST: GUID = GUID::from_u128(0x6332debf_87b5_4670_90c0_5e57b408a49e);
+    const IID_OBJECT_ARRAY: GUID = GUID::from_u128(0x92ca9dcd_5622_4bba_a805_5e9f541bd8c9);
+    const IID_OBJECT_COLLECTION: GUID = GUID::from_u128(0x5632b1a4_e38a_400a_928a_d4cd63230295);
+    const IID_SHELL_LINK: GUID = GUID::from_u128(0x000214f9_0000_0000_c000_000000000046);
+    const IID_PROPERTY_STORE: GUID = GUID::from_u128(0x886d8eeb_8cf2_4446_8d02_cdba1dbdcf99);
+    // PKEY_Title, the text a Jump List shows for a link
+    const TITLE: PropertyKey = PropertyKey { fmtid: GUID::from_u128(0xf29f85e0_4ff9_1068_ab91_08002b27b3d9), pid: 2 };
+    const VT_LPWSTR: u16 = 31;
+
+    // Method positions; 0-2 are IUnknown's
+    const QUERY_INTERFACE: usize = 0;
+    const RELEASE: usize = 2;
+    const OBJECT_ARRAY_GET_COUNT: usize = 3;
+    const OBJECT_ARRAY_GET_AT: usize = 4;
+    const COLLECTION_ADD_OBJECT: usize = 5;
+    const LIST_BEGIN_LIST: usize = 4;
+    const LIST_APPEND_CATEGORY: usize = 5;
+    const LIST_ADD_USER_TASKS: usize = 7;
+    const LIST_COMMIT_LIST: usize = 8;
+    const LIST_ABORT_LIST: usize = 11;
+    const LINK_SET_DESCRIPTION: usize = 7;
+    const LINK_GET_ARGUMENTS: usize = 10;
+    const LINK_SET_ARGUMENTS: usize = 11;
+    const LINK_SET_ICON_LOCATION: usize = 17;
+    const LINK_SET_PATH: usize = 20;
+    const STORE_SET_VALUE: usize = 6;
+    const STORE_COMMIT: usize = 7;
+
+    #[repr(C)]
+    struct PropertyKey {
+        fmtid: GUID,
+        pid: u32,
+    }
+
+    // PROPVARIANT holding a string: the type, three reserved words, then a 16-byte union on 64-bit
+    #[repr(C)]
+    struct PropVariant {
+        vt: u16,
+        reserved: [u16; 3],
+        value: *const u16,
+        padding: usize,
+    }
+
+    // An interface pointer, released when dropped
+    struct Com(*mut c_void);
+
+    impl Com {
+        unsafe fn method(&self, index: usize) -> *const c_void {
+            let table = *(self.0 as *const *const *const c_void);
+            *table.add(index)
+        }
+
+        unsafe fn create(class: &GUID, interface: &GUID) -> Result<Com> {
+            let mut object = std::ptr::null_mut();
+            check(CoCreateInstance(class, std::ptr::null_mut(), CLSCTX_INPROC_SERVER, interface, &mut object))?;
+            Ok(Com(object))
+        }
+
+        unsafe fn query(&self, interface: &GUID) -> Result<Com> {
+            let f: unsafe extern "system" fn(*mut c_void, *const GUID, *mut *mut c_void) -> HRESULT = std::mem::transmute(self.method(QUERY_INTERFACE));
+            let mut object = std::ptr::null_mut();
+            check(f(self.0, interface, &mut object))?;
+            Ok(Com(object))
+        }
+    }
+
+    impl Drop for Com {
+        fn drop(&mut self) {
+            unsafe {
+                let f: unsafe extern "system" fn(*mut c_void) -> u32 = std::mem::transmute(self.method(RELEASE));
+                f(self.0);
+            }
+        }
+    }
+
+    fn check(hr: HRESULT) -> Result<()> {
+        if hr < 0 {
+            Err(anyhow!("Jump List call failed: 0x{:08x}", hr))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    // Windows paths cannot contain quotes, so plain quoting is enough
+    fn argument(path: &Path) -> String {
+        format!("\"{}\"", path.display())
+    }
+
+    pub unsafe fn build(recent: &[PathBuf]) -> Result<()> {
+        let list = Com::create(&DestinationList, &IID_CUSTOM_DESTINATION_LIST)?;
+        let begin: unsafe extern "system" fn(*mut c_void, *mut u32, *const GUID, *mut *mut c_void) -> HRESULT = std::mem::transmute(list.method(LIST_BEGIN_LIST));
+        let mut slots = 0u32;
+        let mut removed = std::ptr::null_mut();
+        check(begin(list.0, &mut slots, &IID_OBJECT_ARRAY, &mut removed))?;
+        let removed = Com(removed);
+        let result = fill(&list, recent, &removed_arguments(&removed), slots as usize);
+        if result.is_err() {
+            let abort: unsafe extern "system" fn(*mut c_void) -> HRESULT = std::mem::transmute(list.method(LIST_ABORT_LIST));
+            abort(list.0);
+        }
+        result
+    }
+
+    unsafe fn fill(list: &Com, recent: &[PathBuf], removed: &[String], slots: usize) -> Result<()> {
+        let exe = std::env::current_exe()?;
+        // Files the user removed from the list must stay off it; the shell refuses the category otherwise
+        let files: Vec<&PathBuf> = recent.iter().filter(|p| p.is_file() && !removed.contains(&argument(p))).take(slots.min(super::MAX_RECENT)).collect();
+        if !files.is_empty() {
+            let category = Com::create(&EnumerableObjectCollection, &IID_OBJECT_COLLECTION)?;
+            for file in files {
+                let title = file.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| file.display().to_string());
+                add(&category, &link(&exe, &argument(file), &title, &file.display().to_string())?)?;
+            }
+            let append: unsafe extern "system" fn(*mut c_void, *const u16, *mut c_void) -> HRESULT = std::mem::transmute(list.method(LIST_APPEND_CATEGORY));
+            check(append(list.0, wide("Recently hashed").as_ptr(), category.0))?;
+        }
+        let tasks = Com::create(&EnumerableObjectCollection, &IID_OBJECT_COLLECTION)?;
+        add(&tasks, &link(&exe, super::VERIFY_LIST_FLAG, "Verify manifest...", "Verify files against a checksum list or copy log")?)?;
+        add(&tasks, &link(&exe, super::TEXT_HASH_FLAG, "Text hash", "SHA-256 of typed or pasted text")?)?;
+        let add_tasks: unsafe extern "system" fn(*mut c_void, *mut c_void) -> HRESULT = std::mem::transmute(list.method(LIST_ADD_USER_TASKS));
+        check(add_tasks(list.0, tasks.0))?;
+        let commit: unsafe extern "system" fn(*mut c_void) -> HRESULT = std::mem::transmute(list.method(LIST_COMMIT_LIST));
+        check(commit(list.0))
+    }
+
+    // A link that starts this executable with `arguments`
+    unsafe fn link(exe: &Path, arguments: &str, title: &str, tooltip: &str) -> Result<Com> {
+        let link = Com::create(&ShellLink, &IID_SHELL_LINK)?;
+        let exe_wide: Vec<u16> = exe.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+        let set_text = |index: usize, value: &[u16]| -> Result<()> {
+            let f: unsafe extern "system" fn(*mut c_void, *const u16) -> HRESULT = std::mem::transmute(link.method(index));
+            check(f(link.0, value.as_ptr()))
+        };
+        set_text(LINK_SET_PATH, &exe_wide)?;
+        set_text(LINK_SET_ARGUMENTS, &wide(arguments))?;
+        set_text(LINK_SET_DESCRIPTION, &wide(tooltip))?;
+        let set_icon: unsafe extern "system" fn(*mut c_void, *const u16, i32) -> HRESULT = std::mem::transmute(link.method(LINK_SET_ICON_LOCATION));
+        check(set_icon(link.0, exe_wide.as_ptr(), 0))?;
+        let store = link.query(&IID_PROPERTY_STORE)?;
+        let title = wide(title);
+        let value = PropVariant { vt: VT_LPWSTR, reserved: [0; 3], value: title.as_ptr(), padding: 0 };
+        let set_value: unsafe extern "system" fn(*mut c_void, *const PropertyKey, *const PropVariant) -> HRESULT = std::mem::transmute(store.method(STORE_SET_VALUE));
+        check(set_value(store.0, &TITLE, &value))?;
+        let commit: unsafe extern "system" fn(*mut c_void) -> HRESULT = std::mem::transmute(store.method(STORE_COMMIT));
+        check(commit(store.0))?;
+        Ok(link)
+    }
+
+    unsafe fn add(collection: &Com, object: &Com) -> Result<()> {
+        let f: unsafe extern "system" fn(*mut c_void, *mut c_void) -> HRESULT = std::mem::transmute(collection.method(COLLECTION_ADD_OBJECT));
+        check(f(collection.0, object.0))
+    }
+
+    // Arguments of the links the user removed from the list since it was last written
+    unsafe fn removed_arguments(removed: &Com) -> Vec<String> {
+        let count: unsafe extern "system" fn(*mut c_void, *mut u32) -> HRESULT = std::mem::transmute(removed.method(OBJECT_ARRAY_GET_COUNT));
+        let get: unsafe extern "system" fn(*mut c_void, u32, *const GUID, *mut *mut c_void) -> HRESULT = std::mem::transmute(removed.method(OBJECT_ARRAY_GET_AT));
+        let mut n = 0u32;
+        if count(removed.0, &mut n) < 0 {
+            return Vec::new();
+        }
+        let mut arguments = Vec::new();
+        for i in 0..n {
+            let mut object = std::ptr::null_mut();
+            if get(removed.0, i, &IID_SHELL_LINK, &mut object) < 0 {
+                continue;
+            }
+            let link = Com(object);
+            let f: unsafe extern "system" fn(*mut c_void, *mut u16, i32) -> HRESULT = std::mem::transmute(link.method(LINK_GET_ARGUMENTS));
+            let mut buffer = [0u16; 1024];
+            if f(link.0, buffer.as_mut_ptr(), buffer.len() as i32) >= 0 {
+                let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+                arguments.push(String::from_utf16_lossy(&buffer[..len]));
+            }
+        }
+        arguments
+    }
+}
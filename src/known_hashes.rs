@@ -0,0 +1,112 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use anyhow::{anyhow, Context, Result};
+
+// Reference set of SHA-256 digests (e.g. an NSRL RDS export or a vendor list) that results are checked against
+pub struct KnownSet {
+    pub source: PathBuf,
+    hashes: HashSet<[u8; 32]>,
+}
+
+impl KnownSet {
+    // Accepts plain lists, sha256sum output and CSV/TSV: every 64-digit hex field on a line counts,
+    // so header rows, file names and other digest columns are skipped
+    pub fn load(path: &Path) -> Result<KnownSet> {
+        let file = File::open(path).with_context(|| format!("Failed to open known-hash set: {}", path.display()))?;
+        let mut hashes = HashSet::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.with_context(|| format!("Failed to read known-hash set: {}", path.display()))?;
+            for field in line.split(|c: char| c == ',' || c == ';' || c == '\t' || c.is_whitespace()) {
+                let field = field.trim_matches(|c| c == '"' || c == '\'' || c == '*');
+                if field.len() != 64 {
+                    continue;
+                }
+                let mut digest = [0u8; 32];
+                if hex::decode_to_slice(field, &mut digest).is_ok() {
+                    hashes.insert(digest);
+                }
+            }
+        }
+        if hashes.is_empty() {
+            // NSRL RDS 2.x text files only carry SHA-1, MD5 and CRC32
+            return Err(anyhow!("No SHA-256 values found in {}", path.display()));
+        }
+        Ok(KnownSet { source: path.to_path_buf(), hashes })
+    }
+
+    pub fn contains(&self, hex_digest: &str) -> bool {
+        let mut digest = [0u8; 32];
+        hex::decode_to_slice(hex_digest.trim(), &mut digest).is_ok() && self.hashes.contains(&digest)
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn name(&self) -> String {
+        self.source.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| self.source.display().to_string())
+    }
+}
+
+// Which reference list a set is loaded as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListKind {
+    // Known-good files (NSRL, allowlists): results are flagged KNOWN or UNKNOWN
+    Known,
+    // Indicators of compromise: matching results are highlighted as BLOCKLISTED
+    Blocklist,
+}
+
+impl ListKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ListKind::Known => "Known-hash set",
+            ListKind::Blocklist => "Blocklist",
+        }
+    }
+}
+
+// A loaded set, or one still being read on a worker thread (large NSRL exports take a while)
+#[derive(Default)]
+pub struct ListSlot {
+    set: Option<KnownSet>,
+    loading: Option<Receiver<Result<KnownSet>>>,
+}
+
+impl ListSlot {
+    pub fn load(&mut self, path: PathBuf) {
+        let (tx, rx) = mpsc::channel();
+        self.loading = Some(rx);
+        thread::spawn(move || {
+            let _ = tx.send(KnownSet::load(&path));
+        });
+    }
+
+    // Some once a load finishes: the new set, or why it could not be read
+    pub fn poll(&mut self) -> Option<Result<&KnownSet>> {
+        let loaded = self.loading.as_ref()?.try_recv().ok()?;
+        self.loading = None;
+        match loaded {
+            Ok(set) => Some(Ok(self.set.insert(set))),
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    pub fn get(&self) -> Option<&KnownSet> {
+        self.set.as_ref()
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.loading.is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.set = None;
+        self.loading = None;
+    }
+}
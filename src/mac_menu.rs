@@ -0,0 +1,223 @@
+use std::path::PathBuf;
+
+use iced::Subscription;
+
+// What the menu bar and the Dock ask of the window; only macOS sends any
+#[derive(Debug, Clone)]
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub enum Action {
+    About,
+    Preferences,
+    Open,
+    Quit,
+    // Files dropped on the Dock icon, or opened with the app from Finder
+    OpenFiles(Vec<PathBuf>),
+}
+
+// Menu bar and Dock actions as they happen; nothing outside macOS
+#[cfg(target_os = "macos")]
+pub fn actions() -> Subscription<Action> {
+    struct Actions;
+    iced::subscription::channel(std::any::TypeId::of::<Actions>(), 16, |output| async move {
+        appkit::listen(output);
+        std::future::pending::<iced::futures::never::Never>().await
+    })
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn actions() -> Subscription<Action> {
+    Subscription::none()
+}
+
+// Files opened from Finder at launch arrive before the event loop runs, so the Dock handler goes in
+// from App::new; the menu waits for `install_menu`, since winit replaces the menu bar once launched
+#[cfg(target_os = "macos")]
+pub fn install_open_handler() {
+    unsafe { appkit::add_open_files() }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn install_open_handler() {}
+
+#[cfg(target_os = "macos")]
+pub fn install_menu() {
+    unsafe { appkit::set_menu() }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn install_menu() {}
+
+// The app menu (About, Preferences ⌘,, Hide, Quit ⌘Q), File (Open ⌘O, Close ⌘W) and Window
+// (Minimize ⌘M, Zoom), built through the Objective-C runtime. Items that AppKit handles by itself
+// go to the first responder; the others reach the app as an `Action`
+#[cfg(target_os = "macos")]
+mod appkit {
+    use std::ffi::{CStr, CString};
+    use std::os::raw::c_char;
+    use std::path::PathBuf;
+    use std::sync::{Mutex, Once};
+
+    use iced::futures::channel::mpsc::Sender;
+    use objc::declare::ClassDecl;
+    use objc::runtime::{class_addMethod, Class, Object, Sel};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    use super::Action;
+
+    #[allow(non_camel_case_types)]
+    type id = *mut Object;
+    const NIL: id = std::ptr::null_mut();
+    // NSEventModifierFlagOption | NSEventModifierFlagCommand
+    const OPTION_COMMAND: usize = (1 << 19) | (1 << 20);
+    const REPLY_SUCCESS: usize = 0;
+
+    // Tags of the items that become an `Action`
+    const TAG_PREFERENCES: isize = 1;
+    const TAG_OPEN: isize = 2;
+    const TAG_QUIT: isize = 3;
+    const TAG_ABOUT: isize = 4;
+
+    // Actions wait here until the subscription listens; files opened at launch come before it does
+    static PENDING: Mutex<Vec<Action>> = Mutex::new(Vec::new());
+    static SENDER: Mutex<Option<Sender<Action>>> = Mutex::new(None);
+
+    pub fn listen(mut output: Sender<Action>) {
+        let mut sender = SENDER.lock().unwrap_or_else(|e| e.into_inner());
+        for action in PENDING.lock().unwrap_or_else(|e| e.into_inner()).drain(..) {
+            let _ = output.try_send(action);
+        }
+        *sender = Some(output);
+    }
+
+    fn send(action: Action) {
+        let mut sender = SENDER.lock().unwrap_or_else(|e| e.into_inner());
+        match sender.as_mut() {
+            Some(tx) => {
+                let _ = tx.try_send(action);
+            }
+            None => PENDING.lock().unwrap_or_else(|e| e.into_inner()).push(action),
+        }
+    }
+
+    unsafe fn ns_string(s: &str) -> id {
+        let c = CString::new(s).unwrap_or_default();
+        msg_send![class!(NSString), stringWithUTF8String: c.as_ptr()]
+    }
+
+    // `application:openFiles:` on winit's application delegate, which has no such method of its own
+    pub unsafe fn add_open_files() {
+        extern "C" fn open_files(_this: &Object, _sel: Sel, app: id, files: id) {
+            unsafe {
+                let count: usize = msg_send![files, count];
+                let paths = (0..count)
+                    .filter_map(|i| {
+                        let file: id = msg_send![files, objectAtIndex: i];
+                        let utf8: *const c_char = msg_send![file, UTF8String];
+                        (!utf8.is_null()).then(|| PathBuf::from(CStr::from_ptr(utf8).to_string_lossy().into_owned()))
+                    })
+                    .collect();
+                send(Action::OpenFiles(paths));
+                let _: () = msg_send![app, replyToOpenOrPrint: REPLY_SUCCESS];
+            }
+        }
+
+        let app: id = msg_send![class!(NSApplication), sharedApplication];
+        let delegate: id = msg_send![app, delegate];
+        if delegate.is_null() {
+            return;
+        }
+        let class: *mut Class = msg_send![delegate, class];
+        let types = CString::new("v@:@@").unwrap_or_default();
+        let imp: extern "C" fn(&Object, Sel, id, id) = open_files;
+        class_addMethod(class, sel!(application:openFiles:), std::mem::transmute::<extern "C" fn(&Object, Sel, id, id), objc::runtime::Imp>(imp), types.as_ptr());
+    }
+
+    // Receives the items with a tag and turns them into actions
+    fn target_class() -> &'static Class {
+        static REGISTER: Once = Once::new();
+        REGISTER.call_once(|| {
+            extern "C" fn chosen(_this: &Object, _sel: Sel, item: id) {
+                let tag: isize = unsafe { msg_send![item, tag] };
+                match tag {
+                    TAG_PREFERENCES => send(Action::Preferences),
+                    TAG_OPEN => send(Action::Open),
+                    TAG_QUIT => send(Action::Quit),
+                    TAG_ABOUT => send(Action::About),
+                    _ => {}
+                }
+            }
+            if let Some(mut decl) = ClassDecl::new("RustHashMenuTarget", class!(NSObject)) {
+                unsafe {
+                    decl.add_method(sel!(chosen:), chosen as extern "C" fn(&Object, Sel, id));
+                }
+                decl.register();
+            }
+        });
+        class!(RustHashMenuTarget)
+    }
+
+    unsafe fn item(menu: id, title: &str, action: Option<Sel>, key: &str, target: id) -> id {
+        let action = action.unwrap_or_else(|| Sel::from_ptr(std::ptr::null()));
+        let item: id = msg_send![class!(NSMenuItem), alloc];
+        let item: id = msg_send![item, initWithTitle: ns_string(title) action: action keyEquivalent: ns_string(key)];
+        let _: () = msg_send![item, setTarget: target];
+        let _: () = msg_send![menu, addItem: item];
+        let _: () = msg_send![item, release];
+        item
+    }
+
+    // An item that comes back as an action
+    unsafe fn tagged(menu: id, title: &str, key: &str, target: id, tag: isize) {
+        let item = item(menu, title, Some(sel!(chosen:)), key, target);
+        let _: () = msg_send![item, setTag: tag];
+    }
+
+    unsafe fn separator(menu: id) {
+        let item: id = msg_send![class!(NSMenuItem), separatorItem];
+        let _: () = msg_send![menu, addItem: item];
+    }
+
+    // A top-level menu with its entry in the menu bar
+    unsafe fn submenu(bar: id, title: &str) -> id {
+        let menu: id = msg_send![class!(NSMenu), alloc];
+        let menu: id = msg_send![menu, initWithTitle: ns_string(title)];
+        let entry = item(bar, title, None, "", NIL);
+        let _: () = msg_send![entry, setSubmenu: menu];
+        let _: () = msg_send![menu, release];
+        menu
+    }
+
+    pub unsafe fn set_menu() {
+        let app: id = msg_send![class!(NSApplication), sharedApplication];
+        // Lives as long as the app; menu items do not retain their target
+        let target: id = msg_send![target_class(), new];
+        let bar: id = msg_send![class!(NSMenu), new];
+
+        let app_menu = submenu(bar, "Rust Hash");
+        // The app's own About page, which has the build details the standard panel lacks
+        tagged(app_menu, "About Rust Hash", "", target, TAG_ABOUT);
+        separator(app_menu);
+        tagged(app_menu, "Preferences…", ",", target, TAG_PREFERENCES);
+        separator(app_menu);
+        item(app_menu, "Hide Rust Hash", Some(sel!(hide:)), "h", NIL);
+        let others = item(app_menu, "Hide Others", Some(sel!(hideOtherApplications:)), "h", NIL);
+        let _: () = msg_send![others, setKeyEquivalentModifierMask: OPTION_COMMAND];
+        item(app_menu, "Show All", Some(sel!(unhideAllApplications:)), "", NIL);
+        separator(app_menu);
+        // Through the app, so quitting with work in flight asks first like closing the window does
+        tagged(app_menu, "Quit Rust Hash", "q", target, TAG_QUIT);
+
+        let file_menu = submenu(bar, "File");
+        tagged(file_menu, "Open…", "o", target, TAG_OPEN);
+        separator(file_menu);
+        item(file_menu, "Close Window", Some(sel!(performClose:)), "w", NIL);
+
+        let window_menu = submenu(bar, "Window");
+        item(window_menu, "Minimize", Some(sel!(performMiniaturize:)), "m", NIL);
+        item(window_menu, "Zoom", Some(sel!(performZoom:)), "", NIL);
+
+        let _: () = msg_send![app, setMainMenu: bar];
+        let _: () = msg_send![app, setWindowsMenu: window_menu];
+        let _: () = msg_send![bar, release];
+    }
+}
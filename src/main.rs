@@ -1,12 +1,13 @@
 #![cfg_attr(all(windows, not(debug_assertions)), windows_subsystem = "windows")]
 
+use std::collections::VecDeque;
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::path::PathBuf;
 use std::time::{Duration, Instant};
 use std::path::Path;
 use std::sync::{Arc, atomic::{AtomicBool, AtomicU64, Ordering}};
-use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
 use std::thread;
 
 use anyhow::{Context, Result};
@@ -14,20 +15,173 @@ use base64::Engine as _;
 use iced::alignment::{Horizontal, Vertical};
 use iced::executor;
 use iced::theme;
-use iced::widget::{button, checkbox, column, container, row, scrollable, text, text_input};
+use iced::widget::{button, checkbox, column, container, pick_list, row, scrollable, text, text_input, tooltip, Text};
 use iced::{clipboard, event, window, Application, Command, Element, Length, Settings, Subscription, Theme, Size};
 // time subscription for periodic UI updates
 use rfd::FileDialog;
 use sha2::{Digest, Sha256};
 
+mod about;
+mod acknowledged;
+mod acquisition;
+mod algo;
+mod archive;
+mod archive_digest;
+mod atomic;
+mod authenticode;
+mod bindiff;
+mod chunking;
+mod cli;
+mod cloud;
+mod config;
+mod content_id;
+mod copy_verify;
+mod custody;
+mod desktop;
+mod disc_image;
+mod elevate;
+mod email;
+mod errors;
+mod ewf;
+mod filetype;
+mod first_run;
+mod folder_choices;
+mod folder_diff;
+mod forensic;
+mod gpu;
+mod hashlist;
+mod integrity;
+mod hex_group;
+mod history;
+mod jobs;
+mod jump_list;
+mod mac_menu;
+mod manifest;
+mod meta_digest;
+mod known_hashes;
+mod palette;
+mod pairing;
+mod par2;
+mod partitions;
+mod path_norm;
+mod placement;
+mod policy;
+mod path_status;
+mod pdf;
+mod post_actions;
+mod power;
+mod progress;
+mod read_buffer;
+mod remote;
+mod report;
+mod rsync_sig;
+mod saved_queue;
+mod segments;
+mod share_read;
+mod shell_integration;
+mod sidecars;
+mod sparse;
+mod snapshot_diff;
+mod special;
+mod staging;
+mod storage;
+mod tiger;
+mod timing;
+mod tree_digest;
+mod uri;
+mod usage;
+mod vdisk;
+mod verify;
+mod webhook;
+mod xattrs;
+
+use authenticode::AuthenticodeHasher;
+use chunking::ChunkEntry;
+use cloud::{CloudDigests, CloudHasher};
+use config::Config;
+use content_id::CidBuilder;
+use errors::{ErrorAction, ErrorKind, HashError};
+use filetype::FileType;
+use hex_group::HexGrouping;
+use history::{History, HistoryEntry};
+use jobs::{BackgroundJob, JobTimeout};
+use known_hashes::{KnownSet, ListKind, ListSlot};
+use palette::{Palette, Status};
+use read_buffer::ReadBuffer;
+use path_status::PathStatus;
+use post_actions::PostActions;
+use rsync_sig::SignatureBuilder;
+use segments::{SegmentEntry, SegmentHasher, SegmentSize};
+use special::SpecialFile;
+
 const BUFFER_SIZE: usize = 2 * 1024 * 1024; // 2 MB buffer
+// Quick triage mode hashes this many MiB from the start and from the end of the file
+const QUICK_SAMPLE_MIB: u64 = 16;
+const QUICK_SAMPLE_SIZE: u64 = QUICK_SAMPLE_MIB * 1024 * 1024;
+// How often follow mode checks a growing file for new data once it reached EOF
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(500);
+// A hash that reads nothing for this long is reported as stalled (dead network share, removed drive)
+const STALL_TIMEOUT: Duration = Duration::from_secs(15);
+// Auto-hash waits this long after the last keystroke in the path input
+const AUTO_HASH_DEBOUNCE: Duration = Duration::from_millis(500);
+// The typed path is looked at once typing pauses this long
+const PATH_CHECK_DEBOUNCE: Duration = Duration::from_millis(200);
+// Length of the drop zone flash after a file is dropped
+const DROP_FLASH: Duration = Duration::from_millis(600);
+// Logical pixels, so the layout keeps its room on high-DPI monitors
+const DEFAULT_WINDOW: Size = Size::new(900.0, 560.0);
+const MIN_WINDOW: Size = Size::new(900.0, 420.0);
+// The window keeps refreshing this long after the last input or running job, for notices that arrive late
+// (webhook and email replies); after that it sleeps until something happens
+const TICK_GRACE: Duration = Duration::from_secs(30);
+// How often the power source is checked while the window is refreshing
+const POWER_CHECK: Duration = Duration::from_secs(30);
+// A moved or resized window is saved once it has been still this long
+const PLACEMENT_SETTLE: Duration = Duration::from_secs(1);
+// The path field takes focus on start, so keyboard users begin where a hash starts
+static PATH_INPUT: once_cell::sync::Lazy<text_input::Id> = once_cell::sync::Lazy::new(|| text_input::Id::new("path"));
+// The text field, which the Jump List's "Text hash" task focuses
+static TEXT_INPUT: once_cell::sync::Lazy<text_input::Id> = once_cell::sync::Lazy::new(|| text_input::Id::new("text"));
+
+// What the window starts with: a file from the command line, or a Jump List task
+#[derive(Debug, Default)]
+enum Launch {
+    #[default]
+    Nothing,
+    Hash(PathBuf),
+    VerifyList,
+    TextHash,
+}
 
 fn main() -> iced::Result {
+    let args: Vec<std::ffi::OsString> = std::env::args_os().collect();
+    if args.get(1).is_some_and(|a| a == elevate::HELPER_FLAG) {
+        std::process::exit(run_elevated_helper(&args[2..]));
+    }
+    if args.get(1).is_some_and(|a| a == cli::CLI_FLAG) {
+        std::process::exit(cli::run(&args[2..]));
+    }
     let mut settings = Settings::default();
-    settings.window.size = Size::new(900.0, 560.0);
+    settings.window.size = DEFAULT_WINDOW;
     settings.window.resizable = true;
-    settings.window.min_size = Some(Size::new(900.0, 420.0));
+    settings.window.min_size = Some(MIN_WINDOW);
     settings.window.position = window::Position::Centered;
+    // Reopen where it was closed, in logical pixels so the size holds on a monitor with another scale
+    if let Some(p) = placement::Placements::load().current() {
+        settings.window.size = Size::new((p.width as f32).max(MIN_WINDOW.width), (p.height as f32).max(MIN_WINDOW.height));
+        if let Some((x, y)) = p.position {
+            settings.window.position = window::Position::Specific(iced::Point::new(x as f32, y as f32));
+        }
+    }
+    // Closing while work is in flight asks first
+    settings.window.exit_on_close_request = false;
+    // A file path argument is hashed on startup
+    settings.flags = match args.get(1) {
+        Some(a) if a == jump_list::VERIFY_LIST_FLAG => Launch::VerifyList,
+        Some(a) if a == jump_list::TEXT_HASH_FLAG => Launch::TextHash,
+        Some(a) => Launch::Hash(PathBuf::from(a)),
+        None => Launch::Nothing,
+    };
     // Try to set window icon from env/paths, then embedded ICO fallback
     settings.window.icon = try_load_icon_from_env()
         .or_else(|| try_load_icon_from_paths())
@@ -38,19 +192,199 @@ fn main() -> iced::Result {
 #[derive(Debug, Clone)]
 enum Message {
     PathChanged(String),
+    PathChecked(String, PathStatus),
     BrowsePressed,
+    BrowseFolderPressed,
+    CloseRequested,
+    CloseConfirmed(bool),
+    WindowMoved(i32, i32),
+    WindowResized(u32, u32),
+    MonitorChecked(String),
     ClearPressed,
     CancelPressed,
+    StopFollowPressed,
+    BackgroundPressed,
+    JobCancel(u64),
+    JobClose(u64),
+    JobCopyHex(u64),
+    KeepWaitingPressed,
+    RehashPressed,
     CopyHex,
     CopyBase64,
+    CopyCid,
+    CopyAuthenticode,
+    CopyMetadataDigest,
+    CopyContentMd5,
+    CopyCrc32c,
+    CopyPreferredDigest,
+    ShareChecksumPressed,
+    ChecksumShared(std::result::Result<String, String>),
+    CopyNiPressed,
+    CopyMultihashPressed(content_id::Multibase),
+    CopyMagnetPressed,
+    CopyIntegrityPressed,
+    CopyTreeDigestPressed,
+    ExpectedChanged(String),
+    UnpairPressed,
+    PublishSidecarsPressed,
+    CompareWithPressed,
+    VerifiedCopyPressed,
+    CopyCancelPressed,
+    CopyClosePressed,
+    CompareFoldersPressed,
+    FolderCompareCancelPressed,
+    FolderCompareClosePressed,
+    DiffBaselinesPressed,
+    SnapshotDiffClosePressed,
+    DryRunListPressed,
+    EditManifestPressed,
+    WatchStagingPressed,
+    RemoteTargetChanged(String),
+    HashRemotePressed,
+    CancelRemotePressed,
+    CopyRemoteDigest,
+    HashTextChanged(String),
+    InstallMenu,
+    MenuAction(mac_menu::Action),
+    CopyTextDigest,
+    StopStagingPressed,
+    // Line index in the list, selected or not
+    ManifestEntryToggled(usize, bool),
+    ManifestRemovePressed,
+    ManifestRehashPressed,
+    ManifestAddFilesPressed,
+    ManifestSavePressed,
+    ManifestCancelPressed,
+    ManifestClosePressed,
+    DryRunClosePressed,
+    SlowestToggled,
+    MoreToolsToggled,
+    VerifyListPressed,
+    TrySamplePressed,
+    FocusNext,
+    FocusPrevious,
+    AcquisitionPressed,
+    AcquisitionCancelPressed,
+    AcquisitionClosePressed,
+    VerifyPar2Pressed,
+    VerifyCancelPressed,
+    VerifyClosePressed,
+    BinaryDiffToggled(bool),
+    ArchiveCheckToggled(bool),
+    ArchiveDigestToggled(bool),
+    PartitionsToggled(bool),
+    FolderChoicesToggled(bool),
+    JobTimeoutSelected(JobTimeout),
+    SavePartitionsPressed,
+    CopyArchiveDigest,
+    DiscContentsToggled(bool),
+    PayloadToggled(bool),
+    MatchSeparatorsToggled(bool),
+    MatchIgnoreCaseToggled(bool),
+    MatchUnicodeToggled(bool),
+    SkipUnopenableToggled(bool),
+    RelativeResultsToggled(bool),
+    TreeXattrsToggled(bool),
+    VerifyStopOnFailureToggled(bool),
+    VerifyRetryPressed,
+    // Index into the run's results
+    VerifyAcknowledgePressed(usize),
+    VerifyClearAcknowledgedPressed,
+    SaveDiscManifestPressed,
+    CompareDiscFolderPressed,
+    PrintReportPressed,
+    SaveResultsPressed,
+    ExportPdfPressed,
+    ReportHeaderChanged(String),
+    ReportLogoChanged(String),
     UppercaseToggled(bool),
     AutoHashToggled(bool),
+    QuickModeToggled(bool),
+    ChunkModeToggled(bool),
+    SaveChunksPressed,
+    CompareChunksPressed,
+    SignatureToggled(bool),
+    FollowToggled(bool),
+    ConcatToggled(bool),
+    QueueToggled(bool),
+    ContinueSavedQueue,
+    DiscardSavedQueue,
+    JobMoveUp(u64),
+    JobMoveDown(u64),
+    JobHashNext(u64),
+    ZeroAwareToggled(bool),
+    ForensicToggled(bool),
+    ExportCustodyPressed,
+    LoadListPressed(ListKind),
+    ClearListPressed(ListKind),
+    SegmentsToggled(bool),
+    CidToggled(bool),
+    CloudToggled(bool),
+    AuthenticodeToggled(bool),
+    MetadataDigestToggled(bool),
+    MetadataFieldsSelected(meta_digest::Fields),
+    PaletteSelected(Palette),
+    UpdateRateSelected(power::UpdateRate),
+    HexGroupingSelected(HexGrouping),
+    SegmentSizeSelected(SegmentSize),
+    SaveSegmentsPressed,
+    CompareSegmentsPressed,
+    ConcatMoveUp(usize),
+    ConcatMoveDown(usize),
+    ConcatRemove(usize),
+    ConcatClearPressed,
+    ConcatHashPressed,
+    SaveSignaturePressed,
+    SettingsToggled,
+    AboutToggled,
+    CopyDiagnostics,
+    AlgorithmSelected(algo::Algorithm),
+    ShellIntegrationPressed(bool),
+    WizardAlgorithmSelected(algo::Algorithm),
+    WizardAutoHashToggled(bool),
+    WizardNetworkToggled(bool),
+    WizardShellToggled(bool),
+    WizardBack,
+    WizardNext,
+    WizardSkipped,
+    ReadBufferSelected(ReadBuffer),
+    HistoryLimitSelected(history::Limit),
+    NetworkToggled(bool),
+    UsageMetricsToggled(bool),
+    UsageToggled,
+    ResetUsagePressed,
+    ExportSettingsPressed,
+    ImportSettingsPressed,
+    PostCopyToggled(bool),
+    PostSidecarToggled(bool),
+    PostManifestChanged(String),
+    PostMoveChanged(String),
+    PostCommandChanged(String),
+    WebhookUrlChanged(String),
+    EmailToggled(bool),
+    EmailFailuresOnlyToggled(bool),
+    EmailMailtoToggled(bool),
+    EmailTextChanged(EmailField, String),
+    ErrorActionPressed(ErrorAction),
     DroppedFile(PathBuf),
+    FileHovered,
+    HoverLeft,
     StartHash,
     Tick,
     Ignored,
 }
 
+// Text settings of the email report, edited through one message
+#[derive(Debug, Clone, Copy)]
+enum EmailField {
+    Server,
+    Port,
+    Username,
+    Password,
+    From,
+    To,
+}
+
 #[derive(Debug, Clone)]
 struct HashResult {
     hex: String,
@@ -58,83 +392,547 @@ struct HashResult {
     elapsed: Duration,
     bytes: u64,
     path: Option<PathBuf>,
+    // Sampled triage digest rather than a full-content hash
+    quick: bool,
+    // FastCDC chunk list, only filled when chunking was requested
+    chunks: Vec<ChunkEntry>,
+    // librsync signature bytes, only filled when requested
+    signature: Option<Vec<u8>>,
+    // Digest of a growing file, covering only what was read before Stop
+    followed: bool,
+    // Number of files hashed as one concatenated stream (0 for a single file)
+    concat_count: usize,
+    // Bytes allocated on disk, to spot sparse files
+    allocated: Option<u64>,
+    // Hole bytes hashed as zeros without being read (zero-aware mode)
+    holes_skipped: u64,
+    // Fixed-size segment digests, only filled when requested
+    segments: Vec<SegmentEntry>,
+    // IPFS CIDv1 the file would get, only filled when requested
+    cid: Option<String>,
+    // Azure/GCS upload checksums, only filled when requested
+    cloud: Option<CloudDigests>,
+    // The default algorithm from Settings when it is not SHA-256, as (algorithm, hex)
+    preferred: Option<(algo::Algorithm, String)>,
+    // PE image hash without the signature, only filled when requested and the file is a PE image
+    authenticode: Option<String>,
+    // Digest of the selected metadata, only filled when requested
+    metadata_digest: Option<String>,
+    // What the first bytes say the file is, and why its digest may not be what was meant
+    file_type: Option<FileType>,
+    type_warning: Option<&'static str>,
+    // Hashed in forensic mode, and whether every file's last-access time was kept
+    forensic: bool,
+    atime_kept: bool,
+    // Container format when the virtual disk payload was hashed rather than the file
+    payload: Option<&'static str>,
+}
+
+type WorkerMessage = (u64, std::result::Result<HashResult, HashError>);
+
+// Handles a background hash needs: its token, the result channel and the shared progress/cancel/stop state
+struct WorkerHandles {
+    token: u64,
+    tx: Sender<WorkerMessage>,
+    progress: Arc<AtomicU64>,
+    cancel: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+}
+
+impl WorkerHandles {
+    fn new(token: u64) -> (Self, Receiver<WorkerMessage>) {
+        let (tx, rx) = mpsc::channel();
+        let handles = WorkerHandles {
+            token,
+            tx,
+            progress: Arc::new(AtomicU64::new(0)),
+            cancel: Arc::new(AtomicBool::new(false)),
+            stop: Arc::new(AtomicBool::new(false)),
+        };
+        (handles, rx)
+    }
+}
+
+// Which optional outputs to compute alongside the digest
+#[derive(Debug, Clone, Copy, Default)]
+struct HashOptions {
+    quick: bool,
+    chunks: bool,
+    rsync_signature: bool,
+    follow: bool,
+    zero_aware: bool,
+    // Open files so their last-access time is kept where the OS allows; no writes next to them
+    forensic: bool,
+    // Segment size in bytes when per-segment digests are requested
+    segment_size: Option<u64>,
+    ipfs_cid: bool,
+    cloud: bool,
+    // Digest computed in the same read besides SHA-256
+    preferred: Option<algo::Algorithm>,
+    authenticode: bool,
+    // Metadata to digest alongside the content
+    metadata: Option<meta_digest::Fields>,
+    // Hash the virtual disk inside a VHD/VHDX/DMG container instead of the container file
+    payload: bool,
+    // Bytes per read; 0 for BUFFER_SIZE
+    read_buffer: usize,
+}
+
+impl HashOptions {
+    fn buffer_size(&self) -> usize {
+        if self.read_buffer == 0 {
+            BUFFER_SIZE
+        } else {
+            self.read_buffer
+        }
+    }
+
+    // The digests part of the options, as remembered per folder
+    fn choice(&self) -> folder_choices::Choice {
+        folder_choices::Choice {
+            chunks: self.chunks,
+            rsync_signature: self.rsync_signature,
+            segment_size: self.segment_size,
+            ipfs_cid: self.ipfs_cid,
+            cloud: self.cloud,
+            authenticode: self.authenticode,
+            metadata: self.metadata.map(|f| f.to_string()),
+            used_at: 0,
+        }
+    }
+
+    fn with_choice(self, choice: &folder_choices::Choice) -> HashOptions {
+        HashOptions {
+            chunks: choice.chunks,
+            rsync_signature: choice.rsync_signature,
+            segment_size: choice.segment_size,
+            ipfs_cid: choice.ipfs_cid,
+            cloud: choice.cloud,
+            authenticode: choice.authenticode,
+            metadata: choice.metadata.as_deref().and_then(meta_digest::Fields::parse),
+            ..self
+        }
+    }
+}
+
+// Optional per-file outputs produced in the same read pass as the digest
+#[derive(Debug, Clone, Default)]
+struct Extras {
+    chunks: Vec<ChunkEntry>,
+    signature: Option<Vec<u8>>,
+    allocated: Option<u64>,
+    holes_skipped: u64,
+    segments: Vec<SegmentEntry>,
+    cid: Option<String>,
+    cloud: Option<CloudDigests>,
+    preferred: Option<(algo::Algorithm, String)>,
+    authenticode: Option<String>,
+    metadata_digest: Option<String>,
+    file_type: Option<FileType>,
+    type_warning: Option<&'static str>,
+    atime_kept: bool,
+    // Container format whose payload was hashed instead of the file itself
+    payload: Option<&'static str>,
+}
+
+// Outcome of comparing a fresh digest with the previous one recorded for the same file
+#[derive(Debug, Clone)]
+enum Comparison {
+    Unchanged(String),
+    Changed(String),
+}
+
+// Outcome of comparing the last file with a pasted digest or integrity value, with the algorithm's label
+#[derive(Debug, Clone)]
+enum Verdict {
+    // The file is being read again for a digest other than SHA-256
+    Pending(&'static str),
+    Match(&'static str),
+    Mismatch(&'static str),
+    Failed(String),
 }
 
 #[derive(Default)]
 struct App {
     // Input
     path_input: String,
-    // Output
-    hex_output: String,
-    base64_output: String,
+    // Output: the raw digest, shown as hex (in the chosen case) and Base64 when rendered
+    digest: Vec<u8>,
     // State
     is_hashing: bool,
-    error: Option<String>,
+    error: Option<HashError>,
     uppercase: bool,
+    // Display only: copies and saved lists use the plain digest
+    hex_grouping: HexGrouping,
     auto_hash: bool,
+    quick_mode: bool,
+    last_quick: bool,
+    chunk_mode: bool,
+    last_chunks: Vec<ChunkEntry>,
+    chunk_summary: Option<String>,
+    signature_mode: bool,
+    last_signature: Option<Vec<u8>>,
+    follow_mode: bool,
+    is_following: bool,
+    last_followed: bool,
+    // Concatenation
+    concat_mode: bool,
+    // Dropped and browsed files are queued as background jobs instead of hashed in the main view
+    queue_mode: bool,
+    // Unfinished queue from the last session, offered until continued or discarded, and what is on disk now
+    restored_queue: Option<saved_queue::SavedQueue>,
+    saved_queue: Vec<String>,
+    concat_files: Vec<PathBuf>,
+    last_concat_count: usize,
+    // Sparse files
+    zero_aware_mode: bool,
+    last_allocated: Option<u64>,
+    // Forensic mode: no atime updates where possible, nothing written next to the evidence.
+    // For the last result: Some(whether its last-access time was kept) when it was hashed this way
+    forensic_mode: bool,
+    last_atime_kept: Option<bool>,
+    last_payload: Option<&'static str>,
+    last_holes_skipped: u64,
+    // Segment digests
+    segment_mode: bool,
+    segment_size: Option<SegmentSize>,
+    last_segments: Vec<SegmentEntry>,
+    segment_summary: Option<String>,
+    // IPFS CID
+    cid_mode: bool,
+    last_cid: Option<String>,
+    // Azure Content-MD5 and GCS CRC32C
+    cloud_mode: bool,
+    last_cloud: Option<CloudDigests>,
+    // Digest in the default algorithm from Settings, when that is not SHA-256
+    last_preferred: Option<(algo::Algorithm, String)>,
+    // Authenticode image hash of executables
+    authenticode_mode: bool,
+    last_authenticode: Option<String>,
+    // Metadata digest (name, size, mtime, permissions) for backup tools
+    metadata_mode: bool,
+    metadata_fields: Option<meta_digest::Fields>,
+    last_metadata_digest: Option<String>,
+    // Sniffed file type of the last result, and the warning for shortcuts and cloud placeholders
+    last_file_type: Option<FileType>,
+    last_type_warning: Option<&'static str>,
+    // Window theme and status colors
+    palette: Palette,
     started_at: Option<Instant>,
     last_elapsed: Option<Duration>,
     last_bytes: Option<u64>,
     last_path: Option<PathBuf>,
     prev_path_before_hash: Option<String>,
+    path_status: PathStatus,
+    // The input `path_status` was worked out for; behind `path_input` while a typed path waits for its check
+    path_checked: String,
+    path_check_due: Option<Instant>,
+    // Pending auto-hash of a typed path: when it fires and the path shown before typing started
+    auto_hash_due: Option<(Instant, String)>,
+    // Drop zone: a drag is over the window / when the last drop happened (for the flash)
+    drag_hover: bool,
+    dropped_at: Option<Instant>,
+    // Checksum file being dragged out on Windows, until the drag ends; dropped back on the window it
+    // arrives as a dropped file
+    shared_checksum: Option<PathBuf>,
+    // History
+    history: History,
+    comparison: Option<Comparison>,
     // Progress
     progress_total: Option<u64>,
     progress_processed: u64,
-    progress_counter: Option<Arc<AtomicU64>>,
+    tracker: Option<Arc<progress::Tracker>>,
+    progress_eta: Option<Duration>,
     cancel_flag: Option<Arc<AtomicBool>>,
-    worker_rx: Option<Receiver<(u64, std::result::Result<HashResult, String>)>>,
+    stop_flag: Option<Arc<AtomicBool>>,
+    // Stall detection: when the progress counter last moved, and whether the UI currently shows "stalled"
+    last_progress_at: Option<Instant>,
+    stall_watch: bool,
+    stalled: bool,
+    worker_rx: Option<Receiver<WorkerMessage>>,
     worker_token: Option<u64>,
     // Concurrency token to ignore late results
     token: u64,
+    // Jobs moved out of the main view with "Run in background"
+    background: Vec<BackgroundJob>,
+    // Shared worker for small queued files
+    small_files: jobs::SmallFiles,
+    next_job_id: u64,
+    // Persisted preferences, including post-job actions
+    config: Config,
+    // The settings or About page is shown instead of the main view
+    show_settings: bool,
+    show_about: bool,
+    read_buffer: ReadBuffer,
+    history_limit: history::Limit,
+    // Off: no webhooks, SMTP email or hashing over SSH
+    network: bool,
+    // Shown alongside SHA-256, which the window is built on; `--cli` takes --algo instead
+    algorithm: algo::Algorithm,
+    // Local usage counts; None unless turned on in Settings
+    usage: Option<usage::Usage>,
+    show_usage: bool,
+    // First-run questions, shown instead of everything else until answered or skipped
+    wizard: Option<first_run::Wizard>,
+    // Slowest-files list under the verify and queue panels
+    show_slowest: bool,
+    // Second row of the rarely used tools under "More tools"
+    more_tools: bool,
+    // One-line note under the results: post-job action outcomes, settings import/export, background failures
+    status_note: Option<String>,
+    // Failures reported from background threads (webhook posts, email reports), one channel per thread
+    // until the thread is done with it
+    notice_rx: Vec<Receiver<String>>,
+    // Queue run in progress: when it started and how its jobs ended so far
+    batch_started: Option<Instant>,
+    batch_succeeded: usize,
+    batch_failed: usize,
+    // One line per finished queue job, for the email report
+    batch_lines: Vec<String>,
+    // "path: reason" for queued files skipped because they could not be opened
+    batch_skipped: Vec<String>,
+    skip_unopenable: bool,
+    verify_stop_on_failure: bool,
+    relative_results: bool,
+    tree_xattrs: bool,
+    // Reference lists results are checked against: known-good files and IOC blocklist
+    known: ListSlot,
+    blocklist: ListSlot,
+    // Queue jobs that matched the blocklist in the current run
+    batch_blocked: usize,
+    // Two-file comparison against the last result: running check and its outcome
+    binary_diff: bool,
+    compare_rx: Option<Receiver<String>>,
+    compare_note: Option<String>,
+    archive_check: bool,
+    archive_rx: Option<Receiver<String>>,
+    archive_note: Option<String>,
+    // Reproducible content digest of a just-hashed archive: members sorted, timestamps and owners left out
+    archive_digest_mode: bool,
+    archive_digest_rx: Option<Receiver<Result<archive_digest::ContentDigest, String>>>,
+    archive_digest: Option<Result<archive_digest::ContentDigest, String>>,
+    archive_digest_cancel: Option<Arc<AtomicBool>>,
+    // Text for the clipboard that needs the last file read again (magnet link, npm integrity), with the note to show
+    copy_rx: Option<Receiver<Result<(String, String), String>>>,
+    // Digest or integrity value pasted to compare the last file with
+    expected_input: String,
+    expected_verdict: Option<Verdict>,
+    // The file's digest in the pasted value's algorithm when that is not SHA-256, read on a worker
+    expected_rx: Option<Receiver<Result<String, String>>>,
+    // Summary of a "Publish sidecars..." run once it is done
+    sidecars_rx: Option<Receiver<String>>,
+    archive_cancel: Option<Arc<AtomicBool>>,
+    disc_contents: bool,
+    payload_mode: bool,
+    // Verify: how list entries from another OS are matched to files here
+    matching: path_norm::Matching,
+    disc_run: Option<disc_image::ContentsRun>,
+    // Per-partition digests of a just-hashed raw disk or image
+    partition_mode: bool,
+    // Digests picked per folder, restored when a file from there is chosen
+    folder_choices_mode: bool,
+    // Checksum file the expected digest was taken from, when one was dropped
+    pair: Option<pairing::Pair>,
+    // Last window position and size seen, and the monitor it settled on
+    placement: placement::Placement,
+    placement_due: Option<Instant>,
+    monitor: Option<String>,
+    // Placement per monitor as of the last settle, written out on exit
+    placements: placement::Placements,
+    // Refresh rate, whether the machine was on battery when last checked, and when input or work was last seen
+    update_rate: power::UpdateRate,
+    on_battery: bool,
+    power_checked: Option<Instant>,
+    active_at: Option<Instant>,
+    // Set by "Finish, then quit": the window is minimized and closes once nothing is left running
+    quit_when_done: bool,
+    // Background jobs running longer are cancelled as TIMED OUT
+    job_timeout: JobTimeout,
+    folder_choices: folder_choices::FolderChoices,
+    partition_run: Option<partitions::PartitionRun>,
+    ewf_run: Option<ewf::EwfRun>,
+    // Verified copy ("Verified copy..."), shown in its own panel
+    copy_run: Option<copy_verify::CopyRun>,
+    folder_compare: Option<folder_diff::CompareRun>,
+    snapshot_diff: Option<snapshot_diff::SnapshotDiff>,
+    // "Dry-run list/log": what a verification would check, without reading the files
+    dry_run: Option<verify::DryRun>,
+    manifest_editor: Option<manifest::Editor>,
+    // `user@host:/path` typed for hashing over SSH, and that hash
+    remote_input: String,
+    remote_run: Option<remote::RemoteRun>,
+    // Typed or pasted text whose SHA-256 is shown beside it
+    hash_text: String,
+    // Windows taskbar Jump List, kept in step with the history; None elsewhere
+    jump_list: Option<jump_list::JumpList>,
+    // Staging folder whose SHA256SUMS is kept up to date
+    staging: Option<staging::StagingWatch>,
+    verify_run: Option<verify::VerifyRun>,
+    acquisition: Option<acquisition::AcquisitionRun>,
 }
 
 impl Application for App {
     type Executor = executor::Default;
     type Message = Message;
     type Theme = Theme;
-    type Flags = ();
+    type Flags = Launch;
 
-    fn new(_flags: Self::Flags) -> (Self, Command<Self::Message>) {
+    fn new(flags: Self::Flags) -> (Self, Command<Self::Message>) {
         let mut app = App::default();
-        app.auto_hash = true;
-        (app, Command::none())
+        app.history = History::load();
+        app.config = Config::load();
+        app.apply_preferences();
+        if config::is_first_run() {
+            app.wizard = Some(first_run::Wizard::new(&app.config.preferences));
+        }
+        if let Some(e) = &policy::get().error {
+            app.status_note = Some(format!("{}; the strictest policy applies", e));
+        }
+        app.folder_choices = folder_choices::FolderChoices::load();
+        app.placements = placement::Placements::load();
+        app.placement = app.placements.current().unwrap_or(placement::Placement {
+            position: None,
+            width: DEFAULT_WINDOW.width as u32,
+            height: DEFAULT_WINDOW.height as u32,
+        });
+        // The window reopens where it closed
+        app.monitor = app.placements.current().map(|_| app.placements.last.clone());
+        let restored = saved_queue::SavedQueue::load();
+        if !restored.paths.is_empty() {
+            app.saved_queue = restored.paths.clone();
+            app.restored_queue = Some(restored);
+        }
+        for kind in [ListKind::Known, ListKind::Blocklist] {
+            let path = app.list_path(kind).trim().to_string();
+            if !path.is_empty() {
+                app.list_slot(kind).load(PathBuf::from(path));
+            }
+        }
+        mac_menu::install_open_handler();
+        app.jump_list = jump_list::JumpList::start();
+        if let Some(list) = &app.jump_list {
+            list.update(app.history.recent_files(jump_list::MAX_RECENT));
+        }
+        let start = match flags {
+            Launch::Hash(path) => {
+                app.path_input = uri::normalize_dropped(path).to_string_lossy().to_string();
+                app.check_path();
+                app.start_hashing(app.path_input.clone(), Some(String::new()));
+                text_input::focus(PATH_INPUT.clone())
+            }
+            Launch::VerifyList => Command::perform(async {}, |_| Message::VerifyListPressed),
+            Launch::TextHash => text_input::focus(TEXT_INPUT.clone()),
+            Launch::Nothing => text_input::focus(PATH_INPUT.clone()),
+        };
+        // Runs once the event loop has started, after winit has put up its own menu bar
+        let menu = Command::perform(async {}, |_| Message::InstallMenu);
+        (app, Command::batch([start, menu]))
     }
 
     fn title(&self) -> String {
         if self.is_hashing {
+            if self.stalled {
+                return format!("Rust Hash256 v{} - stalled ", app_version());
+            }
             if let Some(total) = self.progress_total {
                 if total > 0 {
                     let pct = ((self.progress_processed as f64 / total as f64) * 100.0).clamp(0.0, 100.0);
+                    if let Some(eta) = self.progress_eta.filter(|eta| eta.as_secs() >= 5) {
+                        return format!("Rust Hash256 v{} - {:.0}% ({} left) ", app_version(), pct, human_duration(eta));
+                    }
                     return format!("Rust Hash256 v{} - {:.0}% ", app_version(), pct);
                 }
             }
+            if self.is_following {
+                return format!("Rust Hash256 v{} - following ({}) ", app_version(), human_bytes(self.progress_processed as f64));
+            }
+            // Pipes, devices and streams without a known size: how much and how fast instead of a percentage
+            if self.progress_processed > 0 {
+                return format!("Rust Hash256 v{} - {} ", app_version(), unknown_size_progress(self.progress_processed, self.progress_rate()));
+            }
             return format!("Rust Hash256 v{} - hashing... ", app_version());
         }
-        format!("Rust Hash256 v{} ", app_version())
+        // Screen readers read the title on request (NVDA: Insert+T), so it says how the last hash ended
+        let outcome = match (&self.error, &self.last_path) {
+            (Some(_), _) => " - failed".to_string(),
+            (None, Some(path)) if !self.digest.is_empty() => {
+                format!(" - {} hashed", path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default())
+            }
+            _ => String::new(),
+        };
+        if storage::is_portable() {
+            return format!("Rust Hash256 v{} (portable){} ", app_version(), outcome);
+        }
+        format!("Rust Hash256 v{}{} ", app_version(), outcome)
     }
 
     fn theme(&self) -> Theme {
-        Theme::Dark
+        self.palette.theme()
     }
 
     fn subscription(&self) -> Subscription<Self::Message> {
         let file_drop = event::listen().map(|e| match e {
             event::Event::Window(_, window::Event::FileDropped(path)) => Message::DroppedFile(path),
+            event::Event::Window(_, window::Event::FileHovered(_)) => Message::FileHovered,
+            event::Event::Window(_, window::Event::FilesHoveredLeft) => Message::HoverLeft,
+            event::Event::Window(_, window::Event::CloseRequested) => Message::CloseRequested,
+            event::Event::Window(_, window::Event::Moved { x, y }) => Message::WindowMoved(x, y),
+            event::Event::Window(_, window::Event::Resized { width, height }) => Message::WindowResized(width, height),
             _ => Message::Ignored,
         });
-        let tick = iced::time::every(Duration::from_millis(100)).map(|_| Message::Tick);
-        Subscription::batch(vec![file_drop, tick])
+        // No refresh at all when idle, so an open window does not keep the CPU from sleeping
+        let tick = if self.needs_tick() {
+            iced::time::every(self.update_rate.interval(self.on_battery)).map(|_| Message::Tick)
+        } else {
+            Subscription::none()
+        };
+        // Tab and Shift+Tab move between the input fields in layout order
+        let focus = iced::keyboard::on_key_press(|key, modifiers| match key {
+            iced::keyboard::Key::Named(iced::keyboard::key::Named::Tab) if modifiers.shift() => Some(Message::FocusPrevious),
+            iced::keyboard::Key::Named(iced::keyboard::key::Named::Tab) => Some(Message::FocusNext),
+            _ => None,
+        });
+        Subscription::batch(vec![file_drop, tick, focus, mac_menu::actions().map(Message::MenuAction)])
     }
 
     fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
+        if !matches!(message, Message::Tick | Message::Ignored) {
+            self.active_at = Some(Instant::now());
+        }
         match message {
             Message::PathChanged(value) => {
                 let old_path = self.path_input.clone();
-                self.path_input = value;
+                self.path_input = uri::normalize_input(&value);
                 self.error = None;
-                if self.auto_hash && !self.path_input.trim().is_empty() && !self.is_hashing {
-                    self.start_hashing(self.path_input.clone(), Some(old_path));
-                    return Command::none();
+                // Looked at once typing pauses, and off the UI thread, so a slow or hung mount does not stall each keystroke
+                self.path_check_due = Some(Instant::now() + PATH_CHECK_DEBOUNCE);
+                // Partially typed paths only get inline feedback; hashing waits until typing has paused and the check
+                // found a readable file, so a long path does not start a worker per keystroke
+                if self.auto_hash {
+                    let prev = self.auto_hash_due.take().map_or(old_path, |(_, prev)| prev);
+                    self.auto_hash_due = Some((Instant::now() + AUTO_HASH_DEBOUNCE, prev));
+                }
+                Command::none()
+            }
+            // A check of text that has changed since is stale
+            Message::PathChecked(input, status) => {
+                if input == self.path_input {
+                    self.path_status = status;
+                    self.path_checked = input;
+                }
+                Command::none()
+            }
+            Message::FocusNext => iced::widget::focus_next(),
+            Message::FocusPrevious => iced::widget::focus_previous(),
+            // Hashes this executable, so a first run shows a result without looking for a file
+            Message::TrySamplePressed => {
+                match std::env::current_exe() {
+                    Ok(exe) => {
+                        self.path_input = exe.to_string_lossy().to_string();
+                        self.check_path();
+                        self.start_hashing(self.path_input.clone(), None);
+                    }
+                    Err(e) => self.status_note = Some(format!("Cannot find the sample file: {}", e)),
                 }
                 Command::none()
             }
@@ -159,10 +957,24 @@ impl Application for App {
                         }
                     }
                 }
-                if let Some(path) = dialog.pick_file() {
+                if self.concat_mode {
+                    if let Some(paths) = dialog.pick_files() {
+                        self.concat_files.extend(paths);
+                    }
+                    return Command::none();
+                }
+                let mut paths = dialog.pick_files().unwrap_or_default();
+                // Several files go to the queue whether or not "Add to queue" is ticked
+                if self.queue_mode || paths.len() > 1 {
+                    self.queue_batch(paths);
+                    return Command::none();
+                }
+                if let Some(path) = paths.pop() {
                     let old_path = self.path_input.clone();
                     self.path_input = path.to_string_lossy().to_string();
+                    self.check_path();
                     self.error = None;
+                    self.restore_folder_choice(&path);
                     if self.auto_hash {
                         self.start_hashing(self.path_input.clone(), Some(old_path));
                         return Command::none();
@@ -170,353 +982,5121 @@ impl Application for App {
                 }
                 Command::none()
             }
+            Message::BrowseFolderPressed => {
+                let mut dialog = FileDialog::new().set_title("Hash every file in a folder");
+                if let Some(parent) = self.last_path.as_ref().and_then(|p| p.parent()).filter(|p| p.is_dir()) {
+                    dialog = dialog.set_directory(parent);
+                }
+                let Some(folder) = dialog.pick_folder() else {
+                    return Command::none();
+                };
+                match folder_diff::tree(&folder) {
+                    Ok(files) if files.is_empty() => self.status_note = Some(format!("No files in {}", folder.display())),
+                    Ok(files) => self.queue_batch(files.into_keys().map(|relative| folder.join(relative)).collect()),
+                    Err(e) => self.status_note = Some(format!("{:#}", e)),
+                }
+                Command::none()
+            }
+            Message::CloseRequested => {
+                let work = self.work_in_flight();
+                if work.is_empty() {
+                    self.before_close();
+                    return window::close(window::Id::MAIN);
+                }
+                let choice = rfd::MessageDialog::new()
+                    .set_title("Work in progress")
+                    .set_description(format!(
+                        "Still running: {}.\n\nQuit now cancels it; unfinished queue entries are offered again on the next start.",
+                        work.join(", ")
+                    ))
+                    .set_buttons(rfd::MessageButtons::YesNoCancelCustom(
+                        "Finish, then quit".to_string(),
+                        "Quit now".to_string(),
+                        "Keep open".to_string(),
+                    ))
+                    .show();
+                // Some backends report the custom buttons as Yes/No
+                match choice {
+                    rfd::MessageDialogResult::No => self.update(Message::CloseConfirmed(false)),
+                    rfd::MessageDialogResult::Yes => self.update(Message::CloseConfirmed(true)),
+                    rfd::MessageDialogResult::Custom(label) if label == "Quit now" => self.update(Message::CloseConfirmed(false)),
+                    rfd::MessageDialogResult::Custom(label) if label == "Finish, then quit" => self.update(Message::CloseConfirmed(true)),
+                    _ => Command::none(),
+                }
+            }
+            // true waits for the work to finish
+            Message::CloseConfirmed(finish) => {
+                if !finish {
+                    self.abandon_worker();
+                    self.before_close();
+                    return window::close(window::Id::MAIN);
+                }
+                // A followed file never ends by itself, so it is stopped and its digest taken now
+                if let Some(flag) = self.stop_flag.as_ref().filter(|_| self.is_following) {
+                    flag.store(true, Ordering::Relaxed);
+                }
+                self.quit_when_done = true;
+                window::minimize(window::Id::MAIN, true)
+            }
+            Message::WindowMoved(x, y) => {
+                // Minimizing reports a far-off position, which is not where the window should reopen
+                if placement::is_position(x) && placement::is_position(y) {
+                    self.placement.position = Some((x, y));
+                    self.placement_due = Some(Instant::now() + PLACEMENT_SETTLE);
+                }
+                Command::none()
+            }
+            Message::WindowResized(width, height) => {
+                if width > 0 && height > 0 {
+                    self.placement.width = width;
+                    self.placement.height = height;
+                    self.placement_due = Some(Instant::now() + PLACEMENT_SETTLE);
+                }
+                Command::none()
+            }
+            // After a move between monitors with different scales some platforms keep the physical size, which
+            // shrinks or grows the layout. The live size has already followed that, so the size comes from the
+            // settled placements: the one last used on the new monitor, else the one before the move
+            Message::MonitorChecked(monitor) => {
+                let previous = self.monitor.replace(monitor.clone());
+                let mut command = Command::none();
+                if let Some(previous) = previous.filter(|p| *p != monitor) {
+                    if let Some(known) = self.placements.on(&monitor).or_else(|| self.placements.on(&previous)) {
+                        let (width, height) = (known.width.max(MIN_WINDOW.width as u32), known.height.max(MIN_WINDOW.height as u32));
+                        if self.placement.width.abs_diff(width) > 2 || self.placement.height.abs_diff(height) > 2 {
+                            self.placement.width = width;
+                            self.placement.height = height;
+                            command = window::resize(window::Id::MAIN, Size::new(width as f32, height as f32));
+                        }
+                    }
+                }
+                self.placements.remember(&monitor, self.placement);
+                command
+            }
             Message::ClearPressed => {
                 self.path_input.clear();
-                self.hex_output.clear();
-                self.base64_output.clear();
+                self.check_path();
+                self.auto_hash_due = None;
+                self.digest.clear();
                 self.error = None;
                 self.last_elapsed = None;
                 self.last_bytes = None;
                 self.last_path = None;
+                self.comparison = None;
+                self.last_chunks.clear();
+                self.chunk_summary = None;
+                self.last_signature = None;
+                self.last_concat_count = 0;
+                self.last_allocated = None;
+                self.last_atime_kept = None;
+                self.last_payload = None;
+                self.last_holes_skipped = 0;
+                self.last_segments.clear();
+                self.segment_summary = None;
+                self.last_cid = None;
+                self.last_cloud = None;
+                self.last_preferred = None;
+                self.last_authenticode = None;
+                self.last_metadata_digest = None;
+                self.last_file_type = None;
+                self.last_type_warning = None;
+                self.expected_verdict = None;
+                self.expected_rx = None;
+                self.pair = None;
+                self.compare_rx = None;
+                self.compare_note = None;
+                self.stop_result_checks();
+                self.status_note = None;
                 self.progress_total = None;
                 self.progress_processed = 0;
                 Command::none()
             }
             Message::CancelPressed => {
-                if let Some(flag) = &self.cancel_flag {
-                    flag.store(true, Ordering::Relaxed);
-                }
+                self.abandon_worker();
                 // Try to restore previous path when possible
                 if let Some(prev) = self.prev_path_before_hash.take() {
                     self.path_input = prev;
                 } else if let Some(p) = &self.last_path {
                     self.path_input = p.to_string_lossy().to_string();
                 }
+                self.check_path();
+                Command::none()
+            }
+            Message::BackgroundPressed => {
+                // A followed file never finishes on its own, so it stays in the main view where Stop lives
+                if !self.is_hashing || self.is_following {
+                    return Command::none();
+                }
+                let (Some(rx), Some(progress), Some(cancel)) = (self.worker_rx.take(), self.tracker.take().map(|t| t.counter()), self.cancel_flag.take()) else {
+                    return Command::none();
+                };
+                self.next_job_id += 1;
+                self.background.push(BackgroundJob::new(
+                    self.next_job_id,
+                    self.path_input.trim().to_string(),
+                    self.quick_mode,
+                    self.progress_total,
+                    progress,
+                    cancel,
+                    rx,
+                ));
+                self.count_operation("Run in background", None);
+                // The main view is free for the next file
                 self.is_hashing = false;
+                self.stalled = false;
+                self.stop_flag = None;
+                self.worker_token = None;
                 self.progress_total = None;
                 self.progress_processed = 0;
-                self.worker_rx = None;
+                self.prev_path_before_hash = None;
+                self.path_input.clear();
+                self.check_path();
                 Command::none()
             }
-            Message::CopyHex => clipboard::write(self.hex_output.clone()),
-            Message::CopyBase64 => clipboard::write(self.base64_output.clone()),
-            Message::UppercaseToggled(v) => {
-                self.uppercase = v;
-                if !self.hex_output.is_empty() {
-                    if self.uppercase {
-                        self.hex_output = self.hex_output.to_uppercase();
+            Message::JobCancel(id) => {
+                if let Some(job) = self.background.iter_mut().find(|j| j.id == id) {
+                    job.cancel();
+                }
+                Command::none()
+            }
+            Message::JobClose(id) => {
+                self.background.retain(|j| j.id != id || j.is_running());
+                Command::none()
+            }
+            Message::JobCopyHex(id) => {
+                match self.background.iter().find(|j| j.id == id).and_then(|j| j.outcome.as_ref()) {
+                    Some(Ok(hr)) => clipboard::write(if self.uppercase { hr.hex.to_uppercase() } else { hr.hex.clone() }),
+                    _ => Command::none(),
+                }
+            }
+            Message::KeepWaitingPressed => {
+                // Restart the stall timer; the warning comes back if the read still does not move
+                self.stalled = false;
+                self.last_progress_at = Some(Instant::now());
+                Command::none()
+            }
+            Message::StopFollowPressed => {
+                // The worker treats Stop as end of file and reports the digest of everything read so far
+                if let Some(flag) = &self.stop_flag {
+                    flag.store(true, Ordering::Relaxed);
+                }
+                Command::none()
+            }
+            Message::RehashPressed => {
+                if self.is_hashing {
+                    return Command::none();
+                }
+                if let Some(p) = self.last_path.clone() {
+                    let old_path = self.path_input.clone();
+                    self.path_input = p.to_string_lossy().to_string();
+                    self.check_path();
+                    self.start_hashing(self.path_input.clone(), Some(old_path));
+                }
+                Command::none()
+            }
+            Message::CopyHex => clipboard::write(self.hex_digest()),
+            Message::CopyBase64 => clipboard::write(self.base64_digest()),
+            Message::PrintReportPressed => {
+                let report = report::Report::new(self.report_rows());
+                match report::print(&report) {
+                    Ok(_) => self.count_operation("Print report", None),
+                    Err(e) => self.status_note = Some(format!("{:#}", e)),
+                }
+                Command::none()
+            }
+            Message::SaveResultsPressed => {
+                if self.result_digests().is_empty() {
+                    self.status_note = Some("No full-content digests to save yet".to_string());
+                    return Command::none();
+                }
+                let Some(target) = FileDialog::new().set_file_name("SHA256SUMS").add_filter("Checksum list", &["sha256", "txt"]).save_file() else {
+                    return Command::none();
+                };
+                // An existing list is either merged with or replaced, never half-written. Merging updates
+                // the lines of files hashed again and adds the rest, so nothing is listed twice
+                let merge = if target.exists() {
+                    let choice = rfd::MessageDialog::new()
+                        .set_title("Checksum list exists")
+                        .set_description(format!(
+                            "{} already exists. Merge the new digests into it (update changed files, add new ones, keep the rest) or overwrite it?",
+                            target.display()
+                        ))
+                        .set_buttons(rfd::MessageButtons::YesNoCancelCustom("Merge".to_string(), "Overwrite".to_string(), "Cancel".to_string()))
+                        .show();
+                    match choice {
+                        rfd::MessageDialogResult::Custom(c) if c == "Merge" => true,
+                        rfd::MessageDialogResult::Custom(c) if c == "Overwrite" => false,
+                        _ => return Command::none(),
+                    }
+                } else {
+                    false
+                };
+                self.status_note = Some(if merge {
+                    match manifest::merge_into(&target, self.result_digests(), false) {
+                        Ok(summary) => {
+                            self.count_operation("Save results", None);
+                            format!("Merged into {}: {}", target.display(), summary)
+                        }
+                        Err(e) => format!("{:#}", e),
+                    }
+                } else {
+                    // The list is usually checked from somewhere else: its own folder, or the parent of what was hashed
+                    let base = if self.relative_results {
+                        let folder = FileDialog::new().set_title("Write paths relative to").set_directory(target.parent().unwrap_or(Path::new("."))).pick_folder();
+                        match folder {
+                            Some(folder) => Some(folder),
+                            None => return Command::none(),
+                        }
                     } else {
-                        self.hex_output = self.hex_output.to_lowercase();
+                        None
+                    };
+                    let lines = self.result_lines(base.as_deref());
+                    match atomic::write(&target, lines.as_bytes()) {
+                        Ok(()) => {
+                            self.count_operation("Save results", None);
+                            format!("Saved {} digests to {}", lines.lines().count(), target.display())
+                        }
+                        Err(e) => format!("{:#}", e),
                     }
+                });
+                Command::none()
+            }
+            Message::ExportPdfPressed => {
+                let report = report::Report::new(self.report_rows());
+                if report.rows.is_empty() {
+                    self.status_note = Some("Nothing to report yet".to_string());
+                    return Command::none();
                 }
+                let name = format!("checksum-report-{}.pdf", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+                let Some(target) = FileDialog::new().set_file_name(name).add_filter("PDF", &["pdf"]).save_file() else {
+                    return Command::none();
+                };
+                let logo = Some(self.config.report_logo.trim()).filter(|l| !l.is_empty()).map(Path::new);
+                self.status_note = Some(match report::export_pdf(&report, &self.config.report_header, logo, &target) {
+                    Ok(()) => {
+                        self.count_operation("Export PDF", None);
+                        format!("PDF report written to {}", target.display())
+                    }
+                    Err(e) => format!("{:#}", e),
+                });
                 Command::none()
             }
-            Message::AutoHashToggled(v) => {
-                self.auto_hash = v;
+            Message::ReportHeaderChanged(v) => {
+                self.config.report_header = v;
+                self.save_config()
+            }
+            Message::ReportLogoChanged(v) => {
+                self.config.report_logo = v;
+                self.save_config()
+            }
+            Message::CompareWithPressed => {
+                let Some(a) = self.last_path.clone() else {
+                    return Command::none();
+                };
+                let mut dialog = FileDialog::new();
+                if let Some(parent) = a.parent().filter(|_| !self.forensic_mode) {
+                    dialog = dialog.set_directory(parent);
+                }
+                if let Some(b) = dialog.pick_file() {
+                    self.count_operation("Compare with file", None);
+                    let hex = hex::encode(&self.digest);
+                    let diff = self.binary_diff;
+                    let (tx, rx) = mpsc::channel();
+                    self.compare_rx = Some(rx);
+                    self.compare_note = Some(format!("Comparing with {}...", b.display()));
+                    thread::spawn(move || {
+                        let _ = tx.send(compare_files(&a, &hex, &b, diff));
+                    });
+                }
                 Command::none()
             }
-            Message::DroppedFile(path) => {
-                let old_path = self.path_input.clone();
-                self.path_input = path.to_string_lossy().to_string();
-                self.error = None;
-                if self.auto_hash {
-                    self.start_hashing(self.path_input.clone(), Some(old_path));
+            Message::VerifiedCopyPressed => {
+                // The path in the input (file or folder), or a folder picked now
+                let typed = PathBuf::from(self.path_input.trim());
+                let source = if !self.path_input.trim().is_empty() && typed.exists() { Some(typed) } else { FileDialog::new().set_title("Folder to copy").pick_folder() };
+                let Some(source) = source else {
+                    return Command::none();
+                };
+                let Some(dest) = FileDialog::new().set_title("Copy into folder").pick_folder() else {
                     return Command::none();
+                };
+                match copy_verify::CopyRun::start(source, dest) {
+                    Ok(run) => {
+                        self.copy_run = Some(run);
+                        self.count_operation("Verified copy", None);
+                    }
+                    Err(e) => self.status_note = Some(format!("{:#}", e)),
                 }
                 Command::none()
             }
-            Message::StartHash => {
-                if !self.path_input.trim().is_empty() && !self.is_hashing {
-                    self.start_hashing(self.path_input.clone(), None);
+            Message::CopyCancelPressed => {
+                if let Some(run) = &self.copy_run {
+                    run.cancel();
+                }
+                Command::none()
+            }
+            Message::CopyClosePressed => {
+                self.copy_run = None;
+                Command::none()
+            }
+            Message::CompareFoldersPressed => {
+                // Folder A is the one in the input when it is a folder, otherwise picked now
+                let typed = PathBuf::from(self.path_input.trim());
+                let a = if !self.path_input.trim().is_empty() && typed.is_dir() { Some(typed) } else { FileDialog::new().set_title("Folder A").pick_folder() };
+                let Some(a) = a else {
+                    return Command::none();
+                };
+                let Some(b) = FileDialog::new().set_title(format!("Compare {} with folder B", a.display())).pick_folder() else {
                     return Command::none();
+                };
+                match folder_diff::CompareRun::start(a, b, self.forensic_mode) {
+                    Ok(run) => {
+                        self.folder_compare = Some(run);
+                        self.count_operation("Compare folders", None);
+                    }
+                    Err(e) => self.status_note = Some(format!("{:#}", e)),
                 }
                 Command::none()
             }
-            Message::Tick => {
-                if self.is_hashing {
-                    if let Some(counter) = &self.progress_counter {
-                        self.progress_processed = counter.load(Ordering::Relaxed);
+            Message::FolderCompareCancelPressed => {
+                if let Some(run) = &self.folder_compare {
+                    run.cancel();
+                }
+                Command::none()
+            }
+            Message::FolderCompareClosePressed => {
+                self.folder_compare = None;
+                Command::none()
+            }
+            Message::DiffBaselinesPressed => {
+                let pick = |title: &str| {
+                    FileDialog::new()
+                        .set_title(title)
+                        .add_filter("Checksum lists and manifests", &["sha256", "sha", "txt", "log", "csv"])
+                        .add_filter("All files", &["*"])
+                        .pick_file()
+                };
+                let Some(old) = pick("Older baseline") else {
+                    return Command::none();
+                };
+                let Some(new) = pick("Newer baseline") else {
+                    return Command::none();
+                };
+                match snapshot_diff::diff(&old, &new) {
+                    Ok(diff) => {
+                        self.snapshot_diff = Some(diff);
+                        self.count_operation("Diff baselines", None);
                     }
-                    if let Some(rx) = &self.worker_rx {
-                        if let Ok((token, result)) = rx.try_recv() {
-                            if token == self.token {
-                                self.is_hashing = false;
-                                match result {
-                                    Ok(hr) => {
-                                        self.error = None;
-                                        self.hex_output = if self.uppercase { hr.hex.to_uppercase() } else { hr.hex };
-                                        self.base64_output = hr.base64;
-                                        self.last_elapsed = Some(hr.elapsed);
-                                        self.last_bytes = Some(hr.bytes);
-                                        self.last_path = hr.path;
-                                    }
-                                    Err(e) => {
-                                        if e == "CANCELLED" {
-                                            // Already restored path in CancelPressed
-                                            self.error = None;
-                                        } else {
-                                            self.error = Some(e);
-                                            self.hex_output.clear();
-                                            self.base64_output.clear();
-                                            self.last_elapsed = None;
-                                            self.last_bytes = None;
-                                            self.last_path = None;
-                                        }
-                                    }
-                                }
-                                self.progress_total = None;
-                                self.progress_processed = 0;
-                                self.progress_counter = None;
-                                self.cancel_flag = None;
-                                self.worker_rx = None;
-                                self.worker_token = None;
-                            }
-                        }
+                    Err(e) => self.status_note = Some(format!("{:#}", e)),
+                }
+                Command::none()
+            }
+            Message::SnapshotDiffClosePressed => {
+                self.snapshot_diff = None;
+                Command::none()
+            }
+            Message::DryRunListPressed => {
+                let Some(path) = FileDialog::new()
+                    .set_title("Checksum list or log to dry-run")
+                    .add_filter("Checksum lists and logs", &["sha256", "sha", "txt", "log", "csv"])
+                    .add_filter("All files", &["*"])
+                    .pick_file()
+                else {
+                    return Command::none();
+                };
+                match hashlist::load(&path) {
+                    Ok(list) => {
+                        self.dry_run = Some(verify::dry_run(path, list, self.matching));
+                        self.count_operation("Dry-run list", None);
                     }
+                    Err(e) => self.status_note = Some(format!("{:#}", e)),
                 }
                 Command::none()
             }
-            Message::Ignored => Command::none(),
-        }
-    }
-
-    fn view(&self) -> Element<'_, Self::Message> {
-        let title = text("Rust Hash256").size(28);
-
-        let path_input = text_input("Drag a file here or paste path...", &self.path_input)
-            .on_input(Message::PathChanged)
-            .on_submit(Message::StartHash)
-            .padding(12)
-            .size(16)
-            .width(Length::Fill);
-
-        let browse_btn = if self.is_hashing {
-            button(text("Browse").size(16)).style(theme::Button::Secondary)
-        } else {
-            button(text("Browse").size(16)).on_press(Message::BrowsePressed)
-        };
-
-        let clear_btn = if self.is_hashing {
-            button(text("Clear").size(16)).style(theme::Button::Secondary)
-        } else {
-            button(text("Clear").size(16)).on_press(Message::ClearPressed)
-        };
-
-        let cancel_btn: Option<Element<'_, Message>> = if self.is_hashing {
-            Some(button(text("Cancel").size(16)).on_press(Message::CancelPressed).style(theme::Button::Primary).into())
-        } else {
-            None
-        };
-
-        let toggles = row![
-            checkbox("Uppercase HEX", self.uppercase).on_toggle(Message::UppercaseToggled),
-            checkbox("Auto hash on select", self.auto_hash).on_toggle(Message::AutoHashToggled),
-        ]
-        .spacing(20)
-        .align_items(iced::Alignment::Center);
-
-        let header = if let Some(c) = cancel_btn {
-            row![path_input, browse_btn, clear_btn, c]
-                .spacing(10)
-                .align_items(iced::Alignment::Center)
-        } else {
-            row![path_input, browse_btn, clear_btn]
-                .spacing(10)
-                .align_items(iced::Alignment::Center)
-        };
-
-        let drag_hint = container(text("Drop a file anywhere in this window to hash").size(14))
-            .width(Length::Fill)
-            .padding(6);
-
-        let outputs = column![
+            Message::RemoteTargetChanged(v) => {
+                self.remote_input = v;
+                Command::none()
+            }
+            Message::HashRemotePressed => {
+                if !self.network_allowed() {
+                    self.status_note = Some("Hashing over SSH is off while network features are turned off".to_string());
+                    return Command::none();
+                }
+                if let Some(target) = remote::Target::parse(&self.remote_input) {
+                    if let Some(run) = &self.remote_run {
+                        run.cancel();
+                    }
+                    self.remote_run = Some(remote::RemoteRun::start(target));
+                    self.count_operation("Hash over SSH", None);
+                }
+                Command::none()
+            }
+            Message::CancelRemotePressed => {
+                if let Some(run) = self.remote_run.take() {
+                    run.cancel();
+                }
+                Command::none()
+            }
+            Message::CopyRemoteDigest => {
+                let hex = self.remote_run.as_ref().and_then(|r| r.result.as_ref()?.as_ref().ok()).map(|r| r.hex.clone()).unwrap_or_default();
+                clipboard::write(if self.uppercase { hex.to_uppercase() } else { hex })
+            }
+            Message::HashTextChanged(v) => {
+                self.hash_text = v;
+                Command::none()
+            }
+            Message::CopyTextDigest => {
+                self.count_operation("Text hash", Some(algo::Algorithm::Sha256));
+                clipboard::write(text_digest(&self.hash_text, self.uppercase))
+            }
+            Message::InstallMenu => {
+                mac_menu::install_menu();
+                Command::none()
+            }
+            Message::MenuAction(action) => match action {
+                mac_menu::Action::About => {
+                    self.show_about = true;
+                    self.show_settings = false;
+                    Command::none()
+                }
+                mac_menu::Action::Preferences => {
+                    self.show_settings = true;
+                    self.show_about = false;
+                    Command::none()
+                }
+                mac_menu::Action::Open => self.update(Message::BrowsePressed),
+                mac_menu::Action::Quit => self.update(Message::CloseRequested),
+                mac_menu::Action::OpenFiles(paths) => Command::batch(paths.into_iter().map(|p| self.update(Message::DroppedFile(p))).collect::<Vec<_>>()),
+            },
+            Message::WatchStagingPressed => {
+                if self.forensic_mode {
+                    self.status_note = Some("Watching a staging folder is off in forensic mode, which writes no SHA256SUMS".to_string());
+                    return Command::none();
+                }
+                let Some(folder) = FileDialog::new().set_title("Staging folder to keep a SHA256SUMS for").pick_folder() else {
+                    return Command::none();
+                };
+                if let Some(watch) = &self.staging {
+                    watch.cancel();
+                }
+                match staging::StagingWatch::start(folder) {
+                    Ok(watch) => {
+                        self.staging = Some(watch);
+                        self.count_operation("Watch staging folder", None);
+                    }
+                    Err(e) => self.status_note = Some(format!("{:#}", e)),
+                }
+                Command::none()
+            }
+            Message::StopStagingPressed => {
+                if let Some(watch) = self.staging.take() {
+                    watch.cancel();
+                }
+                Command::none()
+            }
+            Message::EditManifestPressed => {
+                let Some(path) = FileDialog::new()
+                    .set_title("Checksum list to edit")
+                    .add_filter("Checksum lists", &["sha256", "sha", "txt"])
+                    .add_filter("All files", &["*"])
+                    .pick_file()
+                else {
+                    return Command::none();
+                };
+                match manifest::Editor::open(&path) {
+                    Ok(editor) => {
+                        self.manifest_editor = Some(editor);
+                        self.count_operation("Edit manifest", None);
+                    }
+                    Err(e) => self.status_note = Some(format!("{:#}", e)),
+                }
+                Command::none()
+            }
+            Message::ManifestEntryToggled(line, on) => {
+                if let Some(editor) = self.manifest_editor.as_mut() {
+                    editor.toggle(line, on);
+                }
+                Command::none()
+            }
+            Message::ManifestRemovePressed => {
+                if let Some(editor) = self.manifest_editor.as_mut() {
+                    editor.remove_selected();
+                }
+                Command::none()
+            }
+            Message::ManifestRehashPressed => {
+                if let Some(editor) = self.manifest_editor.as_mut() {
+                    editor.rehash_selected();
+                }
+                Command::none()
+            }
+            Message::ManifestAddFilesPressed => {
+                let Some(editor) = self.manifest_editor.as_mut() else {
+                    return Command::none();
+                };
+                let mut dialog = FileDialog::new().set_title("Files to add to the list");
+                if let Some(dir) = editor.manifest.path.parent() {
+                    dialog = dialog.set_directory(dir);
+                }
+                if let Some(files) = dialog.pick_files() {
+                    editor.add_files(files);
+                }
+                Command::none()
+            }
+            Message::ManifestSavePressed => {
+                if let Some(editor) = self.manifest_editor.as_mut() {
+                    self.status_note = Some(match editor.save() {
+                        Ok(()) => format!("Saved {} ({} entries)", editor.manifest.path.display(), editor.manifest.count()),
+                        Err(e) => format!("{:#}", e),
+                    });
+                }
+                Command::none()
+            }
+            Message::ManifestCancelPressed => {
+                if let Some(editor) = &self.manifest_editor {
+                    editor.cancel();
+                }
+                Command::none()
+            }
+            Message::ManifestClosePressed => {
+                if let Some(editor) = &self.manifest_editor {
+                    if editor.dirty {
+                        let discard = rfd::MessageDialog::new()
+                            .set_title("Unsaved changes")
+                            .set_description(format!("Close {} without saving the changes?", editor.manifest.path.display()))
+                            .set_buttons(rfd::MessageButtons::YesNo)
+                            .show();
+                        if discard != rfd::MessageDialogResult::Yes {
+                            return Command::none();
+                        }
+                    }
+                    editor.cancel();
+                }
+                self.manifest_editor = None;
+                Command::none()
+            }
+            Message::DryRunClosePressed => {
+                self.dry_run = None;
+                Command::none()
+            }
+            Message::VerifyListPressed => {
+                // Several lists (e.g. one per folder) are verified in one run with a combined summary
+                let Some(paths) = FileDialog::new()
+                    .set_title("Checksum lists or TeraCopy/FastCopy logs")
+                    .add_filter("Checksum lists and logs", &["sha256", "sha", "txt", "log", "csv"])
+                    .add_filter("All files", &["*"])
+                    .pick_files()
+                else {
+                    return Command::none();
+                };
+                let mut lists = Vec::new();
+                for path in paths {
+                    match hashlist::load(&path) {
+                        Ok(list) => lists.push((path, list)),
+                        Err(e) => {
+                            self.status_note = Some(format!("{:#}", e));
+                            return Command::none();
+                        }
+                    }
+                }
+                let run = match lists.len() {
+                    0 => return Command::none(),
+                    1 => {
+                        let (path, list) = lists.remove(0);
+                        verify::VerifyRun::start(path, list, self.forensic_mode, self.matching)
+                    }
+                    _ => verify::VerifyRun::start_many(lists, self.forensic_mode, self.matching),
+                };
+                self.start_verify(run);
+                self.count_operation("Verify list", None);
+                Command::none()
+            }
+            Message::AcquisitionPressed => {
+                // Devices cannot be picked in a file dialog, so the source comes from the path field
+                let device = PathBuf::from(self.path_input.trim());
+                if self.path_input.trim().is_empty() || !device.exists() {
+                    self.status_note = Some("Enter the source device (e.g. /dev/sdb or \\\\.\\PhysicalDrive1) in the path field first".to_string());
+                    return Command::none();
+                }
+                let mut dialog = FileDialog::new().set_title("Image acquired from this device");
+                if let Some(parent) = self.last_path.as_ref().and_then(|p| p.parent()).filter(|_| !self.forensic_mode) {
+                    dialog = dialog.set_directory(parent);
+                }
+                if let Some(image) = dialog.pick_file() {
+                    self.acquisition = Some(acquisition::AcquisitionRun::start(device, image, self.forensic_mode));
+                    self.count_operation("Verify acquisition", None);
+                }
+                Command::none()
+            }
+            Message::AcquisitionCancelPressed => {
+                if let Some(run) = &self.acquisition {
+                    run.cancel();
+                }
+                Command::none()
+            }
+            Message::AcquisitionClosePressed => {
+                self.acquisition = None;
+                Command::none()
+            }
+            Message::VerifyPar2Pressed => {
+                // PAR2 sets checksum files and blocks with MD5
+                if let Some(refusal) = policy::get().refusal(algo::Algorithm::Md5) {
+                    self.status_note = Some(refusal);
+                    return Command::none();
+                }
+                let Some(path) = FileDialog::new().set_title("PAR2 recovery set").add_filter("PAR2 files", &["par2"]).pick_file() else {
+                    return Command::none();
+                };
+                match par2::start(path) {
+                    Ok(run) => {
+                        self.start_verify(run);
+                        self.count_operation("Verify PAR2", Some(algo::Algorithm::Md5));
+                    }
+                    Err(e) => self.status_note = Some(format!("{:#}", e)),
+                }
+                Command::none()
+            }
+            Message::VerifyCancelPressed => {
+                if let Some(run) = &self.verify_run {
+                    run.cancel();
+                }
+                Command::none()
+            }
+            Message::VerifyClosePressed => {
+                self.verify_run = None;
+                Command::none()
+            }
+            Message::VerifyAcknowledgePressed(index) => {
+                let Some(run) = self.verify_run.as_mut() else {
+                    return Command::none();
+                };
+                let Some(item) = run.done.get(index) else {
+                    return Command::none();
+                };
+                let digest = match &item.status {
+                    verify::Status::Mismatch(hex) | verify::Status::AttributesChanged(hex) => Some(hex.clone()),
+                    _ => None,
+                };
+                let mut store = acknowledged::Acknowledged::load();
+                store.add(run.list_of(item), &item.entry.path, digest);
+                if let Err(e) = store.save() {
+                    self.status_note = Some(format!("{:#}", e));
+                }
+                run.acknowledged = run.lists.iter().flat_map(|(list, _)| store.for_manifest(list)).collect();
+                Command::none()
+            }
+            Message::VerifyClearAcknowledgedPressed => {
+                if let Some(run) = self.verify_run.as_mut() {
+                    let mut store = acknowledged::Acknowledged::load();
+                    for (list, _) in &run.lists {
+                        store.clear_manifest(list);
+                    }
+                    if let Err(e) = store.save() {
+                        self.status_note = Some(format!("{:#}", e));
+                    }
+                    run.acknowledged.clear();
+                }
+                Command::none()
+            }
+            Message::VerifyRetryPressed => {
+                if let Some(run) = self.verify_run.as_mut() {
+                    run.retry_failed();
+                }
+                Command::none()
+            }
+            Message::SlowestToggled => {
+                self.show_slowest = !self.show_slowest;
+                Command::none()
+            }
+            Message::MoreToolsToggled => {
+                self.more_tools = !self.more_tools;
+                Command::none()
+            }
+            Message::BinaryDiffToggled(v) => {
+                self.binary_diff = v;
+                self.store_preferences()
+            }
+            Message::ArchiveCheckToggled(v) => {
+                self.archive_check = v;
+                self.store_preferences()
+            }
+            Message::ArchiveDigestToggled(v) => {
+                self.archive_digest_mode = v;
+                self.store_preferences()
+            }
+            Message::PartitionsToggled(v) => {
+                self.partition_mode = v;
+                self.store_preferences()
+            }
+            Message::JobTimeoutSelected(timeout) => {
+                self.job_timeout = timeout;
+                self.store_preferences()
+            }
+            Message::FolderChoicesToggled(v) => {
+                self.folder_choices_mode = v;
+                self.store_preferences()
+            }
+            Message::SavePartitionsPressed => {
+                let Some((image, Some(Ok(parts)))) = self.partition_run.as_ref().map(|r| (&r.image, r.result.as_ref())) else {
+                    return Command::none();
+                };
+                let name = format!("{}.partitions.sha256", image.file_stem().unwrap_or_default().to_string_lossy());
+                let mut dialog = FileDialog::new().set_file_name(name).add_filter("Checksum list", &["sha256"]);
+                if let Some(parent) = image.parent().filter(|_| !self.forensic_mode) {
+                    dialog = dialog.set_directory(parent);
+                }
+                if let Some(target) = dialog.save_file() {
+                    self.status_note = Some(match atomic::write(&target, partitions::manifest(image, parts).as_bytes()) {
+                        Ok(()) => format!("Saved {} partition digests to {}", parts.len(), target.display()),
+                        Err(e) => format!("{:#}", e),
+                    });
+                }
+                Command::none()
+            }
+            Message::CopyArchiveDigest => clipboard::write(self.archive_digest.as_ref().and_then(|d| d.as_ref().ok()).map(|d| d.hex.clone()).unwrap_or_default()),
+            Message::DiscContentsToggled(v) => {
+                self.disc_contents = v;
+                self.store_preferences()
+            }
+            Message::PayloadToggled(v) => {
+                self.payload_mode = v;
+                self.store_preferences()
+            }
+            Message::MatchSeparatorsToggled(v) => {
+                self.matching.separators = v;
+                self.store_preferences()
+            }
+            Message::MatchIgnoreCaseToggled(v) => {
+                self.matching.ignore_case = v;
+                self.store_preferences()
+            }
+            Message::MatchUnicodeToggled(v) => {
+                self.matching.unicode = v;
+                self.store_preferences()
+            }
+            Message::SkipUnopenableToggled(v) => {
+                self.skip_unopenable = v;
+                self.store_preferences()
+            }
+            Message::RelativeResultsToggled(v) => {
+                self.relative_results = v;
+                self.store_preferences()
+            }
+            Message::TreeXattrsToggled(v) => {
+                self.tree_xattrs = v;
+                self.store_preferences()
+            }
+            Message::VerifyStopOnFailureToggled(v) => {
+                self.verify_stop_on_failure = v;
+                self.store_preferences()
+            }
+            Message::SaveDiscManifestPressed => {
+                let Some((image, Some(Ok(contents)))) = self.disc_run.as_ref().map(|r| (&r.image, r.result.as_ref())) else {
+                    return Command::none();
+                };
+                let name = format!("{}.contents.sha256", image.file_stem().unwrap_or_default().to_string_lossy());
+                let mut dialog = FileDialog::new().set_file_name(name).add_filter("Checksum list", &["sha256"]);
+                if let Some(parent) = image.parent().filter(|_| !self.forensic_mode) {
+                    dialog = dialog.set_directory(parent);
+                }
+                if let Some(target) = dialog.save_file() {
+                    self.status_note = Some(match atomic::write(&target, contents.manifest().as_bytes()) {
+                        Ok(()) => format!("Saved {} file digests to {}", contents.files.len(), target.display()),
+                        Err(e) => format!("{:#}", e),
+                    });
+                }
+                Command::none()
+            }
+            Message::CompareDiscFolderPressed => {
+                let Some((image, Some(Ok(contents)))) = self.disc_run.as_ref().map(|r| (&r.image, r.result.as_ref())) else {
+                    return Command::none();
+                };
+                let Some(folder) = FileDialog::new().set_title("Folder with the files copied from the disc").pick_folder() else {
+                    return Command::none();
+                };
+                let entries = contents.files.iter().map(|f| verify::Entry { path: folder.join(&f.path), expected: f.sha256.clone(), list: 0, also: Vec::new(), xattrs: None }).collect();
+                let list = hashlist::HashList { format: "image contents", entries, skipped: 0 };
+                let run = verify::VerifyRun::start(image.clone(), list, self.forensic_mode, self.matching);
+                self.start_verify(run);
+                Command::none()
+            }
+            Message::ShareChecksumPressed => {
+                let Some(path) = self.last_path.clone() else {
+                    return Command::none();
+                };
+                match write_checksum_file(&path, &hex::encode(&self.digest)) {
+                    Ok(file) => {
+                        self.shared_checksum = cfg!(windows).then(|| file.clone());
+                        window::run_with_handle(window::Id::MAIN, move |handle| {
+                            Message::ChecksumShared(desktop::share_file(&file, handle).map_err(|e| format!("{:#}", e)))
+                        })
+                    }
+                    Err(e) => {
+                        self.status_note = Some(format!("{:#}", e));
+                        Command::none()
+                    }
+                }
+            }
+            Message::ChecksumShared(shared) => {
+                self.shared_checksum = None;
+                if shared.is_ok() {
+                    self.count_operation("Share checksum", None);
+                }
+                self.status_note = Some(shared.unwrap_or_else(|e| e));
+                Command::none()
+            }
+            Message::CopyNiPressed => match content_id::ni_uri(&hex::encode(&self.digest)) {
+                Some(uri) => {
+                    self.count_operation("Content ID", None);
+                    self.status_note = Some("ni URI copied".to_string());
+                    clipboard::write(uri)
+                }
+                None => Command::none(),
+            },
+            Message::CopyMultihashPressed(base) => match content_id::multihash(algo::Algorithm::Sha256.multihash_code(), &hex::encode(&self.digest)) {
+                Some(multihash) => {
+                    self.count_operation("Content ID", None);
+                    self.status_note = Some(format!("Multihash ({}) copied", base.label()));
+                    clipboard::write(base.encode(&multihash))
+                }
+                None => Command::none(),
+            },
+            Message::PublishSidecarsPressed => {
+                if self.forensic_mode {
+                    self.status_note = Some("Publishing sidecars is off in forensic mode, which writes nothing next to the evidence".to_string());
+                    return Command::none();
+                }
+                let Some(artifacts) = FileDialog::new().set_title("Artifacts to publish").pick_files() else {
+                    return Command::none();
+                };
+                self.count_operation("Publish sidecars", None);
+                let (tx, rx) = mpsc::channel();
+                self.sidecars_rx = Some(rx);
+                self.status_note = Some(format!("Writing checksum sidecars for {} artifacts...", artifacts.len()));
+                // Refused algorithms are left out and named in the note
+                let refused: Vec<String> = sidecars::SIDECARS.iter().filter_map(|(a, _)| policy::get().refusal(*a)).collect();
+                thread::spawn(move || {
+                    let cancel = AtomicBool::new(false);
+                    let mut failed = Vec::new();
+                    for artifact in &artifacts {
+                        if let Err(e) = sidecars::publish(artifact, &cancel) {
+                            failed.push(format!("{:#}", e));
+                        }
+                    }
+                    let exts = sidecars::allowed().iter().map(|(_, ext)| format!(".{}", ext)).collect::<Vec<_>>().join(", ");
+                    let mut note = format!("Wrote {} next to {} artifacts", exts, artifacts.len() - failed.len());
+                    if !refused.is_empty() {
+                        note.push_str(&format!("; skipped: {}", refused.join("; ")));
+                    }
+                    if !failed.is_empty() {
+                        note.push_str(&format!("; failed: {}", failed.join("; ")));
+                    }
+                    let _ = tx.send(note);
+                });
+                Command::none()
+            }
+            Message::CopyMagnetPressed => {
+                let Some(path) = self.last_path.clone() else {
+                    return Command::none();
+                };
+                // BitTorrent v1 pieces and info hash are SHA-1
+                if let Some(refusal) = policy::get().refusal(algo::Algorithm::Sha1) {
+                    self.status_note = Some(refusal);
+                    return Command::none();
+                }
+                let (tx, rx) = mpsc::channel();
+                self.copy_rx = Some(rx);
+                self.status_note = Some("Working out the magnet link...".to_string());
+                self.count_operation("Magnet link", Some(algo::Algorithm::Sha1));
+                thread::spawn(move || {
+                    let _ = tx.send(content_id::magnet(&path).map(|link| (link, "Magnet link copied".to_string())).map_err(|e| format!("{:#}", e)));
+                });
+                Command::none()
+            }
+            Message::CopyIntegrityPressed => {
+                let Some(path) = self.last_path.clone() else {
+                    return Command::none();
+                };
+                if let Some(refusal) = policy::get().refusal(algo::Algorithm::Sha512) {
+                    self.status_note = Some(refusal);
+                    return Command::none();
+                }
+                let (tx, rx) = mpsc::channel();
+                self.copy_rx = Some(rx);
+                self.status_note = Some("Working out the SHA-512 integrity value...".to_string());
+                self.count_operation("npm integrity", Some(algo::Algorithm::Sha512));
+                thread::spawn(move || {
+                    let result = algo::hash_file(&path, algo::Algorithm::Sha512, &AtomicU64::new(0), &AtomicBool::new(false))
+                        .map(|(hex, _)| (integrity::sri(algo::Algorithm::Sha512, &hex).unwrap_or_default(), "npm integrity value copied".to_string()))
+                        .map_err(|e| format!("{:#}", e));
+                    let _ = tx.send(result);
+                });
+                Command::none()
+            }
+            Message::CopyTreeDigestPressed => {
+                let Some(folder) = FileDialog::new().set_title("Folder to work out one digest for").pick_folder() else {
+                    return Command::none();
+                };
+                let attributes = self.tree_xattrs;
+                let (tx, rx) = mpsc::channel();
+                self.copy_rx = Some(rx);
+                self.status_note = Some(format!("Working out the tree digest of {}...", folder.display()));
+                self.count_operation("Tree digest", Some(algo::Algorithm::Sha256));
+                thread::spawn(move || {
+                    let result = tree_digest::digest(&folder, attributes, &AtomicU64::new(0), &AtomicBool::new(false))
+                        .map(|(hex, files)| {
+                            let tag = if attributes { tree_digest::TAG_XATTRS } else { tree_digest::TAG };
+                            (hex, format!("{} of {} files copied", tag, files))
+                        })
+                        .map_err(|e| format!("{:#}", e));
+                    let _ = tx.send(result);
+                });
+                Command::none()
+            }
+            Message::ExpectedChanged(v) => {
+                // A typed value replaces the one taken from a checksum file
+                self.pair = None;
+                self.expected_input = v;
+                self.check_expected();
+                Command::none()
+            }
+            Message::UnpairPressed => {
+                self.pair = None;
+                self.expected_input.clear();
+                self.check_expected();
+                Command::none()
+            }
+            Message::UppercaseToggled(v) => {
+                self.uppercase = v;
+                self.store_preferences()
+            }
+            Message::AutoHashToggled(v) => {
+                self.auto_hash = v;
+                if !v {
+                    self.auto_hash_due = None;
+                }
+                self.store_preferences()
+            }
+            Message::QuickModeToggled(v) => {
+                self.quick_mode = v;
+                self.store_preferences()
+            }
+            Message::ChunkModeToggled(v) => {
+                self.chunk_mode = v;
+                self.store_preferences()
+            }
+            Message::SaveChunksPressed => {
+                if self.last_chunks.is_empty() {
+                    return Command::none();
+                }
+                let Some(path) = self.last_path.clone() else {
+                    return Command::none();
+                };
+                let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| "file".to_string());
+                let mut dialog = FileDialog::new().set_file_name(format!("{}.chunks.txt", file_name));
+                if let Some(parent) = path.parent().filter(|_| !self.forensic_mode) {
+                    dialog = dialog.set_directory(parent);
+                }
+                if let Some(target) = dialog.save_file() {
+                    let bytes = self.last_bytes.unwrap_or(0);
+                    let hex = hex::encode(&self.digest);
+                    if let Err(e) = chunking::write_chunk_list(&target, &path, bytes, &hex, &self.last_chunks) {
+                        self.error = Some(HashError::classify(&e, None));
+                    }
+                }
+                Command::none()
+            }
+            Message::SignatureToggled(v) => {
+                // librsync signatures use BLAKE2
+                if let Some(refusal) = policy::get().refusal_of("BLAKE2").filter(|_| v) {
+                    self.status_note = Some(refusal);
+                    return Command::none();
+                }
+                self.signature_mode = v;
+                self.store_preferences()
+            }
+            Message::FollowToggled(v) => {
+                self.follow_mode = v;
+                self.store_preferences()
+            }
+            Message::ConcatToggled(v) => {
+                self.concat_mode = v;
+                if v {
+                    self.queue_mode = false;
+                }
+                Command::none()
+            }
+            Message::ContinueSavedQueue => {
+                for path in self.restored_queue.take().map(|q| q.paths).unwrap_or_default() {
+                    self.enqueue(PathBuf::from(path));
+                }
+                Command::none()
+            }
+            Message::DiscardSavedQueue => {
+                self.restored_queue = None;
+                Command::none()
+            }
+            Message::QueueToggled(v) => {
+                self.queue_mode = v;
+                if v {
+                    self.concat_mode = false;
+                }
+                Command::none()
+            }
+            Message::JobMoveUp(id) => {
+                if let Some(idx) = self.background.iter().position(|j| j.id == id).filter(|&i| i > 0) {
+                    self.background.swap(idx, idx - 1);
+                }
+                Command::none()
+            }
+            Message::JobMoveDown(id) => {
+                if let Some(idx) = self.background.iter().position(|j| j.id == id).filter(|&i| i + 1 < self.background.len()) {
+                    self.background.swap(idx, idx + 1);
+                }
+                Command::none()
+            }
+            Message::JobHashNext(id) => {
+                // Ahead of every other waiting file; whatever is already running keeps going. Small files handed to
+                // the shared worker but not started go back to waiting, so the file does not sit behind their batch
+                if let Some(idx) = self.background.iter().position(|j| j.id == id) {
+                    for (_, _, w) in self.small_files.take_back() {
+                        if let Some(job) = self.background.iter_mut().find(|j| j.id == w.token) {
+                            job.requeue();
+                        }
+                    }
+                    let job = self.background.remove(idx);
+                    let first_waiting = self.background.iter().position(|j| j.waiting).unwrap_or(self.background.len());
+                    self.background.insert(first_waiting, job);
+                }
+                Command::none()
+            }
+            Message::ZeroAwareToggled(v) => {
+                self.zero_aware_mode = v;
+                self.store_preferences()
+            }
+            Message::ForensicToggled(v) => {
+                self.forensic_mode = v;
+                // A running watch would keep rewriting its SHA256SUMS
+                if v {
+                    if let Some(watch) = self.staging.take() {
+                        watch.cancel();
+                    }
+                }
+                let action = if v { "forensic-on" } else { "forensic-off" };
+                if let Err(e) = custody::append(custody::Record { action, ..Default::default() }) {
+                    self.status_note = Some(format!("{:#}", e));
+                }
+                self.store_preferences()
+            }
+            Message::LoadListPressed(kind) => {
+                let dialog = FileDialog::new().add_filter("Hash list", &["txt", "csv", "tsv", "sha256"]).add_filter("All files", &["*"]);
+                let Some(path) = dialog.pick_file() else {
+                    return Command::none();
+                };
+                *self.list_path(kind) = path.display().to_string();
+                self.list_slot(kind).load(path);
+                self.count_operation("Load hash list", None);
+                self.save_config()
+            }
+            Message::ClearListPressed(kind) => {
+                self.list_slot(kind).clear();
+                self.list_path(kind).clear();
+                self.save_config()
+            }
+            Message::ExportCustodyPressed => {
+                let name = format!("custody-report-{}.txt", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+                let Some(target) = FileDialog::new().set_file_name(name).add_filter("Text", &["txt"]).save_file() else {
+                    return Command::none();
+                };
+                let exported = custody::export_report(&target);
+                if exported.is_ok() {
+                    self.count_operation("Export custody log", None);
+                }
+                self.status_note = Some(match exported {
+                    Ok(Ok(signature)) => format!("Custody report written to {}, signed as {}", target.display(), signature.display()),
+                    Ok(Err(e)) => format!("Custody report written to {}, but not signed: {:#}", target.display(), e),
+                    Err(e) => format!("{:#}", e),
+                });
+                Command::none()
+            }
+            Message::SegmentsToggled(v) => {
+                self.segment_mode = v;
+                self.store_preferences()
+            }
+            Message::CidToggled(v) => {
+                self.cid_mode = v;
+                self.store_preferences()
+            }
+            Message::CopyCid => clipboard::write(self.last_cid.clone().unwrap_or_default()),
+            Message::CloudToggled(v) => {
+                self.cloud_mode = v;
+                self.store_preferences()
+            }
+            Message::AuthenticodeToggled(v) => {
+                self.authenticode_mode = v;
+                self.store_preferences()
+            }
+            Message::CopyAuthenticode => clipboard::write(self.last_authenticode.clone().unwrap_or_default()),
+            Message::MetadataDigestToggled(v) => {
+                self.metadata_mode = v;
+                self.store_preferences()
+            }
+            Message::MetadataFieldsSelected(fields) => {
+                self.metadata_fields = Some(fields);
+                self.store_preferences()
+            }
+            Message::HexGroupingSelected(g) => {
+                self.hex_grouping = g;
+                self.store_preferences()
+            }
+            Message::UpdateRateSelected(rate) => {
+                self.update_rate = rate;
+                self.store_preferences()
+            }
+            Message::PaletteSelected(p) => {
+                self.palette = p;
+                palette::set(p);
+                self.store_preferences()
+            }
+            Message::CopyMetadataDigest => clipboard::write(self.last_metadata_digest.clone().unwrap_or_default()),
+            Message::CopyContentMd5 => clipboard::write(self.last_cloud.as_ref().map(|c| c.content_md5.clone()).unwrap_or_default()),
+            Message::CopyCrc32c => clipboard::write(self.last_cloud.as_ref().map(|c| c.crc32c.clone()).unwrap_or_default()),
+            Message::CopyPreferredDigest => clipboard::write(self.last_preferred.as_ref().map(|(_, hex)| hex.clone()).unwrap_or_default()),
+            Message::SegmentSizeSelected(size) => {
+                self.segment_size = Some(size);
+                self.store_preferences()
+            }
+            Message::SaveSegmentsPressed => {
+                if self.last_segments.is_empty() {
+                    return Command::none();
+                }
+                let Some(path) = self.last_path.clone() else {
+                    return Command::none();
+                };
+                let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| "file".to_string());
+                let mut dialog = FileDialog::new().set_file_name(format!("{}.segments.txt", file_name));
+                if let Some(parent) = path.parent().filter(|_| !self.forensic_mode) {
+                    dialog = dialog.set_directory(parent);
+                }
+                if let Some(target) = dialog.save_file() {
+                    let bytes = self.last_bytes.unwrap_or(0);
+                    let hex = hex::encode(&self.digest);
+                    match segments::write_segment_list(&target, &path, bytes, &hex, &self.last_segments) {
+                        Ok(()) => self.count_operation("Save segment digests", None),
+                        Err(e) => self.error = Some(HashError::classify(&e, None)),
+                    }
+                }
+                Command::none()
+            }
+            Message::CompareSegmentsPressed => {
+                if self.last_segments.is_empty() {
+                    return Command::none();
+                }
+                let mut dialog = FileDialog::new().add_filter("Segment list", &["txt"]);
+                if let Some(parent) = self.last_path.as_ref().and_then(|p| p.parent()) {
+                    dialog = dialog.set_directory(parent);
+                }
+                if let Some(other) = dialog.pick_file() {
+                    match segments::read_segment_list(&other) {
+                        Ok(reference) => {
+                            self.segment_summary = Some(format!(
+                                "vs {}: {}",
+                                other.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+                                segments::compare_summary(&self.last_segments, &reference)
+                            ));
+                        }
+                        Err(e) => self.error = Some(HashError::classify(&e, None)),
+                    }
+                }
+                Command::none()
+            }
+            Message::ConcatMoveUp(idx) => {
+                if idx > 0 && idx < self.concat_files.len() {
+                    self.concat_files.swap(idx - 1, idx);
+                }
+                Command::none()
+            }
+            Message::ConcatMoveDown(idx) => {
+                if idx + 1 < self.concat_files.len() {
+                    self.concat_files.swap(idx, idx + 1);
+                }
+                Command::none()
+            }
+            Message::ConcatRemove(idx) => {
+                if idx < self.concat_files.len() {
+                    self.concat_files.remove(idx);
+                }
+                Command::none()
+            }
+            Message::ConcatClearPressed => {
+                self.concat_files.clear();
+                Command::none()
+            }
+            Message::ConcatHashPressed => {
+                if !self.concat_files.is_empty() && !self.is_hashing {
+                    self.count_operation("Concatenated hash", None);
+                }
+                self.start_concat_hashing();
+                Command::none()
+            }
+            Message::SaveSignaturePressed => {
+                let (Some(signature), Some(path)) = (&self.last_signature, &self.last_path) else {
+                    return Command::none();
+                };
+                let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| "file".to_string());
+                let mut dialog = FileDialog::new().set_file_name(format!("{}.sig", file_name));
+                // Forensic mode: do not suggest saving onto the evidence volume
+                if let Some(parent) = path.parent().filter(|_| !self.forensic_mode) {
+                    dialog = dialog.set_directory(parent);
+                }
+                if let Some(target) = dialog.save_file() {
+                    match rsync_sig::write_signature(&target, signature) {
+                        Ok(()) => self.count_operation("Save rsync signature", None),
+                        Err(e) => self.error = Some(HashError::classify(&e, None)),
+                    }
+                }
+                Command::none()
+            }
+            Message::CompareChunksPressed => {
+                if self.last_chunks.is_empty() {
+                    return Command::none();
+                }
+                let mut dialog = FileDialog::new().add_filter("Chunk list", &["txt"]);
+                if let Some(parent) = self.last_path.as_ref().and_then(|p| p.parent()) {
+                    dialog = dialog.set_directory(parent);
+                }
+                if let Some(other) = dialog.pick_file() {
+                    match chunking::read_chunk_list(&other) {
+                        Ok(previous) => {
+                            self.chunk_summary = Some(format!(
+                                "vs {}: {}",
+                                other.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+                                chunking::compare_summary(&self.last_chunks, &previous)
+                            ));
+                        }
+                        Err(e) => self.error = Some(HashError::classify(&e, None)),
+                    }
+                }
+                Command::none()
+            }
+            Message::FileHovered => {
+                self.drag_hover = true;
+                Command::none()
+            }
+            Message::HoverLeft => {
+                self.drag_hover = false;
+                Command::none()
+            }
+            Message::DroppedFile(path) => {
+                self.drag_hover = false;
+                if self.update_rate != power::UpdateRate::Reduced {
+                    self.dropped_at = Some(Instant::now());
+                }
+                let path = uri::normalize_dropped(path);
+                // The end of a drag of our own checksum file that was let go over the window
+                if self.shared_checksum.take().is_some_and(|f| f == path) {
+                    return Command::none();
+                }
+                if self.concat_mode {
+                    self.concat_files.push(path);
+                    return Command::none();
+                }
+                if self.queue_mode {
+                    self.enqueue(path);
+                    return Command::none();
+                }
+                // A checksum file dropped with (or before, or after) a data file is paired with it
+                if pairing::is_checksum_file(&path) {
+                    let data = Some(PathBuf::from(self.path_input.trim())).filter(|_| self.path_status.is_hashable());
+                    match data {
+                        Some(data) => self.bind_pair(path, data),
+                        None => {
+                            self.status_note = Some(format!("Drop the file to check against {}", path.display()));
+                            self.pair = Some(pairing::Pair::waiting(path));
+                        }
+                    }
+                    return Command::none();
+                }
+                let old_path = self.path_input.clone();
+                self.path_input = path.to_string_lossy().to_string();
+                self.check_path();
+                self.error = None;
+                self.restore_folder_choice(&path);
+                if let Some(checksum) = self.pair.as_ref().map(|p| p.checksum.clone()) {
+                    self.bind_pair(checksum, path.clone());
+                }
+                if self.auto_hash {
+                    self.start_hashing(self.path_input.clone(), Some(old_path));
+                    return Command::none();
+                }
+                Command::none()
+            }
+            Message::StartHash => {
+                if !self.path_input.trim().is_empty() && !self.is_hashing {
+                    self.start_hashing(self.path_input.clone(), None);
+                    return Command::none();
+                }
+                Command::none()
+            }
+            Message::Tick => {
+                let mut commands = Vec::new();
+                if self.busy() {
+                    self.active_at = Some(Instant::now());
+                }
+                if self.power_checked.is_none_or(|at| at.elapsed() >= POWER_CHECK) {
+                    self.power_checked = Some(Instant::now());
+                    self.on_battery = power::on_battery();
+                }
+                self.notice_rx.retain(|rx| loop {
+                    match rx.try_recv() {
+                        Ok(notice) => self.status_note = Some(notice),
+                        Err(TryRecvError::Empty) => break true,
+                        Err(TryRecvError::Disconnected) => break false,
+                    }
+                });
+                if let Some(run) = self.copy_run.as_mut() {
+                    run.poll();
+                }
+                if let Some(run) = self.folder_compare.as_mut() {
+                    run.poll();
+                }
+                if let Some(watch) = self.staging.as_mut() {
+                    watch.poll();
+                }
+                if let Some(run) = self.remote_run.as_mut() {
+                    run.poll();
+                }
+                if let Some(editor) = self.manifest_editor.as_mut() {
+                    if editor.poll() && !editor.failed.is_empty() {
+                        self.status_note = Some(format!("{} files not hashed: {}", editor.failed.len(), editor.failed.join("; ")));
+                    }
+                }
+                if let Some(run) = self.verify_run.as_mut() {
+                    run.poll();
+                }
+                if let Some(note) = self.compare_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+                    self.compare_rx = None;
+                    self.compare_note = Some(note);
+                }
+                if let Some(run) = self.disc_run.as_mut() {
+                    run.poll();
+                }
+                if let Some(run) = self.partition_run.as_mut() {
+                    run.poll();
+                }
+                if let Some(run) = self.ewf_run.as_mut() {
+                    run.poll();
+                }
+                if let Some(run) = self.acquisition.as_mut() {
+                    if run.poll() && self.forensic_mode {
+                        if let Some(Ok(outcome)) = &run.result {
+                            let verdict = if outcome.matches() { "MATCH" } else { "MISMATCH" };
+                            let detail = format!("{} image {} sha256 {}", verdict, run.image.display(), outcome.image.hex);
+                            let record = custody::Record {
+                                action: "acquisition-verify",
+                                path: Some(&run.device),
+                                sha256: Some(&outcome.device.hex),
+                                bytes: Some(outcome.device.bytes),
+                                detail: Some(&detail),
+                            };
+                            if let Err(e) = custody::append(record) {
+                                self.status_note = Some(format!("Custody log not written: {:#}", e));
+                            }
+                        }
+                    }
+                }
+                if let Some(note) = self.sidecars_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+                    self.sidecars_rx = None;
+                    self.status_note = Some(note);
+                }
+                if let Some(result) = self.copy_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+                    self.copy_rx = None;
+                    match result {
+                        Ok((value, note)) => {
+                            self.status_note = Some(note);
+                            commands.push(clipboard::write(value));
+                        }
+                        Err(e) => self.status_note = Some(e),
+                    }
+                }
+                if let Some(result) = self.expected_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+                    self.expected_rx = None;
+                    self.expected_verdict = Some(match result {
+                        Ok(digest) => self.expected_verdict_for(&digest),
+                        Err(e) => Verdict::Failed(e),
+                    });
+                }
+                if let Some(note) = self.archive_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+                    self.archive_rx = None;
+                    self.archive_cancel = None;
+                    self.archive_note = Some(note);
+                }
+                if let Some(result) = self.archive_digest_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+                    self.archive_digest_rx = None;
+                    self.archive_digest_cancel = None;
+                    self.archive_digest = Some(result);
+                }
+                for kind in [ListKind::Known, ListKind::Blocklist] {
+                    match self.list_slot(kind).poll() {
+                        Some(Ok(set)) => {
+                            self.status_note = Some(format!("{} {}: {} SHA-256 values", kind.label(), set.name(), set.len()));
+                        }
+                        Some(Err(e)) => self.status_note = Some(format!("{:#}", e)),
+                        None => {}
+                    }
+                }
+                for job in &mut self.background {
+                    if job.poll(self.job_timeout) {
+                        if matches!(&job.outcome, Some(Err(e)) if e.kind == ErrorKind::TimedOut) {
+                            self.small_files.abandon();
+                        }
+                        // A locked or vanished file is noted and passed over, so an overnight queue still completes
+                        let skipped = match &job.outcome {
+                            Some(Err(e)) if job.queued && self.skip_unopenable && e.is_unopenable() => {
+                                self.batch_skipped.push(format!("{}: {}", job.path, e.reason()));
+                                true
+                            }
+                            _ => false,
+                        };
+                        if let Some(result) = &job.outcome {
+                            count_hash(self.usage.as_mut(), result.as_ref());
+                            if job.queued && !skipped {
+                                if result.is_ok() {
+                                    self.batch_succeeded += 1;
+                                } else {
+                                    self.batch_failed += 1;
+                                }
+                            }
+                        }
+                        if let Some(Ok(hr)) = &job.outcome {
+                            job.comparison = record_history(&mut self.history, self.jump_list.as_ref(), hr);
+                            if let Some(outcome) = run_post_actions(&self.config.post_actions, hr, job.comparison.as_ref()) {
+                                if let Some(target) = &outcome.moved_to {
+                                    job.path = target.display().to_string();
+                                }
+                                if let Some(hex) = &outcome.clipboard {
+                                    commands.push(clipboard::write(if self.uppercase { hex.to_uppercase() } else { hex.clone() }));
+                                }
+                                self.status_note = post_action_summary(&outcome);
+                            }
+                        }
+                        if let Some(note) = job.outcome.as_ref().and_then(|r| log_custody(self.forensic_mode, r.as_ref(), Some(&job.path))) {
+                            self.status_note = Some(note);
+                        }
+                        if let Some(result) = &job.outcome {
+                            report_job(self.config.webhook(), &mut self.notice_rx, result.as_ref(), job.comparison.as_ref(), Some(&job.path));
+                        }
+                        if job.queued && !skipped {
+                            let hashed = job.outcome.as_ref().and_then(|r| r.as_ref().ok());
+                            let blocked = hashed.and_then(|hr| known_status(self.blocklist.get(), hr)) == Some(true);
+                            if blocked {
+                                self.batch_blocked += 1;
+                            }
+                            let tag = match hashed.and_then(|hr| known_status(self.known.get(), hr)) {
+                                _ if blocked => " [BLOCKLISTED]",
+                                Some(true) => " [KNOWN]",
+                                Some(false) => " [UNKNOWN]",
+                                None => "",
+                            };
+                            self.batch_lines.push(match &job.outcome {
+                                Some(Ok(hr)) if matches!(job.comparison, Some(Comparison::Changed(_))) => {
+                                    format!("CHANGED {} {}{}{}", hr.hex, job.path, tag, timing_suffix(hr))
+                                }
+                                Some(Ok(hr)) => format!("OK      {} {}{}{}", hr.hex, job.path, tag, timing_suffix(hr)),
+                                Some(Err(e)) if e.kind == ErrorKind::TimedOut => format!("TIMED OUT {}: {}", job.path, e.message()),
+                                Some(Err(e)) => format!("FAILED  {}: {}", job.path, e.message()),
+                                None => continue,
+                            });
+                        }
+                    }
+                }
+                // The queue hashes one file at a time, in list order
+                if !self.background.iter().any(|j| j.queued && j.is_running()) {
+                    if self.background.iter().any(|j| j.waiting) {
+                        if self.batch_started.is_none() {
+                            self.batch_started = Some(Instant::now());
+                            self.batch_succeeded = 0;
+                            self.batch_failed = 0;
+                            self.batch_blocked = 0;
+                            self.batch_lines.clear();
+                            self.batch_skipped.clear();
+                        }
+                        // A run of small files goes to the shared worker in one batch; anything else starts alone
+                        let mut batch = Vec::new();
+                        while let Some(job) = self.background.iter_mut().find(|j| j.waiting) {
+                            if !job.is_small() {
+                                if batch.is_empty() {
+                                    job.start();
+                                }
+                                break;
+                            }
+                            batch.push(job.start_small());
+                            if batch.len() == jobs::SMALL_BATCH {
+                                break;
+                            }
+                        }
+                        self.small_files.submit(batch);
+                    } else if let Some(started) = self.batch_started.take() {
+                        self.report_batch(started.elapsed());
+                    }
+                }
+                self.persist_queue();
+                if self.placement_due.is_some_and(|due| Instant::now() >= due) {
+                    self.placement_due = None;
+                    commands.push(window::run_with_handle(window::Id::MAIN, |handle| Message::MonitorChecked(placement::monitor(handle))));
+                }
+                if let Some(Err(e)) = self.usage.as_mut().map(usage::Usage::save_if_due) {
+                    self.status_note = Some(format!("{:#}", e));
+                }
+                if self.quit_when_done && self.work_in_flight().is_empty() {
+                    self.before_close();
+                    commands.push(window::close(window::Id::MAIN));
+                    return Command::batch(commands);
+                }
+                if self.dropped_at.is_some_and(|at| at.elapsed() >= DROP_FLASH) {
+                    self.dropped_at = None;
+                }
+                if self.path_check_due.is_some_and(|due| Instant::now() >= due) {
+                    self.path_check_due = None;
+                    commands.push(check_path_later(self.path_input.clone()));
+                }
+                // Waits for the check of what was typed last
+                if self.path_checked == self.path_input && self.auto_hash_due.as_ref().is_some_and(|(due, _)| Instant::now() >= *due) {
+                    if let Some((_, prev)) = self.auto_hash_due.take().filter(|_| self.path_status.is_hashable()) {
+                        // A job still running for an earlier version of the typed path is superseded
+                        if self.is_hashing {
+                            self.abandon_worker();
+                        }
+                        self.start_hashing(self.path_input.clone(), Some(prev));
+                        return Command::batch(commands);
+                    }
+                }
+                if self.is_hashing {
+                    if let Some(tracker) = &self.tracker {
+                        let snapshot = tracker.snapshot();
+                        if snapshot.bytes != self.progress_processed {
+                            self.last_progress_at = Some(Instant::now());
+                            self.stalled = false;
+                        }
+                        self.progress_processed = snapshot.bytes;
+                        self.progress_eta = snapshot.eta;
+                    }
+                    // Waiting at the end of a followed file is expected, so follow mode never stalls
+                    if self.stall_watch && !self.is_following && !self.stalled {
+                        self.stalled = self.last_progress_at.is_some_and(|at| at.elapsed() >= STALL_TIMEOUT);
+                    }
+                    if let Some(rx) = &self.worker_rx {
+                        if let Ok((token, result)) = rx.try_recv() {
+                            if token == self.token {
+                                self.is_hashing = false;
+                                let custody_note = log_custody(self.forensic_mode, result.as_ref(), Some(self.path_input.trim()));
+                                count_hash(self.usage.as_mut(), result.as_ref());
+                                match result {
+                                    Ok(hr) => {
+                                        self.error = None;
+                                        self.comparison = record_history(&mut self.history, self.jump_list.as_ref(), &hr);
+                                        report_job(self.config.webhook(), &mut self.notice_rx, Ok(&hr), self.comparison.as_ref(), Some(self.path_input.trim()));
+                                        let mut hashed_path = hr.path.clone();
+                                        if let Some(outcome) = run_post_actions(&self.config.post_actions, &hr, self.comparison.as_ref()) {
+                                            if let Some(target) = &outcome.moved_to {
+                                                hashed_path = Some(target.clone());
+                                            }
+                                            if let Some(hex) = &outcome.clipboard {
+                                                commands.push(clipboard::write(if self.uppercase { hex.to_uppercase() } else { hex.clone() }));
+                                            }
+                                            self.status_note = post_action_summary(&outcome);
+                                        }
+                                        self.digest = hex::decode(&hr.hex).unwrap_or_default();
+                                        self.last_elapsed = Some(hr.elapsed);
+                                        self.last_bytes = Some(hr.bytes);
+                                        self.last_path = hashed_path;
+                                        self.last_quick = hr.quick;
+                                        self.last_chunks = hr.chunks;
+                                        self.last_signature = hr.signature;
+                                        self.last_followed = hr.followed;
+                                        self.last_concat_count = hr.concat_count;
+                                        self.last_allocated = hr.allocated;
+                                        self.last_atime_kept = hr.forensic.then_some(hr.atime_kept);
+                                        self.last_payload = hr.payload;
+                                        self.last_holes_skipped = hr.holes_skipped;
+                                        self.last_segments = hr.segments;
+                                        self.last_cid = hr.cid;
+                                        self.last_cloud = hr.cloud;
+                                        self.last_preferred = hr.preferred;
+                                        self.last_authenticode = hr.authenticode;
+                                        self.last_metadata_digest = hr.metadata_digest;
+                                        self.last_file_type = hr.file_type;
+                                        self.last_type_warning = hr.type_warning;
+                                        self.check_expected();
+                                        // A sampled hash skipped most of the file, so its members are not worth decompressing either
+                                        if self.archive_check && !hr.quick {
+                                            if let Some(path) = self.last_path.clone().filter(|p| archive::Kind::of(p).is_some()) {
+                                                self.start_archive_check(path);
+                                            }
+                                        }
+                                        if self.archive_digest_mode && !hr.quick {
+                                            if let Some(path) = self.last_path.clone().filter(|p| archive_digest::is_supported(p)) {
+                                                self.start_archive_digest(path);
+                                            }
+                                        }
+                                        // The container digest alone says nothing about the evidence inside an E01
+                                        if !hr.quick {
+                                            if let Some(path) = self.last_path.clone().filter(|p| ewf::is_ewf(p)) {
+                                                self.ewf_run = Some(ewf::EwfRun::start(path));
+                                            }
+                                        }
+                                        if self.disc_contents && !hr.quick {
+                                            if let Some(path) = self.last_path.clone().filter(|p| disc_image::is_disc_image(p)) {
+                                                self.disc_run = Some(disc_image::ContentsRun::start(path));
+                                            }
+                                        }
+                                        if self.partition_mode && !hr.quick && hr.payload.is_none() {
+                                            if let Some(path) = self.last_path.clone().filter(|p| partitions::is_disk_image(p)) {
+                                                self.partition_run = Some(partitions::PartitionRun::start(path));
+                                            }
+                                        }
+                                    }
+                                    Err(e) => {
+                                        if e.kind == ErrorKind::Cancelled {
+                                            // Already restored path in CancelPressed
+                                            self.error = None;
+                                        } else {
+                                            report_job(self.config.webhook(), &mut self.notice_rx, Err(&e), None, Some(self.path_input.trim()));
+                                            self.error = Some(e);
+                                            self.digest.clear();
+                                            self.last_elapsed = None;
+                                            self.last_bytes = None;
+                                            self.last_path = None;
+                                            self.comparison = None;
+                                            self.last_chunks.clear();
+                                            self.last_signature = None;
+                                            self.last_segments.clear();
+                                            self.last_cid = None;
+                                            self.last_cloud = None;
+                                            self.last_preferred = None;
+                                            self.last_authenticode = None;
+                                            self.last_metadata_digest = None;
+                                            self.last_file_type = None;
+                                            self.last_type_warning = None;
+                                        }
+                                    }
+                                }
+                                if custody_note.is_some() {
+                                    self.status_note = custody_note;
+                                }
+                                self.progress_total = None;
+                                self.progress_processed = 0;
+                                self.tracker = None;
+                                self.progress_eta = None;
+                                self.cancel_flag = None;
+                                self.stop_flag = None;
+                                self.is_following = false;
+                                self.stalled = false;
+                                self.worker_rx = None;
+                                self.worker_token = None;
+                            }
+                        }
+                    }
+                }
+                Command::batch(commands)
+            }
+            Message::SettingsToggled => {
+                self.show_settings = !self.show_settings;
+                self.show_about = false;
+                Command::none()
+            }
+            Message::AboutToggled => {
+                self.show_about = !self.show_about;
+                self.show_settings = false;
+                Command::none()
+            }
+            Message::CopyDiagnostics => clipboard::write(about::diagnostics(app_version())),
+            Message::AlgorithmSelected(algorithm) => {
+                self.algorithm = algorithm;
+                self.store_preferences()
+            }
+            Message::ShellIntegrationPressed(add) => {
+                let result = if add { shell_integration::install() } else { shell_integration::remove() };
+                self.status_note = Some(result.unwrap_or_else(|e| format!("{:#}", e)));
+                Command::none()
+            }
+            Message::WizardAlgorithmSelected(algorithm) => {
+                if let Some(w) = &mut self.wizard {
+                    w.algorithm = algorithm;
+                }
+                Command::none()
+            }
+            Message::WizardAutoHashToggled(on) => {
+                if let Some(w) = &mut self.wizard {
+                    w.auto_hash = on;
+                }
+                Command::none()
+            }
+            Message::WizardNetworkToggled(on) => {
+                if let Some(w) = &mut self.wizard {
+                    w.network = on;
+                }
+                Command::none()
+            }
+            Message::WizardShellToggled(on) => {
+                if let Some(w) = &mut self.wizard {
+                    w.shell = on;
+                }
+                Command::none()
+            }
+            Message::WizardBack => {
+                if let Some(w) = &mut self.wizard {
+                    w.back();
+                }
+                Command::none()
+            }
+            Message::WizardNext => match self.wizard.take() {
+                Some(mut w) if !w.is_last() => {
+                    w.next();
+                    self.wizard = Some(w);
+                    Command::none()
+                }
+                // The last page: the answers become the settings file
+                Some(w) => {
+                    self.algorithm = w.algorithm;
+                    self.auto_hash = w.auto_hash;
+                    self.network = w.network;
+                    if w.shell {
+                        self.status_note = Some(shell_integration::install().unwrap_or_else(|e| format!("{:#}", e)));
+                    }
+                    self.store_preferences()
+                }
+                None => Command::none(),
+            },
+            // The defaults are written, so the questions are not asked again
+            Message::WizardSkipped => {
+                self.wizard = None;
+                self.store_preferences()
+            }
+            Message::ReadBufferSelected(size) => {
+                self.read_buffer = size;
+                self.store_preferences()
+            }
+            Message::HistoryLimitSelected(limit) => {
+                self.history_limit = limit;
+                self.history.set_limit(limit);
+                if let Err(e) = self.history.save() {
+                    self.status_note = Some(format!("{:#}", e));
+                }
+                self.store_preferences()
+            }
+            Message::NetworkToggled(on) => {
+                self.network = on;
+                self.store_preferences()
+            }
+            Message::UsageMetricsToggled(on) => {
+                self.set_usage_metrics(on);
+                self.show_usage &= on;
+                self.store_preferences()
+            }
+            Message::UsageToggled => {
+                self.show_usage = !self.show_usage && self.usage.is_some();
+                Command::none()
+            }
+            Message::ResetUsagePressed => {
+                if let Some(Err(e)) = self.usage.as_mut().map(usage::Usage::reset) {
+                    self.status_note = Some(format!("{:#}", e));
+                }
+                Command::none()
+            }
+            Message::ExportSettingsPressed => {
+                let dialog = FileDialog::new().set_file_name(config::DEPLOY_FILE).add_filter("Settings", &["json"]);
+                if let Some(target) = dialog.save_file() {
+                    self.status_note = Some(match self.config.export_to(&target) {
+                        Ok(()) => format!("Settings exported to {}", target.display()),
+                        Err(e) => format!("{:#}", e),
+                    });
+                }
+                Command::none()
+            }
+            Message::ImportSettingsPressed => {
+                let Some(source) = FileDialog::new().add_filter("Settings", &["json"]).pick_file() else {
+                    return Command::none();
+                };
+                match Config::import_from(&source) {
+                    Ok(config) => {
+                        self.config = config;
+                        self.apply_preferences();
+                        self.status_note = Some(format!("Settings imported from {}", source.display()));
+                        return self.save_config();
+                    }
+                    Err(e) => self.status_note = Some(format!("{:#}", e)),
+                }
+                Command::none()
+            }
+            Message::PostCopyToggled(v) => {
+                self.config.post_actions.copy_hex = v;
+                self.save_config()
+            }
+            Message::PostSidecarToggled(v) => {
+                self.config.post_actions.write_sidecar = v;
+                self.save_config()
+            }
+            Message::PostManifestChanged(v) => {
+                self.config.post_actions.manifest = v;
+                self.save_config()
+            }
+            Message::PostMoveChanged(v) => {
+                self.config.post_actions.move_to = v;
+                self.save_config()
+            }
+            Message::PostCommandChanged(v) => {
+                self.config.post_actions.command = v;
+                self.save_config()
+            }
+            Message::WebhookUrlChanged(v) => {
+                self.config.webhook_url = v;
+                self.save_config()
+            }
+            Message::EmailToggled(v) => {
+                self.config.email.enabled = v;
+                self.save_config()
+            }
+            Message::EmailFailuresOnlyToggled(v) => {
+                self.config.email.failures_only = v;
+                self.save_config()
+            }
+            Message::EmailMailtoToggled(v) => {
+                self.config.email.use_mailto = v;
+                self.save_config()
+            }
+            Message::EmailTextChanged(field, v) => {
+                let email = &mut self.config.email;
+                match field {
+                    EmailField::Server => email.smtp_server = v,
+                    EmailField::Port => {
+                        // Keep the last valid port while the field is being edited
+                        if let Ok(port) = v.trim().parse() {
+                            email.smtp_port = port;
+                        } else if v.trim().is_empty() {
+                            email.smtp_port = 0;
+                        }
+                    }
+                    EmailField::Username => email.username = v,
+                    EmailField::Password => email.password = v,
+                    EmailField::From => email.from = v,
+                    EmailField::To => email.to = v,
+                }
+                self.save_config()
+            }
+            Message::ErrorActionPressed(action) => {
+                match action {
+                    ErrorAction::Retry => {
+                        if !self.path_input.trim().is_empty() && !self.is_hashing {
+                            self.error = None;
+                            self.start_hashing(self.path_input.clone(), None);
+                        }
+                    }
+                    ErrorAction::Browse => return self.update(Message::BrowsePressed),
+                    ErrorAction::RunElevated => {
+                        if !self.path_input.trim().is_empty() && !self.is_hashing {
+                            self.error = None;
+                            self.start_elevated_hashing(self.path_input.trim().to_string());
+                        }
+                    }
+                }
+                Command::none()
+            }
+            Message::Ignored => Command::none(),
+        }
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        if let Some(wizard) = &self.wizard {
+            return wizard_view(wizard);
+        }
+        if let Some(usage) = self.usage.as_ref().filter(|_| self.show_usage) {
+            return usage_view(usage);
+        }
+        if self.show_settings {
+            return self.settings_view();
+        }
+        if self.show_about {
+            return about_view();
+        }
+        let title = row![
+            text("Rust Hash256").size(28),
+            button(text("Settings").size(14)).on_press(Message::SettingsToggled).style(theme::Button::Secondary),
+            button(text("About").size(14)).on_press(Message::AboutToggled).style(theme::Button::Secondary),
+        ]
+        .spacing(20)
+        .align_items(iced::Alignment::Center);
+
+        let path_input = text_input("Drag a file here or paste path...", &self.path_input)
+            .on_input(Message::PathChanged)
+            .on_submit(Message::StartHash)
+            .id(PATH_INPUT.clone())
+            .padding(12)
+            .size(16)
+            .width(Length::Fill);
+
+        let browse_btn = if self.is_hashing {
+            button(text("Browse").size(16)).style(theme::Button::Secondary)
+        } else {
+            button(text("Browse").size(16)).on_press(Message::BrowsePressed)
+        };
+        let folder_btn = if self.is_hashing {
+            button(text("Browse folder").size(16)).style(theme::Button::Secondary)
+        } else {
+            button(text("Browse folder").size(16)).on_press(Message::BrowseFolderPressed)
+        };
+
+        let clear_btn = if self.is_hashing {
+            button(text("Clear").size(16)).style(theme::Button::Secondary)
+        } else {
+            button(text("Clear").size(16)).on_press(Message::ClearPressed)
+        };
+
+        let rehash_btn = if self.is_hashing || self.last_path.is_none() {
+            button(text("Hash again").size(16)).style(theme::Button::Secondary)
+        } else {
+            button(text("Hash again").size(16)).on_press(Message::RehashPressed)
+        };
+
+        let stop_btn: Option<Element<'_, Message>> = if self.is_following {
+            Some(button(text("Stop").size(16)).on_press(Message::StopFollowPressed).style(theme::Button::Positive).into())
+        } else {
+            None
+        };
+
+        let background_btn: Option<Element<'_, Message>> = if self.is_hashing && !self.is_following {
+            Some(button(text("Run in background").size(16)).on_press(Message::BackgroundPressed).style(theme::Button::Secondary).into())
+        } else {
+            None
+        };
+
+        let cancel_btn: Option<Element<'_, Message>> = if self.is_hashing {
+            Some(button(text("Cancel").size(16)).on_press(Message::CancelPressed).style(theme::Button::Primary).into())
+        } else {
+            None
+        };
+
+        let toggles = column![
+            row![
+                checkbox(format!("Quick triage (first/last {} MiB)", QUICK_SAMPLE_MIB), self.quick_mode).on_toggle(Message::QuickModeToggled),
+                checkbox("Follow growing file", self.follow_mode).on_toggle(Message::FollowToggled),
+                checkbox("Concatenate files", self.concat_mode).on_toggle(Message::ConcatToggled),
+                checkbox("Add to queue", self.queue_mode).on_toggle(Message::QueueToggled),
+                checkbox("Forensic mode (read-only)", self.forensic_mode).on_toggle(Message::ForensicToggled),
+            ]
+            .spacing(20)
+            .align_items(iced::Alignment::Center),
+            row![
+                button(text("Save results...").size(14)).on_press(Message::SaveResultsPressed).style(theme::Button::Secondary),
+                button(text("Print report").size(14)).on_press(Message::PrintReportPressed).style(theme::Button::Secondary),
+                button(text("Verify list/log...").size(14)).on_press(Message::VerifyListPressed).style(theme::Button::Secondary),
+                button(text("Compare folders...").size(14)).on_press(Message::CompareFoldersPressed).style(theme::Button::Secondary),
+                button(text("Verified copy...").size(14)).on_press(Message::VerifiedCopyPressed).style(theme::Button::Secondary),
+                button(text(if self.more_tools { "Fewer tools" } else { "More tools" }).size(14)).on_press(Message::MoreToolsToggled).style(theme::Button::Secondary),
+            ]
+            .push_maybe(self.forensic_mode.then(|| {
+                button(text("Export custody report...").size(14)).on_press(Message::ExportCustodyPressed).style(theme::Button::Secondary)
+            }))
+            .spacing(10),
+        ]
+        .push_maybe(self.more_tools.then(|| {
+            row![
+                button(text("PDF report...").size(14)).on_press(Message::ExportPdfPressed).style(theme::Button::Secondary),
+                button(text("Publish sidecars...").size(14)).on_press_maybe((self.sidecars_rx.is_none() && !self.forensic_mode).then_some(Message::PublishSidecarsPressed)).style(theme::Button::Secondary),
+                button(text("Copy tree digest...").size(14)).on_press_maybe(self.copy_rx.is_none().then_some(Message::CopyTreeDigestPressed)).style(theme::Button::Secondary),
+                button(text("Diff baselines...").size(14)).on_press(Message::DiffBaselinesPressed).style(theme::Button::Secondary),
+                button(text("Dry-run list/log...").size(14)).on_press(Message::DryRunListPressed).style(theme::Button::Secondary),
+                button(text("Edit checksum list...").size(14)).on_press(Message::EditManifestPressed).style(theme::Button::Secondary),
+                button(text("Watch staging folder...").size(14)).on_press_maybe((!self.forensic_mode).then_some(Message::WatchStagingPressed)).style(theme::Button::Secondary),
+                button(text("Verify PAR2...").size(14)).on_press(Message::VerifyPar2Pressed).style(theme::Button::Secondary),
+                button(text("Device vs image...").size(14)).on_press(Message::AcquisitionPressed).style(theme::Button::Secondary),
+            ]
+            .spacing(10)
+        }))
+        .push(row![list_buttons(ListKind::Known, &self.known), list_buttons(ListKind::Blocklist, &self.blocklist)].spacing(20))
+        .push_maybe(self.network_allowed().then(|| remote_row(&self.remote_input, self.remote_run.as_ref(), self.uppercase)))
+        .push(text_row(&self.hash_text, self.uppercase))
+        .spacing(10);
+
+        // Compares against a full hash of a single file only
+        let compare_btn = if self.is_hashing || self.last_path.is_none() || self.last_quick || self.digest.is_empty() || self.compare_rx.is_some() {
+            button(text("Compare with...").size(16)).style(theme::Button::Secondary)
+        } else {
+            button(text("Compare with...").size(16)).on_press(Message::CompareWithPressed)
+        };
+
+        let mut header = row![path_input, browse_btn, folder_btn, rehash_btn, compare_btn, clear_btn]
+            .spacing(10)
+            .align_items(iced::Alignment::Center);
+        if let Some(s) = stop_btn {
+            header = header.push(s);
+        }
+        if let Some(b) = background_btn {
+            header = header.push(b);
+        }
+        if let Some(c) = cancel_btn {
+            header = header.push(c);
+        }
+
+        let drop_action = if self.concat_mode {
+            DropAction::AddToList
+        } else if self.queue_mode {
+            DropAction::AddToQueue
+        } else {
+            DropAction::Hash
+        };
+        // Nothing hashed or running yet: a large drop target that also shows where the other modes are
+        let fresh = matches!(drop_action, DropAction::Hash)
+            && !self.is_hashing
+            && self.digest.is_empty()
+            && self.error.is_none()
+            && self.background.is_empty()
+            && self.verify_run.is_none();
+        let drag_hint = if fresh {
+            empty_state(self.drag_hover, self.dropped_at.map(|at| at.elapsed()))
+        } else {
+            drop_zone(drop_action, self.drag_hover, self.dropped_at.map(|at| at.elapsed()))
+        };
+
+        let (hex_label, base64_label) = if self.last_quick && !self.digest.is_empty() {
+            ("Quick sample (HEX)", "Quick sample (Base64)")
+        } else {
+            ("SHA-256 (HEX)", "SHA-256 (Base64)")
+        };
+
+        let outputs = column![
             labeled_value(
-                "SHA-256 (HEX)",
-                &self.hex_output,
+                hex_label,
+                &self.hex_grouping.apply(&self.hex_digest()),
                 Message::CopyHex,
                 "Copy HEX",
                 self.is_hashing,
             ),
             labeled_value(
-                "SHA-256 (Base64)",
-                &self.base64_output,
+                base64_label,
+                &self.base64_digest(),
                 Message::CopyBase64,
                 "Copy Base64",
                 self.is_hashing,
             ),
         ]
-        .spacing(12);
+        .push_maybe(self.last_preferred.as_ref().map(|(algorithm, hex)| labeled_value(&format!("{} (HEX)", algorithm), hex, Message::CopyPreferredDigest, "Copy", self.is_hashing)))
+        .push_maybe(self.last_cid.as_ref().map(|cid| labeled_value("IPFS CID (v1)", cid, Message::CopyCid, "Copy CID", self.is_hashing)))
+        .push_maybe(self.last_authenticode.as_ref().map(|h| labeled_value("Authenticode (SHA-256)", h, Message::CopyAuthenticode, "Copy", self.is_hashing)))
+        .push_maybe(self.last_metadata_digest.as_ref().map(|h| {
+            let label = format!("Metadata digest ({})", self.metadata_fields.unwrap_or(meta_digest::ALL));
+            labeled_value(&label, h, Message::CopyMetadataDigest, "Copy", self.is_hashing)
+        }))
+        .push_maybe(self.last_cloud.as_ref().map(|c| labeled_value("Content-MD5 (Azure, GCS)", &c.content_md5, Message::CopyContentMd5, "Copy MD5", self.is_hashing)))
+        .push_maybe(self.last_cloud.as_ref().map(|c| labeled_value("CRC32C (GCS)", &c.crc32c, Message::CopyCrc32c, "Copy CRC32C", self.is_hashing)))
+        .push_maybe((!self.digest.is_empty() && !self.last_quick && !self.is_hashing && self.last_path.is_some()).then(|| {
+            row![
+                share_checksum_control(),
+                button(text("Copy ni URI").size(14)).on_press(Message::CopyNiPressed).style(theme::Button::Secondary),
+                button(text("Copy multihash (base32)").size(14)).on_press(Message::CopyMultihashPressed(content_id::Multibase::Base32)).style(theme::Button::Secondary),
+                button(text("Copy multihash (base58btc)").size(14)).on_press(Message::CopyMultihashPressed(content_id::Multibase::Base58Btc)).style(theme::Button::Secondary),
+                button(text("Copy npm integrity").size(14)).on_press_maybe(self.copy_rx.is_none().then_some(Message::CopyIntegrityPressed)).style(theme::Button::Secondary),
+                button(text("Copy magnet link").size(14)).on_press_maybe(self.copy_rx.is_none().then_some(Message::CopyMagnetPressed)).style(theme::Button::Secondary),
+            ]
+            .spacing(10)
+        }))
+        .push(expected_row(&self.expected_input, self.expected_verdict.as_ref()))
+        .push_maybe(self.pair.as_ref().map(pair_row))
+        .spacing(12);
+
+        let meta = meta_info(
+            self.progress_line(),
+            self.last_elapsed.zip(self.last_bytes),
+            self.last_path.as_ref(),
+            self.last_file_type,
+            self.error.as_ref(),
+            self.comparison.as_ref(),
+            self.last_quick,
+        );
+
+        let mut content = column![title];
+        let atime_kept = self.last_atime_kept.filter(|_| !self.digest.is_empty());
+        if self.forensic_mode || atime_kept.is_some() {
+            content = content.push(forensic_banner(atime_kept));
+        }
+        content = content.push(header);
+        if let Some(queue) = &self.restored_queue {
+            content = content.push(saved_queue_banner(queue));
+        }
+        if let Some(status) = path_status_line(self.path_status) {
+            content = content.push(status);
+        }
+        content = content.push(toggles);
+        content = content.push(drag_hint);
+        if self.concat_mode {
+            content = content.push(concat_panel(&self.concat_files, self.is_hashing));
+        }
+        content = content.push(outputs).push(meta);
+        if let Some(warning) = self.last_type_warning.filter(|_| !self.digest.is_empty()) {
+            content = content.push(text(warning).size(14).style(theme::Text::Color(palette::color(Status::Warn).into())));
+        }
+        if let Some(format) = self.last_payload.filter(|_| !self.digest.is_empty()) {
+            content = content.push(text(format!("Digest of the virtual disk inside the {} container, not of the container file", format)).size(14));
+        }
+        if let Some(note) = &self.compare_note {
+            content = content.push(text(note.clone()).size(14));
+        }
+        if let Some(note) = &self.archive_note {
+            content = content.push(text(note.clone()).size(14));
+        }
+        if self.archive_digest_rx.is_some() {
+            content = content.push(text("Hashing archive members for the content digest...").size(14));
+        }
+        match &self.archive_digest {
+            Some(Ok(digest)) => {
+                let label = format!("Archive content digest ({} members)", digest.members);
+                content = content.push(labeled_value(&label, &digest.hex, Message::CopyArchiveDigest, "Copy", self.is_hashing));
+            }
+            Some(Err(e)) => content = content.push(text(format!("Archive content digest failed: {}", e)).size(14)),
+            None => {}
+        }
+        if let Some(run) = &self.disc_run {
+            content = content.push(disc_panel(run));
+        }
+        if let Some(run) = &self.partition_run {
+            content = content.push(partition_panel(run));
+        }
+        if let Some(run) = &self.ewf_run {
+            content = content.push(ewf_panel(run, self.uppercase));
+        }
+        if !self.digest.is_empty() && !self.last_quick {
+            let hex = hex::encode(&self.digest);
+            if let Some(set) = self.blocklist.get().filter(|set| set.contains(&hex)) {
+                content = content.push(blocklist_banner(set));
+            }
+            if let Some(set) = self.known.get() {
+                content = content.push(known_line(set, set.contains(&hex)));
+            }
+        }
+        if let Some(status) = &self.status_note {
+            content = content.push(text(status.clone()).size(14).style(theme::Text::Color(palette::color(Status::Warn).into())));
+        }
+        if let Some(run) = &self.copy_run {
+            content = content.push(copy_panel(run));
+        }
+        if let Some(run) = &self.folder_compare {
+            content = content.push(folder_compare_panel(run));
+        }
+        if let Some(diff) = &self.snapshot_diff {
+            content = content.push(snapshot_diff_panel(diff));
+        }
+        if let Some(run) = &self.dry_run {
+            content = content.push(dry_run_panel(run));
+        }
+        if let Some(editor) = &self.manifest_editor {
+            content = content.push(manifest_editor_panel(editor, self.uppercase));
+        }
+        if let Some(watch) = &self.staging {
+            content = content.push(staging_panel(watch));
+        }
+        if let Some(run) = &self.verify_run {
+            content = content.push(verify_panel(run, self.show_slowest));
+        }
+        if let Some(run) = &self.acquisition {
+            content = content.push(acquisition_panel(run, self.uppercase));
+        }
+        if !self.background.is_empty() {
+            content = content.push(jobs_panel(&self.background, self.uppercase, self.hex_grouping, self.known.get(), self.blocklist.get(), self.show_slowest));
+        }
+        if let (Some(allocated), Some(bytes)) = (self.last_allocated, self.last_bytes) {
+            if allocated < bytes && !self.digest.is_empty() {
+                content = content.push(sparse_info(bytes, allocated, self.last_holes_skipped));
+            }
+        }
+        if self.last_concat_count > 0 && !self.digest.is_empty() {
+            content = content.push(text(format!("Digest of {} files concatenated in the listed order", self.last_concat_count)).size(14));
+        }
+        if self.stalled {
+            content = content.push(stall_panel(self.last_progress_at.map(|at| at.elapsed()).unwrap_or(STALL_TIMEOUT)));
+        }
+        if self.is_following {
+            content = content.push(
+                text(format!(
+                    "Following: {} read so far, waiting for more data. Press Stop to finish and show the digest.",
+                    human_bytes(self.progress_processed as f64)
+                ))
+                .size(14),
+            );
+        } else if self.last_followed && !self.digest.is_empty() {
+            content = content.push(
+                text(format!(
+                    "Followed file: digest covers the first {} read before Stop.",
+                    human_bytes(self.last_bytes.unwrap_or(0) as f64)
+                ))
+                .size(14)
+                .style(theme::Text::Color(palette::color(Status::Warn).into())),
+            );
+        }
+        if !self.last_chunks.is_empty() {
+            content = content.push(chunk_panel(&self.last_chunks, self.chunk_summary.as_ref(), self.is_hashing));
+        }
+        if !self.last_segments.is_empty() {
+            content = content.push(segment_panel(&self.last_segments, self.segment_summary.as_ref(), self.is_hashing));
+        }
+        if let Some(signature) = &self.last_signature {
+            content = content.push(signature_panel(signature.len(), self.is_hashing));
+        }
+        let content = content
+            .spacing(16)
+            .padding(16)
+            .max_width(900)
+            .align_items(iced::Alignment::Start);
+
+        scrollable(container(content).width(Length::Fill))
+            .height(Length::Fill)
+            .into()
+    }
+}
+
+// What the default algorithm may be set to
+fn allowed_algorithms() -> Vec<algo::Algorithm> {
+    algo::ALL.into_iter().filter(|a| policy::get().allows(*a)).collect()
+}
+
+// One first-run question per page, with Back, Next and Skip
+fn wizard_view<'a>(wizard: &first_run::Wizard) -> Element<'a, Message> {
+    let (question, answer): (&str, Element<'a, Message>) = match wizard.step() {
+        first_run::Step::Algorithm => (
+            "Which digest do you work with? The window shows it alongside SHA-256, which history and verification are built on.",
+            pick_list(allowed_algorithms(), Some(wizard.algorithm), Message::WizardAlgorithmSelected).text_size(14).into(),
+        ),
+        first_run::Step::AutoHash => (
+            "Start hashing as soon as a file is dropped or picked?",
+            checkbox("Auto hash on select", wizard.auto_hash).on_toggle(Message::WizardAutoHashToggled).into(),
+        ),
+        first_run::Step::Network => (
+            "May Rust Hash use the network? Webhooks, SMTP email reports and hashing over SSH need it; with it off, nothing leaves this machine.",
+            if policy::get().offline {
+                checkbox("Allow network features (turned off by policy)", false).into()
+            } else {
+                checkbox("Allow network features", wizard.network).on_toggle(Message::WizardNetworkToggled).into()
+            },
+        ),
+        first_run::Step::Shell => (
+            "Add \"Hash with Rust Hash\" to the file manager for your user account? It can be removed again in Settings.",
+            checkbox("Add to the file manager", wizard.shell).on_toggle(Message::WizardShellToggled).into(),
+        ),
+    };
+    let back = button(text("Back").size(14)).on_press_maybe((!wizard.is_first()).then_some(Message::WizardBack)).style(theme::Button::Secondary);
+    let next = button(text(if wizard.is_last() { "Finish" } else { "Next" }).size(14)).on_press(Message::WizardNext).style(theme::Button::Primary);
+    let skip = button(text("Skip, use defaults").size(14)).on_press(Message::WizardSkipped).style(theme::Button::Secondary);
+    let content = column![
+        text("Welcome to Rust Hash256").size(28),
+        text(format!("Setup, {}. Everything here can be changed later in Settings.", wizard.position())).size(14),
+        text(question).size(16),
+        answer,
+        row![back, next, skip].spacing(10),
+    ]
+    .spacing(16)
+    .padding(16)
+    .max_width(700);
+
+    container(content).width(Length::Fill).into()
+}
+
+// Version, build and what the hashers run on
+fn about_view<'a>() -> Element<'a, Message> {
+    let features = about::features();
+    let line = |label: &str, value: String| row![text(format!("{}:", label)).size(14).width(Length::Fixed(160.0)), text(value).size(14)].spacing(10);
+    let mut content = column![
+        row![
+            text("Rust Hash256").size(28),
+            button(text("Done").size(14)).on_press(Message::AboutToggled).style(theme::Button::Primary),
+        ]
+        .spacing(20)
+        .align_items(iced::Alignment::Center),
+        line("Version", app_version().to_string()),
+        line("Commit", about::COMMIT.to_string()),
+        line("Built", about::BUILD_DATE.to_string()),
+        line("Features", if features.is_empty() { "none".to_string() } else { features.join(", ") }),
+        line("Platform", format!("{} {}", std::env::consts::OS, std::env::consts::ARCH)),
+        line("Crypto backend", about::CRYPTO_BACKEND.to_string()),
+    ]
+    .spacing(8);
+    for (algo, path) in about::acceleration() {
+        content = content.push(line(algo, path.to_string()));
+    }
+    let content = content
+        .push(button(text("Copy diagnostics").size(14)).on_press(Message::CopyDiagnostics).style(theme::Button::Secondary))
+        .padding(16)
+        .max_width(900);
+
+    scrollable(container(content).width(Length::Fill))
+        .height(Length::Fill)
+        .into()
+}
+
+// The local usage counts; the same numbers as usage.json, which is never sent anywhere
+fn usage_view<'a>(usage: &usage::Usage) -> Element<'a, Message> {
+    let counts = usage.totals();
+    let table = |title: &'static str, entries: &std::collections::BTreeMap<String, u64>| {
+        let mut col = column![text(title).size(18)].spacing(4).width(Length::FillPortion(1));
+        if entries.is_empty() {
+            col = col.push(text("None yet").size(14));
+        }
+        for (name, count) in usage::ranked(entries) {
+            col = col.push(row![text(name.to_string()).size(14).width(Length::Fill), text(count.to_string()).size(14)].spacing(10));
+        }
+        col
+    };
+    let since = if counts.since == 0 { "Nothing counted yet".to_string() } else { format!("Counted since {}", history::format_timestamp(counts.since)) };
+    let file = usage::usage_path().map_or_else(String::new, |p| format!(" ({})", p.display()));
+    let content = column![
+        row![
+            text("Usage").size(28),
+            button(text("Done").size(14)).on_press(Message::UsageToggled).style(theme::Button::Primary),
+            button(text("Reset counts").size(14)).on_press(Message::ResetUsagePressed).style(theme::Button::Destructive),
+        ]
+        .spacing(20)
+        .align_items(iced::Alignment::Center),
+        text(since).size(14),
+        text(format!("Kept on this machine only{}. Nothing here is uploaded or included in reports, exports or diagnostics.", file)).size(14),
+        row![table("Operations", &counts.operations), table("Algorithms", &counts.algorithms), table("Errors", &counts.errors)].spacing(30),
+    ]
+    .spacing(12)
+    .padding(16)
+    .max_width(900);
+
+    scrollable(container(content).width(Length::Fill))
+        .height(Length::Fill)
+        .into()
+}
+
+fn labeled_value<'a>(label: &str, value: &str, copy_msg: Message, copy_label: &str, disabled: bool) -> Element<'a, Message> {
+    let label_widget = text(label).size(16);
+    let value_widget = text(if value.is_empty() { "-" } else { value })
+        .size(15)
+        .width(Length::Fill);
+
+    let copy_btn = if value.is_empty() || disabled {
+        button(text("Copy")).style(theme::Button::Secondary)
+    } else {
+        button(text(copy_label)).on_press(copy_msg).style(theme::Button::Secondary).width(Length::Fixed(110.0))
+    };
+
+    row![
+        container(label_widget)
+            .width(Length::Fixed(200.0))
+            .align_x(Horizontal::Left)
+            .align_y(Vertical::Center),
+        container(with_tooltip(value_widget, value)).padding(10).width(Length::Fill),
+        copy_btn,
+    ]
+    .spacing(10)
+    .align_items(iced::Alignment::Center)
+    .into()
+}
+
+// Windows: a handle that starts dragging the checksum file as soon as it is pressed; buttons only
+// report a click once the mouse is released, too late to start a drag
+#[cfg(windows)]
+fn share_checksum_control<'a>() -> Element<'a, Message> {
+    let handle = container(text("Drag checksum file (.sha256)").size(14)).padding([5, 10]).style(theme::Container::Box);
+    tooltip(
+        iced::widget::mouse_area(handle).on_press(Message::ShareChecksumPressed),
+        text("Drag into a folder, email or chat; click to copy it as a file").size(13),
+        tooltip::Position::Bottom,
+    )
+    .style(theme::Container::Box)
+    .padding(6)
+    .gap(4)
+    .into()
+}
+
+// Elsewhere the toolkit cannot start a drag, so the file is shown in the file manager to drag from there
+#[cfg(not(windows))]
+fn share_checksum_control<'a>() -> Element<'a, Message> {
+    button(text("Show checksum file (.sha256)").size(14)).on_press(Message::ShareChecksumPressed).style(theme::Button::Secondary).into()
+}
+
+// Shows `full` on hover; for values and paths that may be cut short in their row
+fn with_tooltip<'a>(content: impl Into<Element<'a, Message>>, full: &str) -> Element<'a, Message> {
+    if full.is_empty() {
+        return content.into();
+    }
+    tooltip(content, text(full.to_string()).size(13), tooltip::Position::Bottom)
+        .style(theme::Container::Box)
+        .padding(6)
+        .gap(4)
+        .into()
+}
+
+// A path as text of at most `max` characters, with the full path on hover
+fn path_text<'a>(path: &str, max: usize, size: u16) -> Element<'a, Message> {
+    with_tooltip(text(middle_ellipsis(path, max)).size(size), path)
+}
+
+// Panel heading naming one or more paths: `line` is called with each path shortened for the row, and again
+// in full for the tooltip
+fn path_title<'a>(line: impl Fn(&dyn Fn(&Path) -> String) -> String) -> Element<'a, Message> {
+    let full = line(&|p| p.display().to_string());
+    with_tooltip(text(line(&|p| middle_ellipsis(&p.display().to_string(), 48))).size(16), &full)
+}
+
+// A status line in the current palette, with its symbol when the palette has one
+fn status_text<'a>(line: impl std::fmt::Display, status: Status) -> Text<'a> {
+    text(format!("{}{}", palette::mark(status), line)).style(theme::Text::Color(palette::color(status).into()))
+}
+
+// Which checksum file the expected digest came from, and for which file
+fn pair_row<'a>(pair: &pairing::Pair) -> Element<'a, Message> {
+    let name = |p: &Path| p.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| p.display().to_string());
+    let line = match (&pair.data, pair.line) {
+        (Some(data), Some(line)) => format!("{} is checked against {}, line {}", name(data), name(&pair.checksum), line),
+        (Some(data), None) => format!("{} is checked against {}", name(data), name(&pair.checksum)),
+        (None, _) => format!("Drop the file to check against {}", name(&pair.checksum)),
+    };
+    row![
+        container(text("Paired").size(16)).width(Length::Fixed(200.0)),
+        with_tooltip(text(line).size(14).width(Length::Fill), &pair.checksum.display().to_string()),
+        button(text("Unpair").size(13)).on_press(Message::UnpairPressed).style(theme::Button::Secondary),
+    ]
+    .spacing(10)
+    .align_items(iced::Alignment::Center)
+    .into()
+}
+
+// Paste field for a digest to compare with, and how the last file compares
+fn expected_row<'a>(input: &str, verdict: Option<&Verdict>) -> Element<'a, Message> {
+    let field = text_input("Paste a digest, npm integrity or NuGet hash to compare...", input).on_input(Message::ExpectedChanged).padding(6).size(14).width(Length::Fill);
+    let (line, status) = match verdict {
+        None => (String::new(), Status::Neutral),
+        Some(Verdict::Pending(algorithm)) => (format!("Reading the file for {}...", algorithm), Status::Neutral),
+        Some(Verdict::Match(algorithm)) => (format!("Matches ({})", algorithm), Status::Good),
+        Some(Verdict::Mismatch(algorithm)) => (format!("Does NOT match ({})", algorithm), Status::Bad),
+        Some(Verdict::Failed(e)) => (e.clone(), Status::Bad),
+    };
+    row![
+        container(text("Expected").size(16)).width(Length::Fixed(200.0)),
+        field,
+        status_text(line, status).size(14),
+    ]
+    .spacing(10)
+    .align_items(iced::Alignment::Center)
+    .into()
+}
+
+// A file on a server, by `user@host:/path`, and its SHA-256 once the server or the stream delivered it
+// SHA-256 of the text's UTF-8 bytes, as typed: no trailing newline is added
+fn text_row<'a>(input: &str, uppercase: bool) -> Element<'a, Message> {
+    let field = text_input("Text to hash, e.g. a test vector or a string from a log", input)
+        .id(TEXT_INPUT.clone())
+        .on_input(Message::HashTextChanged)
+        .padding(6)
+        .size(14)
+        .width(Length::Fill);
+    let mut line = row![container(text("Text").size(16)).width(Length::Fixed(200.0)), field].spacing(10).align_items(iced::Alignment::Center);
+    if !input.is_empty() {
+        line = line
+            .push(text(text_digest(input, uppercase)).size(14))
+            .push(button(text("Copy").size(13)).on_press(Message::CopyTextDigest).style(theme::Button::Secondary));
+    }
+    line.into()
+}
+
+fn text_digest(input: &str, uppercase: bool) -> String {
+    let hex = hex::encode(Sha256::digest(input.as_bytes()));
+    if uppercase {
+        hex.to_uppercase()
+    } else {
+        hex
+    }
+}
+
+fn remote_row<'a>(input: &str, run: Option<&remote::RemoteRun>, uppercase: bool) -> Element<'a, Message> {
+    let running = run.is_some_and(|r| r.is_running());
+    let field = text_input("user@host:/path/to/file or sftp://host/path, hashed over SSH", input)
+        .on_input(Message::RemoteTargetChanged)
+        .on_submit(Message::HashRemotePressed)
+        .padding(6)
+        .size(14)
+        .width(Length::Fill);
+    let start = (remote::Target::parse(input).is_some() && !running).then_some(Message::HashRemotePressed);
+    let mut line = row![
+        container(text("Remote file").size(16)).width(Length::Fixed(200.0)),
+        field,
+        button(text("Hash remote").size(14)).on_press_maybe(start).style(theme::Button::Secondary),
+    ]
+    .spacing(10)
+    .align_items(iced::Alignment::Center);
+    let Some(run) = run else {
+        return line.into();
+    };
+    let result: Element<'a, Message> = match &run.result {
+        None => row![
+            text(format!("Reading {}... {}", run.target, human_bytes(run.bytes() as f64))).size(14),
+            button(text("Cancel").size(13)).on_press(Message::CancelRemotePressed).style(theme::Button::Secondary),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center)
+        .into(),
+        Some(Ok(done)) => row![
+            text(if uppercase { done.hex.to_uppercase() } else { done.hex.clone() }).size(14),
+            text(format!("{}, {}", human_bytes(done.bytes as f64), done.via)).size(13),
+            button(text("Copy").size(13)).on_press(Message::CopyRemoteDigest).style(theme::Button::Secondary),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center)
+        .into(),
+        Some(Err(e)) => status_text(e, Status::Bad).size(14).into(),
+    };
+    line = line.push(result);
+    line.into()
+}
+
+fn concat_panel<'a>(files: &[PathBuf], disabled: bool) -> Element<'a, Message> {
+    let mut list = column![].spacing(4);
+    if files.is_empty() {
+        list = list.push(text("Drop or browse files to add them; they are hashed as one stream in this order").size(14));
+    }
+    for (idx, path) in files.iter().enumerate() {
+        let small_btn = |label: &'static str, msg: Message, enabled: bool| {
+            if enabled && !disabled {
+                button(text(label).size(13)).on_press(msg).style(theme::Button::Secondary)
+            } else {
+                button(text(label).size(13)).style(theme::Button::Secondary)
+            }
+        };
+        list = list.push(
+            row![
+                row![text(format!("{}.", idx + 1)).size(14), path_text(&path.display().to_string(), 70, 14)].spacing(6).width(Length::Fill),
+                small_btn("Up", Message::ConcatMoveUp(idx), idx > 0),
+                small_btn("Down", Message::ConcatMoveDown(idx), idx + 1 < files.len()),
+                small_btn("Remove", Message::ConcatRemove(idx), true),
+            ]
+            .spacing(6)
+            .align_items(iced::Alignment::Center),
+        );
+    }
+
+    let hash_btn = if files.is_empty() || disabled {
+        button(text("Hash concatenation"))
+    } else {
+        button(text("Hash concatenation")).on_press(Message::ConcatHashPressed)
+    };
+    let clear_btn = if files.is_empty() || disabled {
+        button(text("Clear list")).style(theme::Button::Secondary)
+    } else {
+        button(text("Clear list")).on_press(Message::ConcatClearPressed).style(theme::Button::Secondary)
+    };
+
+    column![list, row![hash_btn, clear_btn].spacing(10)].spacing(8).padding(6).into()
+}
+
+fn post_actions_panel<'a>(config: &Config) -> Element<'a, Message> {
+    let actions = &config.post_actions;
+    let field = |placeholder: &'static str, value: &str, on_input: fn(String) -> Message| {
+        text_input(placeholder, value).on_input(on_input).padding(6).size(14).width(Length::Fill)
+    };
+    let email = &config.email;
+    let email_field = |placeholder: &'static str, value: &str, which: EmailField| {
+        text_input(placeholder, value).on_input(move |v| Message::EmailTextChanged(which, v)).padding(6).size(14)
+    };
+    let port = if email.smtp_port == 0 { String::new() } else { email.smtp_port.to_string() };
+    let mut email_section = column![row![
+        checkbox("Email a report when the queue finishes", email.enabled).on_toggle(Message::EmailToggled),
+        checkbox("Only if something failed", email.failures_only).on_toggle(Message::EmailFailuresOnlyToggled),
+        checkbox("Draft in mail app (mailto) instead of SMTP", email.use_mailto).on_toggle(Message::EmailMailtoToggled),
+    ]
+    .spacing(20)]
+    .spacing(6);
+    if email.enabled {
+        email_section = email_section.push(email_field("To", &email.to, EmailField::To).width(Length::Fill));
+        if !email.use_mailto {
+            email_section = email_section
+                .push(
+                    row![
+                        email_field("SMTP server", &email.smtp_server, EmailField::Server).width(Length::Fill),
+                        email_field("Port", &port, EmailField::Port).width(Length::Fixed(80.0)),
+                    ]
+                    .spacing(6),
+                )
+                .push(
+                    row![
+                        email_field("Username", &email.username, EmailField::Username).width(Length::Fill),
+                        email_field("Password (this session only; empty = RUST_HASH_SMTP_PASSWORD)", &email.password, EmailField::Password)
+                            .secure(true)
+                            .width(Length::Fill),
+                        email_field("From (default: username)", &email.from, EmailField::From).width(Length::Fill),
+                    ]
+                    .spacing(6),
+                );
+        }
+    }
+    column![
+        text("After a file is hashed (full hashes of single files only):").size(14),
+        row![
+            checkbox("Copy HEX to clipboard", actions.copy_hex).on_toggle(Message::PostCopyToggled),
+            checkbox("Write .sha256 sidecar", actions.write_sidecar).on_toggle(Message::PostSidecarToggled),
+        ]
+        .spacing(20),
+        field("Append to manifest file (path, empty = off)", &actions.manifest, Message::PostManifestChanged),
+        field("Move hashed files to folder (empty = off; CHANGED files stay)", &actions.move_to, Message::PostMoveChanged),
+        field("Run command, e.g. notify.cmd \"{path}\" {hex} (also {base64}, {bytes})", &actions.command, Message::PostCommandChanged),
+        field("Webhook URL: POST a JSON report when a job or the queue finishes (empty = off)", &config.webhook_url, Message::WebhookUrlChanged),
+        email_section,
+        row![
+            field("PDF report header, e.g. company or project name", &config.report_header, Message::ReportHeaderChanged),
+            field("PDF report logo (JPEG file path, empty = none)", &config.report_logo, Message::ReportLogoChanged),
+        ]
+        .spacing(6),
+    ]
+    .spacing(6)
+    .padding(6)
+    .into()
+}
+
+// One card per background job: progress while running, then the digest or the error
+fn jobs_panel<'a>(jobs: &[BackgroundJob], uppercase: bool, grouping: HexGrouping, known: Option<&KnownSet>, blocklist: Option<&KnownSet>, show_slowest: bool) -> Element<'a, Message> {
+    let is_blocked = |job: &BackgroundJob| matches!(&job.outcome, Some(Ok(hr)) if known_status(blocklist, hr) == Some(true));
+    let blocked = jobs.iter().filter(|j| is_blocked(j)).count();
+    let mut header = row![text("Background jobs and queue").size(16)].spacing(10);
+    if blocked > 0 {
+        header = header.push(text(format!("{} BLOCKLISTED", blocked)).size(16).style(theme::Text::Color(palette::color(Status::Blocked).into())));
+    }
+    // Quick samples read a few MiB of each file, so their rate says nothing about the disk
+    let timings: Vec<timing::Timing> = jobs
+        .iter()
+        .filter_map(|j| match &j.outcome {
+            Some(Ok(hr)) if !hr.quick => Some(timing::Timing { path: PathBuf::from(&j.path), bytes: hr.bytes, elapsed: hr.elapsed }),
+            _ => None,
+        })
+        .collect();
+    header = header.push_maybe(slowest_button(&timings, show_slowest));
+    let mut list = column![header].spacing(6);
+    if show_slowest {
+        list = list.push_maybe(slowest_list(&timings));
+    }
+    for (idx, job) in jobs.iter().enumerate() {
+        let status: Element<'a, Message> = match &job.outcome {
+            None if job.waiting => text("Queued").size(14).into(),
+            None => {
+                let done = match job.percent() {
+                    Some(pct) => format!("{:.0}% of {}", pct, human_bytes(job.total.unwrap_or(0) as f64)),
+                    None => unknown_size_progress(job.processed, job.rate()),
+                };
+                text(format!("Hashing... {}", done)).size(14).into()
+            }
+            Some(Ok(hr)) => {
+                let hex = grouping.apply(&if uppercase { hr.hex.to_uppercase() } else { hr.hex.clone() });
+                let mut line = format!("{} • {}", hex, human_duration(hr.elapsed));
+                match &job.comparison {
+                    Some(Comparison::Unchanged(when)) => line.push_str(&format!(" • unchanged since {}", when)),
+                    Some(Comparison::Changed(when)) => line.push_str(&format!(" • CHANGED since {}", when)),
+                    None => {}
+                }
+                if job.quick {
+                    line.push_str(" • quick sample");
+                }
+                match known_status(known, hr) {
+                    Some(true) => line.push_str(" • KNOWN"),
+                    Some(false) => line.push_str(" • UNKNOWN"),
+                    None => {}
+                }
+                if is_blocked(job) {
+                    text(format!("BLOCKLISTED • {}", line)).size(14).style(theme::Text::Color(palette::color(Status::Blocked).into())).into()
+                } else {
+                    text(line).size(14).into()
+                }
+            }
+            Some(Err(e)) => text(e.message()).size(14).style(theme::Text::Color(palette::color(Status::Bad).into())).into(),
+        };
+        let mut buttons = row![].spacing(6);
+        if job.waiting {
+            let small_btn = |label: &'static str, msg: Message, enabled: bool| {
+                let b = button(text(label).size(13)).style(theme::Button::Secondary);
+                if enabled {
+                    b.on_press(msg)
+                } else {
+                    b
+                }
+            };
+            buttons = buttons
+                .push(small_btn("Hash next", Message::JobHashNext(job.id), true))
+                .push(small_btn("Up", Message::JobMoveUp(job.id), idx > 0))
+                .push(small_btn("Down", Message::JobMoveDown(job.id), idx + 1 < jobs.len()));
+        }
+        if job.is_running() || job.waiting {
+            buttons = buttons.push(button(text("Cancel").size(13)).on_press(Message::JobCancel(job.id)).style(theme::Button::Secondary));
+        } else {
+            if matches!(job.outcome, Some(Ok(_))) {
+                buttons = buttons.push(button(text("Copy HEX").size(13)).on_press(Message::JobCopyHex(job.id)).style(theme::Button::Secondary));
+            }
+            buttons = buttons.push(button(text("Close").size(13)).on_press(Message::JobClose(job.id)).style(theme::Button::Secondary));
+        }
+        list = list.push(
+            row![column![path_text(&job.path, 70, 14), status].spacing(2).width(Length::Fill), buttons]
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+        );
+    }
+    list.padding(6).into()
+}
+
+// " • 1.20 s • 85.00 MB/s" for queue report lines
+fn timing_suffix(hr: &HashResult) -> String {
+    let rate = hr.bytes as f64 / hr.elapsed.as_secs_f64().max(0.001);
+    format!(" • {} • {}/s", human_duration(hr.elapsed), human_bytes(rate))
+}
+
+// Shown once at least two files are large enough to rank
+fn slowest_button<'a>(timings: &[timing::Timing], shown: bool) -> Option<Element<'a, Message>> {
+    (timing::slowest(timings, 2).len() > 1).then(|| {
+        button(text(if shown { "Hide slowest" } else { "Slowest files" }).size(13)).on_press(Message::SlowestToggled).style(theme::Button::Secondary).into()
+    })
+}
+
+// Lowest throughput first: a failing disk or a badly fragmented file shows up at the top
+fn slowest_list<'a>(timings: &[timing::Timing]) -> Option<Element<'a, Message>> {
+    let slowest = timing::slowest(timings, 10);
+    if slowest.is_empty() {
+        return None;
+    }
+    let mut col = column![text("Slowest files (by throughput)").size(14)].spacing(2);
+    for t in slowest {
+        col = col.push(text(format!("{}/s • {} in {} • {}", human_bytes(t.rate()), human_bytes(t.bytes as f64), human_duration(t.elapsed), t.path.display())).size(13));
+    }
+    Some(col.into())
+}
+
+// Some(listed) when a known-hash set is loaded; quick samples are not real file digests, so they are never matched
+fn known_status(known: Option<&KnownSet>, hr: &HashResult) -> Option<bool> {
+    known.filter(|_| !hr.quick).map(|set| set.contains(&hr.hex))
+}
+
+fn saved_queue_banner<'a>(queue: &saved_queue::SavedQueue) -> Element<'a, Message> {
+    let files = if queue.paths.len() == 1 { "1 file was".to_string() } else { format!("{} files were", queue.paths.len()) };
+    row![
+        text(format!("{} still queued when rust-hash closed ({}). Continue with the current options?", files, queue.when()))
+            .size(14)
+            .style(theme::Text::Color(palette::color(Status::Warn).into()))
+            .width(Length::Fill),
+        button(text("Continue queue").size(14)).on_press(Message::ContinueSavedQueue),
+        button(text("Discard").size(14)).on_press(Message::DiscardSavedQueue).style(theme::Button::Secondary),
+    ]
+    .spacing(10)
+    .align_items(iced::Alignment::Center)
+    .into()
+}
+
+fn blocklist_banner<'a>(set: &KnownSet) -> Element<'a, Message> {
+    text(format!("BLOCKLISTED: this digest is on {}", set.name())).size(20).style(theme::Text::Color(palette::color(Status::Blocked).into())).into()
+}
+
+// Load/clear controls for one reference list
+fn list_buttons<'a>(kind: ListKind, slot: &ListSlot) -> Element<'a, Message> {
+    let label = match slot.get() {
+        _ if slot.is_loading() => format!("Loading {}...", kind.label().to_lowercase()),
+        Some(set) => format!("{}: {} ({})", kind.label(), set.name(), set.len()),
+        None => format!("Load {}...", kind.label().to_lowercase()),
+    };
+    let mut buttons = row![button(text(label).size(14)).on_press(Message::LoadListPressed(kind)).style(theme::Button::Secondary)].spacing(6);
+    if slot.get().is_some() {
+        buttons = buttons.push(button(text("Clear").size(14)).on_press(Message::ClearListPressed(kind)).style(theme::Button::Secondary));
+    }
+    buttons.into()
+}
+
+fn known_line<'a>(set: &KnownSet, listed: bool) -> Element<'a, Message> {
+    if listed {
+        text(format!("KNOWN: listed in {}", set.name())).size(14).style(theme::Text::Color(palette::color(Status::Good).into())).into()
+    } else {
+        text(format!("UNKNOWN: not in {}", set.name())).size(14).style(theme::Text::Color(palette::color(Status::Warn).into())).into()
+    }
+}
+
+// Progress of a verified copy, then its verdict and the files that need attention
+fn copy_panel<'a>(run: &copy_verify::CopyRun) -> Element<'a, Message> {
+    let title = path_title(|p| format!("Verified copy: {} -> {}", p(&run.source), p(&run.dest)));
+    let mut col = column![title].spacing(4);
+    let verified = run.done.iter().filter(|f| f.verified()).count();
+    if run.is_running() {
+        let pct = if run.total > 0 { (run.processed() as f64 / run.total as f64 * 100.0).clamp(0.0, 100.0) } else { 0.0 };
+        col = col.push(
+            row![
+                text(format!("{:.0}% • {} of {} files done", pct, run.done.len(), run.files)).size(14).width(Length::Fill),
+                button(text("Cancel copy").size(13)).on_press(Message::CopyCancelPressed).style(theme::Button::Secondary),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+        );
+    } else {
+        let mut verdict = format!("{} of {} files copied and verified", verified, run.files);
+        if run.mismatches() > 0 {
+            verdict.push_str(&format!(", {} MISMATCH", run.mismatches()));
+        }
+        if run.failed() > 0 {
+            verdict.push_str(&format!(", {} failed", run.failed()));
+        }
+        let status = if verified == run.files { Status::Good } else { Status::Bad };
+        col = col.push(
+            row![
+                status_text(verdict, status).size(14).width(Length::Fill),
+                button(text("Close").size(13)).on_press(Message::CopyClosePressed).style(theme::Button::Secondary),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+        );
+    }
+    // Problems only; a clean copy of thousands of files would drown them out
+    for file in run.done.iter().filter(|f| !f.verified()).take(20) {
+        let line = match &file.error {
+            Some(e) => format!("FAILED   {}: {}", file.source.display(), e),
+            None => format!("MISMATCH {} -> {}", file.source.display(), file.target.display()),
+        };
+        col = col.push(text(line).size(13).style(theme::Text::Color(palette::color(Status::Bad).into())));
+    }
+    col.padding(6).into()
+}
+
+// Progress of a two-folder comparison, then its counts and the paths that differ
+fn folder_compare_panel<'a>(run: &folder_diff::CompareRun) -> Element<'a, Message> {
+    let title = path_title(|p| format!("Comparing A: {} with B: {}", p(&run.a), p(&run.b)));
+    let mut col = column![title].spacing(4);
+    if run.is_running() {
+        col = col.push(
+            row![
+                text(format!("{} of {} paths compared • {} read of the current file", run.done.len(), run.paths, human_bytes(run.current_bytes() as f64)))
+                    .size(14)
+                    .width(Length::Fill),
+                button(text("Cancel").size(13)).on_press(Message::FolderCompareCancelPressed).style(theme::Button::Secondary),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+        );
+    } else {
+        let same = run.count(&folder_diff::Status::Same);
+        let mut verdict = format!("{} of {} paths identical", same, run.paths);
+        for (status, label) in [
+            (folder_diff::Status::Different, "content different"),
+            (folder_diff::Status::OnlyInA, "only in A"),
+            (folder_diff::Status::OnlyInB, "only in B"),
+        ] {
+            let n = run.count(&status);
+            if n > 0 {
+                verdict.push_str(&format!(", {} {}", n, label));
+            }
+        }
+        let failed = run.done.iter().filter(|c| matches!(c.status, folder_diff::Status::Failed(_))).count();
+        if failed > 0 {
+            verdict.push_str(&format!(", {} failed", failed));
+        }
+        if run.done.len() < run.paths {
+            verdict.push_str(&format!(", {} not compared (cancelled)", run.paths - run.done.len()));
+        }
+        let status = if same == run.paths { Status::Good } else { Status::Bad };
+        col = col.push(
+            row![
+                status_text(verdict, status).size(14).width(Length::Fill),
+                button(text("Close").size(13)).on_press(Message::FolderCompareClosePressed).style(theme::Button::Secondary),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+        );
+    }
+    for item in run.problems().take(20) {
+        let line = match &item.status {
+            folder_diff::Status::Failed(e) => format!("FAILED    {}: {}", item.relative.display(), e),
+            _ => format!("{:<9} {}", item.label(), item.relative.display()),
+        };
+        col = col.push(text(line).size(13).style(theme::Text::Color(palette::color(Status::Bad).into())));
+    }
+    col.padding(6).into()
+}
+
+// Added/removed/changed counts between two baselines and the first entries of each kind
+fn snapshot_diff_panel<'a>(diff: &snapshot_diff::SnapshotDiff) -> Element<'a, Message> {
+    let title = path_title(|p| format!("Baseline diff: {} -> {}", p(&diff.old), p(&diff.new)));
+    let status = if diff.is_clean() { Status::Good } else { Status::Bad };
+    let mut col = column![
+        title,
+        row![
+            status_text(diff.summary(), status).size(14).width(Length::Fill),
+            button(text("Close").size(13)).on_press(Message::SnapshotDiffClosePressed).style(theme::Button::Secondary),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center),
+    ]
+    .spacing(4);
+    if diff.skipped > 0 {
+        col = col.push(text(format!("{} entries skipped: no SHA-256 in the baseline", diff.skipped)).size(13));
+    }
+    let lines = diff
+        .changed
+        .iter()
+        .map(|(p, _, _)| format!("CHANGED  {}", p.display()))
+        .chain(diff.removed.iter().map(|(p, _)| format!("REMOVED  {}", p.display())))
+        .chain(diff.added.iter().map(|(p, _)| format!("ADDED    {}", p.display())));
+    for line in lines.take(20) {
+        col = col.push(text(line).size(13).style(theme::Text::Color(palette::color(Status::Bad).into())));
+    }
+    col.padding(6).into()
+}
+
+// Entries of a checksum list with a checkbox each, and the edit actions for the selection
+fn manifest_editor_panel<'a>(editor: &manifest::Editor, uppercase: bool) -> Element<'a, Message> {
+    // Lists can run to many thousands of lines; the first ones are enough to pick from
+    const SHOWN: usize = 500;
+    let mut title = format!("Editing {} ({} entries)", editor.manifest.path.display(), editor.manifest.count());
+    if editor.dirty {
+        title.push_str(" • unsaved changes");
+    }
+    let busy = editor.is_busy();
+    let action = |label: &str, message: Message, enabled: bool| {
+        let b = button(text(label.to_string()).size(13)).style(theme::Button::Secondary);
+        if enabled {
+            b.on_press(message)
+        } else {
+            b
+        }
+    };
+    let selected = !editor.selected.is_empty();
+    let mut actions = row![
+        action("Remove selected", Message::ManifestRemovePressed, selected && !busy),
+        action("Re-hash selected", Message::ManifestRehashPressed, selected && !busy),
+        action("Add files...", Message::ManifestAddFilesPressed, !busy),
+        action("Save", Message::ManifestSavePressed, editor.dirty && !busy),
+    ]
+    .spacing(10)
+    .align_items(iced::Alignment::Center);
+    if busy {
+        actions = actions.push(text("Hashing...").size(13)).push(action("Cancel", Message::ManifestCancelPressed, true));
+    }
+    actions = actions.push(action("Close", Message::ManifestClosePressed, true));
+    let mut col = column![text(title).size(16), actions].spacing(4);
+    for (line, entry) in editor.manifest.entries().take(SHOWN) {
+        let hex = if uppercase { entry.hex.to_uppercase() } else { entry.hex.clone() };
+        col = col.push(
+            checkbox(format!("{}  {}", hex, entry.name), editor.selected.contains(&line))
+                .on_toggle(move |on| Message::ManifestEntryToggled(line, on))
+                .size(14)
+                .text_size(13),
+        );
+    }
+    if editor.manifest.count() > SHOWN {
+        col = col.push(text(format!("{} more entries not shown", editor.manifest.count() - SHOWN)).size(13));
+    }
+    col.padding(6).into()
+}
+
+// The folder and list being kept in step, and what changed in the list, newest first
+fn staging_panel<'a>(watch: &staging::StagingWatch) -> Element<'a, Message> {
+    const SHOWN: usize = 50;
+    let title = path_title(|p| format!("Watching {} for {}", p(&watch.folder), watch.list.file_name().map(|n| n.to_string_lossy()).unwrap_or_default()));
+    let status = if watch.is_hashing() {
+        "Hashing new and replaced files...".to_string()
+    } else if watch.pending() > 0 {
+        format!("{} file(s) waiting until they stop changing", watch.pending())
+    } else {
+        "Up to date".to_string()
+    };
+    let mut col = column![
+        row![
+            title,
+            text(status).size(13),
+            button(text("Stop").size(13)).on_press(Message::StopStagingPressed).style(theme::Button::Secondary),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center)
+    ]
+    .spacing(4);
+    if watch.log.is_empty() {
+        col = col.push(text("No changes yet").size(13));
+    }
+    for activity in watch.log.iter().rev().take(SHOWN) {
+        let status = if matches!(activity.change, staging::Change::Failed(_)) { Status::Bad } else { Status::Neutral };
+        col = col.push(status_text(activity, status).size(13));
+    }
+    col.padding(6).into()
+}
+
+// Counts and size a verification would cover, and the entries it would report MISSING
+fn dry_run_panel<'a>(run: &verify::DryRun) -> Element<'a, Message> {
+    let title = path_title(|p| format!("Dry run of {} ({})", p(&run.source), run.format));
+    let mut summary = format!("{} files, {} would be read", run.files, human_bytes(run.bytes as f64));
+    if !run.unmatched.is_empty() {
+        summary.push_str(&format!(", {} MISSING", run.unmatched.len()));
+    }
+    let status = if run.unmatched.is_empty() { Status::Good } else { Status::Bad };
+    let mut col = column![
+        title,
+        row![
+            status_text(summary, status).size(14).width(Length::Fill),
+            button(text("Close").size(13)).on_press(Message::DryRunClosePressed).style(theme::Button::Secondary),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center),
+    ]
+    .spacing(4);
+    if run.renamed > 0 {
+        col = col.push(text(format!("{} files found only after normalizing separators, case or Unicode form", run.renamed)).size(13));
+    }
+    if run.skipped > 0 {
+        col = col.push(text(format!("{} entries skipped: no SHA-256 in the list", run.skipped)).size(13));
+    }
+    for path in run.unmatched.iter().take(20) {
+        col = col.push(text(format!("MISSING  {}", path.display())).size(13).style(theme::Text::Color(palette::color(Status::Bad).into())));
+    }
+    col.padding(6).into()
+}
+
+// Progress of a list/log re-verification, then its verdict and the files that need attention
+fn verify_panel<'a>(run: &verify::VerifyRun, show_slowest: bool) -> Element<'a, Message> {
+    let title = if run.lists.len() > 1 {
+        text(format!("Verifying {} lists", run.lists.len())).size(16).into()
+    } else {
+        path_title(|p| format!("Verifying {} ({})", p(&run.source), run.format))
+    };
+    let mut col = column![title].spacing(4);
+    if run.is_running() {
+        let progress = run.progress();
+        let mut line = format!("{} files checked, {} to go", run.done.len(), progress.files_remaining);
+        if let Some(pct) = progress.percent() {
+            line.push_str(&format!(" • {:.0}% of {}", pct, human_bytes(progress.total.unwrap_or(0) as f64)));
+        }
+        if let Some(eta) = progress.eta {
+            line.push_str(&format!(" • about {} left", human_duration(eta)));
+        }
+        if let Some(current) = &progress.current {
+            line.push_str(&format!(" • {}", current.display()));
+        }
+        col = col.push(
+            row![
+                text(line).size(14).width(Length::Fill),
+                button(text("Cancel").size(13)).on_press(Message::VerifyCancelPressed).style(theme::Button::Secondary),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+        );
+    } else {
+        let verified = run.verified();
+        let mut verdict = format!("{} of {} files verified", verified, run.files);
+        for label in ["MISMATCH", "XATTRS", "MISSING", "FAILED"] {
+            let n = run.done.iter().filter(|c| c.label() == label).count();
+            if n > 0 {
+                verdict.push_str(&format!(", {} {}", n, label));
+            }
+        }
+        let acknowledged = run.acknowledged_count();
+        if acknowledged > 0 {
+            verdict.push_str(&format!(" ({} acknowledged)", acknowledged));
+        }
+        if run.stopped {
+            verdict.push_str(&format!(", stopped at the first failure ({} not checked)", run.files - run.done.len()));
+        } else if run.done.len() < run.files {
+            verdict.push_str(&format!(", {} not checked (cancelled)", run.files - run.done.len()));
+        }
+        let status = if verified + acknowledged == run.files { Status::Good } else { Status::Bad };
+        let timings = run.timings();
+        col = col.push(
+            row![status_text(verdict, status).size(14).width(Length::Fill)]
+                .push_maybe((run.retryable() > 0).then(|| {
+                    button(text(format!("Retry failed ({})", run.retryable())).size(13)).on_press(Message::VerifyRetryPressed).style(theme::Button::Secondary)
+                }))
+                .push_maybe(slowest_button(&timings, show_slowest))
+                .push_maybe((!run.acknowledged.is_empty()).then(|| {
+                    button(text("Clear acknowledged").size(13)).on_press(Message::VerifyClearAcknowledgedPressed).style(theme::Button::Secondary)
+                }))
+                .push(button(text("Close").size(13)).on_press(Message::VerifyClosePressed).style(theme::Button::Secondary))
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+        );
+        if show_slowest {
+            col = col.push_maybe(slowest_list(&timings));
+        }
+        if run.lists.len() > 1 {
+            for (list, files, verified, problems) in run.per_list() {
+                let line = format!("{}: {} of {} verified{}", list.display(), verified, files, if problems > 0 { format!(", {} problems", problems) } else { String::new() });
+                col = col.push(text(line).size(13));
+            }
+        }
+    }
+    if run.renamed() > 0 {
+        col = col.push(text(format!("{} files matched after normalizing separators, case or Unicode form", run.renamed())).size(13));
+    }
+    if run.skipped > 0 {
+        col = col.push(text(format!("{} entries skipped: no SHA-256 in the list (only SHA-256 can be re-verified)", run.skipped)).size(13));
+    }
+    for (index, item) in run.problems().take(20) {
+        let mut line = match &item.status {
+            verify::Status::Failed(e) => format!("FAILED   {}: {}", item.entry.path.display(), e),
+            _ => format!("{:<8} {}", item.label(), item.entry.path.display()),
+        };
+        if let Some(note) = &item.note {
+            line.push_str(&format!(" ({})", note));
+        }
+        // Read errors are not a state of the file, so only mismatches and missing files can be acknowledged
+        let acknowledge = (!run.is_running() && matches!(item.status, verify::Status::Mismatch(_) | verify::Status::AttributesChanged(_) | verify::Status::Missing)).then(|| {
+            button(text("Acknowledge").size(12)).on_press(Message::VerifyAcknowledgePressed(index)).style(theme::Button::Secondary)
+        });
+        col = col.push(
+            row![text(line).size(13).style(theme::Text::Color(palette::color(Status::Bad).into())).width(Length::Fill)]
+                .push_maybe(acknowledge)
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+        );
+    }
+    col.padding(6).into()
+}
+
+// Device and image digests side by side with the acquisition verdict
+fn acquisition_panel<'a>(run: &acquisition::AcquisitionRun, uppercase: bool) -> Element<'a, Message> {
+    let mut col = column![path_title(|p| format!("Acquisition check: {} vs {}", p(&run.device), p(&run.image)))].spacing(4);
+    let close = |label: &str, message: Message| button(text(label.to_string()).size(13)).on_press(message).style(theme::Button::Secondary);
+    let case = |hex: &str| if uppercase { hex.to_uppercase() } else { hex.to_string() };
+    match &run.result {
+        None => {
+            let (on_image, read, size) = run.stage();
+            let progress = match size {
+                Some(total) if total > 0 => format!("{:.0}% of {}", (read as f64 / total as f64 * 100.0).min(100.0), human_bytes(total as f64)),
+                _ => format!("{} read", human_bytes(read as f64)),
+            };
+            let step = if on_image { "Step 2/2: hashing the image" } else { "Step 1/2: hashing the device" };
+            col = col.push(
+                row![text(format!("{}... {}", step, progress)).size(14).width(Length::Fill), close("Cancel", Message::AcquisitionCancelPressed)]
+                    .spacing(10)
+                    .align_items(iced::Alignment::Center),
+            );
+        }
+        Some(Err(e)) => {
+            col = col.push(
+                row![
+                    text(e.clone()).size(14).style(theme::Text::Color(palette::color(Status::Bad).into())).width(Length::Fill),
+                    close("Close", Message::AcquisitionClosePressed)
+                ]
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+            );
+        }
+        Some(Ok(outcome)) => {
+            let mono = |label: &str, side: &acquisition::Side| text(format!("{} {} ({} bytes)", label, case(&side.hex), side.bytes)).size(13);
+            col = col.push(mono("Device:", &outcome.device)).push(mono("Image: ", &outcome.image));
+            let (verdict, status) = if outcome.matches() {
+                ("MATCH: the image is an exact copy of the device".to_string(), Status::Good)
+            } else if outcome.device.bytes != outcome.image.bytes {
+                (format!("MISMATCH: sizes differ by {} bytes", outcome.device.bytes.abs_diff(outcome.image.bytes)), Status::Bad)
+            } else {
+                ("MISMATCH: same size, different content".to_string(), Status::Bad)
+            };
+            col = col.push(
+                row![status_text(verdict, status).size(14).width(Length::Fill), close("Close", Message::AcquisitionClosePressed)]
+                    .spacing(10)
+                    .align_items(iced::Alignment::Center),
+            );
+        }
+    }
+    col.padding(6).into()
+}
+
+// Digests of the media inside an E01 set and the verdict against its acquisition hashes
+fn ewf_panel<'a>(run: &ewf::EwfRun, uppercase: bool) -> Element<'a, Message> {
+    let case = |hex: &str| if uppercase { hex.to_uppercase() } else { hex.to_string() };
+    match &run.result {
+        None => {
+            let read = run.processed();
+            let progress = match run.media_size {
+                Some(total) if total > 0 => format!("{:.0}% of {}", (read as f64 / total as f64 * 100.0).min(100.0), human_bytes(total as f64)),
+                _ => format!("{} read", human_bytes(read as f64)),
+            };
+            text(format!("Hashing the media inside the E01... {}", progress)).size(14).into()
+        }
+        Some(Err(e)) => text(format!("E01 content: {}", e)).size(14).style(theme::Text::Color(palette::color(Status::Bad).into())).into(),
+        Some(Ok(l)) => {
+            // A digest the policy rules out says so in its place
+            let digest = |hex: &Option<String>, algorithm: algo::Algorithm| match hex {
+                Some(hex) => case(hex),
+                None => format!("not computed ({})", policy::get().refusal(algorithm).unwrap_or_default()),
+            };
+            let mut col = column![
+                text(format!("Media in {} ({} segment(s), {} bytes)", run.image.display(), l.segments, l.bytes)).size(16),
+                text(format!("SHA-256: {}", case(&l.sha256))).size(13),
+                text(format!("MD5:     {}", digest(&l.md5, algo::Algorithm::Md5))).size(13),
+                text(format!("SHA-1:   {}", digest(&l.sha1, algo::Algorithm::Sha1))).size(13),
+            ]
+            .spacing(4);
+            let stored: Vec<String> = [("MD5", &l.stored_md5), ("SHA-1", &l.stored_sha1)]
+                .into_iter()
+                .filter_map(|(name, hex)| hex.as_ref().map(|h| format!("{} {}", name, case(h))))
+                .collect();
+            let (verdict, status) = match l.matches() {
+                Some(true) => (format!("Matches the acquisition hash ({})", stored.join(", ")), Status::Good),
+                Some(false) => (format!("MISMATCH with the acquisition hash ({})", stored.join(", ")), Status::Bad),
+                None if stored.is_empty() => ("No acquisition hash stored in the container".to_string(), Status::Warn),
+                None => (format!("Acquisition hash not checked; the policy rules out its algorithm ({})", stored.join(", ")), Status::Warn),
+            };
+            col = col.push(status_text(verdict, status).size(14));
+            col.padding(6).into()
+        }
+    }
+}
+
+// Files found inside a hashed ISO/UDF image, with ways to use their digests
+fn disc_panel<'a>(run: &disc_image::ContentsRun) -> Element<'a, Message> {
+    match &run.result {
+        None => text(format!("Hashing the files inside the image... {} read", human_bytes(run.processed() as f64))).size(14).into(),
+        Some(Err(e)) => text(format!("Image contents: {}", e)).size(14).style(theme::Text::Color(palette::color(Status::Bad).into())).into(),
+        Some(Ok(contents)) => row![
+            text(format!("{} contents: {} files, {}", contents.filesystem, contents.files.len(), human_bytes(contents.total_bytes() as f64)))
+                .size(14)
+                .width(Length::Fill),
+            button(text("Save list...").size(13)).on_press(Message::SaveDiscManifestPressed).style(theme::Button::Secondary),
+            button(text("Compare with folder...").size(13)).on_press(Message::CompareDiscFolderPressed).style(theme::Button::Secondary),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center)
+        .into(),
+    }
+}
+
+fn partition_panel<'a>(run: &partitions::PartitionRun) -> Element<'a, Message> {
+    match &run.result {
+        None => text(format!("Hashing partitions... {} read", human_bytes(run.processed() as f64))).size(14).into(),
+        Some(Err(e)) => text(format!("Partitions: {}", e)).size(14).style(theme::Text::Color(palette::color(Status::Bad).into())).into(),
+        Some(Ok(parts)) => {
+            let mut col = column![row![
+                text(format!("{} partitions ({})", parts.len(), parts.first().map_or("", |p| p.scheme))).size(14).width(Length::Fill),
+                button(text("Save list...").size(13)).on_press(Message::SavePartitionsPressed).style(theme::Button::Secondary),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center)]
+            .spacing(4);
+            for p in parts {
+                col = col.push(
+                    text(format!("p{}  {}  at {}, {}  {}", p.number, p.label, human_bytes(p.offset as f64), human_bytes(p.length as f64), p.sha256)).size(13),
+                );
+            }
+            col.padding(6).into()
+        }
+    }
+}
+
+fn sparse_info<'a>(logical: u64, allocated: u64, holes_skipped: u64) -> Element<'a, Message> {
+    let mut col = column![text(format!(
+        "Sparse file: {} logical, {} allocated on disk",
+        human_bytes(logical as f64),
+        human_bytes(allocated as f64)
+    ))
+    .size(14)];
+    if holes_skipped > 0 {
+        col = col.push(
+            text(format!("Zero-aware: {} of holes hashed as zeros without reading the disk", human_bytes(holes_skipped as f64))).size(14),
+        );
+    }
+    col.spacing(4).padding(6).into()
+}
+
+fn chunk_panel<'a>(chunks: &[ChunkEntry], summary: Option<&String>, disabled: bool) -> Element<'a, Message> {
+    let total: u64 = chunks.iter().map(|c| c.length).sum();
+    let avg = total / chunks.len().max(1) as u64;
+    let info = text(format!("FastCDC: {} chunks, average {}", chunks.len(), human_bytes(avg as f64))).size(14);
+
+    let save_btn = if disabled {
+        button(text("Save chunk list")).style(theme::Button::Secondary)
+    } else {
+        button(text("Save chunk list")).on_press(Message::SaveChunksPressed).style(theme::Button::Secondary)
+    };
+    let compare_btn = if disabled {
+        button(text("Compare with chunk list...")).style(theme::Button::Secondary)
+    } else {
+        button(text("Compare with chunk list...")).on_press(Message::CompareChunksPressed).style(theme::Button::Secondary)
+    };
+
+    let mut col = column![row![info, save_btn, compare_btn].spacing(10).align_items(iced::Alignment::Center)].spacing(6);
+    if let Some(s) = summary {
+        col = col.push(text(s.clone()).size(14));
+    }
+    col.padding(6).into()
+}
+
+fn segment_panel<'a>(segments: &[SegmentEntry], summary: Option<&String>, disabled: bool) -> Element<'a, Message> {
+    let size = segments.first().map(|s| s.length).unwrap_or(0);
+    let info = text(format!("Segments: {} x {}", segments.len(), human_bytes(size as f64))).size(14);
+
+    let save_btn = if disabled {
+        button(text("Save segment list")).style(theme::Button::Secondary)
+    } else {
+        button(text("Save segment list")).on_press(Message::SaveSegmentsPressed).style(theme::Button::Secondary)
+    };
+    let compare_btn = if disabled {
+        button(text("Compare with segment list...")).style(theme::Button::Secondary)
+    } else {
+        button(text("Compare with segment list...")).on_press(Message::CompareSegmentsPressed).style(theme::Button::Secondary)
+    };
+
+    let mut col = column![row![info, save_btn, compare_btn].spacing(10).align_items(iced::Alignment::Center)].spacing(6);
+    if let Some(s) = summary {
+        col = col.push(text(s.clone()).size(14));
+    }
+    col.padding(6).into()
+}
+
+fn signature_panel<'a>(size: usize, disabled: bool) -> Element<'a, Message> {
+    let info = text(format!("librsync signature (BLAKE2): {}", human_bytes(size as f64))).size(14);
+    let save_btn = if disabled {
+        button(text("Save signature")).style(theme::Button::Secondary)
+    } else {
+        button(text("Save signature")).on_press(Message::SaveSignaturePressed).style(theme::Button::Secondary)
+    };
+    row![info, save_btn].spacing(10).padding(6).align_items(iced::Alignment::Center).into()
+}
+
+// Drop target that lights up while a drag hovers the window and flashes briefly on drop
+#[derive(Debug, Clone, Copy)]
+enum DropAction {
+    Hash,
+    AddToList,
+    AddToQueue,
+}
+
+fn drop_zone(action: DropAction, hovering: bool, since_drop: Option<Duration>) -> Element<'static, Message> {
+    let label = match (hovering, action) {
+        (true, DropAction::Hash) => "Drop to hash",
+        (true, DropAction::AddToList) => "Drop to add to the list",
+        (true, DropAction::AddToQueue) => "Drop to add to the queue",
+        (false, DropAction::Hash) => "Drop a file anywhere in this window to hash",
+        (false, DropAction::AddToList) => "Drop files anywhere in this window to add them to the list",
+        (false, DropAction::AddToQueue) => "Drop files anywhere in this window to queue them",
+    };
+    let flash = since_drop.map_or(0.0, |d| 1.0 - (d.as_secs_f32() / DROP_FLASH.as_secs_f32()).min(1.0));
+    let glow = if hovering { 1.0 } else { flash };
+    container(text(label).size(if hovering { 18 } else { 14 }))
+        .width(Length::Fill)
+        .padding(if hovering { 18 } else { 6 })
+        .center_x()
+        .style(theme::Container::Custom(Box::new(DropZoneStyle { glow })))
+        .into()
+}
+
+fn empty_state(hovering: bool, since_drop: Option<Duration>) -> Element<'static, Message> {
+    let flash = since_drop.map_or(0.0, |d| 1.0 - (d.as_secs_f32() / DROP_FLASH.as_secs_f32()).min(1.0));
+    let glow = if hovering { 1.0 } else { flash };
+    let link = |label: &'static str, msg: Message| button(text(label).size(14)).on_press(msg).style(theme::Button::Secondary);
+    let body = column![
+        text(if hovering { "Drop to hash" } else { "Drop a file here to hash it" }).size(24),
+        text("or type a path above").size(14).style(theme::Text::Color(palette::color(Status::Neutral).into())),
+        row![
+            button(text("Browse...").size(16)).on_press(Message::BrowsePressed),
+            button(text("Try with a sample file").size(16)).on_press(Message::TrySamplePressed).style(theme::Button::Secondary),
+        ]
+        .spacing(10),
+        text("Other things rust-hash does:").size(14).style(theme::Text::Color(palette::color(Status::Neutral).into())),
+        row![
+            link("Verify a checksum list...", Message::VerifyListPressed),
+            link("Queue many files", Message::QueueToggled(true)),
+            link("Hash several files as one", Message::ConcatToggled(true)),
+        ]
+        .spacing(10),
+    ]
+    .spacing(12)
+    .align_items(iced::Alignment::Center);
+    container(body)
+        .width(Length::Fill)
+        .padding(40)
+        .center_x()
+        .style(theme::Container::Custom(Box::new(DropZoneStyle { glow })))
+        .into()
+}
+
+struct DropZoneStyle {
+    // 0 = idle outline, 1 = fully highlighted
+    glow: f32,
+}
+
+impl iced::widget::container::StyleSheet for DropZoneStyle {
+    type Style = Theme;
+
+    fn appearance(&self, style: &Theme) -> iced::widget::container::Appearance {
+        let palette = style.extended_palette();
+        let accent = palette.primary.base.color;
+        let idle = palette.background.strong.color;
+        let mix = |a: f32, b: f32| a + (b - a) * self.glow;
+        iced::widget::container::Appearance {
+            background: Some(iced::Color { a: 0.25 * self.glow, ..accent }.into()),
+            border: iced::Border {
+                color: iced::Color::from_rgb(mix(idle.r, accent.r), mix(idle.g, accent.g), mix(idle.b, accent.b)),
+                width: 1.0 + self.glow,
+                radius: 6.0.into(),
+            },
+            ..Default::default()
+        }
+    }
+}
+
+// Subtle one-line feedback under the path input; nothing for an empty input
+fn path_status_line(status: PathStatus) -> Option<Element<'static, Message>> {
+    let (ok, warn) = (Status::Good, Status::Warn);
+    let (label, status) = match status {
+        PathStatus::Empty => return None,
+        PathStatus::File(len) => (format!("File, {}", human_bytes(len as f64)), ok),
+        PathStatus::Directory => ("Folder - pick a file inside it".to_string(), warn),
+        PathStatus::Special(kind) if kind.hashable() => (format!("This is a {}; it is hashed as a stream", kind.describe()), ok),
+        PathStatus::Special(kind) => (format!("This is a {}; it cannot be hashed", kind.describe()), warn),
+        PathStatus::Missing => ("No such file".to_string(), warn),
+        PathStatus::NoPermission => ("No permission to read".to_string(), warn),
+    };
+    Some(container(status_text(label, status).size(12)).padding([0, 6]).into())
+}
+
+fn stall_panel(idle: Duration) -> Element<'static, Message> {
+    let info = text(format!(
+        "Stalled: no data read for {}. The network share or drive may have stopped responding.",
+        human_duration(idle)
+    ))
+    .size(14)
+    .style(theme::Text::Color(palette::color(Status::Warn).into()));
+    let wait_btn = button(text("Keep waiting").size(14)).on_press(Message::KeepWaitingPressed);
+    let abort_btn = button(text("Abort").size(14)).on_press(Message::CancelPressed).style(theme::Button::Destructive);
+    row![info, wait_btn, abort_btn].spacing(10).padding(6).align_items(iced::Alignment::Center).into()
+}
+
+// `atime_kept` is Some for a result hashed in forensic mode
+fn forensic_banner(atime_kept: Option<bool>) -> Element<'static, Message> {
+    let (message, status) = match atime_kept {
+        Some(true) => ("FORENSIC MODE: no writes were made to the evidence; its last-access time was preserved.", Status::Good),
+        Some(false) => (
+            "FORENSIC MODE: no writes were made to the evidence, but the OS did not allow keeping its last-access time.",
+            Status::Warn,
+        ),
+        None => ("FORENSIC MODE: files are opened read-only; sidecars and moves are disabled.", Status::Info),
+    };
+    status_text(message, status).size(14).into()
+}
+
+fn meta_info(
+    // Shown while hashing, in place of the finished timing
+    progress: Option<String>,
+    // Elapsed time and bytes of a finished hash
+    timing: Option<(Duration, u64)>,
+    path: Option<&PathBuf>,
+    file_type: Option<FileType>,
+    error: Option<&HashError>,
+    comparison: Option<&Comparison>,
+    quick: bool,
+) -> Element<'static, Message> {
+    let mut parts: Vec<Element<'static, Message>> = Vec::new();
+    if let Some(p) = path {
+        let s = format!("{}", p.display());
+        parts.push(text(s).size(14).into());
+    }
+    if let Some(e) = error {
+        let message = e.message();
+        let show_detail = e.detail != message;
+        parts.push(text(message).style(theme::Text::Color(palette::color(Status::Bad).into())).into());
+        if show_detail {
+            parts.push(text(e.detail.clone()).size(12).style(theme::Text::Color(palette::color(Status::Neutral).into())).into());
+        }
+        if let Some(action) = e.action() {
+            parts.push(button(text(action.label())).on_press(Message::ErrorActionPressed(action)).into());
+        }
+    } else {
+        if let Some((el, b)) = timing {
+            let secs = el.as_secs_f64();
+            let speed = if secs > 0.0 { (b as f64) / secs } else { 0.0 };
+            let speed_human = human_bytes(speed);
+            let b_human = human_bytes(b as f64);
+            let kind = file_type.map(|t| format!(" • {} ({})", t.name, t.mime)).unwrap_or_default();
+            parts.push(text(format!("{} • {} • {}/s{}", human_duration(el), b_human, speed_human, kind)).size(14).into());
+            if quick {
+                parts.push(
+                    text(format!(
+                        "Quick triage: SHA-256 of size + first/last {} MiB only. Not a full hash; confirm candidate duplicates with a full hash.",
+                        QUICK_SAMPLE_MIB
+                    ))
+                    .size(14)
+                    .style(theme::Text::Color(palette::color(Status::Warn).into()))
+                    .into(),
+                );
+            }
+        } else if let Some(line) = progress {
+            parts.push(text(line).size(14).into());
+        }
+        match comparison {
+            Some(Comparison::Unchanged(when)) => {
+                parts.push(text(format!("Unchanged since {}", when)).size(14).style(theme::Text::Color(palette::color(Status::Good).into())).into());
+            }
+            Some(Comparison::Changed(when)) => {
+                parts.push(text(format!("CHANGED since {}", when)).size(16).style(theme::Text::Color(palette::color(Status::Warn).into())).into());
+            }
+            None => {}
+        }
+    }
+
+    column(parts)
+        .spacing(6)
+        .padding(6)
+        .into()
+}
+
+// Progress of a read whose total is unknown: `1.20 GB read • 85.00 MB/s (size unknown)`
+fn unknown_size_progress(bytes: u64, rate: Option<f64>) -> String {
+    match rate.filter(|r| *r > 0.0) {
+        Some(rate) => format!("{} read • {}/s (size unknown)", human_bytes(bytes as f64), human_bytes(rate)),
+        None => format!("{} read (size unknown)", human_bytes(bytes as f64)),
+    }
+}
+
+fn human_duration(d: Duration) -> String {
+    let ms_total = d.as_millis() as f64;
+    if ms_total < 1000.0 {
+        return format!("{} ms", ms_total as u128);
+    }
+    let s_total = d.as_secs_f64();
+    if s_total < 60.0 {
+        return format!("{:.2} s", s_total);
+    }
+    let m_total = s_total / 60.0;
+    if m_total < 60.0 {
+        return format!("{:.2} min", m_total);
+    }
+    let h_total = m_total / 60.0;
+    if h_total < 24.0 {
+        return format!("{:.2} h", h_total);
+    }
+    let d_total = h_total / 24.0;
+    format!("{:.2} d", d_total)
+}
+
+// Cuts the middle out of a long path so the file name stays readable: `C:\Users\me\…\reports\q3.xlsx`.
+// Counts characters, not bytes; a file name longer than `max` is itself cut in the middle
+fn middle_ellipsis(path: &str, max: usize) -> String {
+    let chars: Vec<char> = path.chars().collect();
+    if chars.len() <= max {
+        return path.to_string();
+    }
+    let name_start = chars.iter().rposition(|c| *c == '/' || *c == '\\').map_or(0, |i| i + 1);
+    let name = &chars[name_start..];
+    // Room for the head: what is left after the ellipsis and the separator before the name
+    let head_room = max.saturating_sub(name.len() + 2);
+    if name_start == 0 || head_room < 4 {
+        let keep = max.saturating_sub(1);
+        let (front, back) = (keep / 2, keep - keep / 2);
+        return format!("{}…{}", chars[..front].iter().collect::<String>(), chars[chars.len() - back..].iter().collect::<String>());
+    }
+    format!("{}…{}", chars[..head_room].iter().collect::<String>(), chars[name_start - 1..].iter().collect::<String>())
+}
+
+fn human_bytes(b: f64) -> String {
+    const UNITS: [&str; 6] = ["B", "KB", "MB", "GB", "TB", "PB"];
+    let base = 1000.0;
+    let mut val = if b < 0.0 { 0.0 } else { b };
+    let mut idx = 0;
+    while val >= base && idx < UNITS.len() - 1 {
+        val /= base;
+        idx += 1;
+    }
+    if idx == 0 {
+        format!("{:.0} {}", val, UNITS[idx])
+    } else {
+        format!("{:.2} {}", val, UNITS[idx])
+    }
+}
+
+// old async hash and non-progress variant removed (no longer used)
+
+impl App {
+    fn progress_rate(&self) -> Option<f64> {
+        self.started_at.map(|at| self.progress_processed as f64 / at.elapsed().as_secs_f64().max(0.001))
+    }
+
+    // The line under the path while hashing: percentage when the size is known, bytes and throughput otherwise
+    fn progress_line(&self) -> Option<String> {
+        if !self.is_hashing || self.is_following {
+            return None;
+        }
+        let rate = self.progress_rate().filter(|_| self.progress_processed > 0);
+        let line = match self.progress_total.filter(|t| *t > 0) {
+            Some(total) => {
+                let pct = (self.progress_processed as f64 / total as f64 * 100.0).clamp(0.0, 100.0);
+                let mut line = format!("Hashing... {:.0}% of {}", pct, human_bytes(total as f64));
+                if let Some(rate) = rate {
+                    line.push_str(&format!(" • {}/s", human_bytes(rate)));
+                }
+                if let Some(eta) = self.progress_eta.filter(|eta| eta.as_secs() >= 5) {
+                    line.push_str(&format!(" • {} left", human_duration(eta)));
+                }
+                line
+            }
+            None => format!("Hashing... {}", unknown_size_progress(self.progress_processed, rate)),
+        };
+        Some(line)
+    }
+
+    fn next_token(&mut self) -> u64 {
+        self.is_hashing = true;
+        self.error = None;
+        self.comparison = None;
+        self.chunk_summary = None;
+        self.segment_summary = None;
+        self.compare_rx = None;
+        self.compare_note = None;
+        self.stop_result_checks();
+        self.started_at = Some(Instant::now());
+        self.token = self.token.wrapping_add(1);
+        self.token
+    }
+
+    // Tests the members of a just-hashed ZIP/7z/RAR against their stored CRCs on a worker thread
+    fn start_archive_check(&mut self, path: PathBuf) {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        self.archive_rx = Some(rx);
+        self.archive_cancel = Some(cancel.clone());
+        self.archive_note = Some("Checking archive members against their stored CRCs...".to_string());
+        thread::spawn(move || {
+            let note = match archive::check(&path, &cancel) {
+                Ok(result) => result.describe(),
+                Err(e) => format!("Archive CRC check failed: {:#}", e),
+            };
+            let _ = tx.send(note);
+        });
+    }
+
+    // Hashes the members of a just-hashed ZIP/7z/tar into a digest that ignores timestamps and member order
+    fn start_archive_digest(&mut self, path: PathBuf) {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        self.archive_digest_rx = Some(rx);
+        self.archive_digest_cancel = Some(cancel.clone());
+        self.archive_digest = None;
+        thread::spawn(move || {
+            let _ = tx.send(archive_digest::content_digest(&path, &cancel).map_err(|e| format!("{:#}", e)));
+        });
+    }
+
+    // Drops the archive CRC, archive digest and image-contents checks that belong to the previous result
+    fn stop_result_checks(&mut self) {
+        if let Some(cancel) = self.archive_cancel.take() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+        self.archive_rx = None;
+        self.archive_note = None;
+        if let Some(cancel) = self.archive_digest_cancel.take() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+        self.archive_digest_rx = None;
+        self.archive_digest = None;
+        if let Some(run) = self.disc_run.take() {
+            run.cancel();
+        }
+        if let Some(run) = self.partition_run.take() {
+            run.cancel();
+        }
+        if let Some(run) = self.ewf_run.take() {
+            run.cancel();
+        }
+    }
+
+    // Compares the last full hash with the pasted value. SHA-256 is compared right away; other
+    // algorithms need the file read again, so their verdict arrives on a later tick
+    // Takes the expected digest for `data` from `checksum`; a list without the file leaves the pair waiting
+    fn bind_pair(&mut self, checksum: PathBuf, data: PathBuf) {
+        match pairing::expected_for(&checksum, &data) {
+            Ok((hex, line)) => {
+                self.expected_input = hex;
+                self.pair = Some(pairing::Pair { checksum, data: Some(data), line });
+                self.check_expected();
+            }
+            Err(e) => {
+                self.status_note = Some(format!("{:#}", e));
+                self.expected_input.clear();
+                self.expected_verdict = None;
+                self.pair = Some(pairing::Pair::waiting(checksum));
+            }
+        }
+    }
+
+    fn check_expected(&mut self) {
+        self.expected_rx = None;
+        let expected = integrity::parse(&self.expected_input);
+        let Some(algorithm) = integrity::strongest(&expected).first().map(|e| e.algorithm) else {
+            self.expected_verdict = (!self.expected_input.trim().is_empty()).then(|| Verdict::Failed("Not a digest or integrity value".to_string()));
+            return;
+        };
+        let Some(path) = self.last_path.clone().filter(|_| !self.digest.is_empty() && !self.last_quick && !self.is_hashing) else {
+            self.expected_verdict = None;
+            return;
+        };
+        if algorithm == algo::Algorithm::Sha256 {
+            self.expected_verdict = Some(self.expected_verdict_for(&hex::encode(&self.digest)));
+            return;
+        }
+        if let Some(refusal) = policy::get().refusal(algorithm) {
+            self.expected_verdict = Some(Verdict::Failed(refusal));
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        self.expected_rx = Some(rx);
+        self.expected_verdict = Some(Verdict::Pending(algorithm.label()));
+        thread::spawn(move || {
+            let result = algo::hash_file(&path, algorithm, &AtomicU64::new(0), &AtomicBool::new(false)).map(|(hex, _)| hex).map_err(|e| format!("{:#}", e));
+            let _ = tx.send(result);
+        });
+    }
+
+    // The last digest as shown and copied, in the chosen case
+    fn hex_digest(&self) -> String {
+        let hex = hex::encode(&self.digest);
+        if self.uppercase {
+            hex.to_uppercase()
+        } else {
+            hex
+        }
+    }
+
+    fn base64_digest(&self) -> String {
+        base64::engine::general_purpose::STANDARD.encode(&self.digest)
+    }
+
+    // `digest` is the file's digest in the algorithm of the strongest pasted values
+    fn expected_verdict_for(&self, digest: &str) -> Verdict {
+        let expected = integrity::parse(&self.expected_input);
+        let strongest = integrity::strongest(&expected);
+        let label = strongest.first().map_or("", |e| e.algorithm.label());
+        if strongest.iter().any(|e| e.hex.eq_ignore_ascii_case(digest)) {
+            Verdict::Match(label)
+        } else {
+            Verdict::Mismatch(label)
+        }
+    }
+
+    fn hash_options(&self) -> HashOptions {
+        HashOptions {
+            quick: self.quick_mode,
+            chunks: self.chunk_mode,
+            // A signature saved before a policy arrived is not computed once it rules BLAKE2 out
+            rsync_signature: self.signature_mode && policy::get().refusal_of("BLAKE2").is_none(),
+            follow: self.follow_mode && !self.quick_mode,
+            zero_aware: self.zero_aware_mode,
+            forensic: self.forensic_mode,
+            segment_size: self.segment_mode.then(|| self.segment_size.map_or(segments::DEFAULT_SEGMENT_SIZE, |s| s.0)),
+            ipfs_cid: self.cid_mode,
+            // Content-MD5 is an MD5, which a policy may rule out
+            cloud: self.cloud_mode && policy::get().allows(algo::Algorithm::Md5),
+            preferred: Some(self.algorithm).filter(|a| *a != algo::Algorithm::Sha256 && policy::get().allows(*a)),
+            authenticode: self.authenticode_mode,
+            metadata: self.metadata_mode.then(|| self.metadata_fields.unwrap_or(meta_digest::ALL)),
+            payload: self.payload_mode,
+            read_buffer: self.read_buffer.0,
+        }
+    }
+
+    // Shared setup for a background hash: fresh token, result channel and progress/cancel/stop state
+    fn begin_worker(&mut self, total: Option<u64>, following: bool) -> WorkerHandles {
+        let token = self.next_token();
+        let (w, rx) = WorkerHandles::new(token);
+        self.is_following = following;
+        self.stop_flag = Some(w.stop.clone());
+        self.progress_total = total;
+        self.progress_processed = 0;
+        self.tracker = Some(progress::Tracker::new(w.progress.clone(), total, 1));
+        self.progress_eta = None;
+        self.cancel_flag = Some(w.cancel.clone());
+        self.last_progress_at = Some(Instant::now());
+        self.stall_watch = true;
+        self.stalled = false;
+        self.worker_rx = Some(rx);
+        self.worker_token = Some(token);
+        w
+    }
+
+    fn report_batch(&mut self, elapsed: Duration) {
+        let jobs = self.batch_succeeded + self.batch_failed;
+        let mut summary = format!("rust-hash queue finished: {} jobs, {} ok, {} failed", jobs, self.batch_succeeded, self.batch_failed);
+        if !self.batch_skipped.is_empty() {
+            summary.push_str(&format!(", {} skipped", self.batch_skipped.len()));
+        }
+        if self.blocklist.get().is_some() {
+            summary.push_str(&format!(", {} BLOCKLISTED", self.batch_blocked));
+        }
+        if !self.config.webhook().trim().is_empty() {
+            let report = webhook::BatchReport {
+                event: "batch",
+                text: summary.clone(),
+                jobs,
+                succeeded: self.batch_succeeded,
+                failed: self.batch_failed,
+                skipped: self.batch_skipped.len(),
+                blocklisted: self.batch_blocked,
+                duration_ms: elapsed.as_millis() as u64,
+            };
+            webhook::post(self.config.webhook(), &report, notice_channel(&mut self.notice_rx));
+        }
+        let mut lines = vec![summary.clone(), format!("Duration: {}", human_duration(elapsed)), String::new()];
+        lines.append(&mut self.batch_lines);
+        if !self.batch_skipped.is_empty() {
+            lines.push(String::new());
+            lines.push("Skipped (could not be opened):".to_string());
+            lines.extend(self.batch_skipped.drain(..).map(|s| format!("SKIPPED {}", s)));
+        }
+        // A mailto draft stays on this machine until it is sent from the mail app
+        if self.network_allowed() || self.config.email.use_mailto {
+            let report = email::Report { subject: summary, lines, failed: self.batch_failed };
+            email::send_report(&self.config.email, report, notice_channel(&mut self.notice_rx));
+        }
+    }
+
+    // The user's choice, unless the policy keeps the app offline
+    fn network_allowed(&self) -> bool {
+        self.network && !policy::get().offline
+    }
+
+    fn apply_preferences(&mut self) {
+        let p = &self.config.preferences;
+        self.uppercase = p.uppercase;
+        self.hex_grouping = HexGrouping::from_setting(p.hex_grouping);
+        self.auto_hash = p.auto_hash;
+        self.quick_mode = p.quick;
+        self.chunk_mode = p.chunks;
+        self.signature_mode = p.signature;
+        self.follow_mode = p.follow;
+        self.zero_aware_mode = p.zero_aware;
+        self.forensic_mode = p.forensic;
+        self.binary_diff = p.binary_diff;
+        self.archive_check = p.archive_check;
+        self.archive_digest_mode = p.archive_digest;
+        self.disc_contents = p.disc_contents;
+        self.partition_mode = p.partitions;
+        self.folder_choices_mode = p.folder_choices;
+        self.job_timeout = JobTimeout(p.job_timeout_minutes);
+        self.payload_mode = p.payload;
+        self.matching = path_norm::Matching { separators: p.match_separators, ignore_case: p.match_ignore_case, unicode: p.match_unicode };
+        self.skip_unopenable = p.skip_unopenable;
+        self.verify_stop_on_failure = p.verify_stop_on_failure;
+        self.relative_results = p.relative_results;
+        self.tree_xattrs = p.tree_xattrs;
+        self.segment_mode = p.segments;
+        self.cid_mode = p.ipfs_cid;
+        self.cloud_mode = p.cloud_checksums;
+        self.authenticode_mode = p.authenticode;
+        self.metadata_mode = p.metadata_digest;
+        self.metadata_fields = Some(meta_digest::Fields::parse(&p.metadata_fields).unwrap_or(meta_digest::ALL));
+        self.segment_size = Some(SegmentSize(p.segment_size));
+        self.palette = Palette::parse(&p.palette);
+        palette::set(self.palette);
+        self.update_rate = power::UpdateRate::parse(&p.update_rate);
+        self.read_buffer = ReadBuffer::from_setting(p.read_buffer_kib);
+        self.history_limit = history::Limit::from_setting(p.history_size);
+        self.history.set_limit(self.history_limit);
+        self.network = p.network;
+        self.algorithm = algo::Algorithm::parse(&p.algorithm).unwrap_or_default();
+        self.set_usage_metrics(p.usage_metrics);
+    }
+
+    // Counts already taken are written out before counting stops
+    fn set_usage_metrics(&mut self, on: bool) {
+        match (on, self.usage.is_some()) {
+            (true, false) => self.usage = Some(usage::Usage::load()),
+            (false, true) => {
+                self.flush_usage();
+                self.usage = None;
+            }
+            _ => {}
+        }
+    }
+
+    // Usage counts and window placements are written once, on the way out
+    fn before_close(&mut self) {
+        self.flush_usage();
+        if let Err(e) = self.placements.save() {
+            self.status_note = Some(format!("{:#}", e));
+        }
+    }
+
+    // Counted where an operation goes ahead, so a cancelled dialog, a refusal or a failure counts nothing.
+    // Only the kind of action is counted, never what it was applied to
+    fn count_operation(&mut self, name: &'static str, algorithm: Option<algo::Algorithm>) {
+        if let Some(usage) = &mut self.usage {
+            usage.operation(name);
+            if let Some(algorithm) = algorithm {
+                usage.algorithm(algorithm);
+            }
+        }
+    }
+
+    fn flush_usage(&mut self) {
+        if let Some(Err(e)) = self.usage.as_mut().map(usage::Usage::flush) {
+            self.status_note = Some(format!("{:#}", e));
+        }
+    }
+
+    fn store_preferences(&mut self) -> Command<Message> {
+        self.config.preferences = config::Preferences {
+            uppercase: self.uppercase,
+            hex_grouping: self.hex_grouping.0,
+            auto_hash: self.auto_hash,
+            quick: self.quick_mode,
+            chunks: self.chunk_mode,
+            signature: self.signature_mode,
+            follow: self.follow_mode,
+            zero_aware: self.zero_aware_mode,
+            forensic: self.forensic_mode,
+            binary_diff: self.binary_diff,
+            archive_check: self.archive_check,
+            archive_digest: self.archive_digest_mode,
+            disc_contents: self.disc_contents,
+            partitions: self.partition_mode,
+            folder_choices: self.folder_choices_mode,
+            job_timeout_minutes: self.job_timeout.0,
+            payload: self.payload_mode,
+            segments: self.segment_mode,
+            ipfs_cid: self.cid_mode,
+            cloud_checksums: self.cloud_mode,
+            authenticode: self.authenticode_mode,
+            metadata_digest: self.metadata_mode,
+            metadata_fields: self.metadata_fields.unwrap_or(meta_digest::ALL).to_string(),
+            segment_size: self.segment_size.map_or(segments::DEFAULT_SEGMENT_SIZE, |s| s.0),
+            match_separators: self.matching.separators,
+            match_ignore_case: self.matching.ignore_case,
+            match_unicode: self.matching.unicode,
+            skip_unopenable: self.skip_unopenable,
+            verify_stop_on_failure: self.verify_stop_on_failure,
+            relative_results: self.relative_results,
+            tree_xattrs: self.tree_xattrs,
+            palette: self.palette.key().to_string(),
+            update_rate: self.update_rate.key().to_string(),
+            read_buffer_kib: self.read_buffer.kib(),
+            history_size: self.history_limit.0,
+            network: self.network,
+            algorithm: self.algorithm.key().to_string(),
+            usage_metrics: self.usage.is_some(),
+        };
+        self.save_config()
+    }
+
+    // Options that hold between runs, grouped on a page of their own
+    fn settings_view(&self) -> Element<'_, Message> {
+        let heading = |label: &'static str| text(label).size(18);
+        let policy = policy::get();
+        let history: Element<'_, Message> = if policy.disable_history {
+            text("No history (policy)").size(14).into()
+        } else {
+            pick_list(&history::LIMITS[..], Some(self.history_limit), Message::HistoryLimitSelected).text_size(14).into()
+        };
+        let network_label = if policy.offline { "Allow webhooks, SMTP email and hashing over SSH (turned off by policy)" } else { "Allow webhooks, SMTP email and hashing over SSH" };
+        let mut content = column![
+            row![
+                text("Settings").size(28),
+                button(text("Done").size(14)).on_press(Message::SettingsToggled).style(theme::Button::Primary),
+            ]
+            .spacing(20)
+            .align_items(iced::Alignment::Center),
+        ]
+        .push_maybe(policy.describe().map(|rules| text(format!("Set by your administrator's policy, {}", rules)).size(14)))
+        .push(column![
+            heading("Display"),
+            row![
+                checkbox("Uppercase HEX", self.uppercase).on_toggle(Message::UppercaseToggled),
+                pick_list(&hex_group::GROUPINGS[..], Some(self.hex_grouping), Message::HexGroupingSelected).text_size(14),
+                pick_list(&palette::PALETTES[..], Some(self.palette), Message::PaletteSelected).text_size(14),
+                pick_list(&power::UPDATE_RATES[..], Some(self.update_rate), Message::UpdateRateSelected).text_size(14),
+            ]
+            .spacing(20)
+            .align_items(iced::Alignment::Center),
+            heading("Hashing"),
+            row![
+                checkbox("Auto hash on select", self.auto_hash).on_toggle(Message::AutoHashToggled),
+                pick_list(&read_buffer::SIZES[..], Some(self.read_buffer), Message::ReadBufferSelected).text_size(14),
+                history,
+                checkbox("Remember digests per folder", self.folder_choices_mode).on_toggle(Message::FolderChoicesToggled),
+            ]
+            .spacing(20)
+            .align_items(iced::Alignment::Center),
+            row![
+                text("Default algorithm:").size(14),
+                pick_list(allowed_algorithms(), Some(self.algorithm), Message::AlgorithmSelected).text_size(14),
+                button(text("Add to file manager").size(14)).on_press(Message::ShellIntegrationPressed(true)).style(theme::Button::Secondary),
+                button(text("Remove from file manager").size(14)).on_press(Message::ShellIntegrationPressed(false)).style(theme::Button::Secondary),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+            heading("Queue and saved results"),
+            row![
+                checkbox("Skip locked files", self.skip_unopenable).on_toggle(Message::SkipUnopenableToggled),
+                pick_list(&jobs::TIMEOUTS[..], Some(self.job_timeout), Message::JobTimeoutSelected).text_size(14),
+                checkbox("Relative paths in saved results", self.relative_results).on_toggle(Message::RelativeResultsToggled),
+            ]
+            .spacing(20)
+            .align_items(iced::Alignment::Center),
+            heading("Computed alongside SHA-256"),
+            row![
+                checkbox("FastCDC chunks", self.chunk_mode).on_toggle(Message::ChunkModeToggled),
+                checkbox("rsync signature", self.signature_mode).on_toggle(Message::SignatureToggled),
+                checkbox("Segment digests", self.segment_mode).on_toggle(Message::SegmentsToggled),
+                pick_list(&segments::SEGMENT_SIZES[..], self.segment_size, Message::SegmentSizeSelected).text_size(14),
+                checkbox("IPFS CID", self.cid_mode).on_toggle(Message::CidToggled),
+            ]
+            .spacing(20)
+            .align_items(iced::Alignment::Center),
+            row![
+                checkbox("Azure/GCS checksums", self.cloud_mode).on_toggle(Message::CloudToggled),
+                checkbox("Authenticode hash (PE)", self.authenticode_mode).on_toggle(Message::AuthenticodeToggled),
+                checkbox("Metadata digest", self.metadata_mode).on_toggle(Message::MetadataDigestToggled),
+                pick_list(&meta_digest::FIELD_SETS[..], self.metadata_fields, Message::MetadataFieldsSelected).text_size(14),
+            ]
+            .spacing(20)
+            .align_items(iced::Alignment::Center),
+            heading("Files and containers"),
+            row![
+                checkbox("Zero-aware (sparse)", self.zero_aware_mode).on_toggle(Message::ZeroAwareToggled),
+                checkbox("Binary diff on mismatch", self.binary_diff).on_toggle(Message::BinaryDiffToggled),
+                checkbox("Check archive CRCs", self.archive_check).on_toggle(Message::ArchiveCheckToggled),
+                checkbox("Archive content digest", self.archive_digest_mode).on_toggle(Message::ArchiveDigestToggled),
+            ]
+            .spacing(20)
+            .align_items(iced::Alignment::Center),
+            row![
+                checkbox("Hash files inside ISO/UDF", self.disc_contents).on_toggle(Message::DiscContentsToggled),
+                checkbox("Hash partitions (GPT/MBR)", self.partition_mode).on_toggle(Message::PartitionsToggled),
+                checkbox("Hash VHD/VHDX/DMG payload", self.payload_mode).on_toggle(Message::PayloadToggled),
+                checkbox("Tree digest covers xattrs/ADS", self.tree_xattrs).on_toggle(Message::TreeXattrsToggled),
+            ]
+            .spacing(20)
+            .align_items(iced::Alignment::Center),
+            heading("Verification"),
+            row![
+                checkbox("Stop at first failure", self.verify_stop_on_failure).on_toggle(Message::VerifyStopOnFailureToggled),
+                checkbox("/ and \\ are the same", self.matching.separators).on_toggle(Message::MatchSeparatorsToggled),
+                checkbox("Ignore case", self.matching.ignore_case).on_toggle(Message::MatchIgnoreCaseToggled),
+                checkbox("NFC = NFD names", self.matching.unicode).on_toggle(Message::MatchUnicodeToggled),
+            ]
+            .spacing(20)
+            .align_items(iced::Alignment::Center),
+            heading("Network"),
+            checkbox(network_label, self.network_allowed()).on_toggle_maybe((!policy.offline).then_some(Message::NetworkToggled)),
+            heading("Usage"),
+            row![
+                checkbox("Count which features and algorithms I use, on this machine only", self.usage.is_some()).on_toggle(Message::UsageMetricsToggled),
+                button(text("Show usage").size(14)).on_press_maybe(self.usage.is_some().then_some(Message::UsageToggled)).style(theme::Button::Secondary),
+            ]
+            .spacing(20)
+            .align_items(iced::Alignment::Center),
+            heading("Post-job actions"),
+            post_actions_panel(&self.config),
+            row![
+                button(text("Export settings...").size(14)).on_press(Message::ExportSettingsPressed).style(theme::Button::Secondary),
+                button(text("Import settings...").size(14)).on_press(Message::ImportSettingsPressed).style(theme::Button::Secondary),
+            ]
+            .spacing(10),
+        ]
+        .spacing(12));
+        if let Some(status) = &self.status_note {
+            content = content.push(text(status.clone()).size(14).style(theme::Text::Color(palette::color(Status::Warn).into())));
+        }
+        let content = content.spacing(12).padding(16).max_width(900).align_items(iced::Alignment::Start);
+
+        scrollable(container(content).width(Length::Fill))
+            .height(Length::Fill)
+            .into()
+    }
+
+    // The main result followed by every finished job, for printed and exported reports
+    // sha256sum lines for the main result and finished background jobs; sampled digests are left out
+    fn result_digests(&self) -> Vec<(PathBuf, String)> {
+        let main = Some((self.last_path.as_ref(), hex::encode(&self.digest))).filter(|_| !self.digest.is_empty() && !self.last_quick && !self.is_hashing);
+        let jobs = self.background.iter().filter_map(|j| j.outcome.as_ref()?.as_ref().ok()).filter(|hr| !hr.quick).map(|hr| (hr.path.as_ref(), hr.hex.clone()));
+        main.into_iter().chain(jobs).filter_map(|(path, hex)| Some((path?.clone(), hex))).collect()
+    }
+
+    // Full paths, or relative to `base` for files below it
+    fn result_lines(&self, base: Option<&Path>) -> String {
+        let name = |path: &Path| match base {
+            Some(base) => path_norm::relative_to(path, base).display().to_string(),
+            None => path.display().to_string(),
+        };
+        self.result_digests().iter().map(|(path, hex)| post_actions::sha256sum_line(hex, &name(path))).collect()
+    }
+
+    fn report_rows(&self) -> Vec<report::Row> {
+        let mut rows = Vec::new();
+        if let Some(e) = &self.error {
+            let path = e.path.as_ref().map(|p| p.display().to_string()).unwrap_or_else(|| self.path_input.trim().to_string());
+            rows.push(report::Row { path, status: "FAILED".to_string(), sha256: None, bytes: None, note: Some(e.message()) });
+        } else if !self.digest.is_empty() {
+            let path = match &self.last_path {
+                Some(p) => p.display().to_string(),
+                None => format!("{} files concatenated", self.last_concat_count),
+            };
+            let status = match &self.comparison {
+                Some(Comparison::Changed(when)) => format!("CHANGED since {}", when),
+                Some(Comparison::Unchanged(when)) => format!("Unchanged since {}", when),
+                None => "OK".to_string(),
+            };
+            rows.push(report::Row {
+                path,
+                status,
+                sha256: Some(self.hex_digest()),
+                bytes: self.last_bytes,
+                note: self.flags_note(&hex::encode(&self.digest), self.last_quick),
+            });
+        }
+        if let Some(run) = &self.copy_run {
+            for file in &run.done {
+                let status = match &file.error {
+                    Some(_) => "COPY FAILED",
+                    None if file.verified() => "Copied, verified",
+                    None => "COPY MISMATCH",
+                };
+                rows.push(report::Row {
+                    path: format!("{} -> {}", file.source.display(), file.target.display()),
+                    status: status.to_string(),
+                    sha256: file.source_hex.clone(),
+                    bytes: Some(file.bytes),
+                    note: file.error.clone().or_else(|| file.mismatch().then(|| format!("Destination SHA-256 {}", file.target_hex.clone().unwrap_or_default()))),
+                });
+            }
+        }
+        if let Some(run) = &self.folder_compare {
+            for item in &run.done {
+                let note = match &item.status {
+                    folder_diff::Status::Failed(e) => Some(e.clone()),
+                    folder_diff::Status::Different => Some(match (&item.b_hex, item.a_bytes, item.b_bytes) {
+                        (Some(hex), _, _) => format!("B SHA-256 {}", hex),
+                        (None, Some(a), Some(b)) => format!("Sizes differ: A {} bytes, B {} bytes", a, b),
+                        _ => String::new(),
+                    }),
+                    _ => None,
+                };
+                let root = if item.status == folder_diff::Status::OnlyInB { &run.b } else { &run.a };
+                rows.push(report::Row {
+                    path: root.join(&item.relative).display().to_string(),
+                    status: item.label().to_string(),
+                    sha256: item.a_hex.clone(),
+                    bytes: item.a_bytes.or(item.b_bytes),
+                    note,
+                });
+            }
+        }
+        if let Some(diff) = &self.snapshot_diff {
+            let row = |path: &PathBuf, status: &str, sha256: &str, note: Option<String>| report::Row {
+                path: path.display().to_string(),
+                status: status.to_string(),
+                sha256: Some(sha256.to_string()),
+                bytes: None,
+                note,
+            };
+            rows.extend(diff.changed.iter().map(|(p, old, new)| row(p, "CHANGED", new, Some(format!("Was {}", old)))));
+            rows.extend(diff.removed.iter().map(|(p, hex)| row(p, "REMOVED", hex, None)));
+            rows.extend(diff.added.iter().map(|(p, hex)| row(p, "ADDED", hex, None)));
+        }
+        if let Some(run) = &self.verify_run {
+            for item in &run.done {
+                let (actual, mut note) = match &item.status {
+                    verify::Status::Ok => (Some(item.entry.expected.clone()), None),
+                    verify::Status::Mismatch(actual) => (Some(actual.clone()), Some(format!("Expected {}", item.entry.expected))),
+                    verify::Status::AttributesChanged(actual) => (
+                        Some(item.entry.expected.clone()),
+                        Some(format!("Extended attributes {}, expected {}", actual, item.entry.xattrs.as_deref().unwrap_or_default())),
+                    ),
+                    verify::Status::Missing => (None, Some(format!("Expected {}", item.entry.expected))),
+                    verify::Status::Failed(e) => (None, Some(e.clone())),
+                };
+                if let Some(extra) = &item.note {
+                    note = Some(note.map_or(extra.clone(), |n| format!("{}; {}", n, extra)));
+                }
+                if run.is_acknowledged(item) {
+                    note = Some(note.map_or("Acknowledged".to_string(), |n| format!("{}; acknowledged", n)));
+                }
+                // The SHA-256 column only holds SHA-256; other digests go into the notes
+                let sha256 = if run.algorithm == "SHA-256" {
+                    actual
+                } else {
+                    if let Some(digest) = actual.filter(|_| item.ok()) {
+                        note = Some(format!("{} {}", run.algorithm, digest));
+                    }
+                    None
+                };
+                rows.push(report::Row { path: item.entry.path.display().to_string(), status: item.label().to_string(), sha256, bytes: item.bytes, note });
+            }
+        }
+        for job in &self.background {
+            let row = match &job.outcome {
+                Some(Ok(hr)) => report::Row {
+                    path: job.path.clone(),
+                    status: match &job.comparison {
+                        Some(Comparison::Changed(when)) => format!("CHANGED since {}", when),
+                        Some(Comparison::Unchanged(when)) => format!("Unchanged since {}", when),
+                        None => "OK".to_string(),
+                    },
+                    sha256: Some(if self.uppercase { hr.hex.to_uppercase() } else { hr.hex.clone() }),
+                    bytes: Some(hr.bytes),
+                    note: self.flags_note(&hr.hex, hr.quick),
+                },
+                Some(Err(e)) => report::Row {
+                    path: job.path.clone(),
+                    status: match e.kind {
+                        ErrorKind::Cancelled => "Cancelled",
+                        ErrorKind::TimedOut => "TIMED OUT",
+                        _ => "FAILED",
+                    }
+                    .to_string(),
+                    sha256: None,
+                    bytes: None,
+                    note: Some(e.message()),
+                },
+                None => continue,
+            };
+            rows.push(row);
+        }
+        rows
+    }
+
+    // Quick-sample warning and reference list matches for one digest
+    fn flags_note(&self, hex: &str, quick: bool) -> Option<String> {
+        if quick {
+            return Some("Quick triage sample, not a full hash".to_string());
+        }
+        let mut flags = Vec::new();
+        if self.blocklist.get().is_some_and(|set| set.contains(hex)) {
+            flags.push("BLOCKLISTED");
+        }
+        match self.known.get().map(|set| set.contains(hex)) {
+            Some(true) => flags.push("KNOWN"),
+            Some(false) => flags.push("UNKNOWN"),
+            None => {}
+        }
+        (!flags.is_empty()).then(|| flags.join(", "))
+    }
+
+    fn list_slot(&mut self, kind: ListKind) -> &mut ListSlot {
+        match kind {
+            ListKind::Known => &mut self.known,
+            ListKind::Blocklist => &mut self.blocklist,
+        }
+    }
+
+    // Saved location of the list, reloaded on start
+    fn list_path(&mut self, kind: ListKind) -> &mut String {
+        match kind {
+            ListKind::Known => &mut self.config.known_hashes,
+            ListKind::Blocklist => &mut self.config.blocklist,
+        }
+    }
+
+    fn save_config(&mut self) -> Command<Message> {
+        if let Err(e) = self.config.save() {
+            self.status_note = Some(format!("{:#}", e));
+        }
+        Command::none()
+    }
+
+    // Applies the verify settings and what was acknowledged for this list before
+    fn start_verify(&mut self, run: verify::VerifyRun) {
+        let store = acknowledged::Acknowledged::load();
+        let acknowledged = run.lists.iter().flat_map(|(list, _)| store.for_manifest(list)).collect();
+        self.verify_run = Some(run.stop_on_failure(self.verify_stop_on_failure).with_acknowledged(acknowledged));
+    }
+
+    // Writes the unfinished queue (and a leftover one not yet answered) when it changed since the last write
+    // Anything a Tick polls for: workers, result channels and lists being loaded
+    fn busy(&self) -> bool {
+        let pending_rx = self.worker_rx.is_some()
+            || !self.notice_rx.is_empty()
+            || self.compare_rx.is_some()
+            || self.archive_rx.is_some()
+            || self.archive_digest_rx.is_some()
+            || self.copy_rx.is_some()
+            || self.expected_rx.is_some()
+            || self.sidecars_rx.is_some();
+        pending_rx
+            || self.is_hashing
+            || self.batch_started.is_some()
+            || self.background.iter().any(|j| j.is_running() || j.waiting)
+            || self.copy_run.as_ref().is_some_and(|r| r.is_running())
+            || self.folder_compare.as_ref().is_some_and(|r| r.is_running())
+            || self.verify_run.as_ref().is_some_and(|r| r.is_running())
+            || self.manifest_editor.as_ref().is_some_and(|e| e.is_busy())
+            || self.remote_run.as_ref().is_some_and(|r| r.is_running())
+            || self.disc_run.as_ref().is_some_and(|r| r.result.is_none())
+            || self.partition_run.as_ref().is_some_and(|r| r.result.is_none())
+            || self.ewf_run.as_ref().is_some_and(|r| r.result.is_none())
+            || self.acquisition.as_ref().is_some_and(|r| r.result.is_none())
+            || self.known.is_loading()
+            || self.blocklist.is_loading()
+    }
+
+    // Whether the Tick subscription runs: work, a timer waiting to fire, or recent activity
+    fn needs_tick(&self) -> bool {
+        self.busy()
+            || self.auto_hash_due.is_some()
+            || self.path_check_due.is_some()
+            || self.placement_due.is_some()
+            || self.usage.as_ref().is_some_and(usage::Usage::is_pending)
+            || self.staging.is_some()
+            || self.dropped_at.is_some()
+            || self.quit_when_done
+            || self.active_at.is_none_or(|at| at.elapsed() < TICK_GRACE)
+    }
+
+    // What closing the window would cut short, for the confirmation
+    fn work_in_flight(&self) -> Vec<String> {
+        let mut work = Vec::new();
+        if self.is_hashing {
+            work.push(if self.is_following { "a followed file" } else { "a hash" }.to_string());
+        }
+        let jobs = self.background.iter().filter(|j| j.is_running() || j.waiting).count();
+        if jobs > 0 {
+            work.push(format!("{} background or queued job(s)", jobs));
+        }
+        if self.verify_run.as_ref().is_some_and(|r| r.is_running()) {
+            work.push("a verification".to_string());
+        }
+        if self.copy_run.as_ref().is_some_and(|r| r.is_running()) {
+            work.push("a verified copy".to_string());
+        }
+        if self.folder_compare.as_ref().is_some_and(|r| r.is_running()) {
+            work.push("a folder comparison".to_string());
+        }
+        if self.remote_run.as_ref().is_some_and(|r| r.is_running()) {
+            work.push("a remote hash".to_string());
+        }
+        if self.staging.as_ref().is_some_and(|w| w.is_hashing()) {
+            work.push("a checksum list update".to_string());
+        }
+        work
+    }
+
+    fn persist_queue(&mut self) {
+        let leftover = self.restored_queue.iter().flat_map(|q| q.paths.iter().cloned());
+        let pending: Vec<String> = leftover.chain(self.background.iter().filter(|j| j.queued && j.outcome.is_none()).map(|j| j.path.clone())).collect();
+        if pending != self.saved_queue {
+            if let Err(e) = saved_queue::save(&pending) {
+                self.status_note = Some(format!("{:#}", e));
+            }
+            self.saved_queue = pending;
+        }
+    }
 
-        let meta = meta_info(self.is_hashing, self.last_elapsed, self.last_bytes.as_ref(), self.last_path.as_ref(), self.error.as_ref());
+    // Queues files picked together, after confirming batches estimated to take hours
+    fn queue_batch(&mut self, paths: Vec<PathBuf>) {
+        if paths.is_empty() {
+            return;
+        }
+        let bytes: u64 = paths.iter().filter_map(|p| expected_size(p)).sum();
+        let estimate = self.history.estimate(bytes);
+        let mut plan = format!("{} files, {}", paths.len(), human_bytes(bytes as f64));
+        if let Some(eta) = estimate {
+            plan.push_str(&format!(", about {}", human_duration(eta)));
+        }
+        // Multi-hour batches are confirmed before anything is queued
+        if estimate.is_some_and(|eta| eta > history::CONFIRM_OVER) {
+            let choice = rfd::MessageDialog::new()
+                .set_title("Long batch")
+                .set_description(format!("Hashing {} at the throughput of recent hashes. Queue them?", plan))
+                .set_buttons(rfd::MessageButtons::OkCancel)
+                .show();
+            if choice != rfd::MessageDialogResult::Ok {
+                return;
+            }
+        }
+        for path in paths {
+            self.enqueue(path);
+        }
+        self.status_note = Some(format!("Queued {}", plan));
+    }
 
-        let content = column![title, header, toggles, drag_hint, outputs, meta]
-            .spacing(16)
-            .padding(16)
-            .max_width(900)
-            .align_items(iced::Alignment::Start);
+    // Ticks the digests last used for the file's folder, when remembering is on
+    fn restore_folder_choice(&mut self, file: &Path) {
+        if !self.folder_choices_mode {
+            return;
+        }
+        let Some((dir, choice)) = self.folder_choices.lookup(file) else {
+            return;
+        };
+        let choice = choice.clone();
+        self.chunk_mode = choice.chunks;
+        self.signature_mode = choice.rsync_signature;
+        self.segment_mode = choice.segment_size.is_some();
+        if let Some(size) = choice.segment_size {
+            self.segment_size = Some(SegmentSize(size));
+        }
+        self.cid_mode = choice.ipfs_cid;
+        self.cloud_mode = choice.cloud;
+        self.authenticode_mode = choice.authenticode;
+        self.metadata_mode = choice.metadata.is_some();
+        if let Some(fields) = choice.metadata.as_deref().and_then(meta_digest::Fields::parse) {
+            self.metadata_fields = Some(fields);
+        }
+        self.status_note = Some(format!("{} as last used in {}", choice.describe(), dir.display()));
+    }
 
-        scrollable(container(content).width(Length::Fill))
-            .height(Length::Fill)
-            .into()
+    fn enqueue(&mut self, path: PathBuf) {
+        // A queued file must finish on its own, so follow mode does not apply
+        let mut opts = HashOptions { follow: false, ..self.hash_options() };
+        if let Some((_, choice)) = self.folder_choices_mode.then(|| self.folder_choices.lookup(&path)).flatten() {
+            opts = opts.with_choice(choice);
+        }
+        self.next_job_id += 1;
+        self.background.push(BackgroundJob::queued(self.next_job_id, path.to_string_lossy().to_string(), opts));
+    }
+
+    // Cancels the running job and stops listening for its result
+    fn abandon_worker(&mut self) {
+        if let Some(flag) = &self.cancel_flag {
+            flag.store(true, Ordering::Relaxed);
+        }
+        self.is_hashing = false;
+        self.is_following = false;
+        self.stalled = false;
+        self.progress_total = None;
+        self.progress_processed = 0;
+        self.worker_rx = None;
+    }
+
+    // Right away, where a path is picked, dropped or restored rather than typed
+    fn check_path(&mut self) {
+        self.path_check_due = None;
+        self.path_status = PathStatus::check(&self.path_input);
+        self.path_checked = self.path_input.clone();
+    }
+
+    fn start_hashing(&mut self, path: String, prev: Option<String>) {
+        self.auto_hash_due = None;
+        self.prev_path_before_hash = prev.or_else(|| Some(self.path_input.clone()));
+        let opts = self.hash_options();
+        if self.folder_choices_mode {
+            if let Err(e) = self.folder_choices.remember(Path::new(&path), opts.choice()) {
+                self.status_note = Some(format!("{:#}", e));
+            }
+        }
+        let quick = opts.quick;
+        // Determine total size if possible (for progress); a followed file keeps growing, so it has none
+        let total = if opts.follow {
+            None
+        } else {
+            let container = (opts.payload && !quick).then(|| vdisk::payload_size(Path::new(&path))).flatten();
+            container.or_else(|| expected_size(Path::new(&path)).map(|len| if quick { quick_sample_len(len) } else { len }))
+        };
+        let w = self.begin_worker(total, opts.follow);
+        spawn_file_hash(path, opts, w);
+    }
+
+    // Plain full-content hash done by an elevated helper process; options that need the read pass are not applied
+    fn start_elevated_hashing(&mut self, path: String) {
+        self.prev_path_before_hash = Some(self.path_input.clone());
+        // Still logged and restricted as forensic, though the helper cannot keep the access time
+        let opts = HashOptions { forensic: self.forensic_mode, ..HashOptions::default() };
+        let w = self.begin_worker(None, false);
+        // The helper reports no progress while it runs (and may sit at the UAC prompt), so it cannot be judged stalled
+        self.stall_watch = false;
+
+        thread::spawn(move || {
+            let started = Instant::now();
+            let computed = elevate::hash_elevated(Path::new(&path), &w.cancel).map(|(hex, b64, bytes)| {
+                w.progress.store(bytes, Ordering::Relaxed);
+                ((hex, b64, bytes, Some(PathBuf::from(&path))), Extras::default())
+            });
+            let _ = w.tx.send((w.token, into_hash_result(computed, started, opts, Some(Path::new(&path)))));
+        });
+    }
+
+    fn start_concat_hashing(&mut self) {
+        if self.concat_files.is_empty() || self.is_hashing {
+            return;
+        }
+        self.prev_path_before_hash = Some(self.path_input.clone());
+        let paths = self.concat_files.clone();
+        // Sampling and following are per-file notions, so they do not apply to a concatenation
+        let opts = HashOptions { quick: false, follow: false, ..self.hash_options() };
+        let total = paths.iter().map(|p| std::fs::metadata(p).map(|m| m.len())).sum::<std::io::Result<u64>>().ok();
+        let w = self.begin_worker(total, false);
+
+        thread::spawn(move || {
+            let started = Instant::now();
+            let computed = compute_sha256_concat_progress(&paths, w.progress, w.cancel, opts);
+            let mut result = into_hash_result(computed, started, opts, None);
+            if let Ok(hr) = result.as_mut() {
+                hr.concat_count = paths.len();
+            }
+            let _ = w.tx.send((w.token, result));
+        });
     }
 }
 
-fn labeled_value<'a>(label: &str, value: &str, copy_msg: Message, copy_label: &str, disabled: bool) -> Element<'a, Message> {
-    let label_widget = text(label).size(16);
-    let value_widget = text(if value.is_empty() { "-" } else { value })
-        .size(15)
-        .width(Length::Fill);
+// `PathStatus::check` on a thread of its own: a hung mount holds up that thread, not the window or the executor
+fn check_path_later(input: String) -> Command<Message> {
+    let (tx, rx) = iced::futures::channel::oneshot::channel();
+    let path = input.clone();
+    thread::spawn(move || {
+        let _ = tx.send(PathStatus::check(&path));
+    });
+    Command::perform(rx, move |status| Message::PathChecked(input, status.unwrap_or(PathStatus::Missing)))
+}
 
-    let copy_btn = if value.is_empty() || disabled {
-        button(text("Copy")).style(theme::Button::Secondary)
+// Hashes one file on a worker thread and sends the result back through `w.tx`
+fn spawn_file_hash(path: String, opts: HashOptions, w: WorkerHandles) {
+    thread::spawn(move || {
+        let result = hash_file_now(&path, opts, w.progress, w.cancel, w.stop);
+        let _ = w.tx.send((w.token, result));
+    });
+}
+
+// The hash itself, on the calling thread
+fn hash_file_now(path: &str, opts: HashOptions, progress: Arc<AtomicU64>, cancel: Arc<AtomicBool>, stop: Arc<AtomicBool>) -> std::result::Result<HashResult, HashError> {
+    let started = Instant::now();
+    // Chunk lists and signatures describe the full content, so quick mode skips them
+    let computed = if opts.quick {
+        compute_sha256_quick_progress(path, progress, cancel, opts.forensic)
     } else {
-        button(text(copy_label)).on_press(copy_msg).style(theme::Button::Secondary).width(Length::Fixed(110.0))
+        compute_sha256_file_progress(path, progress, cancel, opts.follow.then_some(stop), opts)
     };
-
-    row![
-        container(label_widget)
-            .width(Length::Fixed(200.0))
-            .align_x(Horizontal::Left)
-            .align_y(Vertical::Center),
-        container(value_widget).padding(10).width(Length::Fill),
-        copy_btn,
-    ]
-    .spacing(10)
-    .align_items(iced::Alignment::Center)
-    .into()
+    into_hash_result(computed, started, opts, Some(Path::new(path)))
 }
 
-fn meta_info(
-    is_hashing: bool,
-    elapsed: Option<Duration>,
-    bytes: Option<&u64>,
-    path: Option<&PathBuf>,
-    error: Option<&String>,
-) -> Element<'static, Message> {
-    let mut parts: Vec<Element<'static, Message>> = Vec::new();
-    if let Some(p) = path {
-        let s = format!("{}", p.display());
-        parts.push(text(s).size(14).into());
-    }
-    if let Some(e) = error {
-        parts.push(text(format!("{}", e)).style(theme::Text::Color([1.0, 0.5, 0.5].into())).into());
-    } else {
-        if let (Some(el), Some(b)) = (elapsed, bytes) {
-            let secs = el.as_secs_f64();
-            let speed = if secs > 0.0 { (*b as f64) / secs } else { 0.0 };
-            let speed_human = human_bytes(speed);
-            let b_human = human_bytes(*b as f64);
-            parts.push(text(format!("{} • {} • {}/s", human_duration(el), b_human, speed_human)).size(14).into());
-        } else if is_hashing {
-            parts.push(text("Hashing...").size(14).into());
+// Hashes `b` and checks it against the digest of `a`; on a mismatch the optional binary diff
+// explains where the files part ways
+fn compare_files(a: &Path, hex_a: &str, b: &Path, binary_diff: bool) -> String {
+    let progress = Arc::new(AtomicU64::new(0));
+    let cancel = Arc::new(AtomicBool::new(false));
+    match compute_sha256_file_progress(&b.to_string_lossy(), progress, cancel.clone(), None, HashOptions::default()) {
+        Err(e) => format!("Compare failed: {:#}", e),
+        Ok(((hex_b, _, _, _), _)) if hex_b == hex_a => format!("Identical to {} (same SHA-256)", b.display()),
+        Ok(((hex_b, _, _, _), _)) => {
+            let mut note = format!("DIFFERENT from {} (SHA-256 {})", b.display(), hex_b);
+            if binary_diff {
+                match bindiff::compare(a, b, &cancel) {
+                    Ok(summary) => note.push_str(&format!(": {}", summary.describe())),
+                    Err(e) => note.push_str(&format!("; binary diff failed: {:#}", e)),
+                }
+            }
+            note
         }
     }
+}
 
-    column(parts)
-        .spacing(6)
-        .padding(6)
-        .into()
+// `<file>.sha256` in the scratch directory, for handing to other apps without touching the file's folder
+fn write_checksum_file(path: &Path, hex: &str) -> Result<PathBuf> {
+    let name = path.file_name().context("No file name")?.to_string_lossy().to_string();
+    let dir = storage::scratch_dir();
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+    let target = dir.join(format!("{}.sha256", name));
+    std::fs::write(&target, post_actions::sha256sum_line(hex, &name)).with_context(|| format!("Failed to write {}", target.display()))?;
+    Ok(target)
 }
 
-fn human_duration(d: Duration) -> String {
-    let ms_total = d.as_millis() as f64;
-    if ms_total < 1000.0 {
-        return format!("{} ms", ms_total as u128);
-    }
-    let s_total = d.as_secs_f64();
-    if s_total < 60.0 {
-        return format!("{:.2} s", s_total);
-    }
-    let m_total = s_total / 60.0;
-    if m_total < 60.0 {
-        return format!("{:.2} min", m_total);
-    }
-    let h_total = m_total / 60.0;
-    if h_total < 24.0 {
-        return format!("{:.2} h", h_total);
+// Chain-of-custody entry for a job hashed in forensic mode; returns a note when it could not be written
+fn log_custody(forensic_mode: bool, result: std::result::Result<&HashResult, &HashError>, fallback_path: Option<&str>) -> Option<String> {
+    let fallback = fallback_path.filter(|p| !p.is_empty()).map(Path::new);
+    let appended = match result {
+        Ok(hr) if hr.forensic => {
+            let mut notes = vec![if hr.atime_kept { "access time preserved" } else { "access time not guaranteed" }];
+            if hr.quick {
+                notes.push("quick triage sample, not a full hash");
+            }
+            if hr.followed {
+                notes.push("followed growing file");
+            }
+            let detail = notes.join("; ");
+            custody::append(custody::Record {
+                action: "hash",
+                path: hr.path.as_deref().or(fallback),
+                sha256: Some(&hr.hex),
+                bytes: Some(hr.bytes),
+                detail: Some(&detail),
+            })
+        }
+        Err(e) if forensic_mode => {
+            let action = match e.kind {
+                ErrorKind::Cancelled => "hash-cancelled",
+                ErrorKind::TimedOut => "hash-timed-out",
+                _ => "hash-failed",
+            };
+            custody::append(custody::Record { action, path: e.path.as_deref().or(fallback), detail: Some(&e.detail), ..Default::default() })
+        }
+        _ => return None,
+    };
+    appended.err().map(|e| format!("{:#}", e))
+}
+
+// Webhook notice for a finished job; cancelled jobs are not reported
+fn report_job(
+    url: &str,
+    notices: &mut Vec<Receiver<String>>,
+    result: std::result::Result<&HashResult, &HashError>,
+    comparison: Option<&Comparison>,
+    fallback_path: Option<&str>,
+) {
+    if url.trim().is_empty() {
+        return;
     }
-    let d_total = h_total / 24.0;
-    format!("{:.2} d", d_total)
+    let report = match result {
+        Ok(hr) => {
+            let path = hr.path.as_ref().map(|p| p.display().to_string()).or_else(|| fallback_path.map(str::to_string));
+            let status = if matches!(comparison, Some(Comparison::Changed(_))) { "changed" } else { "ok" };
+            webhook::JobReport {
+                event: "job",
+                text: format!("rust-hash: {} {} ({})", path.as_deref().unwrap_or("concatenation"), hr.hex, status),
+                path,
+                sha256: Some(hr.hex.clone()),
+                base64: Some(hr.base64.clone()),
+                bytes: Some(hr.bytes),
+                status,
+                duration_ms: Some(hr.elapsed.as_millis() as u64),
+                error: None,
+            }
+        }
+        Err(e) if e.kind == ErrorKind::Cancelled => return,
+        Err(e) => {
+            let path = e.path.as_ref().map(|p| p.display().to_string()).or_else(|| fallback_path.map(str::to_string));
+            webhook::JobReport {
+                event: "job",
+                text: format!("rust-hash: FAILED {}: {}", path.as_deref().unwrap_or("job"), e.message()),
+                path,
+                sha256: None,
+                base64: None,
+                bytes: None,
+                status: if e.kind == ErrorKind::TimedOut { "timed-out" } else { "failed" },
+                duration_ms: None,
+                error: Some(e.detail.clone()),
+            }
+        }
+    };
+    webhook::post(url, &report, notice_channel(notices));
 }
 
-fn human_bytes(b: f64) -> String {
-    const UNITS: [&str; 6] = ["B", "KB", "MB", "GB", "TB", "PB"];
-    let base = 1000.0;
-    let mut val = if b < 0.0 { 0.0 } else { b };
-    let mut idx = 0;
-    while val >= base && idx < UNITS.len() - 1 {
-        val /= base;
-        idx += 1;
+// Sender handed to one background thread; Tick reads its receiver until the thread drops it
+fn notice_channel(receivers: &mut Vec<Receiver<String>>) -> Sender<String> {
+    let (tx, rx) = mpsc::channel();
+    receivers.push(rx);
+    tx
+}
+
+// Post-job actions only apply to full-content digests of a single file
+fn run_post_actions(actions: &PostActions, hr: &HashResult, comparison: Option<&Comparison>) -> Option<post_actions::Outcome> {
+    if !actions.any() || hr.quick || hr.followed {
+        return None;
     }
-    if idx == 0 {
-        format!("{:.0} {}", val, UNITS[idx])
+    let restricted;
+    let actions = if hr.forensic {
+        restricted = forensic::restrict(actions);
+        &restricted
     } else {
-        format!("{:.2} {}", val, UNITS[idx])
+        actions
+    };
+    let path = hr.path.as_ref()?;
+    let digest = post_actions::Digest {
+        path,
+        hex: &hr.hex,
+        base64: &hr.base64,
+        bytes: hr.bytes,
+        changed: matches!(comparison, Some(Comparison::Changed(_))),
+    };
+    Some(post_actions::run(actions, &digest))
+}
+
+fn post_action_summary(outcome: &post_actions::Outcome) -> Option<String> {
+    let mut notes = outcome.errors.clone();
+    if let Some(target) = &outcome.moved_to {
+        notes.insert(0, format!("Moved to {}", target.display()));
     }
+    (!notes.is_empty()).then(|| notes.join("; "))
 }
 
-// old async hash and non-progress variant removed (no longer used)
+// A finished hash for the usage counts; cancelling one is not an error
+fn count_hash(usage: Option<&mut usage::Usage>, result: std::result::Result<&HashResult, &HashError>) {
+    let Some(usage) = usage else { return };
+    match result {
+        Ok(hr) => {
+            usage.operation(if hr.quick { "Quick hash" } else { "Hash file" });
+            usage.algorithm(algo::Algorithm::Sha256);
+            if hr.cloud.is_some() {
+                usage.algorithm(algo::Algorithm::Md5);
+            }
+            if let Some((algorithm, _)) = &hr.preferred {
+                usage.algorithm(*algorithm);
+            }
+        }
+        Err(e) if e.kind == ErrorKind::Cancelled => usage.operation("Cancel hash"),
+        Err(e) => usage.error(e.reason()),
+    }
+}
 
-impl App {
-    fn next_token(&mut self) -> u64 {
-        self.is_hashing = true;
-        self.error = None;
-        self.started_at = Some(Instant::now());
-        self.token = self.token.wrapping_add(1);
-        self.token
+// Compare against the previous digest of the same file, then remember this one
+fn record_history(history: &mut History, jump_list: Option<&jump_list::JumpList>, hr: &HashResult) -> Option<Comparison> {
+    let path = hr.path.as_ref()?;
+    // A followed digest only covers a prefix of the file, so it is neither compared nor recorded
+    if hr.followed {
+        return None;
+    }
+    let comparison = history.latest_for(path, hr.quick).map(|prev| {
+        if prev.hex.eq_ignore_ascii_case(&hr.hex) {
+            Comparison::Unchanged(prev.hashed_at_display())
+        } else {
+            Comparison::Changed(prev.hashed_at_display())
+        }
+    });
+    history.record(HistoryEntry {
+        path: path.clone(),
+        hex: hr.hex.clone(),
+        bytes: hr.bytes,
+        hashed_at: history::now_timestamp(),
+        quick: hr.quick,
+        elapsed_ms: hr.elapsed.as_millis() as u64,
+    });
+    let _ = history.save();
+    if let Some(list) = jump_list {
+        list.update(history.recent_files(jump_list::MAX_RECENT));
     }
+    comparison
+}
 
-    fn start_hashing(&mut self, path: String, prev: Option<String>) {
-        let token = self.next_token();
-        self.prev_path_before_hash = prev.or_else(|| Some(self.path_input.clone()));
-        let (tx, rx): (Sender<(u64, std::result::Result<HashResult, String>)>, Receiver<_>) = mpsc::channel();
-        let progress = Arc::new(AtomicU64::new(0));
-        let cancel = Arc::new(AtomicBool::new(false));
+fn into_hash_result(
+    computed: Result<(FileDigest, Extras)>,
+    started: Instant,
+    opts: HashOptions,
+    path: Option<&Path>,
+) -> std::result::Result<HashResult, HashError> {
+    computed
+        .map(|((hex, b64, bytes, path), extras)| HashResult {
+            hex,
+            base64: b64,
+            elapsed: started.elapsed(),
+            bytes,
+            path,
+            quick: opts.quick,
+            chunks: extras.chunks,
+            signature: extras.signature,
+            followed: opts.follow,
+            concat_count: 0,
+            allocated: extras.allocated,
+            holes_skipped: extras.holes_skipped,
+            segments: extras.segments,
+            cid: extras.cid,
+            cloud: extras.cloud,
+            preferred: extras.preferred,
+            authenticode: extras.authenticode,
+            metadata_digest: extras.metadata_digest,
+            file_type: extras.file_type,
+            type_warning: extras.type_warning,
+            forensic: opts.forensic,
+            atime_kept: extras.atime_kept,
+            payload: extras.payload,
+        })
+        .map_err(|e| HashError::classify(&e, path))
+}
 
-        // Determine total size if possible (for progress)
-        let total = std::fs::metadata(&path).ok().map(|m| m.len());
-        self.progress_total = total;
-        self.progress_processed = 0;
-        self.progress_counter = Some(progress.clone());
-        self.cancel_flag = Some(cancel.clone());
-        self.worker_rx = Some(rx);
-        self.worker_token = Some(token);
+// Entry point of the elevated helper: hashes one file and writes the reply for the instance that spawned it
+fn run_elevated_helper(args: &[std::ffi::OsString]) -> i32 {
+    let (Some(path), Some(reply_path)) = (args.first(), args.get(1)) else {
+        return 2;
+    };
+    let progress = Arc::new(AtomicU64::new(0));
+    let cancel = Arc::new(AtomicBool::new(false));
+    let reply = match compute_sha256_file_progress(&path.to_string_lossy(), progress, cancel, None, HashOptions::default()) {
+        Ok(((hex, base64, bytes, _), _)) => elevate::HelperReply { hex: Some(hex), base64: Some(base64), bytes, error: None },
+        Err(e) => elevate::HelperReply { hex: None, base64: None, bytes: 0, error: Some(format!("{:#}", e)) },
+    };
+    let failed = reply.error.is_some();
+    match elevate::write_reply(Path::new(reply_path), &reply) {
+        Ok(()) if !failed => 0,
+        _ => 1,
+    }
+}
 
-        thread::spawn(move || {
-            let started = Instant::now();
-            let result: std::result::Result<HashResult, String> = compute_sha256_file_progress(&path, progress, cancel)
-                .map(|(hex, b64, bytes, path)| HashResult { hex, base64: b64, elapsed: started.elapsed(), bytes, path })
-                .map_err(|e| format!("{}", e));
-            let _ = tx.send((token, result));
-        });
+// Size used for progress: block devices are measured by seeking, other special files have none
+fn expected_size(path: &Path) -> Option<u64> {
+    match special::detect(path) {
+        None => std::fs::metadata(path).ok().map(|m| m.len()),
+        Some(SpecialFile::BlockDevice) => File::open(path).ok().and_then(|mut f| special::device_size(&mut f)),
+        Some(_) => None,
     }
 }
 
-fn compute_sha256_file_progress(path_str: &str, progress: Arc<AtomicU64>, cancel: Arc<AtomicBool>) -> Result<(String, String, u64, Option<PathBuf>)> {
+// (hex, base64, bytes, path) of a finished hash
+type FileDigest = (String, String, u64, Option<PathBuf>);
+
+// `stop` enables follow mode: EOF waits for more data until the flag is set
+fn compute_sha256_file_progress(
+    path_str: &str,
+    progress: Arc<AtomicU64>,
+    cancel: Arc<AtomicBool>,
+    stop: Option<Arc<AtomicBool>>,
+    opts: HashOptions,
+) -> Result<(FileDigest, Extras)> {
     let path = PathBuf::from(path_str);
-    let file = File::open(&path).with_context(|| format!("Failed to open file: {}", path_str))?;
+    let special = special::detect(&path);
+    if let Some(kind) = special.filter(|k| !k.hashable()) {
+        return Err(special::Unsupported { kind, path }.into());
+    }
+    // Before opening: opening an online-only file is what makes the sync client download it
+    let path_warning = filetype::path_warning(&path);
+    let (mut file, atime_kept) = open_source(&path, opts.forensic).with_context(|| format!("Failed to open file: {}", path_str))?;
     let metadata = file.metadata().ok();
-    let mut reader = BufReader::with_capacity(BUFFER_SIZE, file); // 2 MB buffer
+    let size = match special {
+        None => metadata.as_ref().map(|m| m.len()),
+        Some(SpecialFile::BlockDevice) => special::device_size(&mut file),
+        // A character device is an open-ended stream, so there is no size to measure progress against
+        Some(_) => None,
+    };
+    // A virtual disk container is read through its block map, so its payload hashes the same in any format
+    if let Some(format) = vdisk::Format::of(&path).filter(|_| opts.payload && !opts.follow && special.is_none()) {
+        let (reader, len) = vdisk::open(file, format).with_context(|| format!("Failed to read {} container: {}", format.label(), path_str))?;
+        let (hex, b64, read, mut extras) = hash_source(BufReader::with_capacity(opts.buffer_size(), reader), len, progress, cancel, None, opts)?;
+        extras.atime_kept = atime_kept;
+        extras.payload = Some(format.label());
+        extras.type_warning = path_warning.or(extras.type_warning);
+        extras.metadata_digest = opts.metadata.zip(metadata.as_ref()).map(|(fields, m)| meta_digest::digest(&path, m, fields));
+        return Ok(((hex, b64, read, Some(path)), extras));
+    }
+    let allocated = if special.is_some() { None } else { metadata.as_ref().and_then(|m| sparse::allocated_size(&path, m)) };
+    // Zero-aware mode only pays off (and only is safe) for a fixed-size file whose holes the OS can report
+    let ranges = match (opts.zero_aware && !opts.follow, size) {
+        (true, Some(len)) => sparse::data_ranges(&file, len),
+        _ => None,
+    };
+    let (hex, b64, read, mut extras) = match ranges {
+        Some(ranges) => {
+            let len = size.unwrap_or(0);
+            let holes = sparse::hole_bytes(&ranges, len);
+            let reader = sparse::SparseReader::new(file, ranges, len);
+            let (hex, b64, read, mut extras) = hash_source(reader, len, progress, cancel, stop, opts)?;
+            extras.holes_skipped = holes;
+            (hex, b64, read, extras)
+        }
+        None => {
+            let reader = BufReader::with_capacity(opts.buffer_size(), file);
+            hash_source(reader, size.unwrap_or(0), progress, cancel, stop, opts)?
+        }
+    };
+    extras.allocated = allocated;
+    extras.atime_kept = atime_kept;
+    extras.type_warning = path_warning.or(extras.type_warning);
+    extras.metadata_digest = opts.metadata.zip(metadata.as_ref()).map(|(fields, m)| meta_digest::digest(&path, m, fields));
+    let bytes = if opts.follow { read } else { size.unwrap_or(read) };
+    Ok(((hex, b64, bytes, Some(path)), extras))
+}
+
+// Digest of the files read back to back in the given order, as if they were one file
+fn compute_sha256_concat_progress(paths: &[PathBuf], progress: Arc<AtomicU64>, cancel: Arc<AtomicBool>, opts: HashOptions) -> Result<(FileDigest, Extras)> {
+    // Devices have no end to concatenate after, so only regular files are accepted here
+    if let Some((kind, path)) = paths.iter().find_map(|p| special::detect(p).map(|k| (k, p))) {
+        return Err(special::Unsupported { kind, path: path.clone() }.into());
+    }
+    let size: u64 = paths.iter().filter_map(|p| std::fs::metadata(p).ok()).map(|m| m.len()).sum();
+    let mut reader = ConcatReader { pending: paths.iter().cloned().collect(), current: None, forensic: opts.forensic, atime_kept: true };
+    let (hex, b64, read, mut extras) = hash_source(&mut reader, size, progress, cancel, None, opts)?;
+    extras.atime_kept = reader.atime_kept;
+    // Only the first file's bytes were sniffed, which says nothing about the rest
+    extras.file_type = None;
+    extras.type_warning = None;
+    Ok(((hex, b64, read, None), extras))
+}
+
+// Streams `source` through SHA-256 and the requested extras; returns (hex, base64, bytes read, extras)
+fn hash_source<R: Read>(
+    source: R,
+    size_hint: u64,
+    progress: Arc<AtomicU64>,
+    cancel: Arc<AtomicBool>,
+    stop: Option<Arc<AtomicBool>>,
+    opts: HashOptions,
+) -> Result<(String, String, u64, Extras)> {
+    let signature = opts.rsync_signature.then(|| SignatureBuilder::new(rsync_sig::block_len_for(size_hint)));
+    let segments = opts.segment_size.map(SegmentHasher::new);
+    let cid = opts.ipfs_cid.then(CidBuilder::default);
+    let cloud = opts.cloud.then(CloudHasher::default);
+    let authenticode = opts.authenticode.then(AuthenticodeHasher::default);
+    let preferred = opts.preferred.map(|a| (a, a.hasher()));
+    let mut reader =
+        ProgressReader { inner: source, total: 0, head: Vec::new(), progress, cancel: cancel.clone(), stop, signature, segments, cid, cloud, preferred, authenticode };
     let mut hasher = Sha256::new();
-    let mut buffer = vec![0u8; BUFFER_SIZE];
-    let mut total: u64 = 0;
-    loop {
+    let mut extras = Extras::default();
+    let read = if opts.chunks {
+        // FastCDC owns the read loop and feeds the whole-file hasher chunk by chunk
+        chunking::hash_chunks(&mut reader, &mut hasher).map(|chunks| extras.chunks = chunks)
+    } else {
+        hash_stream(&mut reader, &mut hasher, opts.buffer_size())
+    };
+    if let Err(e) = read {
         if cancel.load(Ordering::Relaxed) {
-            return Err(anyhow::anyhow!("CANCELLED"));
+            return Err(anyhow::anyhow!(errors::CANCELLED));
         }
+        return Err(e);
+    }
+    extras.signature = reader.signature.take().map(SignatureBuilder::finish);
+    extras.segments = reader.segments.take().map(SegmentHasher::finish).unwrap_or_default();
+    extras.cid = reader.cid.take().map(CidBuilder::finish);
+    extras.cloud = reader.cloud.take().map(CloudHasher::finish);
+    extras.preferred = reader.preferred.take().map(|(a, h)| (a, h.finish_hex()));
+    extras.authenticode = reader.authenticode.take().and_then(AuthenticodeHasher::finish);
+    extras.file_type = filetype::sniff(&reader.head);
+    extras.type_warning = filetype::content_warning(&reader.head);
+    let (hex, b64) = encode_digest(hasher.finalize().as_slice());
+    Ok((hex, b64, reader.total, extras))
+}
+
+fn hash_stream<R: Read>(reader: &mut R, hasher: &mut Sha256, buffer_size: usize) -> Result<()> {
+    let mut buffer = vec![0u8; buffer_size];
+    loop {
         let n = reader.read(&mut buffer)?;
         if n == 0 {
             break;
         }
         hasher.update(&buffer[..n]);
-        total += n as u64;
-        progress.store(total, Ordering::Relaxed);
     }
-    let digest = hasher.finalize();
-    let bytes = digest.as_slice();
+    Ok(())
+}
+
+// Number of bytes the quick triage mode actually reads for a file of `size` bytes
+fn quick_sample_len(size: u64) -> u64 {
+    size.min(2 * QUICK_SAMPLE_SIZE)
+}
+
+// Triage digest: SHA-256 over the file size followed by the first and last QUICK_SAMPLE_SIZE bytes.
+// Files that differ only in the middle collide, so matches still need a full hash.
+fn compute_sha256_quick_progress(path_str: &str, progress: Arc<AtomicU64>, cancel: Arc<AtomicBool>, forensic: bool) -> Result<(FileDigest, Extras)> {
+    let path = PathBuf::from(path_str);
+    // Sampling the tail needs a seekable file with a known end
+    let special = special::detect(&path);
+    if let Some(kind) = special.filter(|k| *k != SpecialFile::BlockDevice) {
+        return Err(special::Unsupported { kind, path }.into());
+    }
+    let (mut file, atime_kept) = open_source(&path, forensic).with_context(|| format!("Failed to open file: {}", path_str))?;
+    let size = match special {
+        Some(_) => special::device_size(&mut file).with_context(|| format!("Failed to read device size: {}", path_str))?,
+        None => file.metadata().with_context(|| format!("Failed to read metadata: {}", path_str))?.len(),
+    };
+    let mut hasher = Sha256::new();
+    hasher.update(size.to_le_bytes());
+    let head_end = size.min(QUICK_SAMPLE_SIZE);
+    let tail_start = size.saturating_sub(QUICK_SAMPLE_SIZE).max(head_end);
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut total: u64 = 0;
+    for (start, end) in [(0, head_end), (tail_start, size)] {
+        file.seek(SeekFrom::Start(start))?;
+        let mut remaining = end - start;
+        while remaining > 0 {
+            if cancel.load(Ordering::Relaxed) {
+                return Err(anyhow::anyhow!(errors::CANCELLED));
+            }
+            let want = remaining.min(BUFFER_SIZE as u64) as usize;
+            let n = file.read(&mut buffer[..want])?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buffer[..n]);
+            remaining -= n as u64;
+            total += n as u64;
+            progress.store(total, Ordering::Relaxed);
+        }
+    }
+    let (hex, b64) = encode_digest(hasher.finalize().as_slice());
+    Ok(((hex, b64, size, Some(path)), Extras { atime_kept, ..Extras::default() }))
+}
+
+// Read-only open; forensic mode also asks the OS to leave the last-access time alone.
+// Returns whether it did
+fn open_source(path: &Path, forensic: bool) -> std::io::Result<(File, bool)> {
+    if forensic {
+        forensic::open_read_only(path)
+    } else {
+        File::open(path).map(|f| (f, false))
+    }
+}
+
+// Reads several files back to back as one stream, opening each only when it is reached
+struct ConcatReader {
+    pending: VecDeque<PathBuf>,
+    current: Option<BufReader<File>>,
+    forensic: bool,
+    // Cleared as soon as one file's last-access time could not be kept
+    atime_kept: bool,
+}
+
+impl Read for ConcatReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            let reader = match self.current.as_mut() {
+                Some(r) => r,
+                None => {
+                    let Some(path) = self.pending.pop_front() else {
+                        return Ok(0);
+                    };
+                    let (file, kept) = open_source(&path, self.forensic)
+                        .map_err(|e| std::io::Error::new(e.kind(), format!("Failed to open file: {}: {}", path.display(), e)))?;
+                    self.atime_kept &= kept;
+                    self.current.insert(BufReader::with_capacity(BUFFER_SIZE, file))
+                }
+            };
+            let n = reader.read(buf)?;
+            if n == 0 {
+                self.current = None;
+                continue;
+            }
+            return Ok(n);
+        }
+    }
+}
+
+// Read adapter that publishes progress, stops on cancel and feeds the optional rsync signature layer
+struct ProgressReader<R> {
+    inner: R,
+    total: u64,
+    // The first bytes read, for sniffing the file type
+    head: Vec<u8>,
+    progress: Arc<AtomicU64>,
+    cancel: Arc<AtomicBool>,
+    // Follow mode: EOF is retried until this flag is set, which then acts as the real end of file
+    stop: Option<Arc<AtomicBool>>,
+    signature: Option<SignatureBuilder>,
+    segments: Option<SegmentHasher>,
+    cid: Option<CidBuilder>,
+    cloud: Option<CloudHasher>,
+    preferred: Option<(algo::Algorithm, algo::Hasher)>,
+    authenticode: Option<AuthenticodeHasher>,
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            if self.cancel.load(Ordering::Relaxed) {
+                return Err(std::io::Error::other(errors::CANCELLED));
+            }
+            if self.stop.as_ref().is_some_and(|s| s.load(Ordering::Relaxed)) {
+                return Ok(0);
+            }
+            let n = self.inner.read(buf)?;
+            if n == 0 && self.stop.is_some() {
+                thread::sleep(FOLLOW_POLL_INTERVAL);
+                continue;
+            }
+            if let Some(sig) = self.signature.as_mut() {
+                sig.update(&buf[..n]);
+            }
+            if let Some(seg) = self.segments.as_mut() {
+                seg.update(&buf[..n]);
+            }
+            if let Some(cid) = self.cid.as_mut() {
+                cid.update(&buf[..n]);
+            }
+            if let Some(cloud) = self.cloud.as_mut() {
+                cloud.update(&buf[..n]);
+            }
+            if let Some((_, hasher)) = self.preferred.as_mut() {
+                hasher.update(&buf[..n]);
+            }
+            if let Some(authenticode) = self.authenticode.as_mut() {
+                authenticode.update(&buf[..n]);
+            }
+            if self.head.len() < filetype::SNIFF_LEN {
+                let take = (filetype::SNIFF_LEN - self.head.len()).min(n);
+                self.head.extend_from_slice(&buf[..take]);
+            }
+            self.total += n as u64;
+            self.progress.store(self.total, Ordering::Relaxed);
+            return Ok(n);
+        }
+    }
+}
+
+fn encode_digest(bytes: &[u8]) -> (String, String) {
     let hex = hex::encode(bytes);
     let b64 = base64::engine::general_purpose::STANDARD.encode(bytes);
-    Ok((hex, b64, metadata.map(|m| m.len()).unwrap_or(total), Some(path)))
+    (hex, b64)
 }
 
 fn try_load_icon_from_env() -> Option<window::Icon> {
@@ -542,18 +6122,13 @@ fn try_load_icon_from_paths() -> Option<window::Icon> {
 }
 
 fn load_embedded_icon() -> Option<window::Icon> {
-    // Fallback: embed ICO at compile-time and load it via a temp file
+    // Fallback: embed ICO at compile-time and decode it in memory, so nothing is
+    // written to the host (portable mode)
     const EMBEDDED_ICO: &[u8] = include_bytes!("../assets/app.ico");
     if EMBEDDED_ICO.is_empty() {
         return None;
     }
-    let temp_path = std::env::temp_dir().join("rust-hash-app.ico");
-    if std::fs::write(&temp_path, EMBEDDED_ICO).is_ok() {
-        if let Ok(icon) = window::icon::from_file(&temp_path) {
-            return Some(icon);
-        }
-    }
-    None
+    window::icon::from_file_data(EMBEDDED_ICO, None).ok()
 }
 
 fn app_version() -> &'static str {
@@ -564,4 +6139,49 @@ fn app_version() -> &'static str {
     &VERSION
 }
 
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Write;
 
+    use super::*;
+
+    fn quick(path: &Path) -> String {
+        let ((hex, _, _, _), _) = compute_sha256_quick_progress(&path.display().to_string(), Arc::new(AtomicU64::new(0)), Arc::new(AtomicBool::new(false)), false).unwrap();
+        hex
+    }
+
+    fn patch(path: &Path, offset: u64, byte: u8) {
+        let mut file = fs::OpenOptions::new().write(true).open(path).unwrap();
+        file.seek(SeekFrom::Start(offset)).unwrap();
+        file.write_all(&[byte]).unwrap();
+    }
+
+    // SHA-256 over the size (u64 little-endian), then the first and last QUICK_SAMPLE_SIZE bytes
+    #[test]
+    fn quick_hash_layout() {
+        let path = std::env::temp_dir().join(format!("rust-hash-quick-test-{}", std::process::id()));
+        let small: Vec<u8> = (0..512u32).map(|i| i as u8).collect();
+        fs::write(&path, &small).unwrap();
+        // A file shorter than one sample is read whole, once
+        assert_eq!(quick(&path), "e11c89b9b9686904d945562d3946b590d420ec79b11f6832c21355a6f3e50a31");
+        assert_eq!(quick_sample_len(512), 512);
+
+        let size = 2 * QUICK_SAMPLE_SIZE + 4096;
+        fs::File::create(&path).unwrap().set_len(size).unwrap();
+        let mut expected = Sha256::new();
+        expected.update(size.to_le_bytes());
+        expected.update(vec![0u8; 2 * QUICK_SAMPLE_SIZE as usize]);
+        let before = quick(&path);
+        assert_eq!(before, hex::encode(expected.finalize()));
+        assert_eq!(quick_sample_len(size), 2 * QUICK_SAMPLE_SIZE);
+        // The middle is not read; the head and the tail are
+        patch(&path, QUICK_SAMPLE_SIZE + 100, 1);
+        let middle = quick(&path);
+        patch(&path, size - 1, 1);
+        let tail = quick(&path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(middle, before);
+        assert_ne!(tail, before);
+    }
+}
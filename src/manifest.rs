@@ -0,0 +1,405 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::algo::{self, Algorithm};
+use crate::hashlist;
+use crate::path_norm::{self, absolute, Form, Separator};
+
+// How an entry line is written, kept so an edited list reads like the original
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    // `hash  name`, or `hash *name` in binary mode
+    Gnu { binary: bool },
+    // `SHA256 (name) = hash`
+    Bsd,
+}
+
+#[derive(Debug, Clone)]
+pub struct ManifestEntry {
+    // As written in the list (unescaped), relative to the list's folder unless absolute
+    pub name: String,
+    pub hex: String,
+    style: Style,
+    // The line used coreutils' backslash escaping
+    escaped: bool,
+}
+
+enum Line {
+    Entry(ManifestEntry),
+    // Comments, blank lines and anything that is not a SHA-256 entry, written back untouched
+    Other(String),
+}
+
+// A sha256sum/BSD-style checksum list opened for editing. Lines that are not entries, the line
+// ending and the per-line style survive a save unchanged
+pub struct Manifest {
+    pub path: PathBuf,
+    lines: Vec<Line>,
+    terminator: &'static str,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Manifest> {
+        let data = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let text = String::from_utf8(data).map_err(|_| anyhow!("{} is not UTF-8 text; only sha256sum-style lists can be edited", path.display()))?;
+        let terminator = if text.contains('\0') {
+            "\0"
+        } else if text.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let body = text.strip_suffix(terminator).unwrap_or(&text);
+        let lines: Vec<Line> = if body.is_empty() { Vec::new() } else { body.split(terminator).map(parse_line).collect() };
+        if !lines.is_empty() && !lines.iter().any(|l| matches!(l, Line::Entry(_))) {
+            return Err(anyhow!("No sha256sum or BSD-style SHA-256 lines in {}; logs and CSV reports cannot be edited", path.display()));
+        }
+        Ok(Manifest { path: path.to_path_buf(), lines, terminator })
+    }
+
+    // (line index, entry) for every entry, in file order
+    pub fn entries(&self) -> impl Iterator<Item = (usize, &ManifestEntry)> {
+        self.lines.iter().enumerate().filter_map(|(i, l)| match l {
+            Line::Entry(e) => Some((i, e)),
+            Line::Other(_) => None,
+        })
+    }
+
+    pub fn count(&self) -> usize {
+        self.entries().count()
+    }
+
+    // The file an entry names, resolved like verify does
+    pub fn file(&self, entry: &ManifestEntry) -> PathBuf {
+        let name = PathBuf::from(&entry.name);
+        if name.is_absolute() {
+            name
+        } else {
+            self.path.parent().map(|d| d.join(&name)).unwrap_or(name)
+        }
+    }
+
+    pub fn entry(&self, line: usize) -> Option<&ManifestEntry> {
+        match self.lines.get(line) {
+            Some(Line::Entry(e)) => Some(e),
+            _ => None,
+        }
+    }
+
+    // Removes the given lines; indices are those `entries` reported
+    pub fn remove(&mut self, lines: &[usize]) {
+        let mut index = 0;
+        self.lines.retain(|_| {
+            let keep = !lines.contains(&index);
+            index += 1;
+            keep
+        });
+    }
+
+    pub fn update(&mut self, line: usize, hex: &str) {
+        if let Some(Line::Entry(e)) = self.lines.get_mut(line) {
+            e.hex = hex.to_lowercase();
+        }
+    }
+
+    // A list that does not exist yet; `save` creates it
+    pub fn empty(path: &Path, zero: bool) -> Manifest {
+        Manifest { path: path.to_path_buf(), lines: Vec::new(), terminator: if zero { "\0" } else { "\n" } }
+    }
+
+    // Adds `file` in the style and separator most of the list uses. An existing entry for the same
+    // file is updated instead of listed twice
+    pub fn merge(&mut self, file: &Path, hex: &str) -> Merged {
+        let existing = self.entries().find(|(_, e)| same_file(&self.file(e), file)).map(|(i, e)| (i, e.hex.eq_ignore_ascii_case(hex)));
+        match existing {
+            Some((_, true)) => return Merged::Unchanged,
+            Some((line, false)) => {
+                self.update(line, hex);
+                return Merged::Updated;
+            }
+            None => {}
+        }
+        // Below the list's folder the name is written relative to it, like sha256sum expects; elsewhere in full
+        let relative = path_norm::relative_to(file, self.path.parent().unwrap_or(Path::new("")));
+        let entries: Vec<&ManifestEntry> = self.entries().map(|(_, e)| e).collect();
+        let separator = if entries.iter().any(|e| e.name.contains('\\')) && !entries.iter().any(|e| e.name.contains('/')) {
+            Separator::Backslash
+        } else if entries.iter().any(|e| e.name.contains('/')) {
+            Separator::Slash
+        } else {
+            Separator::Native
+        };
+        let style = entries.iter().map(|e| e.style).max_by_key(|s| entries.iter().filter(|e| e.style == *s).count()).unwrap_or(Style::Gnu { binary: false });
+        let name = path_norm::render(&relative, separator, Form::AsIs);
+        let escaped = matches!(style, Style::Gnu { .. }) && name.contains(['\n', '\r']);
+        self.lines.push(Line::Entry(ManifestEntry { name, hex: hex.to_lowercase(), style, escaped }));
+        Merged::Added
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let mut out = String::new();
+        for line in &self.lines {
+            match line {
+                Line::Entry(e) => out.push_str(&render(e)),
+                Line::Other(raw) => out.push_str(raw),
+            }
+            out.push_str(self.terminator);
+        }
+        crate::atomic::write(&self.path, out.as_bytes())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Merged {
+    Added,
+    Updated,
+    Unchanged,
+}
+
+// Counts from merging digests into a list
+#[derive(Debug, Default)]
+pub struct MergeSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+}
+
+impl std::fmt::Display for MergeSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} added, {} updated, {} unchanged", self.added, self.updated, self.unchanged)
+    }
+}
+
+// Merges (file, SHA-256) pairs into the list at `target`: changed files get their line updated,
+// new ones are added, everything else stays as it was, and no file is listed twice
+pub fn merge_into(target: &Path, digests: impl IntoIterator<Item = (PathBuf, String)>, zero: bool) -> Result<MergeSummary> {
+    let mut manifest = if target.exists() { Manifest::load(target)? } else { Manifest::empty(target, zero) };
+    let mut summary = MergeSummary::default();
+    for (file, hex) in digests {
+        match manifest.merge(&file, &hex) {
+            Merged::Added => summary.added += 1,
+            Merged::Updated => summary.updated += 1,
+            Merged::Unchanged => summary.unchanged += 1,
+        }
+    }
+    manifest.save()?;
+    Ok(summary)
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    absolute(a) == absolute(b)
+}
+
+// What a finished hash in the editor is for
+enum Target {
+    Line(usize),
+    New(PathBuf),
+}
+
+// The manifest editor: a list plus the selection and the files being hashed for it. Nothing
+// reaches the disk until `save`
+pub struct Editor {
+    pub manifest: Manifest,
+    // Line indices of the selected entries
+    pub selected: Vec<usize>,
+    pub dirty: bool,
+    // Files that could not be hashed, for the status line
+    pub failed: Vec<String>,
+    cancel: Arc<AtomicBool>,
+    rx: Option<Receiver<(Target, Result<String>)>>,
+}
+
+impl Editor {
+    pub fn open(path: &Path) -> Result<Editor> {
+        Ok(Editor { manifest: Manifest::load(path)?, selected: Vec::new(), dirty: false, failed: Vec::new(), cancel: Arc::new(AtomicBool::new(false)), rx: None })
+    }
+
+    pub fn toggle(&mut self, line: usize, on: bool) {
+        self.selected.retain(|l| *l != line);
+        if on {
+            self.selected.push(line);
+        }
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.rx.is_some()
+    }
+
+    // Line numbers shift on removal, so this waits for running hashes
+    pub fn remove_selected(&mut self) {
+        if self.is_busy() || self.selected.is_empty() {
+            return;
+        }
+        self.manifest.remove(&self.selected);
+        self.selected.clear();
+        self.dirty = true;
+    }
+
+    pub fn rehash_selected(&mut self) {
+        let targets = self.selected.iter().filter_map(|l| Some((Target::Line(*l), self.manifest.file(self.manifest.entry(*l)?)))).collect();
+        self.hash(targets);
+    }
+
+    pub fn add_files(&mut self, files: Vec<PathBuf>) {
+        self.hash(files.into_iter().map(|f| (Target::New(f.clone()), f)).collect());
+    }
+
+    fn hash(&mut self, targets: Vec<(Target, PathBuf)>) {
+        if self.is_busy() || targets.is_empty() {
+            return;
+        }
+        self.failed.clear();
+        self.cancel = Arc::new(AtomicBool::new(false));
+        let cancel = self.cancel.clone();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let progress = AtomicU64::new(0);
+            for (target, file) in targets {
+                let result = algo::hash_file(&file, Algorithm::Sha256, &progress, &cancel).map(|(hex, _)| hex);
+                if tx.send((target, result.with_context(|| file.display().to_string()))).is_err() {
+                    break;
+                }
+            }
+        });
+        self.rx = Some(rx);
+    }
+
+    // Applies finished hashes; true the one time a batch of them ends
+    pub fn poll(&mut self) -> bool {
+        let Some(rx) = &self.rx else {
+            return false;
+        };
+        loop {
+            match rx.try_recv() {
+                Ok((target, Ok(hex))) => {
+                    match target {
+                        Target::Line(line) => self.manifest.update(line, &hex),
+                        Target::New(file) => {
+                            self.manifest.merge(&file, &hex);
+                        }
+                    }
+                    self.dirty = true;
+                }
+                Ok((_, Err(e))) => self.failed.push(format!("{:#}", e)),
+                Err(TryRecvError::Empty) => return false,
+                Err(TryRecvError::Disconnected) => {
+                    self.rx = None;
+                    return true;
+                }
+            }
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn save(&mut self) -> Result<()> {
+        self.manifest.save()?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+fn parse_line(raw: &str) -> Line {
+    let line = raw.trim_end_matches('\r');
+    if let Some(rest) = line.strip_prefix("SHA256 (") {
+        if let Some((name, hex)) = rest.rsplit_once(") = ") {
+            if is_sha256(hex) {
+                return Line::Entry(ManifestEntry { name: name.to_string(), hex: hex.to_lowercase(), style: Style::Bsd, escaped: false });
+            }
+        }
+    }
+    let (body, escaped) = match line.strip_prefix('\\') {
+        Some(rest) => (rest, true),
+        None => (line, false),
+    };
+    if body.len() > 66 && body.is_char_boundary(64) && is_sha256(&body[..64]) && body.as_bytes()[64] == b' ' && matches!(body.as_bytes()[65], b' ' | b'*') {
+        let binary = body.as_bytes()[65] == b'*';
+        let name = &body[66..];
+        let name = if escaped { hashlist::unescape(name) } else { name.to_string() };
+        return Line::Entry(ManifestEntry { name, hex: body[..64].to_lowercase(), style: Style::Gnu { binary }, escaped });
+    }
+    Line::Other(raw.to_string())
+}
+
+fn render(e: &ManifestEntry) -> String {
+    match e.style {
+        Style::Bsd => format!("SHA256 ({}) = {}", e.name, e.hex),
+        Style::Gnu { binary } => {
+            let marker = if binary { '*' } else { ' ' };
+            if e.escaped {
+                let name = e.name.replace('\\', "\\\\").replace('\n', "\\n").replace('\r', "\\r");
+                format!("\\{} {}{}", e.hex, marker, name)
+            } else {
+                format!("{} {}{}", e.hex, marker, e.name)
+            }
+        }
+    }
+}
+
+fn is_sha256(t: &str) -> bool {
+    t.len() == 64 && t.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    fn temp_list(tag: &str, text: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("rust-hash-manifest-{}-{}.sha256", tag, std::process::id()));
+        fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn lines_survive_parse_and_render() {
+        let escaped = format!("\\{}  odd\\\\name\\nline", A);
+        for line in [format!("{}  a.txt", A), format!("{} *bin/b", A), format!("SHA256 (c d) = {}", A), escaped.clone()] {
+            let Line::Entry(entry) = parse_line(&line) else {
+                panic!("not an entry: {}", line);
+            };
+            assert_eq!(render(&entry), line);
+        }
+        let Line::Entry(entry) = parse_line(&escaped) else {
+            panic!("not an entry: {}", escaped);
+        };
+        assert_eq!(entry.name, "odd\\name\nline");
+        assert!(matches!(parse_line("# generated by rust-hash"), Line::Other(_)));
+        assert!(matches!(parse_line(&format!("{}  short-hash", &A[..40])), Line::Other(_)));
+    }
+
+    #[test]
+    fn save_keeps_comments_and_line_endings() {
+        let text = format!("# list\r\n{}  a.txt\r\n\r\nSHA256 (b) = {}\r\n{}  sub/c\r\n", A, A, A);
+        let path = temp_list("save", &text);
+        let manifest = Manifest::load(&path).unwrap();
+        assert_eq!(manifest.count(), 3);
+        manifest.save().unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(saved, text);
+    }
+
+    #[test]
+    fn merge_follows_the_list_style() {
+        let text = format!("# list\r\n{}  a.txt\r\n{}  sub/c\r\n", A, A);
+        let path = temp_list("merge", &text);
+        let mut manifest = Manifest::load(&path).unwrap();
+        // A new file takes the style and separator most of the list uses
+        assert_eq!(manifest.merge(&path.with_file_name("new").join("d"), &A.to_uppercase()), Merged::Added);
+        assert_eq!(manifest.merge(&path.with_file_name("a.txt"), A), Merged::Unchanged);
+        manifest.save().unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(saved, format!("{}{}  new/d\r\n", text, A));
+    }
+}
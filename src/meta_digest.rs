@@ -0,0 +1,102 @@
+use std::fs::Metadata;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use sha2::{Digest, Sha256};
+
+// Which metadata goes into the metadata digest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fields {
+    pub name: bool,
+    pub size: bool,
+    pub mtime: bool,
+    pub permissions: bool,
+}
+
+pub const ALL: Fields = Fields { name: true, size: true, mtime: true, permissions: true };
+
+// The choices offered in the window; a renamed or re-permissioned file counts as changed or not depending on these
+pub const FIELD_SETS: [Fields; 4] = [
+    ALL,
+    Fields { name: true, size: true, mtime: true, permissions: false },
+    Fields { name: false, size: true, mtime: true, permissions: true },
+    Fields { name: false, size: true, mtime: true, permissions: false },
+];
+
+impl Fields {
+    // Comma-separated names as written by Display, e.g. `name,size,mtime`; None when one is unknown or none is given
+    pub fn parse(s: &str) -> Option<Fields> {
+        let mut fields = Fields { name: false, size: false, mtime: false, permissions: false };
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            match part.to_ascii_lowercase().as_str() {
+                "name" => fields.name = true,
+                "size" => fields.size = true,
+                "mtime" => fields.mtime = true,
+                "permissions" | "mode" => fields.permissions = true,
+                _ => return None,
+            }
+        }
+        (fields != Fields { name: false, size: false, mtime: false, permissions: false }).then_some(fields)
+    }
+}
+
+impl std::fmt::Display for Fields {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<&str> = [(self.name, "name"), (self.size, "size"), (self.mtime, "mtime"), (self.permissions, "permissions")]
+            .into_iter()
+            .filter_map(|(on, name)| on.then_some(name))
+            .collect();
+        write!(f, "{}", names.join(","))
+    }
+}
+
+// SHA-256 of the selected metadata, one `key=value` line each in this order:
+//   name=<file name as UTF-8, lossy>
+//   size=<bytes>
+//   mtime=<seconds since the Unix epoch>.<nanoseconds, 9 digits>   (negative before 1970)
+//   permissions=<Unix mode bits in octal, e.g. 0644> | readonly | readwrite (Windows)
+// Lines end with \n. A backup tool can compare it with the previous run to catch changes that leave the
+// content digest alone
+pub fn digest(path: &Path, metadata: &Metadata, fields: Fields) -> String {
+    hex::encode(Sha256::digest(serialize(path, metadata, fields).as_bytes()))
+}
+
+fn serialize(path: &Path, metadata: &Metadata, fields: Fields) -> String {
+    let mut out = String::new();
+    if fields.name {
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        out.push_str(&format!("name={}\n", name));
+    }
+    if fields.size {
+        out.push_str(&format!("size={}\n", metadata.len()));
+    }
+    if fields.mtime {
+        let mtime = match metadata.modified() {
+            Ok(t) => match t.duration_since(UNIX_EPOCH) {
+                Ok(d) => format!("{}.{:09}", d.as_secs(), d.subsec_nanos()),
+                Err(e) => {
+                    let d = e.duration();
+                    format!("-{}.{:09}", d.as_secs(), d.subsec_nanos())
+                }
+            },
+            // Some filesystems keep none; say so rather than guess
+            Err(_) => "unknown".to_string(),
+        };
+        out.push_str(&format!("mtime={}\n", mtime));
+    }
+    if fields.permissions {
+        out.push_str(&format!("permissions={}\n", permissions(metadata)));
+    }
+    out
+}
+
+#[cfg(unix)]
+fn permissions(metadata: &Metadata) -> String {
+    use std::os::unix::fs::PermissionsExt;
+    format!("{:04o}", metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn permissions(metadata: &Metadata) -> String {
+    if metadata.permissions().readonly() { "readonly" } else { "readwrite" }.to_string()
+}
@@ -0,0 +1,73 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::hashlist;
+
+// Larger files are not checksum files, whatever their name
+const MAX_CHECKSUM_FILE: u64 = 4 * 1024 * 1024;
+
+const CHECKSUM_EXTENSIONS: [&str; 9] = ["sha256", "sha512", "sha1", "md5", "sha256sum", "sha512sum", "sha1sum", "md5sum", "sums"];
+
+// A data file and the checksum file it is checked against, set up by dropping both (in either order)
+#[derive(Debug, Clone)]
+pub struct Pair {
+    pub checksum: PathBuf,
+    // None while waiting for the data file to be dropped
+    pub data: Option<PathBuf>,
+    // Line of the checksum file the expected digest came from, 1-based
+    pub line: Option<usize>,
+}
+
+impl Pair {
+    pub fn waiting(checksum: PathBuf) -> Pair {
+        Pair { checksum, data: None, line: None }
+    }
+}
+
+// `app.iso.sha256`, `SHA256SUMS`, `checksums.md5` and the like
+pub fn is_checksum_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_ascii_lowercase()) else {
+        return false;
+    };
+    let by_extension = path.extension().is_some_and(|e| CHECKSUM_EXTENSIONS.iter().any(|x| e.eq_ignore_ascii_case(x)));
+    let by_name = name.contains("sums") || name.starts_with("checksum");
+    (by_extension || by_name) && fs::metadata(path).is_ok_and(|m| m.is_file() && m.len() <= MAX_CHECKSUM_FILE)
+}
+
+// The digest `checksum` gives for `data`, with its line number: a bare digest (a sidecar such as
+// `app.iso.sha256`) applies to whatever file it is paired with; a list is searched for the data file's name
+pub fn expected_for(checksum: &Path, data: &Path) -> Result<(String, Option<usize>)> {
+    let bytes = fs::read(checksum).with_context(|| format!("Failed to read {}", checksum.display()))?;
+    let text = String::from_utf8_lossy(&bytes);
+    let text = text.trim_start_matches('\u{feff}');
+    let lines: Vec<&str> = text.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#') && !l.starts_with(';')).collect();
+    if let [only] = lines.as_slice() {
+        if !only.contains(char::is_whitespace) && only.len() >= 32 && only.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Ok((only.to_lowercase(), None));
+        }
+    }
+    let wanted = data.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    for (idx, line) in text.lines().enumerate() {
+        let Some((hex, name)) = bsd_line(line.trim()).or_else(|| hashlist::split_hash_line(line.trim())) else {
+            continue;
+        };
+        let name = name.trim_start_matches("./");
+        let listed = Path::new(name).file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let same = if cfg!(windows) { listed.eq_ignore_ascii_case(&wanted) } else { listed == wanted };
+        if same {
+            return Ok((hex, Some(idx + 1)));
+        }
+    }
+    Err(anyhow!("{} is not listed in {}", wanted, checksum.display()))
+}
+
+// `SHA512 (name) = hash`, `MD5 (name) = hash` and other BSD-style lines
+fn bsd_line(line: &str) -> Option<(String, String)> {
+    let (algorithm, rest) = line.split_once(" (")?;
+    let (name, hash) = rest.rsplit_once(") = ")?;
+    let hash = hash.trim();
+    let looks_right = algorithm.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') && hash.chars().all(|c| c.is_ascii_hexdigit());
+    (looks_right && !hash.is_empty()).then(|| (hash.to_lowercase(), name.to_string()))
+}
@@ -0,0 +1,121 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use iced::{Color, Theme};
+
+// Colors for the window and for status lines. Set once from the saved settings and again when changed, so
+// the free view functions can read it without threading it through every call
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Palette {
+    #[default]
+    Standard,
+    // Black background, white text and saturated status colors
+    HighContrast,
+    // Okabe-Ito colors (blue/vermillion/yellow instead of green/red/orange), with a symbol on each status line
+    ColorBlindSafe,
+}
+
+pub const PALETTES: [Palette; 3] = [Palette::Standard, Palette::HighContrast, Palette::ColorBlindSafe];
+
+// What a status line says, independent of how it is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Good,
+    Bad,
+    Warn,
+    Info,
+    Blocked,
+    Neutral,
+}
+
+impl Palette {
+    pub fn parse(name: &str) -> Palette {
+        match name {
+            "high-contrast" => Palette::HighContrast,
+            "color-blind-safe" => Palette::ColorBlindSafe,
+            _ => Palette::Standard,
+        }
+    }
+
+    // As stored in the settings file
+    pub fn key(self) -> &'static str {
+        match self {
+            Palette::Standard => "standard",
+            Palette::HighContrast => "high-contrast",
+            Palette::ColorBlindSafe => "color-blind-safe",
+        }
+    }
+
+    pub fn theme(self) -> Theme {
+        match self {
+            Palette::HighContrast => Theme::custom(
+                "High contrast".to_string(),
+                iced::theme::Palette {
+                    background: Color::BLACK,
+                    text: Color::WHITE,
+                    primary: Color::from_rgb(1.0, 1.0, 0.0),
+                    success: Color::from_rgb(0.3, 1.0, 0.3),
+                    danger: Color::from_rgb(1.0, 0.35, 0.35),
+                },
+            ),
+            _ => Theme::Dark,
+        }
+    }
+}
+
+impl std::fmt::Display for Palette {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Palette::Standard => "Standard colors",
+            Palette::HighContrast => "High contrast",
+            Palette::ColorBlindSafe => "Color-blind safe",
+        })
+    }
+}
+
+pub fn set(palette: Palette) {
+    CURRENT.store(palette as u8, Ordering::Relaxed);
+}
+
+fn current() -> Palette {
+    match CURRENT.load(Ordering::Relaxed) {
+        1 => Palette::HighContrast,
+        2 => Palette::ColorBlindSafe,
+        _ => Palette::Standard,
+    }
+}
+
+pub fn color(status: Status) -> [f32; 3] {
+    match (current(), status) {
+        (Palette::Standard, Status::Good) => [0.5, 0.9, 0.5],
+        (Palette::Standard, Status::Bad) => [1.0, 0.5, 0.5],
+        (Palette::Standard, Status::Warn) => [1.0, 0.75, 0.3],
+        (Palette::Standard, Status::Info) => [0.6, 0.8, 1.0],
+        (Palette::Standard, Status::Blocked) => [1.0, 0.3, 0.3],
+        (Palette::Standard, Status::Neutral) => [0.7, 0.7, 0.7],
+        (Palette::HighContrast, Status::Good) => [0.3, 1.0, 0.3],
+        (Palette::HighContrast, Status::Bad | Status::Blocked) => [1.0, 0.35, 0.35],
+        (Palette::HighContrast, Status::Warn) => [1.0, 1.0, 0.0],
+        (Palette::HighContrast, Status::Info) => [0.4, 0.9, 1.0],
+        (Palette::HighContrast, Status::Neutral) => [0.9, 0.9, 0.9],
+        (Palette::ColorBlindSafe, Status::Good) => [0.34, 0.71, 0.91],
+        (Palette::ColorBlindSafe, Status::Bad | Status::Blocked) => [0.93, 0.45, 0.1],
+        (Palette::ColorBlindSafe, Status::Warn) => [0.94, 0.89, 0.26],
+        (Palette::ColorBlindSafe, Status::Info) => [0.8, 0.47, 0.65],
+        (Palette::ColorBlindSafe, Status::Neutral) => [0.7, 0.7, 0.7],
+    }
+}
+
+// Prefix that tells the status apart without color; empty in the standard palette
+pub fn mark(status: Status) -> &'static str {
+    if current() == Palette::Standard {
+        return "";
+    }
+    match status {
+        Status::Good => "[OK] ",
+        Status::Bad | Status::Blocked => "[X] ",
+        Status::Warn => "[!] ",
+        Status::Info | Status::Neutral => "",
+    }
+}
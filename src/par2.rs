@@ -0,0 +1,329 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use md5::{Digest, Md5};
+
+use crate::verify::{Checked, Entry, Status, VerifyRun};
+
+const MAGIC: &[u8; 8] = b"PAR2\0PKT";
+const HEADER_LEN: u64 = 64;
+const TYPE_MAIN: &[u8; 16] = b"PAR 2.0\0Main\0\0\0\0";
+const TYPE_FILE_DESC: &[u8; 16] = b"PAR 2.0\0FileDesc";
+const TYPE_SLICE_CHECKSUMS: &[u8; 16] = b"PAR 2.0\0IFSC\0\0\0\0";
+const TYPE_RECOVERY: &[u8; 16] = b"PAR 2.0\0RecvSlic";
+// Only recovery slices are large; anything else this big is damaged
+const MAX_PACKET: u64 = 64 * 1024 * 1024;
+
+// A protected file as described by the recovery set
+#[derive(Debug, Clone, Default)]
+pub struct FileDesc {
+    pub name: String,
+    pub length: u64,
+    pub md5: [u8; 16],
+    // MD5 of each slice (the last one zero-padded), from the IFSC packet
+    pub slices: Vec<[u8; 16]>,
+}
+
+#[derive(Debug, Default)]
+pub struct RecoverySet {
+    pub slice_size: u64,
+    pub files: Vec<FileDesc>,
+    // Recovery blocks found in the .par2/.vol*.par2 files next to the index
+    pub recovery_blocks: usize,
+}
+
+// Reads the index file and every .par2 file beside it that belongs to the same set.
+// Packets whose own MD5 does not match are ignored, as par2 clients do.
+pub fn read_set(index: &Path) -> Result<RecoverySet> {
+    let mut set_id = None;
+    let mut main: Option<(u64, Vec<[u8; 16]>)> = None;
+    let mut descs: HashMap<[u8; 16], FileDesc> = HashMap::new();
+    let mut slices: HashMap<[u8; 16], Vec<[u8; 16]>> = HashMap::new();
+    let mut recovery = 0;
+    let dir = index.parent().map(Path::to_path_buf).unwrap_or_default();
+    let mut paths = vec![index.to_path_buf()];
+    if let Ok(entries) = fs::read_dir(&dir) {
+        let mut siblings: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p != index && p.extension().is_some_and(|x| x.eq_ignore_ascii_case("par2")))
+            .collect();
+        siblings.sort();
+        paths.extend(siblings);
+    }
+    for path in &paths {
+        // Only the index has to be readable; damaged volume files just contribute fewer packets
+        let is_index = path == index;
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if is_index => return Err(e).with_context(|| format!("Failed to open file: {}", path.display())),
+            Err(_) => continue,
+        };
+        let read = for_each_packet(&mut file, |id, kind, body| {
+            // The index decides which set the other files must belong to
+            if *set_id.get_or_insert(id) != id {
+                return;
+            }
+            match kind {
+                TYPE_RECOVERY => recovery += 1,
+                TYPE_MAIN if body.len() >= 12 => {
+                    let slice_size = u64::from_le_bytes(body[0..8].try_into().unwrap());
+                    let count = u32::from_le_bytes(body[8..12].try_into().unwrap()) as usize;
+                    let ids = body[12..].chunks_exact(16).take(count).map(|c| c.try_into().unwrap()).collect();
+                    main.get_or_insert((slice_size, ids));
+                }
+                TYPE_FILE_DESC if body.len() >= 56 => {
+                    let name = String::from_utf8_lossy(&body[56..]).trim_end_matches('\0').to_string();
+                    descs.entry(body[0..16].try_into().unwrap()).or_insert(FileDesc {
+                        name,
+                        length: u64::from_le_bytes(body[48..56].try_into().unwrap()),
+                        md5: body[16..32].try_into().unwrap(),
+                        slices: Vec::new(),
+                    });
+                }
+                TYPE_SLICE_CHECKSUMS if body.len() >= 16 => {
+                    let list = body[16..].chunks_exact(20).map(|c| c[..16].try_into().unwrap()).collect();
+                    slices.entry(body[0..16].try_into().unwrap()).or_insert(list);
+                }
+                _ => {}
+            }
+        });
+        if is_index {
+            read.with_context(|| format!("Failed to read {}", path.display()))?;
+            if set_id.is_none() {
+                return Err(anyhow!("{} is not a PAR2 file", index.display()));
+            }
+        }
+    }
+    let (slice_size, ids) = main.ok_or_else(|| anyhow!("No main packet in the PAR2 set; the index may be damaged"))?;
+    let mut files = Vec::with_capacity(ids.len());
+    for id in ids {
+        let mut desc = descs.remove(&id).ok_or_else(|| anyhow!("The PAR2 set lacks the description of a protected file"))?;
+        desc.slices = slices.remove(&id).unwrap_or_default();
+        files.push(desc);
+    }
+    Ok(RecoverySet { slice_size, files, recovery_blocks: recovery })
+}
+
+// Calls `f(set id, packet type, body)` for every packet with a valid MD5; recovery slices are
+// passed without their body and without the (expensive) check
+fn for_each_packet(file: &mut File, mut f: impl FnMut([u8; 16], &[u8; 16], &[u8])) -> Result<()> {
+    let end = file.metadata()?.len();
+    let mut pos = 0;
+    let mut header = [0u8; HEADER_LEN as usize];
+    while pos + HEADER_LEN <= end {
+        file.seek(SeekFrom::Start(pos))?;
+        file.read_exact(&mut header)?;
+        if &header[0..8] != MAGIC {
+            // Resynchronise on the next packet after damage
+            pos += 4;
+            continue;
+        }
+        let len = u64::from_le_bytes(header[8..16].try_into().unwrap());
+        if len < HEADER_LEN || len % 4 != 0 || pos + len > end {
+            pos += 4;
+            continue;
+        }
+        let set_id: [u8; 16] = header[32..48].try_into().unwrap();
+        let kind: &[u8; 16] = header[48..64].try_into().unwrap();
+        if kind == TYPE_RECOVERY {
+            f(set_id, kind, &[]);
+        } else if len <= MAX_PACKET {
+            let mut body = vec![0u8; (len - HEADER_LEN) as usize];
+            file.read_exact(&mut body)?;
+            let mut md5 = Md5::new();
+            md5.update(&header[32..]);
+            md5.update(&body);
+            if md5.finalize()[..] != header[16..32] {
+                pos += 4;
+                continue;
+            }
+            f(set_id, kind, &body);
+        }
+        pos += len;
+    }
+    Ok(())
+}
+
+// Re-verifies every file of the set (relative to the index's folder) on a worker thread
+pub fn start(index: PathBuf) -> Result<VerifyRun> {
+    let set = read_set(&index)?;
+    let dir = index.parent().map(Path::to_path_buf).unwrap_or_default();
+    let format = format!("PAR2 set, {} recovery blocks", set.recovery_blocks);
+    let mut by_path = HashMap::new();
+    let mut entries = Vec::with_capacity(set.files.len());
+    for desc in set.files {
+        let path = dir.join(&desc.name);
+        entries.push(Entry { path: path.clone(), expected: hex::encode(desc.md5), list: 0, also: Vec::new(), xattrs: None });
+        by_path.insert(path, desc);
+    }
+    let slice_size = set.slice_size;
+    Ok(VerifyRun::spawn(index, format, "MD5", entries, 0, move |entry, progress, cancel| {
+        let desc = &by_path[&entry.path];
+        check(entry, desc, slice_size, progress, cancel)
+    }))
+}
+
+fn check(entry: Entry, desc: &FileDesc, slice_size: u64, progress: &Arc<AtomicU64>, cancel: &Arc<AtomicBool>) -> Checked {
+    let total_slices = if slice_size > 0 { desc.length.div_ceil(slice_size) } else { 0 };
+    if !entry.path.is_file() {
+        let note = Some(format!("{} of {} blocks missing", total_slices, total_slices));
+        return Checked { entry, status: Status::Missing, bytes: None, note, elapsed: Duration::ZERO };
+    }
+    match hash_slices(&entry.path, desc, slice_size, progress, cancel) {
+        Ok((md5, bytes, bad)) => {
+            let status = if md5 == desc.md5 && bytes == desc.length { Status::Ok } else { Status::Mismatch(hex::encode(md5)) };
+            let mut notes = Vec::new();
+            if bytes != desc.length {
+                notes.push(format!("size {} instead of {}", bytes, desc.length));
+            }
+            if bad > 0 && !desc.slices.is_empty() {
+                notes.push(format!("{} of {} blocks damaged", bad, total_slices));
+            }
+            let note = (!notes.is_empty()).then(|| notes.join(", "));
+            Checked { entry, status, bytes: Some(bytes), note, elapsed: Duration::ZERO }
+        }
+        Err(e) => Checked { entry, status: Status::Failed(format!("{:#}", e)), bytes: None, note: None, elapsed: Duration::ZERO },
+    }
+}
+
+// Whole-file MD5, bytes read and the number of slices whose MD5 differs from the IFSC list
+fn hash_slices(path: &Path, desc: &FileDesc, slice_size: u64, progress: &AtomicU64, cancel: &AtomicBool) -> Result<([u8; 16], u64, usize)> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+    let mut whole = Md5::new();
+    let mut buffer = vec![0u8; slice_size.clamp(1, 16 * 1024 * 1024) as usize];
+    let mut bytes = 0u64;
+    let mut bad = 0;
+    let mut index = 0;
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(anyhow!(crate::errors::CANCELLED));
+        }
+        // One slice per round, so per-slice MD5s line up with the IFSC list
+        let mut slice = Md5::new();
+        let mut filled = 0u64;
+        while filled < slice_size.max(1) {
+            let want = (slice_size.max(1) - filled).min(buffer.len() as u64) as usize;
+            let n = file.read(&mut buffer[..want]).with_context(|| format!("Failed to read {}", path.display()))?;
+            if n == 0 {
+                break;
+            }
+            whole.update(&buffer[..n]);
+            slice.update(&buffer[..n]);
+            filled += n as u64;
+            bytes += n as u64;
+            progress.store(bytes, Ordering::Relaxed);
+        }
+        if filled == 0 {
+            break;
+        }
+        if let Some(expected) = desc.slices.get(index) {
+            // The last slice is hashed as if padded with zeros to the full slice size
+            let padding = vec![0u8; (slice_size.saturating_sub(filled)) as usize];
+            slice.update(&padding);
+            if slice.finalize()[..] != expected[..] {
+                bad += 1;
+            }
+        }
+        index += 1;
+        if filled < slice_size {
+            break;
+        }
+    }
+    // Slices the file no longer reaches count as damaged too
+    bad += desc.slices.len().saturating_sub(index);
+    Ok((whole.finalize().into(), bytes, bad))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SET: [u8; 16] = [7; 16];
+    const FILE_ID: [u8; 16] = [9; 16];
+
+    fn packet(set: [u8; 16], kind: &[u8; 16], body: &[u8]) -> Vec<u8> {
+        let mut signed = set.to_vec();
+        signed.extend_from_slice(kind);
+        signed.extend_from_slice(body);
+        let mut packet = MAGIC.to_vec();
+        packet.extend_from_slice(&(32 + signed.len() as u64).to_le_bytes());
+        packet.extend_from_slice(&Md5::digest(&signed));
+        packet.extend_from_slice(&signed);
+        packet
+    }
+
+    // An index for `data` in 4-byte slices, after some junk, and a volume file with one recovery block
+    // and a packet of another set
+    fn write_set(dir: &Path, data: &[u8]) -> PathBuf {
+        let mut main = 4u64.to_le_bytes().to_vec();
+        main.extend_from_slice(&1u32.to_le_bytes());
+        main.extend_from_slice(&FILE_ID);
+        let mut desc = FILE_ID.to_vec();
+        desc.extend_from_slice(&Md5::digest(data));
+        desc.extend_from_slice(&Md5::digest(data));
+        desc.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        desc.extend_from_slice(b"data.bin");
+        let mut ifsc = FILE_ID.to_vec();
+        for slice in data.chunks(4) {
+            let mut padded = slice.to_vec();
+            padded.resize(4, 0);
+            ifsc.extend_from_slice(&Md5::digest(&padded));
+            ifsc.extend_from_slice(&[0; 4]);
+        }
+        let index = [b"junk".to_vec(), packet(SET, TYPE_MAIN, &main), packet(SET, TYPE_FILE_DESC, &desc), packet(SET, TYPE_SLICE_CHECKSUMS, &ifsc)].concat();
+        fs::write(dir.join("set.par2"), index).unwrap();
+        let volume = [packet(SET, TYPE_RECOVERY, &[0; 8]), packet([8; 16], TYPE_RECOVERY, &[0; 8])].concat();
+        fs::write(dir.join("set.vol0+1.par2"), volume).unwrap();
+        dir.join("set.par2")
+    }
+
+    fn damaged(set: &RecoverySet, path: &Path) -> (bool, u64, usize) {
+        let (md5, bytes, bad) = hash_slices(path, &set.files[0], set.slice_size, &AtomicU64::new(0), &AtomicBool::new(false)).unwrap();
+        (md5 == set.files[0].md5, bytes, bad)
+    }
+
+    #[test]
+    fn slices_are_checked_against_the_set() {
+        let dir = std::env::temp_dir().join(format!("rust-hash-par2-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let data = b"0123456789";
+        let index = write_set(&dir, data);
+        let set = read_set(&index).unwrap();
+        let file = dir.join("data.bin");
+        fs::write(&file, data).unwrap();
+        let intact = damaged(&set, &file);
+        fs::write(&file, b"0123x56789").unwrap();
+        let changed = damaged(&set, &file);
+        fs::write(&file, b"012345").unwrap();
+        let cut = damaged(&set, &file);
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!((set.slice_size, set.recovery_blocks), (4, 1));
+        assert_eq!((set.files[0].name.as_str(), set.files[0].length, set.files[0].slices.len()), ("data.bin", 10, 3));
+        assert_eq!(intact, (true, 10, 0));
+        assert_eq!(changed, (false, 10, 1));
+        // The second slice is short and the third is gone
+        assert_eq!(cut, (false, 6, 2));
+    }
+
+    #[test]
+    fn packets_with_a_bad_md5_are_ignored() {
+        let dir = std::env::temp_dir().join(format!("rust-hash-par2-bad-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let index = write_set(&dir, b"0123456789");
+        let mut bytes = fs::read(&index).unwrap();
+        // Inside the file description's name
+        let at = bytes.windows(8).position(|w| w == b"data.bin").unwrap();
+        bytes[at] = b'D';
+        fs::write(&index, bytes).unwrap();
+        let result = read_set(&index);
+        let _ = fs::remove_dir_all(&dir);
+        assert!(result.unwrap_err().to_string().contains("lacks the description"));
+    }
+}
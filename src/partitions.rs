@@ -0,0 +1,384 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+
+use anyhow::{anyhow, bail, Context, Result};
+use sha2::{Digest, Sha256};
+
+const BUFFER_SIZE: usize = 1024 * 1024;
+const MBR_SECTOR: u64 = 512;
+const GPT_SIGNATURE: &[u8] = b"EFI PART";
+const PROTECTIVE_MBR: u8 = 0xee;
+// Extended partitions chain EBRs holding the logical partitions
+const EXTENDED: [u8; 3] = [0x05, 0x0f, 0x85];
+// Guards against EBR loops and absurd entry counts in damaged tables
+const MAX_LOGICAL: usize = 128;
+const MAX_GPT_ENTRIES: u32 = 1024;
+// Raw image extensions; devices are recognized by their file type
+const IMAGE_EXTENSIONS: [&str; 5] = ["img", "raw", "dd", "001", "bin"];
+
+// (number, scheme, label, offset, length) of a table entry
+type TableEntry = (usize, &'static str, String, u64, u64);
+
+const GPT_TYPES: &[(&str, &str)] = &[
+    ("C12A7328-F81F-11D2-BA4B-00A0C93EC93B", "EFI System"),
+    ("21686148-6449-6E6F-744E-656564454649", "BIOS boot"),
+    ("E3C9E316-0B5C-4DB8-817D-F92DF00215AE", "Microsoft reserved"),
+    ("EBD0A0A2-B9E5-4433-87C0-68B6B72699C7", "Microsoft basic data"),
+    ("DE94BBA4-06D1-4D40-A16A-BFD50179D6AC", "Windows recovery"),
+    ("0FC63DAF-8483-4772-8E79-3D69D8477DE4", "Linux filesystem"),
+    ("0657FD6D-A4AB-43C4-84E5-0933C84B4F4F", "Linux swap"),
+    ("E6D6D379-F507-44C2-A23C-238F2A3DF928", "Linux LVM"),
+    ("A19D880F-05FC-4D3B-A006-743F0F84911E", "Linux RAID"),
+    ("48465300-0000-11AA-AA11-00306543ECAC", "Apple HFS+"),
+    ("7C3457EF-0000-11AA-AA11-00306543ECAC", "Apple APFS"),
+];
+
+const MBR_TYPES: &[(u8, &str)] = &[
+    (0x01, "FAT12"),
+    (0x04, "FAT16"),
+    (0x06, "FAT16"),
+    (0x07, "NTFS/exFAT"),
+    (0x0b, "FAT32"),
+    (0x0c, "FAT32 (LBA)"),
+    (0x0e, "FAT16 (LBA)"),
+    (0x27, "Windows recovery"),
+    (0x82, "Linux swap"),
+    (0x83, "Linux"),
+    (0x8e, "Linux LVM"),
+    (0xa5, "FreeBSD"),
+    (0xaf, "Apple HFS+"),
+    (0xef, "EFI System"),
+    (0xfd, "Linux RAID"),
+];
+
+// A partition of a raw disk or device image with the SHA-256 of its bytes
+#[derive(Debug, Clone)]
+pub struct Partition {
+    // 1-based, in table order; MBR logical partitions start at 5 as Linux numbers them
+    pub number: usize,
+    pub scheme: &'static str,
+    pub label: String,
+    pub offset: u64,
+    pub length: u64,
+    pub sha256: String,
+}
+
+// Raw disks and sector-by-sector images, which may start with a partition table
+pub fn is_disk_image(path: &Path) -> bool {
+    let ext = path.extension().map(|e| e.to_string_lossy().to_ascii_lowercase()).unwrap_or_default();
+    IMAGE_EXTENSIONS.contains(&ext.as_str()) || crate::special::detect(path) == Some(crate::special::SpecialFile::BlockDevice)
+}
+
+// Partitions the table at the start of a raw disk or image describes, without reading them; empty when there is
+// no table or it points outside the image
+fn read_table(file: &mut File, size: u64) -> Result<Vec<TableEntry>> {
+    let mbr = read_at(file, 0, MBR_SECTOR as usize)?;
+    if mbr.len() < MBR_SECTOR as usize || mbr[510..512] != [0x55, 0xaa] {
+        return Ok(Vec::new());
+    }
+    let primary = mbr_entries(&mbr);
+    let table = if primary.iter().any(|(kind, _, _)| *kind == PROTECTIVE_MBR) {
+        gpt_entries(file)?
+    } else {
+        let mut table = Vec::new();
+        for (i, (kind, start, count)) in primary.iter().enumerate() {
+            if EXTENDED.contains(kind) {
+                table.extend(logical_entries(file, *start)?);
+            } else {
+                table.push((i + 1, "MBR", mbr_label(*kind), start * MBR_SECTOR, count * MBR_SECTOR));
+            }
+        }
+        table
+    };
+    // A boot sector that merely ends in 55 AA is not a partition table
+    if table.iter().any(|(_, _, _, offset, length)| *length == 0 || offset.saturating_add(*length) > size) {
+        return Ok(Vec::new());
+    }
+    Ok(table)
+}
+
+// (type, first sector, sector count) of the used entries of an MBR or EBR
+fn mbr_entries(sector: &[u8]) -> Vec<(u8, u64, u64)> {
+    (0..4)
+        .map(|i| &sector[446 + i * 16..446 + (i + 1) * 16])
+        .filter(|e| e[4] != 0)
+        .map(|e| (e[4], u32_at(e, 8) as u64, u32_at(e, 12) as u64))
+        .collect()
+}
+
+fn logical_entries(file: &mut File, extended_start: u64) -> Result<Vec<TableEntry>> {
+    let mut out = Vec::new();
+    let mut ebr = extended_start;
+    while out.len() < MAX_LOGICAL {
+        let sector = read_at(file, ebr * MBR_SECTOR, MBR_SECTOR as usize)?;
+        if sector.len() < MBR_SECTOR as usize || sector[510..512] != [0x55, 0xaa] {
+            break;
+        }
+        let entries = mbr_entries(&sector);
+        // The first entry is relative to this EBR, the link to the next EBR relative to the extended partition
+        if let Some((kind, start, count)) = entries.iter().find(|(k, _, _)| !EXTENDED.contains(k)) {
+            out.push((5 + out.len(), "MBR", mbr_label(*kind), (ebr + start) * MBR_SECTOR, count * MBR_SECTOR));
+        }
+        match entries.iter().find(|(k, _, _)| EXTENDED.contains(k)) {
+            Some((_, next, _)) if *next > 0 => ebr = extended_start + next,
+            _ => break,
+        }
+    }
+    Ok(out)
+}
+
+fn gpt_entries(file: &mut File) -> Result<Vec<TableEntry>> {
+    // The header sits in LBA 1, whose offset depends on the logical sector size
+    for sector_size in [512u64, 4096] {
+        let header = read_at(file, sector_size, 92)?;
+        if !header.starts_with(GPT_SIGNATURE) {
+            continue;
+        }
+        let entries_lba = u64_at(&header, 72);
+        let count = u32_at(&header, 80).min(MAX_GPT_ENTRIES);
+        let entry_size = u32_at(&header, 84) as usize;
+        if !(128..=4096).contains(&entry_size) {
+            bail!("GPT header gives an invalid entry size ({})", entry_size);
+        }
+        let entries = read_at(file, entries_lba.saturating_mul(sector_size), count as usize * entry_size)?;
+        let mut out = Vec::new();
+        for (i, entry) in entries.chunks_exact(entry_size).enumerate() {
+            if entry[..16].iter().all(|b| *b == 0) {
+                continue;
+            }
+            let (first, last) = (u64_at(entry, 32), u64_at(entry, 40));
+            if last < first {
+                continue;
+            }
+            let name: Vec<u16> = entry[56..128].chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).take_while(|c| *c != 0).collect();
+            let name = String::from_utf16_lossy(&name);
+            let kind = guid(&entry[..16]);
+            let kind = GPT_TYPES.iter().find(|(g, _)| *g == kind).map(|(_, n)| n.to_string()).unwrap_or(kind);
+            let label = if name.is_empty() { kind } else { format!("{} ({})", name, kind) };
+            out.push((i + 1, "GPT", label, first.saturating_mul(sector_size), (last - first + 1).saturating_mul(sector_size)));
+        }
+        return Ok(out);
+    }
+    Err(anyhow!("Protective MBR found but no GPT header"))
+}
+
+fn mbr_label(kind: u8) -> String {
+    let name = MBR_TYPES.iter().find(|(k, _)| *k == kind).map(|(_, n)| *n).unwrap_or("unknown type");
+    format!("{} (0x{:02X})", name, kind)
+}
+
+// Mixed-endian, as GUIDs are written in text
+fn guid(b: &[u8]) -> String {
+    format!(
+        "{:08X}-{:04X}-{:04X}-{}-{}",
+        u32_at(b, 0),
+        u16::from_le_bytes([b[4], b[5]]),
+        u16::from_le_bytes([b[6], b[7]]),
+        hex::encode_upper(&b[8..10]),
+        hex::encode_upper(&b[10..16])
+    )
+}
+
+fn u32_at(b: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([b[at], b[at + 1], b[at + 2], b[at + 3]])
+}
+
+fn u64_at(b: &[u8], at: usize) -> u64 {
+    let mut v = [0u8; 8];
+    v.copy_from_slice(&b[at..at + 8]);
+    u64::from_le_bytes(v)
+}
+
+// Up to `len` bytes at `offset`; shorter at the end of the image
+fn read_at(file: &mut File, offset: u64, len: usize) -> Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(offset))?;
+    let mut buf = Vec::with_capacity(len);
+    file.by_ref().take(len as u64).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+// Hashes every partition the table lists, in table order
+pub fn hash_partitions(path: &Path, progress: &AtomicU64, cancel: &AtomicBool) -> Result<Vec<Partition>> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+    // Block devices report no length in their metadata
+    let size = file.seek(SeekFrom::End(0))?;
+    let table = read_table(&mut file, size)?;
+    if table.is_empty() {
+        bail!("No GPT or MBR partition table found");
+    }
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut done = 0u64;
+    let mut out = Vec::new();
+    for (number, scheme, label, offset, length) in table {
+        file.seek(SeekFrom::Start(offset))?;
+        let mut hasher = Sha256::new();
+        let mut left = length;
+        while left > 0 {
+            if cancel.load(Ordering::Relaxed) {
+                bail!(crate::errors::CANCELLED);
+            }
+            let want = left.min(BUFFER_SIZE as u64) as usize;
+            let n = file.read(&mut buffer[..want]).with_context(|| format!("Failed to read partition {}", number))?;
+            if n == 0 {
+                bail!("Image ends inside partition {}", number);
+            }
+            hasher.update(&buffer[..n]);
+            left -= n as u64;
+            done += n as u64;
+            progress.store(done, Ordering::Relaxed);
+        }
+        out.push(Partition { number, scheme, label, offset, length, sha256: hex::encode(hasher.finalize()) });
+    }
+    Ok(out)
+}
+
+// One line per partition: `<sha256>  p<number>  <offset> <length>  <label>`
+pub fn manifest(image: &Path, partitions: &[Partition]) -> String {
+    let mut out = format!("# rust-hash partition digests: {}\n", image.display());
+    for p in partitions {
+        out.push_str(&format!("{}  p{}  {} {}  {} {}\n", p.sha256, p.number, p.offset, p.length, p.scheme, p.label));
+    }
+    out
+}
+
+// Partition hashing of a just-hashed disk or image on a worker thread
+pub struct PartitionRun {
+    pub image: PathBuf,
+    pub result: Option<Result<Vec<Partition>, String>>,
+    progress: Arc<AtomicU64>,
+    cancel: Arc<AtomicBool>,
+    rx: Option<Receiver<Result<Vec<Partition>, String>>>,
+}
+
+impl PartitionRun {
+    pub fn start(image: PathBuf) -> PartitionRun {
+        let progress = Arc::new(AtomicU64::new(0));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        let (path, p, c) = (image.clone(), progress.clone(), cancel.clone());
+        thread::spawn(move || {
+            let _ = tx.send(hash_partitions(&path, &p, &c).map_err(|e| format!("{:#}", e)));
+        });
+        PartitionRun { image, result: None, progress, cancel, rx: Some(rx) }
+    }
+
+    pub fn poll(&mut self) {
+        if let Some(result) = self.rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.result = Some(result);
+            self.rx = None;
+        }
+    }
+
+    pub fn processed(&self) -> u64 {
+        self.progress.load(Ordering::Relaxed)
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const SECTORS: usize = 64;
+
+    // (number, label, offset, length, sha256)
+    type Row = (usize, String, u64, u64, String);
+
+    fn entry(sector: &mut [u8], slot: usize, kind: u8, start: u32, count: u32) {
+        let at = 446 + slot * 16;
+        sector[at + 4] = kind;
+        sector[at + 8..at + 12].copy_from_slice(&start.to_le_bytes());
+        sector[at + 12..at + 16].copy_from_slice(&count.to_le_bytes());
+        sector[510..512].copy_from_slice(&[0x55, 0xaa]);
+    }
+
+    // Every sector filled with its own number, so each partition hashes differently
+    fn blank() -> Vec<u8> {
+        (0..SECTORS).flat_map(|s| vec![s as u8; MBR_SECTOR as usize]).collect()
+    }
+
+    fn sector(image: &mut [u8], n: usize) -> &mut [u8] {
+        &mut image[n * MBR_SECTOR as usize..(n + 1) * MBR_SECTOR as usize]
+    }
+
+    fn partitions(tag: &str, image: &[u8]) -> Result<Vec<Row>> {
+        let path = std::env::temp_dir().join(format!("rust-hash-partition-{}-test-{}.img", tag, std::process::id()));
+        fs::write(&path, image).unwrap();
+        let found = hash_partitions(&path, &AtomicU64::new(0), &AtomicBool::new(false));
+        let _ = fs::remove_file(&path);
+        Ok(found?.into_iter().map(|p| (p.number, p.label, p.offset, p.length, p.sha256)).collect())
+    }
+
+    fn sha256(image: &[u8], offset: u64, length: u64) -> String {
+        hex::encode(Sha256::digest(&image[offset as usize..(offset + length) as usize]))
+    }
+
+    // A primary partition and an extended one holding two logical partitions through a chain of EBRs
+    #[test]
+    fn mbr_primary_and_logical_partitions() {
+        let mut image = blank();
+        sector(&mut image, 0).fill(0);
+        entry(sector(&mut image, 0), 0, 0x83, 2, 4);
+        entry(sector(&mut image, 0), 1, 0x05, 8, 20);
+        sector(&mut image, 8).fill(0);
+        entry(sector(&mut image, 8), 0, 0x07, 1, 3);
+        entry(sector(&mut image, 8), 1, 0x05, 6, 4);
+        sector(&mut image, 14).fill(0);
+        entry(sector(&mut image, 14), 0, 0x0b, 1, 2);
+        let found = partitions("mbr", &image).unwrap();
+        let expected = [(1, "Linux (0x83)", 2, 4), (5, "NTFS/exFAT (0x07)", 9, 3), (6, "FAT32 (0x0B)", 15, 2)];
+        assert_eq!(found.len(), expected.len());
+        for (got, (number, label, start, count)) in found.iter().zip(expected) {
+            let (offset, length) = (start * MBR_SECTOR, count * MBR_SECTOR);
+            assert_eq!(got, &(number, label.to_string(), offset, length, sha256(&image, offset, length)));
+        }
+    }
+
+    #[test]
+    fn gpt_entries_behind_a_protective_mbr() {
+        let mut image = blank();
+        sector(&mut image, 0).fill(0);
+        entry(sector(&mut image, 0), 0, PROTECTIVE_MBR, 1, SECTORS as u32 - 1);
+        let header = sector(&mut image, 1);
+        header.fill(0);
+        header[..8].copy_from_slice(GPT_SIGNATURE);
+        header[72..80].copy_from_slice(&2u64.to_le_bytes());
+        header[80..84].copy_from_slice(&4u32.to_le_bytes());
+        header[84..88].copy_from_slice(&128u32.to_le_bytes());
+        let table = sector(&mut image, 2);
+        table.fill(0);
+        // EFI System, stored mixed-endian
+        table[..16].copy_from_slice(&[0x28, 0x73, 0x2A, 0xC1, 0x1F, 0xF8, 0xD2, 0x11, 0xBA, 0x4B, 0x00, 0xA0, 0xC9, 0x3E, 0xC9, 0x3B]);
+        table[32..40].copy_from_slice(&10u64.to_le_bytes());
+        table[40..48].copy_from_slice(&19u64.to_le_bytes());
+        for (i, unit) in "boot".encode_utf16().enumerate() {
+            table[56 + 2 * i..58 + 2 * i].copy_from_slice(&unit.to_le_bytes());
+        }
+        // The third entry is used too; the second is empty
+        table[256..272].copy_from_slice(&[0x11; 16]);
+        table[288..296].copy_from_slice(&20u64.to_le_bytes());
+        table[296..304].copy_from_slice(&20u64.to_le_bytes());
+        let found = partitions("gpt", &image).unwrap();
+        assert_eq!(found[0], (1, "boot (EFI System)".to_string(), 5120, 5120, sha256(&image, 5120, 5120)));
+        assert_eq!(found[1], (3, "11111111-1111-1111-1111-111111111111".to_string(), 10240, 512, sha256(&image, 10240, 512)));
+        assert_eq!(found.len(), 2);
+    }
+
+    // A boot sector that merely ends in 55 AA
+    #[test]
+    fn tables_pointing_outside_the_image_are_no_tables() {
+        let mut image = blank();
+        entry(sector(&mut image, 0), 0, 0x83, 2, 1000);
+        let error = partitions("boot", &image).unwrap_err();
+        assert!(error.to_string().contains("No GPT or MBR partition table"), "{}", error);
+    }
+}
@@ -0,0 +1,198 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use icu_normalizer::{ComposingNormalizerBorrowed, DecomposingNormalizerBorrowed};
+
+// Separator written between path components in manifests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Separator {
+    Native,
+    Slash,
+    Backslash,
+}
+
+impl Separator {
+    pub fn parse(name: &str) -> Option<Separator> {
+        match name {
+            "native" => Some(Separator::Native),
+            "/" | "slash" => Some(Separator::Slash),
+            "\\" | "backslash" => Some(Separator::Backslash),
+            _ => None,
+        }
+    }
+
+    fn char(self) -> char {
+        match self {
+            Separator::Native => std::path::MAIN_SEPARATOR,
+            Separator::Slash => '/',
+            Separator::Backslash => '\\',
+        }
+    }
+}
+
+// Unicode form for names; macOS hands out decomposed (NFD) names, Windows and Linux usually composed ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Form {
+    AsIs,
+    Nfc,
+    Nfd,
+}
+
+impl Form {
+    pub fn parse(name: &str) -> Option<Form> {
+        match name.to_ascii_lowercase().as_str() {
+            "none" => Some(Form::AsIs),
+            "nfc" => Some(Form::Nfc),
+            "nfd" => Some(Form::Nfd),
+            _ => None,
+        }
+    }
+
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            Form::AsIs => text.to_string(),
+            Form::Nfc => ComposingNormalizerBorrowed::new_nfc().normalize(text).into_owned(),
+            Form::Nfd => DecomposingNormalizerBorrowed::new_nfd().normalize(text).into_owned(),
+        }
+    }
+}
+
+// `path` with its components joined by `separator` and every name in `form`. Only real separators
+// are replaced: a `\` inside a Linux file name stays part of the name
+pub fn render(path: &Path, separator: Separator, form: Form) -> String {
+    let sep = separator.char();
+    let mut out = String::new();
+    // False at the start, after a root and after a drive prefix (`C:name` is relative to the drive)
+    let mut needs_sep = false;
+    for component in path.components() {
+        match component {
+            Component::Prefix(p) => out.push_str(&p.as_os_str().to_string_lossy()),
+            Component::RootDir => out.push(sep),
+            other => {
+                if needs_sep {
+                    out.push(sep);
+                }
+                out.push_str(&form.apply(&other.as_os_str().to_string_lossy()));
+                needs_sep = true;
+                continue;
+            }
+        }
+        needs_sep = false;
+    }
+    out
+}
+
+// Lexical, so it works for files that no longer exist; "" is the working folder
+pub fn absolute(path: &Path) -> PathBuf {
+    let path = if path.as_os_str().is_empty() { Path::new(".") } else { path };
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+// `file` as seen from `base`, for manifests verified from another folder; files outside `base` keep their full path
+pub fn relative_to(file: &Path, base: &Path) -> PathBuf {
+    let file = absolute(file);
+    match file.strip_prefix(absolute(base)) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => file,
+    }
+}
+
+// How loosely verify matches a listed name that does not exist as written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Matching {
+    // `\` in a list from Windows separates folders on Linux and macOS too
+    pub separators: bool,
+    pub ignore_case: bool,
+    // NFC and NFD spellings of a name are the same file
+    pub unicode: bool,
+}
+
+impl Matching {
+    fn key(&self, name: &str) -> String {
+        let name = if self.unicode { Form::Nfc.apply(name) } else { name.to_string() };
+        if self.ignore_case {
+            name.to_lowercase()
+        } else {
+            name
+        }
+    }
+}
+
+// The file `path` names once separators, case and Unicode form are allowed to differ, looked up
+// one folder at a time. None when nothing matches
+pub fn locate(path: &Path, matching: Matching) -> Option<PathBuf> {
+    let mut found = PathBuf::new();
+    for component in path.components() {
+        let Component::Normal(name) = component else {
+            found.push(component);
+            continue;
+        };
+        let name = name.to_string_lossy();
+        let parts: Vec<&str> = if matching.separators && !cfg!(windows) { name.split('\\').filter(|p| !p.is_empty()).collect() } else { vec![&name] };
+        for part in parts {
+            let exact = found.join(part);
+            if exact.symlink_metadata().is_ok() {
+                found = exact;
+                continue;
+            }
+            let key = matching.key(part);
+            let dir = if found.as_os_str().is_empty() { Path::new(".") } else { &found };
+            let entry = fs::read_dir(dir).ok()?.filter_map(|e| e.ok()).find(|e| matching.key(&e.file_name().to_string_lossy()) == key)?;
+            found.push(entry.file_name());
+        }
+    }
+    Some(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separators_are_rewritten() {
+        let path = Path::new("dir").join("sub").join("file.txt");
+        assert_eq!(render(&path, Separator::Slash, Form::AsIs), "dir/sub/file.txt");
+        assert_eq!(render(&path, Separator::Backslash, Form::AsIs), "dir\\sub\\file.txt");
+        assert_eq!(render(Path::new("/abs/x"), Separator::Slash, Form::AsIs), "/abs/x");
+    }
+
+    // A backslash is part of a Linux or macOS file name, not a separator
+    #[cfg(unix)]
+    #[test]
+    fn backslash_in_a_name_stays() {
+        assert_eq!(render(Path::new("dir/a\\b"), Separator::Slash, Form::AsIs), "dir/a\\b");
+    }
+
+    #[test]
+    fn unicode_forms() {
+        let decomposed = "cafe\u{301}";
+        assert_eq!(Form::Nfc.apply(decomposed), "caf\u{e9}");
+        assert_eq!(Form::Nfd.apply("caf\u{e9}"), decomposed);
+        assert_eq!(render(&Path::new("d\u{e9}j\u{e0}").join(decomposed), Separator::Slash, Form::Nfc), "d\u{e9}j\u{e0}/caf\u{e9}");
+        assert_eq!(Form::parse("NFD"), Some(Form::Nfd));
+        assert_eq!(Form::parse("nfkc"), None);
+    }
+
+    #[test]
+    fn locate_ignores_case_and_form_when_asked() {
+        let dir = std::env::temp_dir().join(format!("rust-hash-path-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("Sub")).unwrap();
+        fs::write(dir.join("Sub").join("caf\u{e9}.txt"), b"").unwrap();
+        let listed = dir.join("sub").join("CAFE\u{301}.TXT");
+        let loose = Matching { separators: true, ignore_case: true, unicode: true };
+        let found = locate(&listed, loose);
+        let strict = locate(&listed, Matching::default());
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(found, Some(dir.join("Sub").join("caf\u{e9}.txt")));
+        // Windows and macOS file systems ignore case themselves
+        if cfg!(target_os = "linux") {
+            assert_eq!(strict, None);
+        }
+    }
+
+    #[test]
+    fn names_relative_to_a_base() {
+        assert_eq!(relative_to(&Path::new("top").join("sub").join("f"), Path::new("top")), Path::new("sub").join("f"));
+        assert_eq!(relative_to(Path::new("other/f"), Path::new("top")), absolute(Path::new("other/f")));
+    }
+}
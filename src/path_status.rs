@@ -0,0 +1,53 @@
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use crate::special::{self, SpecialFile};
+
+// What the typed path currently points at, checked on each edit for inline feedback
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathStatus {
+    #[default]
+    Empty,
+    File(u64),
+    Directory,
+    Special(SpecialFile),
+    Missing,
+    NoPermission,
+}
+
+impl PathStatus {
+    pub fn check(input: &str) -> Self {
+        let input = input.trim();
+        if input.is_empty() {
+            return PathStatus::Empty;
+        }
+        let path = Path::new(input);
+        let meta = match std::fs::metadata(path) {
+            Ok(meta) => meta,
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => return PathStatus::NoPermission,
+            Err(_) => return PathStatus::Missing,
+        };
+        if meta.is_dir() {
+            return PathStatus::Directory;
+        }
+        // Checked before opening: opening a FIFO blocks until a writer appears
+        if let Some(kind) = special::detect(path) {
+            return PathStatus::Special(kind);
+        }
+        match File::open(path) {
+            Ok(_) => PathStatus::File(meta.len()),
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => PathStatus::NoPermission,
+            Err(_) => PathStatus::Missing,
+        }
+    }
+
+    // Auto-hash only fires for paths that can actually be read
+    pub fn is_hashable(self) -> bool {
+        match self {
+            PathStatus::File(_) => true,
+            PathStatus::Special(kind) => kind.hashable(),
+            _ => false,
+        }
+    }
+}
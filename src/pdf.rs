@@ -0,0 +1,220 @@
+use std::fmt::Write as _;
+
+use anyhow::{anyhow, Result};
+
+// Minimal PDF 1.4 writer: text in the standard Helvetica/Courier fonts, lines and one optional
+// JPEG image. Enough for tabular reports without pulling in a PDF crate.
+
+// Landscape A4 in points
+pub const PAGE_WIDTH: f32 = 842.0;
+pub const PAGE_HEIGHT: f32 = 595.0;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Font {
+    Regular,
+    Bold,
+    Mono,
+}
+
+impl Font {
+    fn resource(self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+            Font::Mono => "F3",
+        }
+    }
+
+    // Rough average glyph width as a fraction of the font size, used to fit text into columns
+    pub fn char_width(self) -> f32 {
+        match self {
+            Font::Regular => 0.5,
+            Font::Bold => 0.55,
+            Font::Mono => 0.6,
+        }
+    }
+}
+
+pub struct Jpeg {
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    components: u8,
+}
+
+impl Jpeg {
+    // JPEG data is embedded as-is (DCTDecode); only the frame header is read for size and color space
+    pub fn parse(data: Vec<u8>) -> Result<Jpeg> {
+        if data.len() < 4 || data[0] != 0xFF || data[1] != 0xD8 {
+            return Err(anyhow!("Logo is not a JPEG file"));
+        }
+        let mut pos = 2;
+        while pos + 9 < data.len() {
+            if data[pos] != 0xFF {
+                pos += 1;
+                continue;
+            }
+            let marker = data[pos + 1];
+            // Fill bytes before a marker
+            if marker == 0xFF {
+                pos += 1;
+                continue;
+            }
+            let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+            // SOF0..SOF15 except DHT (C4), JPG (C8) and DAC (CC)
+            if (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+                let height = u16::from_be_bytes([data[pos + 5], data[pos + 6]]) as u32;
+                let width = u16::from_be_bytes([data[pos + 7], data[pos + 8]]) as u32;
+                let components = data[pos + 9];
+                return Ok(Jpeg { data, width, height, components });
+            }
+            pos += 2 + len;
+        }
+        Err(anyhow!("Logo JPEG has no frame header"))
+    }
+
+    pub fn aspect(&self) -> f32 {
+        self.width as f32 / self.height.max(1) as f32
+    }
+}
+
+#[derive(Default)]
+pub struct Document {
+    pages: Vec<String>,
+    current: String,
+    image: Option<Jpeg>,
+}
+
+impl Document {
+    pub fn new() -> Self {
+        Document::default()
+    }
+
+    pub fn new_page(&mut self) {
+        if !self.current.is_empty() {
+            self.pages.push(std::mem::take(&mut self.current));
+        }
+    }
+
+    // `y` is the baseline, measured from the bottom of the page
+    pub fn text(&mut self, font: Font, size: f32, x: f32, y: f32, s: &str) {
+        let _ = writeln!(self.current, "BT /{} {} Tf {:.2} {:.2} Td ({}) Tj ET", font.resource(), size, x, y, encode_text(s));
+    }
+
+    pub fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32) {
+        let _ = writeln!(self.current, "0.5 w {:.2} {:.2} m {:.2} {:.2} l S", x1, y1, x2, y2);
+    }
+
+    // Light fill behind a table row
+    pub fn shade(&mut self, x: f32, y: f32, width: f32, height: f32, gray: f32) {
+        let _ = writeln!(self.current, "q {:.2} g {:.2} {:.2} {:.2} {:.2} re f Q", gray, x, y, width, height);
+    }
+
+    pub fn set_image(&mut self, image: Jpeg) {
+        self.image = Some(image);
+    }
+
+    // Draws the image set with `set_image` on the current page
+    pub fn draw_image(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        if self.image.is_some() {
+            let _ = writeln!(self.current, "q {:.2} 0 0 {:.2} {:.2} {:.2} cm /Im1 Do Q", width, height, x, y);
+        }
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        self.new_page();
+        if self.pages.is_empty() {
+            self.pages.push(String::new());
+        }
+        // Fixed objects: 1 catalog, 2 page tree, 3-5 fonts, 6 image (optional); then page + content pairs
+        let first_page = if self.image.is_some() { 7 } else { 6 };
+        let mut objects: Vec<Vec<u8>> = Vec::new();
+        objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+        let kids: Vec<String> = (0..self.pages.len()).map(|i| format!("{} 0 R", first_page + i * 2)).collect();
+        objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), self.pages.len()).into_bytes());
+        for base in ["Helvetica", "Helvetica-Bold", "Courier"] {
+            objects.push(format!("<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>", base).into_bytes());
+        }
+        let xobject = match &self.image {
+            Some(img) => {
+                let color = match img.components {
+                    1 => "/DeviceGray",
+                    4 => "/DeviceCMYK",
+                    _ => "/DeviceRGB",
+                };
+                let mut obj = format!(
+                    "<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace {} /BitsPerComponent 8 /Filter /DCTDecode /Length {} >>\nstream\n",
+                    img.width,
+                    img.height,
+                    color,
+                    img.data.len()
+                )
+                .into_bytes();
+                obj.extend_from_slice(&img.data);
+                obj.extend_from_slice(b"\nendstream");
+                objects.push(obj);
+                " /XObject << /Im1 6 0 R >>"
+            }
+            None => "",
+        };
+        for (i, content) in self.pages.iter().enumerate() {
+            let content_id = first_page + i * 2 + 1;
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R /F3 5 0 R >>{} >> /Contents {} 0 R >>",
+                    PAGE_WIDTH, PAGE_HEIGHT, xobject, content_id
+                )
+                .into_bytes(),
+            );
+            objects.push(format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content).into_bytes());
+        }
+
+        let mut out = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, obj) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+            out.extend_from_slice(obj);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+        let xref = out.len();
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(table, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(table, "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref);
+        out.extend_from_slice(table.as_bytes());
+        out
+    }
+}
+
+// Cuts `s` to what fits in `width` points, marking the cut with "..."
+pub fn fit(s: &str, font: Font, size: f32, width: f32) -> String {
+    let max = (width / (size * font.char_width())).floor() as usize;
+    let count = s.chars().count();
+    if count <= max {
+        return s.to_string();
+    }
+    // Keep the end of the text: for paths the file name matters most
+    let keep = max.saturating_sub(3);
+    format!("...{}", s.chars().skip(count - keep).collect::<String>())
+}
+
+// Literal string in WinAnsi (Latin-1 range); other characters become '?'
+fn encode_text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            '\u{a0}'..='\u{ff}' => {
+                let _ = write!(out, "\\{:03o}", c as u32);
+            }
+            _ => out.push('?'),
+        }
+    }
+    out
+}
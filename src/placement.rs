@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+const PLACEMENT_FILE: &str = "window.json";
+
+// Where the window was and how big, in logical pixels, so it opens the same size on a 100% or a 150%
+// monitor. Kept apart from the settings, which are exported to other machines with other screens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Placement {
+    // None until the window has been moved; it then opens centered
+    pub position: Option<(i32, i32)>,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Placement {
+    // A minimized window reports a zero size and, on Windows, a position of -32000; neither is worth keeping
+    pub fn plausible(&self) -> bool {
+        self.width > 0 && self.height > 0 && self.position.is_none_or(|(x, y)| is_position(x) && is_position(y))
+    }
+}
+
+// The last placement on each monitor, keyed by `monitor`, so moving to a monitor the window has been on
+// before brings back the size it had there
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Placements {
+    // Monitor the window was on when it closed, where it reopens
+    pub last: String,
+    pub monitors: BTreeMap<String, Placement>,
+}
+
+impl Placements {
+    // Empty when missing or unreadable; a file from before monitors were told apart becomes the
+    // placement on an unnamed one
+    pub fn load() -> Self {
+        let Some(data) = placement_path().and_then(|p| fs::read(p).ok()) else { return Self::default() };
+        if let Ok(placements) = serde_json::from_slice::<Placements>(&data) {
+            if !placements.monitors.is_empty() {
+                return placements;
+            }
+        }
+        let mut placements = Self::default();
+        if let Ok(old) = serde_json::from_slice::<Placement>(&data) {
+            placements.remember("", old);
+        }
+        placements
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = placement_path().context("No data directory available for the window placement")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+        }
+        crate::atomic::write(&path, &serde_json::to_vec_pretty(self)?)
+    }
+
+    // Where the window closed last
+    pub fn current(&self) -> Option<Placement> {
+        self.on(&self.last)
+    }
+
+    pub fn on(&self, monitor: &str) -> Option<Placement> {
+        self.monitors.get(monitor).copied()
+    }
+
+    // Ignored unless `plausible`
+    pub fn remember(&mut self, monitor: &str, placement: Placement) {
+        if placement.plausible() {
+            self.monitors.insert(monitor.to_string(), placement);
+            self.last = monitor.to_string();
+        }
+    }
+}
+
+// Name of the monitor the window is on ("\\.\DISPLAY2" on Windows); "" where monitors are not told apart
+#[cfg(windows)]
+pub fn monitor(handle: &iced::window::raw_window_handle::WindowHandle<'_>) -> String {
+    use iced::window::raw_window_handle::RawWindowHandle;
+    use windows_sys::Win32::Graphics::Gdi::{GetMonitorInfoW, MonitorFromWindow, MONITORINFO, MONITORINFOEXW, MONITOR_DEFAULTTONEAREST};
+
+    let RawWindowHandle::Win32(window) = handle.as_raw() else { return String::new() };
+    unsafe {
+        let monitor = MonitorFromWindow(window.hwnd.get() as _, MONITOR_DEFAULTTONEAREST);
+        let mut info: MONITORINFOEXW = std::mem::zeroed();
+        info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+        if monitor.is_null() || GetMonitorInfoW(monitor, &mut info as *mut MONITORINFOEXW as *mut MONITORINFO) == 0 {
+            return String::new();
+        }
+        let len = info.szDevice.iter().position(|&c| c == 0).unwrap_or(info.szDevice.len());
+        String::from_utf16_lossy(&info.szDevice[..len])
+    }
+}
+
+#[cfg(not(windows))]
+pub fn monitor(_handle: &iced::window::raw_window_handle::WindowHandle<'_>) -> String {
+    String::new()
+}
+
+pub fn is_position(v: i32) -> bool {
+    (-10_000..=30_000).contains(&v)
+}
+
+fn placement_path() -> Option<PathBuf> {
+    storage::data_dir().map(|d| d.join(PLACEMENT_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_monitor_keeps_its_own_placement() {
+        let mut placements = Placements::default();
+        placements.remember("A", Placement { position: Some((10, 10)), width: 900, height: 700 });
+        placements.remember("B", Placement { position: Some((2000, 10)), width: 1200, height: 800 });
+        placements.remember("A", Placement { position: Some((-32000, -32000)), width: 0, height: 0 });
+        assert_eq!(placements.last, "B");
+        assert_eq!(placements.on("A").map(|p| p.width), Some(900));
+        assert_eq!(placements.current().map(|p| p.width), Some(1200));
+        let json = serde_json::to_vec(&placements).unwrap();
+        assert_eq!(serde_json::from_slice::<Placements>(&json).unwrap(), placements);
+    }
+}
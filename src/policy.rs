@@ -0,0 +1,132 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::algo::Algorithm;
+
+// Next to the executable, for installs where its folder is writable by administrators only
+pub const POLICY_FILE: &str = "rust-hash.policy.json";
+
+// Settings an administrator fixes for every user of the machine. They win over config.json and the
+// Settings page, which only shows them. The file is read once per run and never written
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Policy {
+    // No webhooks, SMTP email or hashing over SSH, whatever the user turned on
+    pub offline: bool,
+    // `sha256`, `blake3`, ...: what may be computed besides SHA-256, which the window is built on and is
+    // always allowed. Empty allows all
+    allowed_algorithms: Vec<String>,
+    // Nothing is read from or written to the history, so no record of hashed files is left behind
+    pub disable_history: bool,
+    #[serde(skip)]
+    allowed: Vec<Algorithm>,
+    // Where the policy came from; None when there is none
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+    // Why the file could not be used; the strictest policy applies instead
+    #[serde(skip)]
+    pub error: Option<String>,
+}
+
+static POLICY: Lazy<Policy> = Lazy::new(load);
+
+pub fn get() -> &'static Policy {
+    &POLICY
+}
+
+impl Policy {
+    pub fn allows(&self, algorithm: Algorithm) -> bool {
+        algorithm == Algorithm::Sha256 || self.allowed.is_empty() || self.allowed.contains(&algorithm)
+    }
+
+    // Why `algorithm` may not be computed; None when it may
+    pub fn refusal(&self, algorithm: Algorithm) -> Option<String> {
+        let source = self.source.as_ref().filter(|_| !self.allows(algorithm))?;
+        Some(format!("{} is not allowed by the policy in {}", algorithm.label(), source.display()))
+    }
+
+    // The same for a digest that is not an `Algorithm` (the BLAKE2 of rsync signatures). It cannot be
+    // listed in `allowed_algorithms`, so any list rules it out
+    pub fn refusal_of(&self, name: &str) -> Option<String> {
+        let source = self.source.as_ref().filter(|_| !self.allowed.is_empty())?;
+        Some(format!("{} is not allowed by the policy in {}", name, source.display()))
+    }
+
+    // For the Settings page and diagnostics, e.g. `/etc/rust-hash/policy.json: offline, no history`
+    pub fn describe(&self) -> Option<String> {
+        let source = self.source.as_ref()?;
+        let mut rules = Vec::new();
+        if self.offline {
+            rules.push("offline".to_string());
+        }
+        if !self.allowed.is_empty() {
+            let names: Vec<&str> = self.allowed.iter().map(|a| a.label()).collect();
+            rules.push(format!("algorithms {}", names.join(", ")));
+        }
+        if self.disable_history {
+            rules.push("no history".to_string());
+        }
+        if rules.is_empty() {
+            rules.push("no restrictions".to_string());
+        }
+        Some(format!("{}: {}", source.display(), rules.join(", ")))
+    }
+}
+
+fn load() -> Policy {
+    let Some(path) = locations().into_iter().find(|p| p.is_file()) else {
+        return Policy::default();
+    };
+    match parse(&path) {
+        Ok(policy) => Policy { source: Some(path), ..policy },
+        // A policy that cannot be read must not mean no policy
+        Err(e) => Policy {
+            offline: true,
+            disable_history: true,
+            allowed: vec![Algorithm::Sha256],
+            error: Some(format!("{:#}", e)),
+            source: Some(path),
+            ..Policy::default()
+        },
+    }
+}
+
+fn parse(path: &Path) -> Result<Policy> {
+    let data = fs::read(path).with_context(|| format!("Failed to read policy: {}", path.display()))?;
+    let mut policy: Policy = serde_json::from_slice(&data).with_context(|| format!("Not a valid policy file: {}", path.display()))?;
+    policy.allowed = policy
+        .allowed_algorithms
+        .iter()
+        .map(|name| Algorithm::parse(name).ok_or_else(|| anyhow!("Unknown algorithm {} in {} (use {})", name, path.display(), Algorithm::NAMES)))
+        .collect::<Result<_>>()?;
+    Ok(policy)
+}
+
+// The machine-wide location first, then the one beside the executable
+fn locations() -> Vec<PathBuf> {
+    let mut paths = vec![system_path()];
+    if let Some(dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)) {
+        paths.push(dir.join(POLICY_FILE));
+    }
+    paths
+}
+
+#[cfg(windows)]
+fn system_path() -> PathBuf {
+    let program_data = std::env::var_os("ProgramData").map_or_else(|| PathBuf::from("C:\\ProgramData"), PathBuf::from);
+    program_data.join("rust-hash").join("policy.json")
+}
+
+#[cfg(target_os = "macos")]
+fn system_path() -> PathBuf {
+    PathBuf::from("/Library/Application Support/rust-hash/policy.json")
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn system_path() -> PathBuf {
+    PathBuf::from("/etc/rust-hash/policy.json")
+}
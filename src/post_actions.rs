@@ -0,0 +1,253 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+// What to do after a file hashed successfully; empty text fields mean "off"
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PostActions {
+    pub copy_hex: bool,
+    // `<file>.sha256` next to the file, in sha256sum format
+    pub write_sidecar: bool,
+    // Manifest file that every digest is appended to
+    pub manifest: String,
+    // Folder that files are moved to once hashed (unless the digest CHANGED since the last hash)
+    pub move_to: String,
+    // Program and arguments; {path}, {hex}, {base64} and {bytes} are replaced per argument
+    pub command: String,
+}
+
+impl PostActions {
+    pub fn any(&self) -> bool {
+        self.copy_hex || self.write_sidecar || !self.manifest.trim().is_empty() || !self.move_to.trim().is_empty() || !self.command.trim().is_empty()
+    }
+}
+
+// The finished digest the actions work with
+pub struct Digest<'a> {
+    pub path: &'a Path,
+    pub hex: &'a str,
+    pub base64: &'a str,
+    pub bytes: u64,
+    // The digest differs from the one recorded for this file earlier
+    pub changed: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Outcome {
+    // Text to put on the clipboard; the UI owns the clipboard, so it is handed back
+    pub clipboard: Option<String>,
+    pub moved_to: Option<PathBuf>,
+    pub errors: Vec<String>,
+}
+
+pub fn run(actions: &PostActions, digest: &Digest) -> Outcome {
+    let mut out = Outcome::default();
+    if actions.copy_hex {
+        out.clipboard = Some(digest.hex.to_string());
+    }
+    if actions.write_sidecar {
+        if let Err(e) = write_sidecar(digest) {
+            out.errors.push(format!("{:#}", e));
+        }
+    }
+    if !actions.manifest.trim().is_empty() {
+        if let Err(e) = append_manifest(Path::new(actions.manifest.trim()), digest) {
+            out.errors.push(format!("{:#}", e));
+        }
+    }
+    // The command sees the original location, so it runs before the move
+    if !actions.command.trim().is_empty() {
+        if let Err(e) = run_command(actions.command.trim(), digest) {
+            out.errors.push(format!("{:#}", e));
+        }
+    }
+    if !actions.move_to.trim().is_empty() {
+        if digest.changed {
+            out.errors.push(format!("Not moved: {} CHANGED since it was last hashed", digest.path.display()));
+        } else {
+            match move_file(digest.path, Path::new(actions.move_to.trim())) {
+                Ok(target) => out.moved_to = Some(target),
+                Err(e) => out.errors.push(format!("{:#}", e)),
+            }
+        }
+    }
+    out
+}
+
+// Windows paths are written as is here: the GUI's sidecars and manifests are read back by Windows tools
+pub fn sha256sum_line(hex: &str, name: &str) -> String {
+    format!("{} *{}\n", hex, name)
+}
+
+// One coreutils-style line. Names with a backslash, CR or LF get coreutils' escaping (the line
+// starts with `\`) so `sha256sum -c` reads them back; `zero` ends the line with NUL instead and
+// writes the name as is, like `sha256sum --zero`.
+pub fn checksum_line(hex: &str, name: &str, binary: bool, zero: bool) -> String {
+    let marker = if binary { '*' } else { ' ' };
+    if zero {
+        return format!("{} {}{}\0", hex, marker, name);
+    }
+    if name.contains(['\\', '\n', '\r']) {
+        let escaped = name.replace('\\', "\\\\").replace('\n', "\\n").replace('\r', "\\r");
+        return format!("\\{} {}{}\n", hex, marker, escaped);
+    }
+    format!("{} {}{}\n", hex, marker, name)
+}
+
+// The BSD-style `TAG (name) = hex` form of `checksum_line`, escaped the same way
+pub fn tagged_line(tag: &str, hex: &str, name: &str, zero: bool) -> String {
+    if zero {
+        return format!("{} ({}) = {}\0", tag, name, hex);
+    }
+    if name.contains(['\\', '\n', '\r']) {
+        let escaped = name.replace('\\', "\\\\").replace('\n', "\\n").replace('\r', "\\r");
+        return format!("\\{} ({}) = {}\n", tag, escaped, hex);
+    }
+    format!("{} ({}) = {}\n", tag, name, hex)
+}
+
+// The rpm `FILEDIGESTS` form, `/name hex`, escaped and marked the same way
+pub fn rpm_line(hex: &str, name: &str, zero: bool) -> String {
+    if zero {
+        return format!("/{} {}\0", name, hex);
+    }
+    if name.contains(['\\', '\n', '\r']) {
+        let escaped = name.replace('\\', "\\\\").replace('\n', "\\n").replace('\r', "\\r");
+        return format!("\\/{} {}\n", escaped, hex);
+    }
+    format!("/{} {}\n", name, hex)
+}
+
+fn write_sidecar(digest: &Digest) -> Result<()> {
+    let name = digest.path.file_name().ok_or_else(|| anyhow!("No file name: {}", digest.path.display()))?;
+    let mut target = digest.path.as_os_str().to_owned();
+    target.push(".sha256");
+    let target = PathBuf::from(target);
+    fs::write(&target, sha256sum_line(digest.hex, &name.to_string_lossy()))
+        .with_context(|| format!("Failed to write sidecar: {}", target.display()))
+}
+
+// Rewritten through a temporary file, so a crash mid-write cannot truncate a long-lived manifest
+fn append_manifest(manifest: &Path, digest: &Digest) -> Result<()> {
+    crate::atomic::append(manifest, sha256sum_line(digest.hex, &digest.path.display().to_string()).as_bytes())
+        .with_context(|| format!("Failed to append to manifest: {}", manifest.display()))
+}
+
+fn move_file(path: &Path, folder: &Path) -> Result<PathBuf> {
+    let name = path.file_name().ok_or_else(|| anyhow!("No file name: {}", path.display()))?;
+    fs::create_dir_all(folder).with_context(|| format!("Failed to create directory: {}", folder.display()))?;
+    let target = folder.join(name);
+    if target.exists() {
+        return Err(anyhow!("Not moved: {} already exists", target.display()));
+    }
+    // rename fails across volumes; fall back to copy + delete
+    if fs::rename(path, &target).is_err() {
+        fs::copy(path, &target).with_context(|| format!("Failed to copy {} to {}", path.display(), target.display()))?;
+        fs::remove_file(path).with_context(|| format!("Copied, but failed to remove: {}", path.display()))?;
+    }
+    Ok(target)
+}
+
+// Started without waiting, so a slow script does not hold up the next job; a thread waits for it
+// instead, so it does not linger as a zombie once it exits
+fn run_command(command_line: &str, digest: &Digest) -> Result<()> {
+    let mut parts = split_command_line(command_line).into_iter().map(|arg| fill_placeholders(&arg, digest));
+    let program = parts.next().ok_or_else(|| anyhow!("Empty post-job command"))?;
+    let mut child = Command::new(&program)
+        .args(parts)
+        .spawn()
+        .with_context(|| format!("Failed to run post-job command: {}", program))?;
+    thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+// `{path}`, `{hex}`, `{base64}` and `{bytes}` in one pass over the argument, so a path that itself
+// contains `{hex}` is passed on as it is
+fn fill_placeholders(arg: &str, digest: &Digest) -> String {
+    let mut out = String::with_capacity(arg.len());
+    let mut rest = arg;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let placeholder = rest.find('}').map_or(rest, |end| &rest[..=end]);
+        let value = match placeholder {
+            "{path}" => digest.path.display().to_string(),
+            "{hex}" => digest.hex.to_string(),
+            "{base64}" => digest.base64.to_string(),
+            "{bytes}" => digest.bytes.to_string(),
+            _ => {
+                out.push('{');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+        out.push_str(&value);
+        rest = &rest[placeholder.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+// Whitespace-separated arguments; double quotes group an argument that contains spaces
+fn split_command_line(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_arg = false;
+    for c in line.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+    if has_arg {
+        args.push(current);
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_are_filled_once() {
+        let path = Path::new("/data/{hex} and {bytes}.iso");
+        let digest = Digest { path, hex: "abc", base64: "q8=", bytes: 42, changed: false };
+        assert_eq!(fill_placeholders("--file={path}", &digest), "--file=/data/{hex} and {bytes}.iso");
+        assert_eq!(fill_placeholders("{hex}:{base64}:{bytes}", &digest), "abc:q8=:42");
+        assert_eq!(fill_placeholders("{{hex}} {unknown} {", &digest), "{abc} {unknown} {");
+    }
+
+    #[test]
+    fn quoted_arguments() {
+        assert_eq!(split_command_line(r#"notify "a b"  {path} """#), vec!["notify", "a b", "{path}", ""]);
+    }
+
+    #[test]
+    fn odd_names_are_escaped() {
+        assert_eq!(checksum_line("ab", "a\\b\nc", false, false), "\\ab  a\\\\b\\nc\n");
+        assert_eq!(checksum_line("ab", "a\\b", true, true), "ab *a\\b\0");
+        assert_eq!(rpm_line("ab", "usr/a b\nc", false), "\\/usr/a b\\nc ab\n");
+        assert_eq!(rpm_line("ab", "usr/a\\b", true), "/usr/a\\b ab\0");
+    }
+}
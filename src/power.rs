@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+// How often the window refreshes while something is running
+pub const NORMAL_TICK: Duration = Duration::from_millis(100);
+pub const REDUCED_TICK: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpdateRate {
+    Normal,
+    // Reduced while the machine runs on battery
+    #[default]
+    ReducedOnBattery,
+    // Reduced always, which also drops the drop-zone flash for anyone who prefers less motion
+    Reduced,
+}
+
+pub const UPDATE_RATES: [UpdateRate; 3] = [UpdateRate::Normal, UpdateRate::ReducedOnBattery, UpdateRate::Reduced];
+
+impl UpdateRate {
+    pub fn parse(name: &str) -> UpdateRate {
+        match name {
+            "normal" => UpdateRate::Normal,
+            "reduced" => UpdateRate::Reduced,
+            _ => UpdateRate::ReducedOnBattery,
+        }
+    }
+
+    // As stored in the settings file
+    pub fn key(self) -> &'static str {
+        match self {
+            UpdateRate::Normal => "normal",
+            UpdateRate::ReducedOnBattery => "battery",
+            UpdateRate::Reduced => "reduced",
+        }
+    }
+
+    pub fn interval(self, on_battery: bool) -> Duration {
+        match self {
+            UpdateRate::Normal => NORMAL_TICK,
+            UpdateRate::ReducedOnBattery if !on_battery => NORMAL_TICK,
+            _ => REDUCED_TICK,
+        }
+    }
+}
+
+impl std::fmt::Display for UpdateRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            UpdateRate::Normal => "Smooth updates",
+            UpdateRate::ReducedOnBattery => "Fewer updates on battery",
+            UpdateRate::Reduced => "Fewer updates, less motion",
+        })
+    }
+}
+
+// True when the machine is known to run on battery; false when on mains power or when it cannot tell
+#[cfg(windows)]
+pub fn on_battery() -> bool {
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+    let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+    // ACLineStatus: 0 offline, 1 online, 255 unknown
+    unsafe { GetSystemPowerStatus(&mut status) != 0 && status.ACLineStatus == 0 }
+}
+
+#[cfg(target_os = "macos")]
+pub fn on_battery() -> bool {
+    std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .is_ok_and(|out| String::from_utf8_lossy(&out.stdout).contains("'Battery Power'"))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn on_battery() -> bool {
+    let Ok(supplies) = std::fs::read_dir("/sys/class/power_supply") else {
+        return false;
+    };
+    supplies.filter_map(|e| e.ok()).any(|supply| {
+        let read = |name: &str| std::fs::read_to_string(supply.path().join(name)).map(|s| s.trim().to_string()).unwrap_or_default();
+        read("type") == "Battery" && read("status") == "Discharging"
+    })
+}
@@ -0,0 +1,123 @@
+// Progress of a hashing job as one source of truth for every front end.
+//
+// The worker only bumps the byte counter it was handed (and, for multi-file jobs, calls
+// `start_file`/`finish_file`); readers take a `Snapshot` whenever they like, e.g. on each GUI
+// tick, or get one pushed at a fixed interval through `watch`. Every field is an atomic or sits behind a short-lived mutex, so a `Tracker` can be
+// shared through an `Arc` and read from any thread while the worker writes to it. Readers never
+// block the worker for longer than a path clone.
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// Stored in `total` when the size is not known (character devices, followed files)
+const UNKNOWN: u64 = u64::MAX;
+
+pub struct Tracker {
+    // Bytes read by the worker; the counter the hashing functions already update
+    bytes: Arc<AtomicU64>,
+    total: AtomicU64,
+    files: usize,
+    files_done: AtomicUsize,
+    // Bytes of the files finished so far, when `bytes` restarts at 0 for every file
+    finished_bytes: AtomicU64,
+    current: Mutex<Option<PathBuf>>,
+    finished: AtomicBool,
+    started: Instant,
+}
+
+// What a front end shows at one moment
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub bytes: u64,
+    pub total: Option<u64>,
+    pub current: Option<PathBuf>,
+    pub files_remaining: usize,
+    // From the average rate so far; None until something was read or without a total
+    pub eta: Option<Duration>,
+    pub finished: bool,
+}
+
+impl Snapshot {
+    pub fn percent(&self) -> Option<f64> {
+        self.total.filter(|t| *t > 0).map(|t| (self.bytes as f64 / t as f64 * 100.0).clamp(0.0, 100.0))
+    }
+}
+
+impl Tracker {
+    // `bytes` is the counter passed to the hashing functions; `total` covers all `files`
+    pub fn new(bytes: Arc<AtomicU64>, total: Option<u64>, files: usize) -> Arc<Tracker> {
+        Arc::new(Tracker {
+            bytes,
+            total: AtomicU64::new(total.unwrap_or(UNKNOWN)),
+            files,
+            files_done: AtomicUsize::new(0),
+            finished_bytes: AtomicU64::new(0),
+            current: Mutex::new(None),
+            finished: AtomicBool::new(false),
+            started: Instant::now(),
+        })
+    }
+
+    // The counter to hand to the worker
+    pub fn counter(&self) -> Arc<AtomicU64> {
+        self.bytes.clone()
+    }
+
+    // For totals only known once the worker has measured its inputs
+    pub fn set_total(&self, total: Option<u64>) {
+        self.total.store(total.unwrap_or(UNKNOWN), Ordering::Relaxed);
+    }
+
+    pub fn start_file(&self, path: &Path) {
+        *self.current.lock().unwrap_or_else(|e| e.into_inner()) = Some(path.to_path_buf());
+    }
+
+    // For workers whose counter restarts per file: folds the file's bytes into the running total
+    pub fn finish_file(&self) {
+        self.finished_bytes.fetch_add(self.bytes.swap(0, Ordering::Relaxed), Ordering::Relaxed);
+        self.files_done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn finish(&self) {
+        self.finished.store(true, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let bytes = self.finished_bytes.load(Ordering::Relaxed) + self.bytes.load(Ordering::Relaxed);
+        let total = Some(self.total.load(Ordering::Relaxed)).filter(|t| *t != UNKNOWN);
+        let elapsed = self.started.elapsed();
+        let eta = match total {
+            Some(total) if bytes > 0 && total >= bytes => {
+                let rate = bytes as f64 / elapsed.as_secs_f64().max(0.001);
+                Some(Duration::from_secs_f64((total - bytes) as f64 / rate))
+            }
+            _ => None,
+        };
+        Snapshot {
+            bytes,
+            total,
+            current: self.current.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+            files_remaining: self.files.saturating_sub(self.files_done.load(Ordering::Relaxed)),
+            eta,
+            finished: self.finished.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// Stream form for front ends without an event loop (CLI progress output): calls `report` every
+// `every` on its own thread until `finish` is called, then once more with the final state
+pub fn watch<F>(tracker: Arc<Tracker>, every: Duration, mut report: F) -> JoinHandle<()>
+where
+    F: FnMut(&Snapshot) + Send + 'static,
+{
+    thread::spawn(move || loop {
+        let snapshot = tracker.snapshot();
+        report(&snapshot);
+        if snapshot.finished {
+            break;
+        }
+        thread::sleep(every);
+    })
+}
@@ -0,0 +1,38 @@
+// How much of a file each read asks for. Larger reads keep a RAID set or a network share busy, smaller
+// ones keep memory down while many jobs run at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadBuffer(pub usize);
+
+const KIB: usize = 1024;
+const MIB: usize = 1024 * 1024;
+
+pub const DEFAULT: ReadBuffer = ReadBuffer(2 * MIB);
+
+pub const SIZES: [ReadBuffer; 5] = [ReadBuffer(256 * KIB), ReadBuffer(MIB), DEFAULT, ReadBuffer(8 * MIB), ReadBuffer(32 * MIB)];
+
+impl ReadBuffer {
+    // Sizes not in the list fall back to the default, so a hand-edited setting cannot ask for gigabytes
+    pub fn from_setting(kib: u64) -> ReadBuffer {
+        SIZES.into_iter().find(|s| s.kib() == kib).unwrap_or(DEFAULT)
+    }
+
+    pub fn kib(self) -> u64 {
+        (self.0 / KIB) as u64
+    }
+}
+
+impl Default for ReadBuffer {
+    fn default() -> Self {
+        DEFAULT
+    }
+}
+
+impl std::fmt::Display for ReadBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0 >= MIB {
+            write!(f, "Read {} MiB at a time", self.0 / MIB)
+        } else {
+            write!(f, "Read {} KiB at a time", self.0 / KIB)
+        }
+    }
+}
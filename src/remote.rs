@@ -0,0 +1,255 @@
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::algo::{self, Algorithm};
+
+// What the remote shell says for a program it cannot find
+const COMMAND_NOT_FOUND: i32 = 127;
+
+// A file on another machine: `user@host:/path` as scp takes it, or `ssh://user@host:port/path`
+// (`sftp://` is accepted too; both go through the ssh client)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    // `host` or `user@host`, passed to ssh as is, so aliases from ~/.ssh/config work
+    pub host: String,
+    pub port: Option<u16>,
+    pub path: String,
+}
+
+impl Target {
+    pub fn parse(input: &str) -> Option<Target> {
+        let input = input.trim();
+        let url = ["ssh://", "sftp://"].iter().find_map(|scheme| input.get(..scheme.len()).filter(|s| s.eq_ignore_ascii_case(scheme)).map(|s| &input[s.len()..]));
+        if let Some(rest) = url {
+            let (authority, path) = rest.split_at(rest.find('/')?);
+            let (host, port) = match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port.parse().ok()?)),
+                None => (authority, None),
+            };
+            // `sftp://host/~/file` is relative to the home folder, like scp's `host:file`
+            let path = path.strip_prefix("/~/").map(str::to_string).unwrap_or_else(|| path.to_string());
+            return (valid_host(host) && !path.is_empty()).then(|| Target { host: host.to_string(), port, path });
+        }
+        let (host, path) = input.split_once(':')?;
+        // `C:\dir` is a local drive, not a host called C
+        if host.len() < 2 || host.contains(['/', '\\']) || !valid_host(host) || path.is_empty() {
+            return None;
+        }
+        Some(Target { host: host.to_string(), port: None, path: path.to_string() })
+    }
+
+    // ssh with `command` run on the remote machine. BatchMode fails instead of asking for a password
+    // nobody can type into, so keys or an agent are needed
+    fn ssh(&self, command: &str) -> Command {
+        let mut ssh = Command::new("ssh");
+        ssh.args(["-o", "BatchMode=yes"]);
+        if let Some(port) = self.port {
+            ssh.arg("-p").arg(port.to_string());
+        }
+        ssh.arg(&self.host).arg("--").arg(command);
+        ssh.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+        ssh
+    }
+}
+
+// ssh reads a host starting with `-` as an option, e.g. `-oProxyCommand=...`, which would run a local command
+fn valid_host(host: &str) -> bool {
+    !host.is_empty() && !host.starts_with('-')
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.port {
+            Some(port) => write!(f, "{}:{}:{}", self.host, port, self.path),
+            None => write!(f, "{}:{}", self.host, self.path),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Via {
+    // rust-hash on the remote machine read the file; only its JSON result came back
+    RemoteCli,
+    // The bytes came over the connection and were hashed here
+    Stream,
+}
+
+impl std::fmt::Display for Via {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Via::RemoteCli => "hashed by rust-hash on the server",
+            Via::Stream => "streamed over SSH and hashed here",
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Remote {
+    pub hex: String,
+    pub bytes: u64,
+    pub via: Via,
+}
+
+// SHA-256 of a remote file on a worker thread. The remote rust-hash is asked first, since then only a
+// digest crosses the network; without one the file is streamed with `cat`
+pub struct RemoteRun {
+    pub target: Target,
+    pub result: Option<Result<Remote, String>>,
+    progress: Arc<AtomicU64>,
+    cancel: Arc<AtomicBool>,
+    rx: Receiver<Result<Remote>>,
+}
+
+impl RemoteRun {
+    pub fn start(target: Target) -> RemoteRun {
+        let progress = Arc::new(AtomicU64::new(0));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        let (t, p, c) = (target.clone(), progress.clone(), cancel.clone());
+        thread::spawn(move || {
+            let result = match remote_cli(&t, &p, &c) {
+                Ok(Some(remote)) => Ok(remote),
+                Ok(None) => stream(&t, &p, &c),
+                Err(e) => Err(e),
+            };
+            let _ = tx.send(result);
+        });
+        RemoteRun { target, result: None, progress, cancel, rx }
+    }
+
+    pub fn poll(&mut self) {
+        if self.result.is_none() {
+            if let Ok(result) = self.rx.try_recv() {
+                self.result = Some(result.map_err(|e| format!("{:#}", e)));
+            }
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.result.is_none()
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.progress.load(Ordering::Relaxed)
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+// None when the server has no rust-hash, so the caller can stream instead
+fn remote_cli(target: &Target, progress: &AtomicU64, cancel: &AtomicBool) -> Result<Option<Remote>> {
+    // The algorithm is spelled out, since the server's own default may be another one
+    let command = format!("rust-hash --cli --algo sha256 --progress json -- {}", remote_path(&target.path));
+    let mut child = spawn(target.ssh(&command))?;
+    let stderr = child.stderr.take().context("ssh gave no error stream")?;
+    // The lines are read on the side, so a server that goes quiet can still be cancelled
+    let (status, (file_event, other)) = thread::scope(|scope| {
+        let reader = scope.spawn(|| {
+            // `file` is the line with the result; `progress` lines move the byte count meanwhile
+            let mut file_event = None;
+            let mut other = Vec::new();
+            for line in BufReader::new(stderr).lines().map_while(|l| l.ok()) {
+                match serde_json::from_str::<serde_json::Value>(&line) {
+                    Ok(event) if event["event"] == "progress" => progress.store(event["bytes"].as_u64().unwrap_or(0), Ordering::Relaxed),
+                    Ok(event) if event["event"] == "file" => file_event = Some(event),
+                    Ok(_) => {}
+                    Err(_) => other.push(line),
+                }
+            }
+            (file_event, other)
+        });
+        let status = wait(&mut child, cancel);
+        (status, reader.join().unwrap_or_default())
+    });
+    let status = status?;
+    if cancel.load(Ordering::Relaxed) {
+        return Err(anyhow!(crate::errors::CANCELLED));
+    }
+    if status.code() == Some(COMMAND_NOT_FOUND) {
+        return Ok(None);
+    }
+    let Some(event) = file_event else {
+        return Err(ssh_failed(target, &other));
+    };
+    if let Some(error) = event["error"].as_str() {
+        return Err(anyhow!("{}: {}", target, error));
+    }
+    if event["algorithm"].as_str() != Some(Algorithm::Sha256.label()) {
+        return Err(anyhow!("{}: the remote rust-hash sent {} instead of SHA-256", target, event["algorithm"].as_str().unwrap_or("an unknown digest")));
+    }
+    let hex = event["digest"].as_str().filter(|h| h.len() == 64).with_context(|| format!("{}: the remote rust-hash sent no SHA-256", target))?;
+    Ok(Some(Remote { hex: hex.to_lowercase(), bytes: event["bytes"].as_u64().unwrap_or(0), via: Via::RemoteCli }))
+}
+
+fn stream(target: &Target, progress: &AtomicU64, cancel: &AtomicBool) -> Result<Remote> {
+    progress.store(0, Ordering::Relaxed);
+    let mut child = spawn(target.ssh(&format!("cat -- {}", remote_path(&target.path))))?;
+    let mut stdout = child.stdout.take().context("ssh gave no output stream")?;
+    // Read on the side so a chatty server cannot fill the pipe and stall the transfer
+    let mut stderr = child.stderr.take().context("ssh gave no error stream")?;
+    let errors = thread::spawn(move || {
+        let mut text = String::new();
+        let _ = stderr.read_to_string(&mut text);
+        text
+    });
+    let hashed = algo::hash_reader(&mut stdout, &target.to_string(), &[Algorithm::Sha256], progress, cancel);
+    if hashed.is_err() {
+        let _ = child.kill();
+    }
+    let status = wait(&mut child, cancel)?;
+    let errors = errors.join().unwrap_or_default();
+    let (mut digests, bytes) = hashed?;
+    if !status.success() {
+        return Err(ssh_failed(target, &errors.lines().map(str::to_string).collect::<Vec<_>>()));
+    }
+    Ok(Remote { hex: digests.remove(0), bytes, via: Via::Stream })
+}
+
+fn spawn(mut command: Command) -> Result<Child> {
+    command.spawn().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => anyhow!("ssh was not found; install the OpenSSH client to hash files on servers"),
+        _ => anyhow!(e).context("Failed to start ssh"),
+    })
+}
+
+fn wait(child: &mut Child, cancel: &AtomicBool) -> Result<std::process::ExitStatus> {
+    loop {
+        if let Some(status) = child.try_wait().context("Failed to wait for ssh")? {
+            return Ok(status);
+        }
+        if cancel.load(Ordering::Relaxed) {
+            let _ = child.kill();
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+// The last thing ssh or the remote shell said, which is usually the reason
+fn ssh_failed(target: &Target, stderr: &[String]) -> anyhow::Error {
+    match stderr.iter().rev().map(|l| l.trim()).find(|l| !l.is_empty()) {
+        Some(reason) => anyhow!("{}: {}", target, reason),
+        None => anyhow!("{}: ssh failed", target),
+    }
+}
+
+// `~/` stays unquoted so the remote shell expands it
+fn remote_path(path: &str) -> String {
+    match path.strip_prefix("~/") {
+        Some(rest) => format!("~/{}", shell_quote(rest)),
+        None => shell_quote(path),
+    }
+}
+
+// One argument for a POSIX shell on the server: `it's` becomes `'it'\''s'`
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
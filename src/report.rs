@@ -0,0 +1,176 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Local;
+
+use crate::pdf::{self, Font, PAGE_HEIGHT, PAGE_WIDTH};
+use crate::{desktop, storage};
+
+// One checked file in a printed or PDF report
+pub struct Row {
+    pub path: String,
+    // "OK", "CHANGED", "FAILED", ... as shown in the app
+    pub status: String,
+    pub sha256: Option<String>,
+    pub bytes: Option<u64>,
+    // KNOWN/BLOCKLISTED flags, quick-sample warning or the error message
+    pub note: Option<String>,
+}
+
+pub struct Report {
+    pub title: String,
+    pub generated: String,
+    pub rows: Vec<Row>,
+}
+
+impl Report {
+    pub fn new(rows: Vec<Row>) -> Self {
+        Report { title: "SHA-256 checksum report".to_string(), generated: Local::now().format("%Y-%m-%d %H:%M:%S %z").to_string(), rows }
+    }
+
+    pub fn failed(&self) -> usize {
+        self.rows.iter().filter(|r| r.sha256.is_none()).count()
+    }
+
+    // Self-contained page that opens the print dialog as soon as it is shown
+    pub fn to_print_html(&self) -> String {
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title>\n<style>\
+             body{{font-family:sans-serif;font-size:11pt;margin:1.5cm}}\
+             table{{border-collapse:collapse;width:100%}}\
+             th,td{{border:1px solid #888;padding:4px 6px;text-align:left;vertical-align:top}}\
+             td.hex{{font-family:monospace;font-size:9pt;word-break:break-all}}\
+             .sign{{margin-top:2cm}}.sign span{{display:inline-block;width:7cm;border-top:1px solid #000;margin-right:1cm;padding-top:4px}}\
+             </style></head>\n<body onload=\"window.print()\">\n",
+            escape(&self.title)
+        );
+        let _ = writeln!(out, "<h1>{}</h1>", escape(&self.title));
+        let _ = writeln!(
+            out,
+            "<p>Generated {} by rust-hash v{}. {} files, {} failed.</p>",
+            escape(&self.generated),
+            crate::app_version(),
+            self.rows.len(),
+            self.failed()
+        );
+        let _ = writeln!(out, "<table><tr><th>#</th><th>File</th><th>Status</th><th>Size</th><th>SHA-256</th><th>Notes</th></tr>");
+        for (i, row) in self.rows.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"hex\">{}</td><td>{}</td></tr>",
+                i + 1,
+                escape(&row.path),
+                escape(&row.status),
+                row.bytes.map(|b| b.to_string()).unwrap_or_default(),
+                escape(row.sha256.as_deref().unwrap_or("")),
+                escape(row.note.as_deref().unwrap_or(""))
+            );
+        }
+        let _ = writeln!(out, "</table>\n<p class=\"sign\"><span>Checked by / date</span><span>Approved by / date</span></p>\n</body></html>");
+        out
+    }
+}
+
+// Opens the report in the default browser, which shows the system print dialog (including print to PDF)
+pub fn print(report: &Report) -> Result<PathBuf> {
+    if report.rows.is_empty() {
+        return Err(anyhow!("Nothing to print yet"));
+    }
+    let dir = storage::scratch_dir();
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+    let target = dir.join(format!("print-{}.html", Local::now().format("%Y%m%d-%H%M%S")));
+    fs::write(&target, report.to_print_html()).with_context(|| format!("Failed to write {}", target.display()))?;
+    desktop::open(&target.display().to_string())?;
+    Ok(target)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// Writes the report as a PDF with an optional header line and JPEG logo at the top of the first page
+pub fn export_pdf(report: &Report, header: &str, logo: Option<&Path>, target: &Path) -> Result<()> {
+    const MARGIN: f32 = 40.0;
+    const ROW: f32 = 14.0;
+    // #, file, status, size, SHA-256, notes
+    const COLUMNS: [(&str, f32); 6] = [("#", 20.0), ("File", 230.0), ("Status", 100.0), ("Size", 60.0), ("SHA-256", 275.0), ("Notes", 77.0)];
+
+    let mut doc = pdf::Document::new();
+    let mut y = PAGE_HEIGHT - MARGIN;
+    let mut text_x = MARGIN;
+    if let Some(path) = logo {
+        let data = fs::read(path).with_context(|| format!("Failed to read logo: {}", path.display()))?;
+        let image = pdf::Jpeg::parse(data).with_context(|| format!("Unusable logo: {}", path.display()))?;
+        let height = 40.0;
+        let width = (height * image.aspect()).min(160.0);
+        doc.set_image(image);
+        doc.draw_image(MARGIN, y - height, width, height);
+        text_x = MARGIN + width + 12.0;
+    }
+    if !header.trim().is_empty() {
+        doc.text(Font::Bold, 16.0, text_x, y - 16.0, header.trim());
+    }
+    doc.text(Font::Bold, 13.0, text_x, y - 34.0, &report.title);
+    y -= 58.0;
+    doc.text(Font::Regular, 9.0, MARGIN, y, &format!("Generated {} by rust-hash v{}", report.generated, crate::app_version()));
+    y -= 13.0;
+    let changed = report.rows.iter().filter(|r| r.status.starts_with("CHANGED")).count();
+    let blocked = report.rows.iter().filter(|r| r.note.as_deref().is_some_and(|n| n.contains("BLOCKLISTED"))).count();
+    let mut summary = format!("{} files: {} hashed, {} failed", report.rows.len(), report.rows.len() - report.failed(), report.failed());
+    if changed > 0 {
+        summary.push_str(&format!(", {} CHANGED", changed));
+    }
+    if blocked > 0 {
+        summary.push_str(&format!(", {} BLOCKLISTED", blocked));
+    }
+    doc.text(Font::Bold, 10.0, MARGIN, y, &summary);
+    y -= 22.0;
+
+    let table_header = |doc: &mut pdf::Document, y: f32| {
+        let mut x = MARGIN;
+        for (title, width) in COLUMNS {
+            doc.text(Font::Bold, 8.0, x + 2.0, y, title);
+            x += width;
+        }
+        doc.line(MARGIN, y - 4.0, PAGE_WIDTH - MARGIN, y - 4.0);
+    };
+    let mut page = 1;
+    let footer = |doc: &mut pdf::Document, page: usize| {
+        doc.text(Font::Regular, 8.0, MARGIN, MARGIN / 2.0, &format!("{} - page {}", report.generated, page));
+    };
+    table_header(&mut doc, y);
+    y -= ROW + 2.0;
+    for (i, row) in report.rows.iter().enumerate() {
+        if y < MARGIN {
+            footer(&mut doc, page);
+            doc.new_page();
+            page += 1;
+            y = PAGE_HEIGHT - MARGIN;
+            table_header(&mut doc, y);
+            y -= ROW + 2.0;
+        }
+        if i % 2 == 1 {
+            doc.shade(MARGIN, y - 4.0, PAGE_WIDTH - 2.0 * MARGIN, ROW, 0.93);
+        }
+        let cells = [
+            (Font::Regular, 8.0, (i + 1).to_string()),
+            (Font::Regular, 8.0, row.path.clone()),
+            (Font::Regular, 8.0, row.status.clone()),
+            (Font::Regular, 8.0, row.bytes.map(|b| b.to_string()).unwrap_or_default()),
+            (Font::Mono, 6.8, row.sha256.clone().unwrap_or_default()),
+            (Font::Regular, 8.0, row.note.clone().unwrap_or_default()),
+        ];
+        let mut x = MARGIN;
+        for ((font, size, value), (_, width)) in cells.into_iter().zip(COLUMNS) {
+            doc.text(font, size, x + 2.0, y, &pdf::fit(&value, font, size, width - 4.0));
+            x += width;
+        }
+        y -= ROW;
+    }
+    footer(&mut doc, page);
+    fs::write(target, doc.finish()).with_context(|| format!("Failed to write PDF report: {}", target.display()))
+}
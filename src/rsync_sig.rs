@@ -0,0 +1,132 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Digest};
+
+// librsync BLAKE2 signature format (`rdiff signature --hash=blake2`), readable by librsync >= 1.0
+const BLAKE2_SIG_MAGIC: u32 = 0x7273_0137;
+const DEFAULT_BLOCK_LEN: usize = 2048;
+const STRONG_SUM_LEN: usize = 32;
+const ROLLSUM_CHAR_OFFSET: u64 = 31;
+
+// Roughly sqrt(size) rounded down to a multiple of 128 like librsync picks, but never below the default
+pub fn block_len_for(size: u64) -> usize {
+    let sqrt = (size as f64).sqrt() as u64;
+    let rounded = (sqrt & !127) as usize;
+    rounded.max(DEFAULT_BLOCK_LEN)
+}
+
+// Builds a signature incrementally from arbitrarily sized reads of the file
+pub struct SignatureBuilder {
+    block_len: usize,
+    pending: Vec<u8>,
+    out: Vec<u8>,
+}
+
+impl SignatureBuilder {
+    pub fn new(block_len: usize) -> Self {
+        let mut out = Vec::with_capacity(12);
+        out.extend_from_slice(&BLAKE2_SIG_MAGIC.to_be_bytes());
+        out.extend_from_slice(&(block_len as u32).to_be_bytes());
+        out.extend_from_slice(&(STRONG_SUM_LEN as u32).to_be_bytes());
+        SignatureBuilder { block_len, pending: Vec::with_capacity(block_len), out }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        if !self.pending.is_empty() {
+            let take = (self.block_len - self.pending.len()).min(data.len());
+            self.pending.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.pending.len() < self.block_len {
+                return;
+            }
+            let block = std::mem::take(&mut self.pending);
+            self.push_block(&block);
+            self.pending = block;
+            self.pending.clear();
+        }
+        while data.len() >= self.block_len {
+            let (block, rest) = data.split_at(self.block_len);
+            self.push_block(block);
+            data = rest;
+        }
+        self.pending.extend_from_slice(data);
+    }
+
+    // The trailing short block is signed as-is, matching librsync
+    pub fn finish(mut self) -> Vec<u8> {
+        if !self.pending.is_empty() {
+            let block = std::mem::take(&mut self.pending);
+            self.push_block(&block);
+        }
+        self.out
+    }
+
+    fn push_block(&mut self, block: &[u8]) {
+        self.out.extend_from_slice(&weak_sum(block).to_be_bytes());
+        let strong = Blake2b::<U32>::digest(block);
+        self.out.extend_from_slice(&strong[..STRONG_SUM_LEN]);
+    }
+}
+
+pub fn write_signature(target: &Path, signature: &[u8]) -> Result<()> {
+    fs::write(target, signature).with_context(|| format!("Failed to write signature: {}", target.display()))
+}
+
+// librsync rollsum digest of a whole block (the value the delta side rolls over the new file)
+fn weak_sum(block: &[u8]) -> u32 {
+    let mut s1: u64 = 0;
+    let mut s2: u64 = 0;
+    for &b in block {
+        s1 = s1.wrapping_add(b as u64);
+        s2 = s2.wrapping_add(s1);
+    }
+    let len = block.len() as u64;
+    s1 = s1.wrapping_add(len * ROLLSUM_CHAR_OFFSET);
+    s2 = s2.wrapping_add((len * (len + 1) / 2) * ROLLSUM_CHAR_OFFSET);
+    (((s2 & 0xffff) as u32) << 16) | (s1 & 0xffff) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_length_follows_the_file_size() {
+        assert_eq!(block_len_for(0), DEFAULT_BLOCK_LEN);
+        assert_eq!(block_len_for(100 * 1024 * 1024), 10240);
+    }
+
+    #[test]
+    fn rollsum_of_a_block() {
+        // s1 = 294 + 3 * 31, s2 = 586 + 6 * 31
+        assert_eq!(weak_sum(b"abc"), (772 << 16) | 387);
+    }
+
+    #[test]
+    fn signature_layout() {
+        let mut builder = SignatureBuilder::new(2048);
+        builder.update(b"abc");
+        let signature = builder.finish();
+        assert_eq!(&signature[..12], b"rs\x017\0\0\x08\0\0\0\0\x20");
+        assert_eq!(&signature[12..16], &0x0304_0183u32.to_be_bytes());
+        assert_eq!(hex::encode(&signature[16..]), "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319");
+    }
+
+    #[test]
+    fn read_sizes_do_not_change_the_signature() {
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let mut whole = SignatureBuilder::new(2048);
+        whole.update(&data);
+        let mut pieces = SignatureBuilder::new(2048);
+        for piece in data.chunks(777) {
+            pieces.update(piece);
+        }
+        let signature = whole.finish();
+        // Four full blocks and the short tail, 36 bytes each
+        assert_eq!(signature.len(), 12 + 5 * 36);
+        assert_eq!(signature, pieces.finish());
+    }
+}
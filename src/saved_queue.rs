@@ -0,0 +1,52 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::history;
+use crate::storage;
+
+const QUEUE_FILE: &str = "queue.json";
+
+// Queued files that had not finished, written whenever the queue changes so a crash or reboot loses nothing.
+// Only the paths are kept: a continued queue hashes them with the options set at that time
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SavedQueue {
+    pub paths: Vec<String>,
+    pub saved_at: i64,
+}
+
+impl SavedQueue {
+    // Missing or unreadable file means nothing was left over
+    pub fn load() -> Self {
+        queue_path()
+            .and_then(|p| fs::read(p).ok())
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn when(&self) -> String {
+        history::format_timestamp(self.saved_at)
+    }
+}
+
+// An empty queue removes the file, so the next start has nothing to offer
+pub fn save(paths: &[String]) -> Result<()> {
+    let path = queue_path().context("No data directory available for the queue")?;
+    if paths.is_empty() {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e).with_context(|| format!("Failed to remove {}", path.display())),
+            _ => Ok(()),
+        };
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+    }
+    let data = serde_json::to_vec_pretty(&SavedQueue { paths: paths.to_vec(), saved_at: history::now_timestamp() })?;
+    crate::atomic::write(&path, &data)
+}
+
+fn queue_path() -> Option<PathBuf> {
+    storage::data_dir().map(|d| d.join(QUEUE_FILE))
+}
@@ -0,0 +1,152 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use sha2::{Digest, Sha256};
+
+pub const DEFAULT_SEGMENT_SIZE: u64 = 1024 * 1024 * 1024;
+pub const SEGMENT_SIZES: [SegmentSize; 4] = [
+    SegmentSize(64 * 1024 * 1024),
+    SegmentSize(256 * 1024 * 1024),
+    SegmentSize(DEFAULT_SEGMENT_SIZE),
+    SegmentSize(4 * 1024 * 1024 * 1024),
+];
+
+const HEADER: &str = "# rust-hash segment list";
+
+// Pick-list wrapper so sizes render as "1 GiB" rather than raw byte counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegmentSize(pub u64);
+
+impl std::fmt::Display for SegmentSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const GIB: u64 = 1024 * 1024 * 1024;
+        if self.0 >= GIB && self.0.is_multiple_of(GIB) {
+            write!(f, "{} GiB", self.0 / GIB)
+        } else {
+            write!(f, "{} MiB", self.0 / (1024 * 1024))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SegmentEntry {
+    pub index: u64,
+    pub offset: u64,
+    pub length: u64,
+    pub hex: String,
+}
+
+// Splits the stream into fixed-size segments and digests each one
+pub struct SegmentHasher {
+    segment_size: u64,
+    current: Sha256,
+    filled: u64,
+    offset: u64,
+    out: Vec<SegmentEntry>,
+}
+
+impl SegmentHasher {
+    pub fn new(segment_size: u64) -> Self {
+        SegmentHasher { segment_size: segment_size.max(1), current: Sha256::new(), filled: 0, offset: 0, out: Vec::new() }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let take = (self.segment_size - self.filled).min(data.len() as u64) as usize;
+            self.current.update(&data[..take]);
+            self.filled += take as u64;
+            data = &data[take..];
+            if self.filled == self.segment_size {
+                self.close_segment();
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> Vec<SegmentEntry> {
+        if self.filled > 0 {
+            self.close_segment();
+        }
+        self.out
+    }
+
+    fn close_segment(&mut self) {
+        let digest = std::mem::replace(&mut self.current, Sha256::new()).finalize();
+        self.out.push(SegmentEntry { index: self.out.len() as u64, offset: self.offset, length: self.filled, hex: hex::encode(digest) });
+        self.offset += self.filled;
+        self.filled = 0;
+    }
+}
+
+pub fn write_segment_list(target: &Path, path: &Path, bytes: u64, hex: &str, segments: &[SegmentEntry]) -> Result<()> {
+    let mut out = String::new();
+    let _ = writeln!(out, "{}", HEADER);
+    let _ = writeln!(out, "# file: {}", path.display());
+    let _ = writeln!(out, "# size: {}", bytes);
+    let _ = writeln!(out, "# sha256: {}", hex);
+    // All segments but the last have the full size
+    if let Some(size) = segments.iter().map(|s| s.length).max() {
+        let _ = writeln!(out, "# segment-size: {}", size);
+    }
+    let _ = writeln!(out, "# index offset length sha256");
+    for s in segments {
+        let _ = writeln!(out, "{} {} {} {}", s.index, s.offset, s.length, s.hex);
+    }
+    fs::write(target, out).with_context(|| format!("Failed to write segment list: {}", target.display()))
+}
+
+pub fn read_segment_list(target: &Path) -> Result<Vec<SegmentEntry>> {
+    let data = fs::read_to_string(target).with_context(|| format!("Failed to read segment list: {}", target.display()))?;
+    if !data.starts_with(HEADER) {
+        return Err(anyhow!("Not a rust-hash segment list: {}", target.display()));
+    }
+    let mut segments = Vec::new();
+    for (idx, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let parsed = (|| {
+            let index = fields.next()?.parse().ok()?;
+            let offset = fields.next()?.parse().ok()?;
+            let length = fields.next()?.parse().ok()?;
+            let hex = fields.next()?.to_ascii_lowercase();
+            Some(SegmentEntry { index, offset, length, hex })
+        })();
+        match parsed {
+            Some(s) => segments.push(s),
+            None => return Err(anyhow!("Malformed segment list line {}: {}", idx + 1, line)),
+        }
+    }
+    Ok(segments)
+}
+
+// Segments whose offset/length match but whose digest differs, plus segments present on only one side
+pub fn compare_summary(current: &[SegmentEntry], reference: &[SegmentEntry]) -> String {
+    let mut differing = Vec::new();
+    for s in current {
+        match reference.iter().find(|r| r.offset == s.offset) {
+            Some(r) if r.length == s.length && r.hex == s.hex => {}
+            _ => differing.push(s),
+        }
+    }
+    let missing = reference.iter().filter(|r| !current.iter().any(|s| s.offset == r.offset)).count();
+    if differing.is_empty() && missing == 0 {
+        return format!("All {} segments match", current.len());
+    }
+    let listed: Vec<String> = differing
+        .iter()
+        .take(20)
+        .map(|s| format!("#{} @ {}..{}", s.index, s.offset, s.offset + s.length))
+        .collect();
+    let mut summary = format!("{} of {} segments differ: {}", differing.len(), current.len(), listed.join(", "));
+    if differing.len() > listed.len() {
+        let _ = write!(summary, ", ... ({} more)", differing.len() - listed.len());
+    }
+    if missing > 0 {
+        let _ = write!(summary, "; {} reference segments beyond the end of this file", missing);
+    }
+    summary
+}
@@ -0,0 +1,152 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+
+use anyhow::{anyhow, Context, Result};
+use blake3::hazmat::{left_subtree_len, merge_subtrees_non_root, merge_subtrees_root, ChainingValue, HasherExt, Mode};
+
+// Handles opened on a network share when `auto` picks the count
+pub const AUTO_READERS: usize = 8;
+// Ranges smaller than this are not worth another handle and round trip
+const MIN_RANGE: u64 = 4 * 1024 * 1024;
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+// BLAKE3 of a file read through `readers` handles at once, each over its own range. On an SMB or NFS
+// share every read waits a round trip, so one handle reading in order leaves most of the link idle.
+// BLAKE3 is a tree hash: the ranges are hashed as subtrees and merged, which gives the same digest as
+// reading the file front to back. (lowercase hex, bytes read)
+pub fn blake3(path: &Path, readers: usize, progress: &AtomicU64, cancel: &AtomicBool) -> Result<(String, u64)> {
+    let len = std::fs::metadata(path).with_context(|| format!("Failed to read metadata: {}", path.display()))?.len();
+    // One chunk or less is its own root and cannot be split
+    if readers <= 1 || len <= blake3::CHUNK_LEN as u64 {
+        return crate::algo::hash_file(path, crate::algo::Algorithm::Blake3, progress, cancel);
+    }
+    let left = left_subtree_len(len);
+    let (a, b) = split(path, (0, left), (left, len - left), readers, progress, cancel)?;
+    Ok((merge_subtrees_root(&a, &b, Mode::Hash).to_hex().to_string(), len))
+}
+
+// Chaining values of two neighbouring subtrees, on two threads while there are readers to spare
+fn split(path: &Path, a: (u64, u64), b: (u64, u64), readers: usize, progress: &AtomicU64, cancel: &AtomicBool) -> Result<(ChainingValue, ChainingValue)> {
+    let half = readers / 2;
+    thread::scope(|s| {
+        let left = s.spawn(|| subtree(path, a, half.max(1), progress, cancel));
+        let right = subtree(path, b, (readers - half).max(1), progress, cancel);
+        let left = left.join().map_err(|_| anyhow!("A reader thread panicked"))?;
+        Ok((left?, right?))
+    })
+}
+
+fn subtree(path: &Path, (offset, len): (u64, u64), readers: usize, progress: &AtomicU64, cancel: &AtomicBool) -> Result<ChainingValue> {
+    if readers <= 1 || len < 2 * MIN_RANGE {
+        return read_range(path, offset, len, progress, cancel);
+    }
+    let left = left_subtree_len(len);
+    let (a, b) = split(path, (offset, left), (offset + left, len - left), readers, progress, cancel)?;
+    Ok(merge_subtrees_non_root(&a, &b, Mode::Hash))
+}
+
+// One range through its own handle
+fn read_range(path: &Path, offset: u64, len: u64, progress: &AtomicU64, cancel: &AtomicBool) -> Result<ChainingValue> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+    file.seek(SeekFrom::Start(offset)).with_context(|| format!("Failed to seek in {}", path.display()))?;
+    let mut hasher = blake3::Hasher::new();
+    hasher.set_input_offset(offset);
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut left = len;
+    while left > 0 {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(anyhow!(crate::errors::CANCELLED));
+        }
+        let want = left.min(BUFFER_SIZE as u64) as usize;
+        let n = file.read(&mut buffer[..want]).with_context(|| format!("Failed to read {}", path.display()))?;
+        if n == 0 {
+            return Err(anyhow!("{} got shorter while it was read", path.display()));
+        }
+        hasher.update(&buffer[..n]);
+        left -= n as u64;
+        progress.fetch_add(n as u64, Ordering::Relaxed);
+    }
+    Ok(hasher.finalize_non_root())
+}
+
+// True for files on an SMB/CIFS or NFS mount; false for local disks or when the platform cannot tell
+#[cfg(target_os = "linux")]
+pub fn is_network_share(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    // Magic numbers from statfs(2)
+    const NFS: u32 = 0x6969;
+    const SMB: u32 = 0x517b;
+    const CIFS: u32 = 0xff53_4d42;
+    const SMB2: u32 = 0xfe53_4d42;
+
+    let Ok(c_path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    let mut st: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(c_path.as_ptr(), &mut st) } != 0 {
+        return false;
+    }
+    matches!(st.f_type as u32, NFS | SMB | CIFS | SMB2)
+}
+
+#[cfg(target_os = "macos")]
+pub fn is_network_share(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(c_path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    let mut st: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(c_path.as_ptr(), &mut st) } != 0 {
+        return false;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(st.f_fstypename.as_ptr()) };
+    matches!(name.to_bytes(), b"smbfs" | b"nfs" | b"afpfs" | b"webdav")
+}
+
+#[cfg(windows)]
+pub fn is_network_share(path: &Path) -> bool {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::{GetDriveTypeW, GetVolumePathNameW};
+    // From WindowsProgramming, which is not worth a feature for one constant
+    const DRIVE_REMOTE: u32 = 4;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    // `X:\` for a mapped drive, `\\server\share\` for a UNC path
+    let mut root = [0u16; 1024];
+    if unsafe { GetVolumePathNameW(wide.as_ptr(), root.as_mut_ptr(), root.len() as u32) } == 0 {
+        return false;
+    }
+    unsafe { GetDriveTypeW(root.as_ptr()) == DRIVE_REMOTE }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn is_network_share(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Enough for ranges of MIN_RANGE to split several times, with a tail that is not a whole chunk
+    #[test]
+    fn parallel_reads_give_the_sequential_digest() {
+        let data: Vec<u8> = (0..17 * 1024 * 1024 + 1234u32).map(|i| (i.wrapping_mul(2654435761) >> 24) as u8).collect();
+        let small = &data[..blake3::CHUNK_LEN];
+        let path = std::env::temp_dir().join(format!("rust-hash-share-read-test-{}", std::process::id()));
+        let mut results = Vec::new();
+        for (content, readers) in [(&data[..], 2), (&data[..], 3), (&data[..], 4), (&data[..], 8), (small, 8)] {
+            std::fs::write(&path, content).unwrap();
+            results.push((blake3(&path, readers, &AtomicU64::new(0), &AtomicBool::new(false)).unwrap(), content));
+        }
+        let _ = std::fs::remove_file(&path);
+        for ((hex, read), content) in results {
+            assert_eq!(hex, blake3::hash(content).to_hex().to_string());
+            assert_eq!(read, content.len() as u64);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use anyhow::Result;
+#[cfg(not(target_os = "macos"))]
+use anyhow::Context;
+#[cfg(not(any(windows, target_os = "macos")))]
+use anyhow::anyhow;
+
+// "Hash with Rust Hash" for files in the file manager: a context menu entry for the current user on
+// Windows, an "Open With" entry on Linux. Nothing needs installing on macOS, where the bundle's
+// Info.plist does it. Each returns the note shown under the results
+#[cfg(windows)]
+pub fn install() -> Result<String> {
+    let exe = std::env::current_exe().context("Cannot tell where rust-hash is installed")?;
+    let exe = exe.display().to_string();
+    registry::set(registry::KEY, "Hash with Rust Hash")?;
+    registry::set_value(registry::KEY, "Icon", &exe)?;
+    registry::set(&format!("{}\\command", registry::KEY), &format!("\"{}\" \"%1\"", exe))?;
+    registry::notify();
+    Ok("\"Hash with Rust Hash\" added to the Explorer context menu (on Windows 11 under \"Show more options\")".to_string())
+}
+
+#[cfg(windows)]
+pub fn remove() -> Result<String> {
+    registry::delete(registry::KEY)?;
+    registry::notify();
+    Ok("\"Hash with Rust Hash\" removed from the Explorer context menu".to_string())
+}
+
+#[cfg(target_os = "macos")]
+pub fn install() -> Result<String> {
+    Ok("Finder lists Rust Hash under Open With once the app is bundled with assets/Info.plist; there is nothing to add".to_string())
+}
+
+#[cfg(target_os = "macos")]
+pub fn remove() -> Result<String> {
+    Ok("Nothing was added to Finder, so there is nothing to remove".to_string())
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+pub fn install() -> Result<String> {
+    let exe = std::env::current_exe().context("Cannot tell where rust-hash is installed")?;
+    let path = desktop_entry().ok_or_else(|| anyhow!("No data directory for the desktop entry"))?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+    }
+    let entry = format!(
+        "[Desktop Entry]\nType=Application\nName=Rust Hash\nComment=Hash with Rust Hash\nExec={} %f\nMimeType=application/octet-stream;\nNoDisplay=true\nTerminal=false\n",
+        exec_quote(&exe.display().to_string())
+    );
+    std::fs::write(&path, entry).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(format!("Rust Hash added to \"Open With\" in the file manager ({})", path.display()))
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+pub fn remove() -> Result<String> {
+    let path = desktop_entry().ok_or_else(|| anyhow!("No data directory for the desktop entry"))?;
+    match std::fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e).with_context(|| format!("Failed to remove {}", path.display())),
+        _ => Ok("Rust Hash removed from \"Open With\"".to_string()),
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn desktop_entry() -> Option<std::path::PathBuf> {
+    dirs::data_dir().map(|d| d.join("applications").join("rust-hash.desktop"))
+}
+
+// An Exec argument as the desktop entry spec wants it: in double quotes, with `"`, `` ` ``, `$` and `\` escaped
+#[cfg(not(any(windows, target_os = "macos")))]
+fn exec_quote(arg: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+// Keys under HKEY_CURRENT_USER, so no administrator rights are needed
+#[cfg(windows)]
+mod registry {
+    use anyhow::{anyhow, Result};
+    use windows_sys::Win32::System::Registry::{RegDeleteTreeW, RegSetKeyValueW, HKEY_CURRENT_USER, REG_SZ};
+    use windows_sys::Win32::UI::Shell::{SHChangeNotify, SHCNE_ASSOCCHANGED, SHCNF_IDLIST};
+
+    pub const KEY: &str = "Software\\Classes\\*\\shell\\RustHash";
+    const ERROR_FILE_NOT_FOUND: u32 = 2;
+
+    fn wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    // The key's default value
+    pub fn set(key: &str, data: &str) -> Result<()> {
+        write(key, None, data)
+    }
+
+    pub fn set_value(key: &str, name: &str, data: &str) -> Result<()> {
+        write(key, Some(name), data)
+    }
+
+    fn write(key: &str, name: Option<&str>, data: &str) -> Result<()> {
+        let (key_w, data_w) = (wide(key), wide(data));
+        let name_w = name.map(wide);
+        let name_ptr = name_w.as_ref().map_or(std::ptr::null(), |n| n.as_ptr());
+        let status = unsafe {
+            RegSetKeyValueW(HKEY_CURRENT_USER, key_w.as_ptr(), name_ptr, REG_SZ, data_w.as_ptr().cast(), (data_w.len() * 2) as u32)
+        };
+        match status {
+            0 => Ok(()),
+            code => Err(anyhow!("Failed to write HKEY_CURRENT_USER\\{} (error {})", key, code)),
+        }
+    }
+
+    // Gone already counts as removed
+    pub fn delete(key: &str) -> Result<()> {
+        let key_w = wide(key);
+        match unsafe { RegDeleteTreeW(HKEY_CURRENT_USER, key_w.as_ptr()) } {
+            0 | ERROR_FILE_NOT_FOUND => Ok(()),
+            code => Err(anyhow!("Failed to remove HKEY_CURRENT_USER\\{} (error {})", key, code)),
+        }
+    }
+
+    // Explorer reads the menu again without a sign-out
+    pub fn notify() {
+        unsafe { SHChangeNotify(SHCNE_ASSOCCHANGED as i32, SHCNF_IDLIST, std::ptr::null(), std::ptr::null()) }
+    }
+}
@@ -0,0 +1,37 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64};
+
+use anyhow::{anyhow, Result};
+
+use crate::algo::{self, Algorithm};
+
+// The checksum files Maven repositories accept next to an artifact, by extension
+pub const SIDECARS: [(Algorithm, &str); 4] = [(Algorithm::Md5, "md5"), (Algorithm::Sha1, "sha1"), (Algorithm::Sha256, "sha256"), (Algorithm::Sha512, "sha512")];
+
+// Writes `<artifact>.md5`, `.sha1`, `.sha256` and `.sha512` from one read of the file. Each holds the
+// bare lowercase hex digest and nothing else (no file name, no newline), which is what repository
+// managers compare byte for byte; an existing sidecar is replaced. Algorithms the policy rules out
+// are not computed and get no sidecar
+pub fn publish(artifact: &Path, cancel: &AtomicBool) -> Result<Vec<PathBuf>> {
+    if SIDECARS.iter().any(|(_, ext)| artifact.extension().is_some_and(|e| e == *ext)) {
+        return Err(anyhow!("{} is a checksum file itself", artifact.display()));
+    }
+    let allowed = allowed();
+    let algorithms: Vec<Algorithm> = allowed.iter().map(|(a, _)| *a).collect();
+    let (digests, _) = algo::hash_file_multi(artifact, &algorithms, &AtomicU64::new(0), cancel)?;
+    let mut written = Vec::new();
+    for ((_, ext), hex) in allowed.iter().zip(digests) {
+        let mut target = artifact.as_os_str().to_owned();
+        target.push(".");
+        target.push(ext);
+        let target = PathBuf::from(target);
+        crate::atomic::write(&target, hex.as_bytes())?;
+        written.push(target);
+    }
+    Ok(written)
+}
+
+// The sidecars the policy allows; SHA-256 always is
+pub fn allowed() -> Vec<(Algorithm, &'static str)> {
+    SIDECARS.into_iter().filter(|(a, _)| crate::policy::get().allows(*a)).collect()
+}
@@ -0,0 +1,60 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::hashlist;
+use crate::verify::Entry;
+
+// Two saved baselines compared entry by entry; neither the files nor their volumes are read
+pub struct SnapshotDiff {
+    pub old: PathBuf,
+    pub new: PathBuf,
+    // Paths relative to each baseline's common folder
+    pub added: Vec<(PathBuf, String)>,
+    pub removed: Vec<(PathBuf, String)>,
+    // (path, old digest, new digest)
+    pub changed: Vec<(PathBuf, String, String)>,
+    pub unchanged: usize,
+    // Lines without a SHA-256 in either baseline
+    pub skipped: usize,
+}
+
+impl SnapshotDiff {
+    pub fn is_clean(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        format!("{} added, {} removed, {} changed, {} unchanged", self.added.len(), self.removed.len(), self.changed.len(), self.unchanged)
+    }
+}
+
+// Baselines taken on different volumes or folders line up by the part below their deepest shared folder
+fn keyed(entries: Vec<Entry>) -> BTreeMap<PathBuf, String> {
+    let mut root = entries.first().and_then(|e| e.path.parent()).map(Path::to_path_buf).unwrap_or_default();
+    for entry in &entries {
+        while !entry.path.starts_with(&root) {
+            if !root.pop() {
+                break;
+            }
+        }
+    }
+    entries.into_iter().map(|e| (e.path.strip_prefix(&root).map(Path::to_path_buf).unwrap_or(e.path), e.expected)).collect()
+}
+
+pub fn diff(old: &Path, new: &Path) -> Result<SnapshotDiff> {
+    let (before, after) = (hashlist::load(old)?, hashlist::load(new)?);
+    let skipped = before.skipped + after.skipped;
+    let (before, mut after) = (keyed(before.entries), keyed(after.entries));
+    let mut result = SnapshotDiff { old: old.to_path_buf(), new: new.to_path_buf(), added: Vec::new(), removed: Vec::new(), changed: Vec::new(), unchanged: 0, skipped };
+    for (path, hex) in before {
+        match after.remove(&path) {
+            Some(now) if now == hex => result.unchanged += 1,
+            Some(now) => result.changed.push((path, hex, now)),
+            None => result.removed.push((path, hex)),
+        }
+    }
+    result.added = after.into_iter().collect();
+    Ok(result)
+}
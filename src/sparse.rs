@@ -0,0 +1,189 @@
+use std::fs::{File, Metadata};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+// Bytes actually allocated on disk, when the platform can tell
+#[cfg(unix)]
+pub fn allocated_size(_path: &Path, meta: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(meta.blocks() * 512)
+}
+
+#[cfg(windows)]
+pub fn allocated_size(path: &Path, _meta: &Metadata) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetCompressedFileSizeW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    let mut high: u32 = 0;
+    let low = unsafe { GetCompressedFileSizeW(wide.as_ptr(), &mut high) };
+    // INVALID_FILE_SIZE is also a valid low word, so only an error code makes it a failure
+    if low == u32::MAX && io::Error::last_os_error().raw_os_error() != Some(0) {
+        return None;
+    }
+    Some(((high as u64) << 32) | low as u64)
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn allocated_size(_path: &Path, _meta: &Metadata) -> Option<u64> {
+    None
+}
+
+// Data (non-hole) ranges as (start, end) offsets, or None when the file system cannot report them
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd"))]
+pub fn data_ranges(file: &File, size: u64) -> Option<Vec<(u64, u64)>> {
+    use std::os::unix::io::AsRawFd;
+
+    let fd = file.as_raw_fd();
+    let mut ranges = Vec::new();
+    let mut pos: u64 = 0;
+    while pos < size {
+        let start = unsafe { libc::lseek(fd, pos as libc::off_t, libc::SEEK_DATA) };
+        if start < 0 {
+            // ENXIO means there is no more data after `pos`, i.e. the rest is a hole
+            if io::Error::last_os_error().raw_os_error() == Some(libc::ENXIO) {
+                break;
+            }
+            return None;
+        }
+        let end = unsafe { libc::lseek(fd, start, libc::SEEK_HOLE) };
+        if end < 0 {
+            return None;
+        }
+        let (start, end) = (start as u64, (end as u64).min(size));
+        if end <= start {
+            break;
+        }
+        ranges.push((start, end));
+        pos = end;
+    }
+    Some(ranges)
+}
+
+#[cfg(windows)]
+pub fn data_ranges(file: &File, size: u64) -> Option<Vec<(u64, u64)>> {
+    use std::ffi::c_void;
+    use std::mem::size_of;
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Foundation::{GetLastError, ERROR_MORE_DATA, HANDLE};
+    use windows_sys::Win32::System::Ioctl::{FILE_ALLOCATED_RANGE_BUFFER, FSCTL_QUERY_ALLOCATED_RANGES};
+    use windows_sys::Win32::System::IO::DeviceIoControl;
+
+    let handle = file.as_raw_handle() as HANDLE;
+    let entry_size = size_of::<FILE_ALLOCATED_RANGE_BUFFER>();
+    let mut ranges = Vec::new();
+    let mut query = FILE_ALLOCATED_RANGE_BUFFER { FileOffset: 0, Length: size as i64 };
+    let mut out = vec![FILE_ALLOCATED_RANGE_BUFFER { FileOffset: 0, Length: 0 }; 512];
+    loop {
+        let mut returned: u32 = 0;
+        let ok = unsafe {
+            DeviceIoControl(
+                handle,
+                FSCTL_QUERY_ALLOCATED_RANGES,
+                &query as *const FILE_ALLOCATED_RANGE_BUFFER as *const c_void,
+                entry_size as u32,
+                out.as_mut_ptr() as *mut c_void,
+                (out.len() * entry_size) as u32,
+                &mut returned,
+                std::ptr::null_mut(),
+            )
+        };
+        // ERROR_MORE_DATA: the output filled up and the query continues after the last range
+        let more = ok == 0 && unsafe { GetLastError() } == ERROR_MORE_DATA;
+        if ok == 0 && !more {
+            return None;
+        }
+        let count = returned as usize / entry_size;
+        for r in &out[..count] {
+            ranges.push((r.FileOffset as u64, (r.FileOffset + r.Length) as u64));
+        }
+        if !more || count == 0 {
+            break;
+        }
+        let resume = ranges.last().map_or(size, |r| r.1);
+        query = FILE_ALLOCATED_RANGE_BUFFER { FileOffset: resume as i64, Length: size.saturating_sub(resume) as i64 };
+    }
+    Some(ranges)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd", windows)))]
+pub fn data_ranges(_file: &File, _size: u64) -> Option<Vec<(u64, u64)>> {
+    None
+}
+
+// Bytes of `size` that are not covered by any data range
+pub fn hole_bytes(ranges: &[(u64, u64)], size: u64) -> u64 {
+    let data: u64 = ranges.iter().map(|&(s, e)| e.min(size).saturating_sub(s)).sum();
+    size.saturating_sub(data)
+}
+
+// Reads only the data ranges from disk and serves holes as zeros, so the digest is unchanged
+pub struct SparseReader {
+    file: File,
+    ranges: Vec<(u64, u64)>,
+    // Index of the first range that ends after `pos`
+    next: usize,
+    pos: u64,
+    file_pos: u64,
+    size: u64,
+}
+
+impl SparseReader {
+    pub fn new(file: File, ranges: Vec<(u64, u64)>, size: u64) -> Self {
+        SparseReader { file, ranges, next: 0, pos: 0, file_pos: u64::MAX, size }
+    }
+}
+
+impl Read for SparseReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.size || buf.is_empty() {
+            return Ok(0);
+        }
+        while self.next < self.ranges.len() && self.ranges[self.next].1 <= self.pos {
+            self.next += 1;
+        }
+        match self.ranges.get(self.next) {
+            Some(&(start, end)) if start <= self.pos => {
+                let want = (end.min(self.size) - self.pos).min(buf.len() as u64) as usize;
+                if self.file_pos != self.pos {
+                    self.file.seek(SeekFrom::Start(self.pos))?;
+                    self.file_pos = self.pos;
+                }
+                let n = self.file.read(&mut buf[..want])?;
+                // The file shrank since its ranges were listed; returning 0 would end the digest early and quietly
+                if n == 0 {
+                    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "File ends inside a data range"));
+                }
+                self.pos += n as u64;
+                self.file_pos += n as u64;
+                Ok(n)
+            }
+            next => {
+                let hole_end = next.map_or(self.size, |r| r.0).min(self.size);
+                let n = (hole_end - self.pos).min(buf.len() as u64) as usize;
+                buf[..n].fill(0);
+                self.pos += n as u64;
+                Ok(n)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holes_read_as_zeros_and_a_short_file_is_an_error() {
+        let path = std::env::temp_dir().join(format!("rust-hash-sparse-test-{}", std::process::id()));
+        std::fs::write(&path, b"abcdefghij").unwrap();
+        let mut whole = Vec::new();
+        let read = SparseReader::new(File::open(&path).unwrap(), vec![(2, 4), (8, 10)], 12).read_to_end(&mut whole);
+        let mut short = Vec::new();
+        let shrunk = SparseReader::new(File::open(&path).unwrap(), vec![(0, 16)], 16).read_to_end(&mut short);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(read.unwrap(), 12);
+        assert_eq!(whole, b"\0\0cd\0\0\0\0ij\0\0");
+        assert_eq!(shrunk.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
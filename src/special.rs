@@ -0,0 +1,75 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+// Non-regular files that need care: reading a FIFO blocks until a writer shows up, and devices report no size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecialFile {
+    Fifo,
+    Socket,
+    BlockDevice,
+    CharDevice,
+}
+
+impl SpecialFile {
+    pub fn describe(self) -> &'static str {
+        match self {
+            SpecialFile::Fifo => "named pipe (FIFO)",
+            SpecialFile::Socket => "socket",
+            SpecialFile::BlockDevice => "block device",
+            SpecialFile::CharDevice => "character device",
+        }
+    }
+
+    // Devices are hashed on purpose (disk images, /dev/urandom samples); pipes and sockets are refused
+    pub fn hashable(self) -> bool {
+        matches!(self, SpecialFile::BlockDevice | SpecialFile::CharDevice)
+    }
+}
+
+// Looks at the metadata only, so a FIFO is never opened
+#[cfg(unix)]
+pub fn detect(path: &Path) -> Option<SpecialFile> {
+    use std::os::unix::fs::FileTypeExt;
+
+    let ft = std::fs::metadata(path).ok()?.file_type();
+    if ft.is_fifo() {
+        Some(SpecialFile::Fifo)
+    } else if ft.is_socket() {
+        Some(SpecialFile::Socket)
+    } else if ft.is_block_device() {
+        Some(SpecialFile::BlockDevice)
+    } else if ft.is_char_device() {
+        Some(SpecialFile::CharDevice)
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+pub fn detect(_path: &Path) -> Option<SpecialFile> {
+    None
+}
+
+// Block devices report a zero length in their metadata; seeking to the end gives the real capacity
+pub fn device_size(file: &mut File) -> Option<u64> {
+    let size = file.seek(SeekFrom::End(0)).ok()?;
+    file.seek(SeekFrom::Start(0)).ok()?;
+    Some(size)
+}
+
+// Error for special files that are refused instead of opened
+#[derive(Debug)]
+pub struct Unsupported {
+    pub kind: SpecialFile,
+    pub path: PathBuf,
+}
+
+impl fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Not hashing {}: it is a {}, not a regular file", self.path.display(), self.kind.describe())
+    }
+}
+
+impl std::error::Error for Unsupported {}
@@ -0,0 +1,296 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{Context, Result};
+
+use crate::algo::{self, Algorithm};
+use crate::folder_diff;
+use crate::history;
+use crate::manifest::{Manifest, Merged};
+use crate::path_norm::absolute;
+
+// Name of the list kept up to date when the folder has none yet
+pub const DEFAULT_LIST: &str = "SHA256SUMS";
+// How often the folder is listed again
+const SCAN_INTERVAL: Duration = Duration::from_secs(2);
+// Entries kept in the activity log, newest last
+const MAX_LOG: usize = 200;
+// Files still being downloaded or written by a build tool
+const PARTIAL_EXTENSIONS: [&str; 5] = ["part", "partial", "crdownload", "download", "tmp"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Updated,
+    Removed,
+    Failed(String),
+}
+
+// One line of the activity log
+#[derive(Debug, Clone)]
+pub struct Activity {
+    pub at: i64,
+    pub file: PathBuf,
+    pub change: Change,
+}
+
+impl std::fmt::Display for Activity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let what = match &self.change {
+            Change::Added => "added".to_string(),
+            Change::Updated => "updated".to_string(),
+            Change::Removed => "removed".to_string(),
+            Change::Failed(e) => format!("FAILED: {}", e),
+        };
+        write!(f, "{}  {}  {}", history::format_timestamp(self.at), self.file.display(), what)
+    }
+}
+
+// Size and modification time of a file when it was last listed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct State {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Seen {
+    state: State,
+    // The list has this state's digest (or hashing it failed), so it is left alone until the file changes
+    settled: bool,
+}
+
+// A release staging folder whose checksum list follows its files: new and replaced files are hashed
+// once they stop changing, deleted ones are taken off the list. Polled from the Tick, like the other runs
+pub struct StagingWatch {
+    pub folder: PathBuf,
+    pub list: PathBuf,
+    pub log: Vec<Activity>,
+    seen: BTreeMap<PathBuf, Seen>,
+    scanned_at: Option<Instant>,
+    // Entries of files already gone were dropped on the first listing
+    pruned: bool,
+    cancel: Arc<AtomicBool>,
+    rx: Option<Receiver<(PathBuf, State, Result<String>)>>,
+    // Digests finished in the running batch, written to the list when it ends
+    finished: Vec<(PathBuf, String)>,
+}
+
+impl StagingWatch {
+    // Keeps an existing `SHA256SUMS`-style list in `folder` up to date, or creates one
+    pub fn start(folder: PathBuf) -> Result<StagingWatch> {
+        if !fs::metadata(&folder).with_context(|| format!("Failed to read metadata: {}", folder.display()))?.is_dir() {
+            anyhow::bail!("Not a folder: {}", folder.display());
+        }
+        let list = ["SHA256SUMS", "SHA256SUMS.txt", "sha256sums.txt", "checksums.sha256"]
+            .iter()
+            .map(|name| folder.join(name))
+            .find(|p| p.is_file())
+            .unwrap_or_else(|| folder.join(DEFAULT_LIST));
+        if list.exists() {
+            // Fail now rather than on the first update when the list is not one that can be edited
+            Manifest::load(&list)?;
+        }
+        Ok(StagingWatch {
+            folder,
+            list,
+            log: Vec::new(),
+            seen: BTreeMap::new(),
+            scanned_at: None,
+            pruned: false,
+            cancel: Arc::new(AtomicBool::new(false)),
+            rx: None,
+            finished: Vec::new(),
+        })
+    }
+
+    pub fn is_hashing(&self) -> bool {
+        self.rx.is_some()
+    }
+
+    // Files waiting to be hashed: new or changed, or still being written
+    pub fn pending(&self) -> usize {
+        self.seen.values().filter(|s| !s.settled).count()
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    // Collects finished digests and lists the folder again when due; true when the list was written
+    pub fn poll(&mut self) -> bool {
+        let mut written = false;
+        if let Some(rx) = self.rx.take() {
+            loop {
+                match rx.try_recv() {
+                    Ok((file, state, result)) => {
+                        if let Some(seen) = self.seen.get_mut(&file) {
+                            // A file replaced again while it was being hashed is hashed once more
+                            seen.settled = seen.state == state;
+                        }
+                        match result {
+                            Ok(hex) => self.finished.push((file, hex)),
+                            Err(e) => self.record(file, Change::Failed(format!("{:#}", e))),
+                        }
+                    }
+                    Err(TryRecvError::Empty) => {
+                        self.rx = Some(rx);
+                        break;
+                    }
+                    Err(TryRecvError::Disconnected) => {
+                        let finished = std::mem::take(&mut self.finished);
+                        written |= self.update_list(finished, Vec::new());
+                        break;
+                    }
+                }
+            }
+        }
+        if self.rx.is_none() && self.scanned_at.is_none_or(|at| at.elapsed() >= SCAN_INTERVAL) {
+            self.scanned_at = Some(Instant::now());
+            written |= self.scan();
+        }
+        written
+    }
+
+    fn scan(&mut self) -> bool {
+        let files = match folder_diff::tree(&self.folder) {
+            Ok(files) => files,
+            Err(e) => {
+                self.record(self.folder.clone(), Change::Failed(format!("{:#}", e)));
+                return false;
+            }
+        };
+        let mut present = BTreeMap::new();
+        for relative in files.into_keys() {
+            let file = self.folder.join(&relative);
+            if self.ignored(&file) {
+                continue;
+            }
+            let Ok(meta) = fs::metadata(&file) else { continue };
+            present.insert(file, State { len: meta.len(), modified: meta.modified().ok() });
+        }
+        let mut removed: Vec<PathBuf> = self.seen.keys().filter(|f| !present.contains_key(*f)).cloned().collect();
+        if !self.pruned {
+            // Entries for files deleted before the watch started
+            self.pruned = true;
+            if let Ok(list) = self.load_list() {
+                let stale = list.entries().map(|(_, e)| list.file(e)).filter(|f| f.starts_with(&self.folder) && !f.exists());
+                removed.extend(stale);
+            }
+        }
+        let mut due = Vec::new();
+        for (file, state) in present {
+            match self.seen.get_mut(&file) {
+                // Unchanged for a whole interval, so no longer being written
+                Some(seen) if seen.state == state => {
+                    if !seen.settled {
+                        due.push((file, state));
+                    }
+                }
+                Some(seen) => *seen = Seen { state, settled: false },
+                None => {
+                    self.seen.insert(file, Seen { state, settled: false });
+                }
+            }
+        }
+        for file in &removed {
+            self.seen.remove(file);
+        }
+        if !due.is_empty() {
+            self.hash(due);
+        }
+        !removed.is_empty() && self.update_list(Vec::new(), removed)
+    }
+
+    // The list and its signatures (`SHA256SUMS.asc`), the temporary file it is written through, hidden
+    // files and partial downloads
+    fn ignored(&self, file: &Path) -> bool {
+        let name = file.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let list_name = self.list.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        name.starts_with(&list_name)
+            || name.starts_with('.')
+            || name.ends_with('~')
+            || file.extension().is_some_and(|e| PARTIAL_EXTENSIONS.iter().any(|x| e.eq_ignore_ascii_case(x)))
+    }
+
+    fn hash(&mut self, files: Vec<(PathBuf, State)>) {
+        self.cancel = Arc::new(AtomicBool::new(false));
+        let cancel = self.cancel.clone();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let progress = AtomicU64::new(0);
+            for (file, state) in files {
+                if cancel.load(Ordering::Relaxed) {
+                    break;
+                }
+                let result = algo::hash_file(&file, Algorithm::Sha256, &progress, &cancel).map(|(hex, _)| hex);
+                if tx.send((file, state, result)).is_err() {
+                    break;
+                }
+            }
+        });
+        self.rx = Some(rx);
+    }
+
+    // Merges new digests into the list and drops the entries of deleted files, in one write
+    fn update_list(&mut self, digests: Vec<(PathBuf, String)>, removed: Vec<PathBuf>) -> bool {
+        if digests.is_empty() && removed.is_empty() {
+            return false;
+        }
+        let mut list = match self.load_list() {
+            Ok(list) => list,
+            Err(e) => {
+                self.record(self.list.clone(), Change::Failed(format!("{:#}", e)));
+                return false;
+            }
+        };
+        let mut changes = Vec::new();
+        for (file, hex) in digests {
+            match list.merge(&file, &hex) {
+                Merged::Added => changes.push((file, Change::Added)),
+                Merged::Updated => changes.push((file, Change::Updated)),
+                Merged::Unchanged => {}
+            }
+        }
+        let gone: Vec<PathBuf> = removed.iter().map(|f| absolute(f)).collect();
+        let lines: Vec<usize> = list.entries().filter(|(_, e)| gone.contains(&absolute(&list.file(e)))).map(|(i, _)| i).collect();
+        if !lines.is_empty() {
+            list.remove(&lines);
+            changes.extend(removed.into_iter().map(|f| (f, Change::Removed)));
+        }
+        if changes.is_empty() {
+            return false;
+        }
+        if let Err(e) = list.save() {
+            self.record(self.list.clone(), Change::Failed(format!("{:#}", e)));
+            return false;
+        }
+        for (file, change) in changes {
+            self.record(file, change);
+        }
+        true
+    }
+
+    // Read again before every update, so edits made to the list in the meantime are kept
+    fn load_list(&self) -> Result<Manifest> {
+        if self.list.exists() {
+            Manifest::load(&self.list)
+        } else {
+            Ok(Manifest::empty(&self.list, false))
+        }
+    }
+
+    fn record(&mut self, file: PathBuf, change: Change) {
+        let file = file.strip_prefix(&self.folder).map(Path::to_path_buf).unwrap_or(file);
+        self.log.push(Activity { at: history::now_timestamp(), file, change });
+        if self.log.len() > MAX_LOG {
+            self.log.remove(0);
+        }
+    }
+}
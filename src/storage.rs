@@ -0,0 +1,35 @@
+use std::path::PathBuf;
+
+use once_cell::sync::Lazy;
+
+const APP_DIR_NAME: &str = "rust-hash";
+// Placed next to the executable to keep all app data beside it (e.g. on a USB stick)
+pub const PORTABLE_FLAG: &str = "portable.flag";
+const PORTABLE_DATA_DIR: &str = "data";
+
+static PORTABLE_DIR: Lazy<Option<PathBuf>> = Lazy::new(|| {
+    let exe = std::env::current_exe().ok()?;
+    let dir = exe.parent()?;
+    dir.join(PORTABLE_FLAG).is_file().then(|| dir.join(PORTABLE_DATA_DIR))
+});
+
+pub fn is_portable() -> bool {
+    PORTABLE_DIR.is_some()
+}
+
+// Directory for persisted app data (history, settings): per-user, or next to the
+// executable in portable mode so nothing is written to the host profile
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = PORTABLE_DIR.as_ref() {
+        return Some(dir.clone());
+    }
+    dirs::data_dir().map(|d| d.join(APP_DIR_NAME))
+}
+
+// Short-lived files handed to other processes; kept with the app data in portable mode
+pub fn scratch_dir() -> PathBuf {
+    match PORTABLE_DIR.as_ref() {
+        Some(dir) => dir.clone(),
+        None => std::env::temp_dir().join(APP_DIR_NAME),
+    }
+}
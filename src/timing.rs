@@ -0,0 +1,29 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+// Smaller files finish too quickly for their rate to say anything about the disk
+const MIN_BYTES: u64 = 1024 * 1024;
+
+// How long one file of a queue run or verification took to read
+#[derive(Debug, Clone)]
+pub struct Timing {
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub elapsed: Duration,
+}
+
+impl Timing {
+    // Bytes per second
+    pub fn rate(&self) -> f64 {
+        self.bytes as f64 / self.elapsed.as_secs_f64().max(0.001)
+    }
+}
+
+// Up to `n` files with the lowest throughput, slowest first. Ranked by rate rather than duration,
+// so a big file on a healthy disk does not hide a small one on a failing disk or badly fragmented
+pub fn slowest(timings: &[Timing], n: usize) -> Vec<&Timing> {
+    let mut ranked: Vec<&Timing> = timings.iter().filter(|t| t.bytes >= MIN_BYTES).collect();
+    ranked.sort_by(|a, b| a.rate().total_cmp(&b.rate()));
+    ranked.truncate(n);
+    ranked
+}
@@ -0,0 +1,165 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64};
+
+use anyhow::{anyhow, Context, Result};
+use sha2::{Digest, Sha256};
+
+use crate::algo::{self, Algorithm};
+use crate::path_norm::{self, Form, Separator};
+
+// Tags of the root line: `TREE-SHA256 (folder) = hash`, or with attributes folded in
+pub const TAG: &str = "TREE-SHA256";
+pub const TAG_XATTRS: &str = "TREE-XATTRS-SHA256";
+// Start of the serialization; a later layout gets a new one
+const HEADER: &[u8] = b"rust-hash tree 1\n";
+
+// A file or folder below the root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    // From `name`: relative to the root, `/` between names, NFC; the root folder itself is ""
+    pub path: String,
+    // SHA-256 (lowercase hex) of a file's content; None for folders
+    pub content: Option<String>,
+    // `xattrs::digest` of the file or folder; needed only when attributes are included
+    pub attributes: Option<String>,
+}
+
+// How a path below the root is written, the same on every OS
+pub fn name(relative: &Path) -> String {
+    path_norm::render(relative, Separator::Slash, Form::Nfc)
+}
+
+// One SHA-256 over a folder tree, so two trees can be compared by a single value. With `attributes`
+// it also covers each file's and folder's extended attributes or ADS, so the root changes whenever a
+// restore would recreate something different. The bytes hashed are HEADER, one byte (1 with
+// attributes, 0 without), then every record sorted by path (byte order) as:
+//   kind        (`d` folder, `f` file)
+//   path length (u32, big-endian)
+//   path        (UTF-8)
+//   content     (32 bytes, files only)
+//   attributes  (32 bytes, only with `attributes`)
+// Folders are records too, so empty ones count
+pub fn root(records: &[Record], attributes: bool) -> Result<String> {
+    let mut sorted: Vec<&Record> = records.iter().collect();
+    sorted.sort_by(|a, b| a.path.cmp(&b.path));
+    let mut hasher = Sha256::new();
+    hasher.update(HEADER);
+    hasher.update([attributes as u8]);
+    for record in sorted {
+        hasher.update([if record.content.is_some() { b'f' } else { b'd' }]);
+        hasher.update((record.path.len() as u32).to_be_bytes());
+        hasher.update(&record.path);
+        if let Some(content) = &record.content {
+            hasher.update(raw(content)?);
+        }
+        if attributes {
+            let digest = record.attributes.as_deref().ok_or_else(|| anyhow!("No attribute digest for {}", record.path))?;
+            hasher.update(raw(digest)?);
+        }
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn raw(digest: &str) -> Result<[u8; 32]> {
+    let bytes = hex::decode(digest).ok().filter(|b| b.len() == 32).ok_or_else(|| anyhow!("Not a SHA-256 digest: {}", digest))?;
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&bytes);
+    Ok(out)
+}
+
+// Folders below `root` and the root itself, relative to it; symlinks are not followed
+pub fn folders(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut out = vec![PathBuf::new()];
+    let mut pending = vec![PathBuf::new()];
+    while let Some(relative) = pending.pop() {
+        let dir = root.join(&relative);
+        for entry in fs::read_dir(&dir).with_context(|| format!("Failed to list folder: {}", dir.display()))?.filter_map(|e| e.ok()) {
+            if entry.file_type().is_ok_and(|ft| ft.is_dir()) {
+                let child = relative.join(entry.file_name());
+                out.push(child.clone());
+                pending.push(child);
+            }
+        }
+    }
+    Ok(out)
+}
+
+// Root digest of everything below `folder`, reading each file; (hex, files)
+pub fn digest(folder: &Path, attributes: bool, progress: &AtomicU64, cancel: &AtomicBool) -> Result<(String, usize)> {
+    let attributes_of = |path: &Path| attributes.then(|| crate::xattrs::digest(path)).transpose();
+    let mut records = Vec::new();
+    for relative in crate::folder_diff::tree(folder)?.into_keys() {
+        let path = folder.join(&relative);
+        let (hex, _) = algo::hash_file(&path, Algorithm::Sha256, progress, cancel)?;
+        records.push(Record { path: name(&relative), content: Some(hex), attributes: attributes_of(&path)? });
+    }
+    let files = records.len();
+    for relative in folders(folder)? {
+        records.push(Record { path: name(&relative), content: None, attributes: attributes_of(&folder.join(&relative))? });
+    }
+    Ok((root(&records, attributes)?, files))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    const B: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    fn record(path: &str, content: Option<&str>, attributes: Option<&str>) -> Record {
+        Record { path: path.to_string(), content: content.map(str::to_string), attributes: attributes.map(str::to_string) }
+    }
+
+    // The documented layout, written out by hand
+    #[test]
+    fn records_in_the_documented_serialization() {
+        let records = [record("sub/a.txt", Some(A), Some(B)), record("", None, Some(A))];
+        let mut plain = HEADER.to_vec();
+        plain.push(0);
+        plain.extend_from_slice(b"d\0\0\0\0");
+        plain.push(b'f');
+        plain.extend_from_slice(&9u32.to_be_bytes());
+        plain.extend_from_slice(b"sub/a.txt");
+        plain.extend_from_slice(&hex::decode(A).unwrap());
+        assert_eq!(root(&records, false).unwrap(), hex::encode(Sha256::digest(&plain)));
+
+        let mut with = HEADER.to_vec();
+        with.push(1);
+        with.extend_from_slice(b"d\0\0\0\0");
+        with.extend_from_slice(&hex::decode(A).unwrap());
+        with.push(b'f');
+        with.extend_from_slice(&9u32.to_be_bytes());
+        with.extend_from_slice(b"sub/a.txt");
+        with.extend_from_slice(&hex::decode(A).unwrap());
+        with.extend_from_slice(&hex::decode(B).unwrap());
+        assert_eq!(root(&records, true).unwrap(), hex::encode(Sha256::digest(&with)));
+    }
+
+    #[test]
+    fn attributes_and_empty_folders_change_the_root() {
+        let records = vec![record("", None, Some(B)), record("a", Some(A), Some(B))];
+        let mut changed = records.clone();
+        changed[1].attributes = Some(A.to_string());
+        assert_eq!(root(&records, false).unwrap(), root(&changed, false).unwrap());
+        assert_ne!(root(&records, true).unwrap(), root(&changed, true).unwrap());
+        let mut emptied = records.clone();
+        emptied.push(record("empty", None, Some(B)));
+        assert_ne!(root(&records, true).unwrap(), root(&emptied, true).unwrap());
+        assert!(root(&[record("a", Some(A), None)], true).is_err());
+        assert!(root(&[record("a", Some("00"), None)], false).is_err());
+    }
+
+    #[test]
+    fn a_folder_on_disk_gives_the_root_of_its_records() {
+        let dir = std::env::temp_dir().join(format!("rust-hash-tree-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub").join("empty")).unwrap();
+        fs::write(dir.join("sub").join("a.txt"), b"abc").unwrap();
+        fs::write(dir.join("b.txt"), b"").unwrap();
+        let hashed = digest(&dir, false, &AtomicU64::new(0), &AtomicBool::new(false));
+        let _ = fs::remove_dir_all(&dir);
+        let records = [record("", None, None), record("sub", None, None), record("sub/empty", None, None), record("sub/a.txt", Some(A), None), record("b.txt", Some(B), None)];
+        assert_eq!(hashed.unwrap(), (root(&records, false).unwrap(), 2));
+    }
+}
@@ -0,0 +1,97 @@
+use std::path::{Path, PathBuf};
+
+// Turns pasted `file://` URIs, quoted paths and percent-encoded paths into plain filesystem paths.
+// Anything else is returned unchanged so normal typing is not disturbed.
+pub fn normalize_input(input: &str) -> String {
+    let trimmed = input.trim();
+    // Explorer's "Copy as path" wraps the path in double quotes
+    let unquoted = match trimmed.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(inner) => inner,
+        None => trimmed,
+    };
+    if let Some(path) = file_uri_to_path(unquoted) {
+        return path;
+    }
+    // Only decode when that turns a missing path into an existing one; '%' is legal in file names
+    if unquoted.contains('%') && !Path::new(unquoted).exists() {
+        if let Some(decoded) = percent_decode(unquoted).filter(|d| Path::new(d).exists()) {
+            return decoded;
+        }
+    }
+    if unquoted.len() != trimmed.len() {
+        return unquoted.to_string();
+    }
+    input.to_string()
+}
+
+// Dropped paths are usually real paths already; some browsers hand over the URI instead
+pub fn normalize_dropped(path: PathBuf) -> PathBuf {
+    match path.to_str().and_then(file_uri_to_path) {
+        Some(p) => PathBuf::from(p),
+        None => path,
+    }
+}
+
+// file:///C:/dir/a%20b.txt -> C:\dir\a b.txt, file://server/share/x -> \\server\share\x, file:///home/x -> /home/x
+pub fn file_uri_to_path(uri: &str) -> Option<String> {
+    let scheme = uri.get(..7)?;
+    if !scheme.eq_ignore_ascii_case("file://") {
+        return None;
+    }
+    let rest = &uri[7..];
+    // A raw '#' or '?' starts a fragment or query; characters that belong to the path are percent-encoded
+    let rest = &rest[..rest.find(['?', '#']).unwrap_or(rest.len())];
+    let (host, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, ""),
+    };
+    let host = percent_decode(host)?;
+    let path = percent_decode(path)?;
+    let out = if host.is_empty() || host.eq_ignore_ascii_case("localhost") {
+        match path.strip_prefix('/').filter(|p| is_drive(p)) {
+            Some(drive_path) => fix_drive(drive_path),
+            None => path,
+        }
+    } else if is_drive(&host) {
+        // Malformed but common: file://C:/dir
+        fix_drive(&format!("{}{}", host, path))
+    } else {
+        // Remote host: a UNC path
+        format!("//{}{}", host, path)
+    };
+    if out.is_empty() {
+        return None;
+    }
+    Some(if cfg!(windows) { out.replace('/', "\\") } else { out })
+}
+
+fn is_drive(s: &str) -> bool {
+    let b = s.as_bytes();
+    b.len() >= 2 && b[0].is_ascii_alphabetic() && (b[1] == b':' || b[1] == b'|') && (b.len() == 2 || b[2] == b'/')
+}
+
+// Old URIs write the drive as C| instead of C:
+fn fix_drive(s: &str) -> String {
+    let mut out = s.to_string();
+    if out.as_bytes().get(1) == Some(&b'|') {
+        out.replace_range(1..2, ":");
+    }
+    out
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3)?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
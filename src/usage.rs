@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::algo::Algorithm;
+use crate::history::now_timestamp;
+use crate::storage;
+
+const USAGE_FILE: &str = "usage.json";
+// Counts from a busy batch are written together rather than once per file
+const SAVE_AFTER: Duration = Duration::from_secs(5);
+
+// How often each feature, algorithm and kind of error came up, so a team can see what it relies on.
+// Only counted once turned on in Settings. The counts are kept in usage.json in the data folder and
+// shown on the Usage page, and that is all: this module uses no network code, and nothing here is
+// read by exports, reports, diagnostics, webhooks or email. Counts only, never paths or digests
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Counts {
+    // Unix timestamp of the first count, or of the last reset
+    pub since: i64,
+    pub operations: BTreeMap<String, u64>,
+    pub algorithms: BTreeMap<String, u64>,
+    pub errors: BTreeMap<String, u64>,
+}
+
+impl Counts {
+    fn is_empty(&self) -> bool {
+        self.operations.is_empty() && self.algorithms.is_empty() && self.errors.is_empty()
+    }
+
+    fn add(&mut self, other: &Counts) {
+        if self.since == 0 || (other.since != 0 && other.since < self.since) {
+            self.since = other.since;
+        }
+        for (mine, theirs) in [(&mut self.operations, &other.operations), (&mut self.algorithms, &other.algorithms), (&mut self.errors, &other.errors)] {
+            for (name, count) in theirs {
+                *mine.entry(name.clone()).or_default() += count;
+            }
+        }
+    }
+}
+
+// Most frequent first, for the Usage page
+pub fn ranked(counts: &BTreeMap<String, u64>) -> Vec<(&str, u64)> {
+    let mut ranked: Vec<(&str, u64)> = counts.iter().map(|(name, count)| (name.as_str(), *count)).collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    ranked
+}
+
+#[derive(Debug, Default)]
+pub struct Usage {
+    saved: Counts,
+    // Counted since the last save; added to whatever the file holds then, so the window and the
+    // command line do not overwrite each other's counts
+    pending: Counts,
+    due: Option<Instant>,
+}
+
+impl Usage {
+    // Missing or unreadable counts start again from zero
+    pub fn load() -> Self {
+        let saved = usage_path()
+            .and_then(|p| fs::read(p).ok())
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+        Usage { saved, ..Usage::default() }
+    }
+
+    pub fn operation(&mut self, name: &str) {
+        self.count(|c| &mut c.operations, name);
+    }
+
+    pub fn algorithm(&mut self, algorithm: Algorithm) {
+        self.count(|c| &mut c.algorithms, algorithm.label());
+    }
+
+    pub fn error(&mut self, reason: &str) {
+        self.count(|c| &mut c.errors, reason);
+    }
+
+    fn count(&mut self, field: fn(&mut Counts) -> &mut BTreeMap<String, u64>, name: &str) {
+        if self.pending.since == 0 {
+            self.pending.since = now_timestamp();
+        }
+        *field(&mut self.pending).entry(name.to_string()).or_default() += 1;
+        self.due.get_or_insert_with(|| Instant::now() + SAVE_AFTER);
+    }
+
+    // Everything counted so far, saved or not
+    pub fn totals(&self) -> Counts {
+        let mut totals = self.saved.clone();
+        totals.add(&self.pending);
+        totals
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.due.is_some()
+    }
+
+    pub fn save_if_due(&mut self) -> Result<()> {
+        match self.due {
+            Some(due) if Instant::now() >= due => self.flush(),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.due = None;
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let path = usage_path().context("No data directory available for usage counts")?;
+        let mut counts: Counts = fs::read(&path).ok().and_then(|data| serde_json::from_slice(&data).ok()).unwrap_or_default();
+        counts.add(&self.pending);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+        }
+        let data = serde_json::to_vec_pretty(&counts)?;
+        fs::write(&path, data).with_context(|| format!("Failed to write usage counts: {}", path.display()))?;
+        self.saved = counts;
+        self.pending = Counts::default();
+        Ok(())
+    }
+
+    pub fn reset(&mut self) -> Result<()> {
+        *self = Usage::default();
+        match usage_path().map(fs::remove_file) {
+            Some(Err(e)) if e.kind() != std::io::ErrorKind::NotFound => Err(e).context("Failed to remove usage counts"),
+            _ => Ok(()),
+        }
+    }
+}
+
+pub fn usage_path() -> Option<PathBuf> {
+    storage::data_dir().map(|d| d.join(USAGE_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    // The counts stay on this machine: this module reaches no network or export code, and the modules
+    // that send or export anything never read it. Checked on the source, so a later import fails here
+    #[test]
+    fn counts_stay_out_of_network_and_export_code() {
+        let own = include_str!("usage.rs").split("#[cfg(test)]").next().unwrap();
+        for network in ["ureq", "lettre", "std::net", "std::process", "crate::webhook", "crate::email", "crate::remote", "crate::report", "crate::pdf"] {
+            assert!(!own.contains(network), "usage.rs uses {}", network);
+        }
+        let senders = [
+            ("webhook.rs", include_str!("webhook.rs")),
+            ("email.rs", include_str!("email.rs")),
+            ("remote.rs", include_str!("remote.rs")),
+            ("report.rs", include_str!("report.rs")),
+            ("pdf.rs", include_str!("pdf.rs")),
+        ];
+        for (file, source) in senders {
+            assert!(!source.contains("usage"), "{} reads the usage counts", file);
+        }
+    }
+}
@@ -0,0 +1,447 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine as _;
+use flate2::read::ZlibDecoder;
+
+const SECTOR: u64 = 512;
+const MIB: u64 = 1024 * 1024;
+
+// VHDX region and metadata item GUIDs as they appear on disk
+const VHDX_BAT: &str = "2DC27766-F623-4200-9D64-115E9BFD4A08";
+const VHDX_METADATA: &str = "8B7CA206-4790-4B9A-B8FE-575F050F886E";
+const VHDX_FILE_PARAMETERS: &str = "CAA16737-FA36-4D43-B3B6-33F0AA44E76B";
+const VHDX_DISK_SIZE: &str = "2FA54224-CD1B-4876-B211-5DBED83BF4B8";
+const VHDX_SECTOR_SIZE: &str = "8141BF1D-A96F-4709-BA47-F233A8FAAB5F";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Vhd,
+    Vhdx,
+    Dmg,
+}
+
+impl Format {
+    pub fn of(path: &Path) -> Option<Format> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "vhd" => Some(Format::Vhd),
+            "vhdx" => Some(Format::Vhdx),
+            "dmg" => Some(Format::Dmg),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Format::Vhd => "VHD",
+            Format::Vhdx => "VHDX",
+            Format::Dmg => "DMG",
+        }
+    }
+}
+
+// Where a run of virtual disk bytes comes from
+#[derive(Debug, Clone, Copy)]
+enum Source {
+    Zero,
+    Raw(u64),
+    // DMG block chunk: zlib stream at (offset, compressed length)
+    Zlib(u64, u64),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Piece {
+    len: u64,
+    source: Source,
+}
+
+// Reads the virtual disk a container describes, front to back, as if it were a raw image
+pub struct PayloadReader {
+    file: File,
+    pieces: Vec<Piece>,
+    index: usize,
+    offset: u64,
+    decoded: Option<Vec<u8>>,
+}
+
+// Opens the container and maps its payload; returns the reader and the virtual disk size
+pub fn open(file: File, format: Format) -> Result<(PayloadReader, u64)> {
+    let mut file = file;
+    let pieces = match format {
+        Format::Vhd => vhd_pieces(&mut file)?,
+        Format::Vhdx => vhdx_pieces(&mut file)?,
+        Format::Dmg => dmg_pieces(&mut file)?,
+    };
+    let size = pieces.iter().map(|p| p.len).sum();
+    Ok((PayloadReader { file, pieces, index: 0, offset: 0, decoded: None }, size))
+}
+
+impl Read for PayloadReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let Some(piece) = self.pieces.get(self.index).copied() else {
+                return Ok(0);
+            };
+            let left = piece.len - self.offset;
+            if left == 0 {
+                self.index += 1;
+                self.offset = 0;
+                self.decoded = None;
+                continue;
+            }
+            let want = (buf.len() as u64).min(left) as usize;
+            let n = match piece.source {
+                Source::Zero => {
+                    buf[..want].fill(0);
+                    want
+                }
+                Source::Raw(at) => {
+                    self.file.seek(SeekFrom::Start(at + self.offset))?;
+                    let n = self.file.read(&mut buf[..want])?;
+                    if n == 0 {
+                        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Container ends inside its payload"));
+                    }
+                    n
+                }
+                Source::Zlib(at, compressed) => {
+                    if self.decoded.is_none() {
+                        let mut raw = vec![0u8; compressed as usize];
+                        self.file.seek(SeekFrom::Start(at))?;
+                        self.file.read_exact(&mut raw)?;
+                        let mut out = Vec::with_capacity(piece.len as usize);
+                        ZlibDecoder::new(&raw[..]).read_to_end(&mut out)?;
+                        if (out.len() as u64) < piece.len {
+                            return Err(io::Error::new(io::ErrorKind::InvalidData, "DMG chunk decompresses to fewer bytes than it covers"));
+                        }
+                        self.decoded = Some(out);
+                    }
+                    let data = self.decoded.as_ref().unwrap();
+                    let start = self.offset as usize;
+                    buf[..want].copy_from_slice(&data[start..start + want]);
+                    want
+                }
+            };
+            self.offset += n as u64;
+            return Ok(n);
+        }
+    }
+}
+
+fn read_at(file: &mut File, offset: u64, len: usize) -> Result<Vec<u8>> {
+    let mut buf = vec![0u8; len];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut buf).with_context(|| format!("Container structure at offset {} is cut off", offset))?;
+    Ok(buf)
+}
+
+fn be32(b: &[u8], at: usize) -> u32 {
+    u32::from_be_bytes(b[at..at + 4].try_into().unwrap())
+}
+
+fn be64(b: &[u8], at: usize) -> u64 {
+    u64::from_be_bytes(b[at..at + 8].try_into().unwrap())
+}
+
+fn le32(b: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(b[at..at + 4].try_into().unwrap())
+}
+
+fn le64(b: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(b[at..at + 8].try_into().unwrap())
+}
+
+// Blocks of `block` bytes from `sources`, with the last one cut at `size`
+fn blocks(sources: impl Iterator<Item = Source>, block: u64, size: u64) -> Vec<Piece> {
+    let mut pieces = Vec::new();
+    let mut covered = 0;
+    for source in sources {
+        if covered >= size {
+            break;
+        }
+        let len = block.min(size - covered);
+        pieces.push(Piece { len, source });
+        covered += len;
+    }
+    if covered < size {
+        pieces.push(Piece { len: size - covered, source: Source::Zero });
+    }
+    pieces
+}
+
+// VHD: fixed disks are the raw image plus a 512-byte footer; dynamic disks map blocks through the BAT
+fn vhd_pieces(file: &mut File) -> Result<Vec<Piece>> {
+    let len = file.metadata()?.len();
+    if len < SECTOR {
+        bail!("Too small to be a VHD");
+    }
+    let footer = read_at(file, len - SECTOR, SECTOR as usize)?;
+    if &footer[..8] != b"conectix" {
+        bail!("No VHD footer (\"conectix\") at the end of the file");
+    }
+    let size = be64(&footer, 48);
+    match be32(&footer, 60) {
+        2 => Ok(vec![Piece { len: size.min(len - SECTOR), source: Source::Raw(0) }]),
+        3 => {
+            let header = read_at(file, be64(&footer, 16), 1024)?;
+            if &header[..8] != b"cxsparse" {
+                bail!("VHD dynamic disk header missing");
+            }
+            let (table, entries, block) = (be64(&header, 16), be32(&header, 28) as usize, be32(&header, 32) as u64);
+            // Each block starts with a sector bitmap, padded to whole sectors
+            let bitmap = (block / SECTOR).div_ceil(8).div_ceil(SECTOR) * SECTOR;
+            let bat = read_at(file, table, entries * 4)?;
+            let sources = bat.chunks_exact(4).map(|e| match be32(e, 0) {
+                u32::MAX => Source::Zero,
+                sector => Source::Raw(sector as u64 * SECTOR + bitmap),
+            });
+            Ok(blocks(sources, block, size))
+        }
+        4 => bail!("Differencing VHDs need their parent disk; hash the merged disk instead"),
+        other => bail!("Unknown VHD disk type {}", other),
+    }
+}
+
+fn guid(text: &str) -> [u8; 16] {
+    let hex: String = text.chars().filter(|c| *c != '-').collect();
+    let b = hex::decode(hex).expect("valid GUID literal");
+    // The first three fields are stored little-endian
+    [b[3], b[2], b[1], b[0], b[5], b[4], b[7], b[6], b[8], b[9], b[10], b[11], b[12], b[13], b[14], b[15]]
+}
+
+fn vhdx_pieces(file: &mut File) -> Result<Vec<Piece>> {
+    if read_at(file, 0, 8)? != b"vhdxfile" {
+        bail!("No VHDX file identifier");
+    }
+    // The header with the higher sequence number is current; pending log entries must be replayed first
+    let headers = [read_at(file, 64 * 1024, 80)?, read_at(file, 128 * 1024, 80)?];
+    let header = headers.iter().filter(|h| &h[..4] == b"head").max_by_key(|h| le64(h, 8)).context("No valid VHDX header")?;
+    if header[48..64].iter().any(|&b| b != 0) {
+        bail!("The VHDX has unapplied log entries; attach it once in Windows/Hyper-V so they are replayed");
+    }
+    let regions = read_at(file, 192 * 1024, 64 * 1024)?;
+    if &regions[..4] != b"regi" {
+        bail!("No VHDX region table");
+    }
+    let find_region = |id: &str| {
+        let id = guid(id);
+        (0..le32(&regions, 8) as usize).map(|i| 16 + i * 32).find(|&at| regions[at..at + 16] == id).map(|at| (le64(&regions, at + 16), le32(&regions, at + 24)))
+    };
+    let (bat_at, bat_len) = find_region(VHDX_BAT).context("VHDX BAT region missing")?;
+    let (meta_at, meta_len) = find_region(VHDX_METADATA).context("VHDX metadata region missing")?;
+    let meta = read_at(file, meta_at, meta_len as usize)?;
+    if &meta[..8] != b"metadata" {
+        bail!("No VHDX metadata table");
+    }
+    let item = |id: &str| {
+        let id = guid(id);
+        (0..u16::from_le_bytes([meta[10], meta[11]]) as usize)
+            .map(|i| 32 + i * 32)
+            .find(|&at| meta[at..at + 16] == id)
+            .map(|at| le32(&meta, at + 16) as usize)
+            .filter(|&offset| offset + 8 <= meta.len())
+    };
+    let params = item(VHDX_FILE_PARAMETERS).context("VHDX file parameters missing")?;
+    let (block, flags) = (le32(&meta, params) as u64, le32(&meta, params + 4));
+    if flags & 0x2 != 0 {
+        bail!("Differencing VHDXs need their parent disk; hash the merged disk instead");
+    }
+    let size = le64(&meta, item(VHDX_DISK_SIZE).context("VHDX virtual disk size missing")?);
+    let sector = le32(&meta, item(VHDX_SECTOR_SIZE).context("VHDX logical sector size missing")?) as u64;
+    if block == 0 || sector == 0 {
+        bail!("Damaged VHDX metadata");
+    }
+    // One sector bitmap entry follows every `chunk_ratio` payload entries in the BAT
+    let chunk_ratio = ((1u64 << 23) * sector / block).max(1);
+    let bat = read_at(file, bat_at, bat_len as usize)?;
+    let data_blocks = size.div_ceil(block);
+    let mut sources = Vec::with_capacity(data_blocks as usize);
+    for i in 0..data_blocks {
+        let at = ((i + i / chunk_ratio) * 8) as usize;
+        let entry = bat.get(at..at + 8).map(|e| le64(e, 0)).unwrap_or(0);
+        sources.push(match entry & 0x7 {
+            // Fully present
+            6 => Source::Raw((entry >> 20) * MIB),
+            // Partially present blocks only occur in differencing disks
+            7 => bail!("VHDX block {} is only partially present", i),
+            // Not present, undefined, zero or unmapped all read as zeros
+            _ => Source::Zero,
+        });
+    }
+    Ok(blocks(sources.into_iter(), block, size))
+}
+
+// DMG (UDIF): the "koly" trailer points at an XML property list whose "blkx" entries map sectors to chunks
+fn dmg_pieces(file: &mut File) -> Result<Vec<Piece>> {
+    let len = file.metadata()?.len();
+    if len < SECTOR {
+        bail!("Too small to be a DMG");
+    }
+    let koly = read_at(file, len - SECTOR, SECTOR as usize)?;
+    if &koly[..4] != b"koly" {
+        bail!("No UDIF trailer (\"koly\"); only UDIF disk images are supported");
+    }
+    let data_fork = be64(&koly, 24);
+    let (xml_at, xml_len) = (be64(&koly, 216), be64(&koly, 224));
+    let sectors = be64(&koly, 492);
+    if xml_len == 0 {
+        bail!("DMG without a property list (old NDIF images) is not supported");
+    }
+    let xml = String::from_utf8_lossy(&read_at(file, xml_at, xml_len as usize)?).into_owned();
+    let start = xml.find("<key>blkx</key>").context("DMG property list has no blkx table")?;
+    let end = xml[start..].find("</array>").map_or(xml.len(), |e| start + e);
+    let mut chunks = Vec::new();
+    let mut rest = &xml[start..end];
+    while let Some(open) = rest.find("<data>") {
+        let close = rest[open..].find("</data>").context("Unterminated <data> in DMG property list")? + open;
+        let encoded: String = rest[open + 6..close].chars().filter(|c| !c.is_whitespace()).collect();
+        let mish = base64::engine::general_purpose::STANDARD.decode(encoded).context("Bad base64 in DMG blkx table")?;
+        rest = &rest[close..];
+        if mish.len() < 204 || &mish[..4] != b"mish" {
+            continue;
+        }
+        let first_sector = be64(&mish, 8);
+        for c in mish[204..].chunks_exact(40).take(be32(&mish, 200) as usize) {
+            let (kind, sector, count, offset, length) = (be32(c, 0), be64(c, 8), be64(c, 16), be64(c, 24), be64(c, 32));
+            let source = match kind {
+                // Zero fill and free space
+                0x0000_0000 | 0x0000_0002 => Source::Zero,
+                0x0000_0001 => Source::Raw(data_fork + offset),
+                0x8000_0005 => Source::Zlib(data_fork + offset, length),
+                // Comment and terminator entries carry no data
+                0x7FFF_FFFE | 0xFFFF_FFFF => continue,
+                0x8000_0004 => bail!("ADC-compressed DMGs are not supported"),
+                0x8000_0006 => bail!("bzip2-compressed DMGs (UDBZ) are not supported; convert to UDZO or UDRO first"),
+                0x8000_0007 => bail!("LZFSE-compressed DMGs (ULFO) are not supported; convert to UDZO or UDRO first"),
+                0x8000_0008 => bail!("LZMA-compressed DMGs (ULMO) are not supported; convert to UDZO or UDRO first"),
+                other => bail!("Unknown DMG chunk type {:#010x}", other),
+            };
+            if count > 0 {
+                chunks.push((first_sector + sector, count, source));
+            }
+        }
+    }
+    if chunks.is_empty() {
+        return Err(anyhow!("The DMG blkx table lists no data"));
+    }
+    chunks.sort_by_key(|c| c.0);
+    let mut pieces = Vec::with_capacity(chunks.len());
+    let mut next = 0;
+    for (sector, count, source) in chunks {
+        if sector > next {
+            pieces.push(Piece { len: (sector - next) * SECTOR, source: Source::Zero });
+        } else if sector < next {
+            bail!("Overlapping chunks in the DMG blkx table");
+        }
+        pieces.push(Piece { len: count * SECTOR, source });
+        next = sector + count;
+    }
+    if sectors > next {
+        pieces.push(Piece { len: (sectors - next) * SECTOR, source: Source::Zero });
+    }
+    Ok(pieces)
+}
+
+// Virtual disk size of a container, for progress; None when it cannot be mapped
+pub fn payload_size(path: &Path) -> Option<u64> {
+    let format = Format::of(path)?;
+    File::open(path).ok().and_then(|f| open(f, format).ok()).map(|(_, size)| size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+
+    fn payload(container: &[u8], format: Format) -> (Vec<u8>, u64) {
+        let path = std::env::temp_dir().join(format!("rust-hash-vdisk-test-{}-{}", std::process::id(), format.label()));
+        fs::write(&path, container).unwrap();
+        let (mut reader, size) = open(File::open(&path).unwrap(), format).unwrap();
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).unwrap();
+        let _ = fs::remove_file(&path);
+        (data, size)
+    }
+
+    fn vhd_footer(kind: u32, size: u64, header_at: u64) -> Vec<u8> {
+        let mut footer = vec![0u8; SECTOR as usize];
+        footer[..8].copy_from_slice(b"conectix");
+        footer[16..24].copy_from_slice(&header_at.to_be_bytes());
+        footer[48..56].copy_from_slice(&size.to_be_bytes());
+        footer[60..64].copy_from_slice(&kind.to_be_bytes());
+        footer
+    }
+
+    #[test]
+    fn fixed_vhd_is_the_image_before_the_footer() {
+        let data: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+        let (read, size) = payload(&[data.clone(), vhd_footer(2, 1000, u64::MAX)].concat(), Format::Vhd);
+        assert_eq!((read, size), (data, 1000));
+    }
+
+    // Two 4 KiB blocks, the first present behind its one-sector bitmap and the second never written,
+    // then a tail past the last BAT entry
+    #[test]
+    fn dynamic_vhd_maps_blocks_through_the_bat() {
+        let block: Vec<u8> = (0..4096u32).map(|i| (i % 251) as u8).collect();
+        let size = 3 * 4096 - 100;
+        let mut header = vec![0u8; 1024];
+        header[..8].copy_from_slice(b"cxsparse");
+        header[16..24].copy_from_slice(&1536u64.to_be_bytes());
+        header[28..32].copy_from_slice(&2u32.to_be_bytes());
+        header[32..36].copy_from_slice(&4096u32.to_be_bytes());
+        let mut bat = [4u32.to_be_bytes(), u32::MAX.to_be_bytes()].concat();
+        bat.resize(512, 0xFF);
+        let container = [vhd_footer(3, size, 512), header, bat, vec![0xFF; 512], block.clone(), vhd_footer(3, size, 512)].concat();
+        let (read, len) = payload(&container, Format::Vhd);
+        assert_eq!(len, size);
+        assert_eq!(read, [block, vec![0; size as usize - 4096]].concat());
+    }
+
+    fn mish_chunk(kind: u32, sector: u64, count: u64, offset: u64, length: u64) -> Vec<u8> {
+        let mut chunk = vec![0u8; 40];
+        chunk[..4].copy_from_slice(&kind.to_be_bytes());
+        chunk[8..16].copy_from_slice(&sector.to_be_bytes());
+        chunk[16..24].copy_from_slice(&count.to_be_bytes());
+        chunk[24..32].copy_from_slice(&offset.to_be_bytes());
+        chunk[32..40].copy_from_slice(&length.to_be_bytes());
+        chunk
+    }
+
+    // A zlib chunk of two sectors, a zero-fill sector, a raw sector and a trailing unlisted sector
+    #[test]
+    fn dmg_chunks_decompress_in_sector_order() {
+        let first: Vec<u8> = (0..1024u32).map(|i| (i % 7) as u8).collect();
+        let raw = vec![0x5A; SECTOR as usize];
+        let mut zlib = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        zlib.write_all(&first).unwrap();
+        let compressed = zlib.finish().unwrap();
+        let fork = [compressed.clone(), raw.clone()].concat();
+        let chunks = [
+            mish_chunk(0x0000_0001, 3, 1, compressed.len() as u64, SECTOR),
+            mish_chunk(0x8000_0005, 0, 2, 0, compressed.len() as u64),
+            mish_chunk(0x0000_0002, 2, 1, 0, 0),
+            mish_chunk(0xFFFF_FFFF, 4, 0, 0, 0),
+        ];
+        let mut mish = vec![0u8; 204];
+        mish[..4].copy_from_slice(b"mish");
+        mish[200..204].copy_from_slice(&(chunks.len() as u32).to_be_bytes());
+        mish.extend(chunks.concat());
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&mish);
+        let xml = format!("<plist><dict><key>resource-fork</key><dict><key>blkx</key><array><dict><key>Data</key><data>\n{}\n</data></dict></array></dict></dict></plist>", encoded);
+        let mut koly = vec![0u8; SECTOR as usize];
+        koly[..4].copy_from_slice(b"koly");
+        koly[216..224].copy_from_slice(&(fork.len() as u64).to_be_bytes());
+        koly[224..232].copy_from_slice(&(xml.len() as u64).to_be_bytes());
+        koly[492..500].copy_from_slice(&5u64.to_be_bytes());
+        let (read, size) = payload(&[fork, xml.into_bytes(), koly].concat(), Format::Dmg);
+        assert_eq!(size, 5 * SECTOR);
+        assert_eq!(read, [first, vec![0; SECTOR as usize], raw, vec![0; SECTOR as usize]].concat());
+    }
+}
@@ -0,0 +1,388 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+
+use crate::acknowledged::Ack;
+use crate::algo::{self, Algorithm};
+use crate::path_norm::{self, Matching};
+use crate::progress::{Snapshot, Tracker};
+use crate::timing::Timing;
+
+// A file and the SHA-256 (lowercase hex) a checksum list or log says it should have
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub expected: String,
+    // Index into the run's `lists`; 0 unless several lists are verified together
+    pub list: usize,
+    // Other digests listed for the same file (hashdeep's MD5 and SHA-1 columns), checked in the same read
+    pub also: Vec<(Algorithm, String)>,
+    // Digest of the file's extended attributes or alternate data streams, when the list has one
+    pub xattrs: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Status {
+    Ok,
+    Mismatch(String),
+    // The content matched but the extended attributes or ADS did not; holds their digest now
+    AttributesChanged(String),
+    Missing,
+    Failed(String),
+}
+
+// One re-verified list entry
+#[derive(Debug, Clone)]
+pub struct Checked {
+    pub entry: Entry,
+    pub status: Status,
+    pub bytes: Option<u64>,
+    // Extra detail, e.g. how many PAR2 blocks are damaged
+    pub note: Option<String>,
+    // Time spent on this file; set by the run, not by the check itself
+    pub elapsed: Duration,
+}
+
+impl Checked {
+    pub fn label(&self) -> &'static str {
+        match self.status {
+            Status::Ok => "Verified",
+            Status::Mismatch(_) => "MISMATCH",
+            Status::AttributesChanged(_) => "XATTRS",
+            Status::Missing => "MISSING",
+            Status::Failed(_) => "FAILED",
+        }
+    }
+
+    pub fn ok(&self) -> bool {
+        matches!(self.status, Status::Ok)
+    }
+}
+
+// Checks one entry; kept by the run so failed entries can be checked again
+type CheckFn = Arc<dyn Fn(Entry, &Arc<AtomicU64>, &Arc<AtomicBool>) -> Checked + Send + Sync>;
+
+// Hashes every listed file on a worker thread and compares it with the expected digest
+pub struct VerifyRun {
+    // The list or log the entries came from; the first one when several are verified together
+    pub source: PathBuf,
+    // Every list in the run with its number of checkable entries
+    pub lists: Vec<(PathBuf, usize)>,
+    pub format: String,
+    // Digest the expected values use
+    pub algorithm: &'static str,
+    pub files: usize,
+    // List lines that were not SHA-256 and so could not be checked
+    pub skipped: usize,
+    pub done: Vec<Checked>,
+    // Set when the run ended early because stop-on-failure was on
+    pub stopped: bool,
+    // Failures of this list accepted in an earlier run; they are no longer reported as problems
+    pub acknowledged: Vec<Ack>,
+    stop_on_failure: Arc<AtomicBool>,
+    check: CheckFn,
+    tracker: Arc<Tracker>,
+    cancel: Arc<AtomicBool>,
+    rx: Option<Receiver<Checked>>,
+}
+
+impl VerifyRun {
+    pub fn start(source: PathBuf, list: crate::hashlist::HashList, forensic: bool, matching: Matching) -> VerifyRun {
+        Self::spawn(source, list.format.to_string(), "SHA-256", list.entries, list.skipped, move |entry, p, c| check(entry, p, c, forensic, matching))
+    }
+
+    // One run over the entries of all `lists`, with a combined summary and one per list
+    pub fn start_many(lists: Vec<(PathBuf, crate::hashlist::HashList)>, forensic: bool, matching: Matching) -> VerifyRun {
+        let source = lists.first().map(|(path, _)| path.clone()).unwrap_or_default();
+        let format = format!("{} lists", lists.len());
+        let skipped = lists.iter().map(|(_, list)| list.skipped).sum();
+        let counts = lists.iter().map(|(path, list)| (path.clone(), list.entries.len())).collect();
+        let entries = lists.into_iter().enumerate().flat_map(|(i, (_, list))| list.entries.into_iter().map(move |e| Entry { list: i, ..e })).collect();
+        let mut run = Self::spawn(source, format, "SHA-256", entries, skipped, move |entry, p, c| check(entry, p, c, forensic, matching));
+        run.lists = counts;
+        run
+    }
+
+    // Runs `check` on every entry in order; for lists whose digests are not SHA-256
+    pub fn spawn<F>(source: PathBuf, format: String, algorithm: &'static str, entries: Vec<Entry>, skipped: usize, check: F) -> VerifyRun
+    where
+        F: Fn(Entry, &Arc<AtomicU64>, &Arc<AtomicBool>) -> Checked + Send + Sync + 'static,
+    {
+        let files = entries.len();
+        let mut run = VerifyRun {
+            lists: vec![(source.clone(), files)],
+            source,
+            format,
+            algorithm,
+            files,
+            skipped,
+            done: Vec::new(),
+            stopped: false,
+            acknowledged: Vec::new(),
+            stop_on_failure: Arc::new(AtomicBool::new(false)),
+            check: Arc::new(check),
+            tracker: Tracker::new(Arc::new(AtomicU64::new(0)), None, 0),
+            cancel: Arc::new(AtomicBool::new(false)),
+            rx: None,
+        };
+        run.launch(entries);
+        run
+    }
+
+    fn launch(&mut self, entries: Vec<Entry>) {
+        let tracker = Tracker::new(Arc::new(AtomicU64::new(0)), None, entries.len());
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        let (t, c, stop, check) = (tracker.clone(), cancel.clone(), self.stop_on_failure.clone(), self.check.clone());
+        thread::spawn(move || {
+            // Sizes are measured here so a long list on a slow share does not hold up the window
+            t.set_total(Some(entries.iter().filter_map(|e| std::fs::metadata(&e.path).ok()).map(|m| m.len()).sum()));
+            let p = t.counter();
+            for entry in entries {
+                if c.load(Ordering::Relaxed) {
+                    break;
+                }
+                t.start_file(&entry.path);
+                let started = Instant::now();
+                let mut checked = check(entry, &p, &c);
+                checked.elapsed = started.elapsed();
+                t.finish_file();
+                let failed = !checked.ok();
+                if tx.send(checked).is_err() || (failed && stop.load(Ordering::Relaxed)) {
+                    break;
+                }
+            }
+        });
+        self.tracker = tracker;
+        self.cancel = cancel;
+        self.rx = Some(rx);
+    }
+
+    // Checks the FAILED and MISSING entries of a finished run again; their new results replace the old
+    // ones. Mismatches are left alone, since reading the file again gives the same digest
+    pub fn retry_failed(&mut self) {
+        if self.is_running() {
+            return;
+        }
+        let done = std::mem::take(&mut self.done);
+        let (retry, keep): (Vec<Checked>, Vec<Checked>) = done.into_iter().partition(|c| self.is_retryable(c));
+        self.done = keep;
+        self.stopped = false;
+        self.launch(retry.into_iter().map(|c| c.entry).collect());
+    }
+
+    fn is_retryable(&self, item: &Checked) -> bool {
+        matches!(item.status, Status::Failed(_) | Status::Missing) && !self.is_acknowledged(item)
+    }
+
+    pub fn retryable(&self) -> usize {
+        self.done.iter().filter(|c| self.is_retryable(c)).count()
+    }
+
+    // Quick smoke checks: end the run at the first entry that does not verify instead of summarizing all of them
+    pub fn stop_on_failure(self, on: bool) -> VerifyRun {
+        self.stop_on_failure.store(on, Ordering::Relaxed);
+        self
+    }
+
+    pub fn with_acknowledged(mut self, acknowledged: Vec<Ack>) -> VerifyRun {
+        self.acknowledged = acknowledged;
+        self
+    }
+
+    // A mismatch with the digest that was acknowledged, or a file acknowledged as missing that still is
+    pub fn is_acknowledged(&self, item: &Checked) -> bool {
+        let digest = match &item.status {
+            Status::Mismatch(hex) | Status::AttributesChanged(hex) => Some(hex),
+            Status::Missing => None,
+            _ => return false,
+        };
+        let manifest = self.list_of(item);
+        self.acknowledged.iter().any(|a| a.manifest == manifest && a.path == item.entry.path && a.digest.as_ref().map(|d| d.to_lowercase()) == digest.map(|d| d.to_lowercase()))
+    }
+
+    // The list `item` was read from
+    pub fn list_of(&self, item: &Checked) -> &Path {
+        self.lists.get(item.entry.list).map_or(&self.source, |(path, _)| path)
+    }
+
+    pub fn acknowledged_count(&self) -> usize {
+        self.done.iter().filter(|c| self.is_acknowledged(c)).count()
+    }
+
+    // Collects finished files; true the one time the run ends
+    pub fn poll(&mut self) -> bool {
+        let Some(rx) = &self.rx else {
+            return false;
+        };
+        loop {
+            match rx.try_recv() {
+                // Results the worker sent before it saw the flag are dropped, so the run always ends at its first failure
+                Ok(_) if self.stopped => {}
+                Ok(item) => {
+                    let failed = !item.ok();
+                    self.done.push(item);
+                    if failed && self.stop_on_failure.load(Ordering::Relaxed) {
+                        self.stopped = true;
+                        self.cancel();
+                    }
+                }
+                Err(TryRecvError::Empty) => return false,
+                Err(TryRecvError::Disconnected) => {
+                    self.rx = None;
+                    return true;
+                }
+            }
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.rx.is_some()
+    }
+
+    pub fn progress(&self) -> Snapshot {
+        self.tracker.snapshot()
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn verified(&self) -> usize {
+        self.done.iter().filter(|c| c.ok()).count()
+    }
+
+    // Failures not acknowledged earlier, with their index in `done`
+    pub fn problems(&self) -> impl Iterator<Item = (usize, &Checked)> {
+        self.done.iter().enumerate().filter(|(_, c)| !c.ok() && !self.is_acknowledged(c))
+    }
+
+    // Files that were read, for the slowest-files view
+    pub fn timings(&self) -> Vec<Timing> {
+        self.done.iter().filter_map(|c| Some(Timing { path: c.entry.path.clone(), bytes: c.bytes?, elapsed: c.elapsed })).collect()
+    }
+
+    // (list, entries, verified, problems) for each list of a combined run
+    pub fn per_list(&self) -> Vec<(&Path, usize, usize, usize)> {
+        let mut summary: Vec<_> = self.lists.iter().map(|(path, files)| (path.as_path(), *files, 0, 0)).collect();
+        for item in &self.done {
+            let Some(row) = summary.get_mut(item.entry.list) else { continue };
+            if item.ok() {
+                row.2 += 1;
+            } else if !self.is_acknowledged(item) {
+                row.3 += 1;
+            }
+        }
+        summary
+    }
+
+    // Verified files whose listed name only matched after normalizing it
+    pub fn renamed(&self) -> usize {
+        self.done.iter().filter(|c| c.ok() && c.note.is_some()).count()
+    }
+}
+
+// What a verification would do, worked out from the list and file metadata without reading any file
+pub struct DryRun {
+    pub source: PathBuf,
+    pub format: String,
+    pub files: usize,
+    pub bytes: u64,
+    // Entries with no file, even after normalizing the name
+    pub unmatched: Vec<PathBuf>,
+    // Entries found only under a normalized name
+    pub renamed: usize,
+    pub skipped: usize,
+}
+
+pub fn dry_run(source: PathBuf, list: crate::hashlist::HashList, matching: Matching) -> DryRun {
+    let mut run = DryRun { source, format: list.format.to_string(), files: 0, bytes: 0, unmatched: Vec::new(), renamed: 0, skipped: list.skipped };
+    for entry in list.entries {
+        let found = if entry.path.is_file() { Some(entry.path.clone()) } else { path_norm::locate(&entry.path, matching).filter(|p| p.is_file()) };
+        match found {
+            Some(path) => {
+                run.files += 1;
+                run.bytes += std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                if path != entry.path {
+                    run.renamed += 1;
+                }
+            }
+            None => run.unmatched.push(entry.path),
+        }
+    }
+    run
+}
+
+fn check(mut entry: Entry, progress: &Arc<AtomicU64>, cancel: &Arc<AtomicBool>, forensic: bool, matching: Matching) -> Checked {
+    // Lists written on another OS may spell the name differently from the file system here
+    let (path, note) = if entry.path.is_file() {
+        (entry.path.clone(), None)
+    } else {
+        match path_norm::locate(&entry.path, matching).filter(|p| p.is_file()) {
+            Some(found) => {
+                let note = format!("found as {}", found.display());
+                (found, Some(note))
+            }
+            None => return Checked { entry, status: Status::Missing, bytes: None, note: None, elapsed: Duration::ZERO },
+        }
+    };
+    // Digests the policy rules out are not computed; the SHA-256 still decides
+    entry.also.retain(|(algorithm, _)| crate::policy::get().allows(*algorithm));
+    let checked = if !entry.also.is_empty() {
+        check_all(entry, path.clone(), note, progress, cancel, forensic)
+    } else {
+        let opts = crate::HashOptions { forensic, ..Default::default() };
+        match crate::compute_sha256_file_progress(&path.display().to_string(), progress.clone(), cancel.clone(), None, opts) {
+            Ok(((hex, _, bytes, _), _)) => {
+                let status = if hex.eq_ignore_ascii_case(&entry.expected) { Status::Ok } else { Status::Mismatch(hex) };
+                Checked { entry, status, bytes: Some(bytes), note, elapsed: Duration::ZERO }
+            }
+            Err(e) => Checked { entry, status: Status::Failed(format!("{:#}", e)), bytes: None, note, elapsed: Duration::ZERO },
+        }
+    };
+    check_xattrs(checked, &path)
+}
+
+// Content that matched is only OK if the listed attribute digest matches too
+fn check_xattrs(mut checked: Checked, path: &Path) -> Checked {
+    let Some(expected) = checked.entry.xattrs.clone().filter(|_| checked.ok()) else {
+        return checked;
+    };
+    match crate::xattrs::digest(path) {
+        Ok(hex) if hex.eq_ignore_ascii_case(&expected) => {}
+        Ok(hex) => checked.status = Status::AttributesChanged(hex),
+        Err(e) => checked.status = Status::Failed(format!("{:#}", e)),
+    }
+    checked
+}
+
+// Every listed digest from one read of the file, rather than one read per algorithm
+fn check_all(entry: Entry, path: PathBuf, note: Option<String>, progress: &Arc<AtomicU64>, cancel: &Arc<AtomicBool>, forensic: bool) -> Checked {
+    let mut algorithms = vec![Algorithm::Sha256];
+    algorithms.extend(entry.also.iter().map(|(a, _)| *a));
+    let hashed = crate::open_source(&path, forensic)
+        .with_context(|| format!("Failed to open file: {}", path.display()))
+        .and_then(|(mut file, _)| algo::hash_reader(&mut file, &path.display().to_string(), &algorithms, progress, cancel));
+    let (digests, bytes) = match hashed {
+        Ok(hashed) => hashed,
+        Err(e) => return Checked { entry, status: Status::Failed(format!("{:#}", e)), bytes: None, note, elapsed: Duration::ZERO },
+    };
+    if !digests[0].eq_ignore_ascii_case(&entry.expected) {
+        let status = Status::Mismatch(digests[0].clone());
+        return Checked { entry, status, bytes: Some(bytes), note, elapsed: Duration::ZERO };
+    }
+    // SHA-256 matched but another listed digest did not; the note says which one
+    let other = entry.also.iter().zip(&digests[1..]).find(|((_, expected), hex)| !hex.eq_ignore_ascii_case(expected));
+    let Some(((algorithm, expected), hex)) = other else {
+        return Checked { entry, status: Status::Ok, bytes: Some(bytes), note, elapsed: Duration::ZERO };
+    };
+    let detail = format!("{} expected {}", algorithm.label(), expected);
+    let note = Some(note.map_or(detail.clone(), |n| format!("{}; {}", n, detail)));
+    let status = Status::Mismatch(format!("{}: {}", algorithm.label(), hex));
+    Checked { entry, status, bytes: Some(bytes), note, elapsed: Duration::ZERO }
+}
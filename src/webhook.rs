@@ -0,0 +1,64 @@
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+// Sent when a single job finishes
+#[derive(Debug, Serialize)]
+pub struct JobReport {
+    pub event: &'static str,
+    // One-line summary; chat webhooks such as Slack display this field
+    pub text: String,
+    pub path: Option<String>,
+    pub sha256: Option<String>,
+    pub base64: Option<String>,
+    pub bytes: Option<u64>,
+    // "ok", "changed" (digest differs from the previous hash of the file) or "failed"
+    pub status: &'static str,
+    pub duration_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+// Sent when the queue has run dry
+#[derive(Debug, Serialize)]
+pub struct BatchReport {
+    pub event: &'static str,
+    pub text: String,
+    pub jobs: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    // Files that could not be opened, when the queue skips them instead of failing
+    pub skipped: usize,
+    // Jobs whose digest is on the loaded blocklist
+    pub blocklisted: usize,
+    pub duration_ms: u64,
+}
+
+// POSTs the payload as JSON on a worker thread; failures are reported through `errors`
+pub fn post<T: Serialize>(url: &str, payload: &T, errors: Sender<String>) {
+    let url = url.trim().to_string();
+    let body = match serde_json::to_string(payload) {
+        Ok(body) => body,
+        Err(e) => {
+            let _ = errors.send(format!("Webhook: {}", e));
+            return;
+        }
+    };
+    thread::spawn(move || {
+        if let Err(e) = send(&url, &body) {
+            let _ = errors.send(format!("Webhook {}: {:#}", url, e));
+        }
+    });
+}
+
+fn send(url: &str, body: &str) -> Result<()> {
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(anyhow!("URL must start with http:// or https://"));
+    }
+    ureq::post(url).timeout(TIMEOUT).set("Content-Type", "application/json").send_string(body)?;
+    Ok(())
+}
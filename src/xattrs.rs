@@ -0,0 +1,190 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+
+// Tag of the manifest line carrying a file's attribute digest: `XATTRS-SHA256 (name) = hash`
+pub const TAG: &str = "XATTRS-SHA256";
+
+// SHA-256 of a file's extended attributes (Linux, macOS) or alternate data streams (Windows), so a
+// manifest also catches what a copy or restore can silently drop. The attributes are sorted by name
+// (byte order) and each is written as:
+//   name length  (u32, big-endian)
+//   name         (raw bytes on Unix; UTF-8 of the stream name without `:` and `:$DATA` on Windows)
+//   value length (u64, big-endian)
+//   value
+// A file without any gives the digest of no bytes, so attributes added later still show up. Symlinks are
+// not followed: a link's own attributes are read, as a restore would recreate them
+pub fn digest(path: &Path) -> Result<String> {
+    let attributes = read(path).with_context(|| format!("Failed to read extended attributes: {}", path.display()))?;
+    Ok(serialized_digest(attributes))
+}
+
+fn serialized_digest(mut attributes: Vec<(Vec<u8>, Vec<u8>)>) -> String {
+    attributes.sort();
+    let mut hasher = Sha256::new();
+    for (name, value) in &attributes {
+        hasher.update((name.len() as u32).to_be_bytes());
+        hasher.update(name);
+        hasher.update((value.len() as u64).to_be_bytes());
+        hasher.update(value);
+    }
+    hex::encode(hasher.finalize())
+}
+
+// The manifest line for `name`, escaped like `checksum_line` does
+pub fn manifest_line(hex: &str, name: &str, zero: bool) -> String {
+    crate::post_actions::tagged_line(TAG, hex, name, zero)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn read(path: &Path) -> std::io::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    let names = fetch(|buf, len| unsafe { libc::llistxattr(c_path.as_ptr(), buf as *mut libc::c_char, len) })?;
+    let mut attributes = Vec::new();
+    for name in names.split(|b| *b == 0).filter(|n| !n.is_empty()) {
+        let c_name = CString::new(name)?;
+        let value = fetch(|buf, len| unsafe { libc::lgetxattr(c_path.as_ptr(), c_name.as_ptr(), buf as *mut libc::c_void, len) })?;
+        attributes.push((name.to_vec(), value));
+    }
+    Ok(attributes)
+}
+
+#[cfg(target_os = "macos")]
+fn read(path: &Path) -> std::io::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    let names = fetch(|buf, len| unsafe { libc::listxattr(c_path.as_ptr(), buf as *mut libc::c_char, len, libc::XATTR_NOFOLLOW) })?;
+    let mut attributes = Vec::new();
+    for name in names.split(|b| *b == 0).filter(|n| !n.is_empty()) {
+        let c_name = CString::new(name)?;
+        let value = fetch(|buf, len| unsafe { libc::getxattr(c_path.as_ptr(), c_name.as_ptr(), buf as *mut libc::c_void, len, 0, libc::XATTR_NOFOLLOW) })?;
+        attributes.push((name.to_vec(), value));
+    }
+    Ok(attributes)
+}
+
+// Asks for the size, then the bytes; asks again if they grew in between. A file system without
+// extended attributes has none rather than failing
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+fn fetch(call: impl Fn(*mut u8, usize) -> isize) -> std::io::Result<Vec<u8>> {
+    loop {
+        let size = call(std::ptr::null_mut(), 0);
+        if size < 0 {
+            let e = std::io::Error::last_os_error();
+            return if e.raw_os_error() == Some(libc::ENOTSUP) { Ok(Vec::new()) } else { Err(e) };
+        }
+        let mut buf = vec![0u8; size as usize];
+        let got = call(buf.as_mut_ptr(), buf.len());
+        if got >= 0 {
+            buf.truncate(got as usize);
+            return Ok(buf);
+        }
+        let e = std::io::Error::last_os_error();
+        if e.raw_os_error() != Some(libc::ERANGE) {
+            return Err(e);
+        }
+    }
+}
+
+#[cfg(windows)]
+fn read(path: &Path) -> std::io::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Foundation::{GetLastError, ERROR_HANDLE_EOF, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::Storage::FileSystem::{FindClose, FindFirstStreamW, FindNextStreamW, FindStreamInfoStandard, WIN32_FIND_STREAM_DATA};
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    let mut data: WIN32_FIND_STREAM_DATA = unsafe { std::mem::zeroed() };
+    let find = unsafe { FindFirstStreamW(wide.as_ptr(), FindStreamInfoStandard, &mut data as *mut _ as *mut _, 0) };
+    if find == INVALID_HANDLE_VALUE {
+        // No streams at all (a folder, or a file system without them)
+        return if unsafe { GetLastError() } == ERROR_HANDLE_EOF { Ok(Vec::new()) } else { Err(std::io::Error::last_os_error()) };
+    }
+    let mut names = Vec::new();
+    loop {
+        let len = data.cStreamName.iter().position(|c| *c == 0).unwrap_or(data.cStreamName.len());
+        // `:name:$DATA`; the unnamed `::$DATA` stream is the file's content
+        let full = String::from_utf16_lossy(&data.cStreamName[..len]);
+        if let Some(name) = full.strip_prefix(':').and_then(|n| n.strip_suffix(":$DATA")).filter(|n| !n.is_empty()) {
+            names.push(name.to_string());
+        }
+        if unsafe { FindNextStreamW(find, &mut data as *mut _ as *mut _) } == 0 {
+            break;
+        }
+    }
+    let error = unsafe { GetLastError() };
+    unsafe { FindClose(find) };
+    if error != ERROR_HANDLE_EOF {
+        return Err(std::io::Error::from_raw_os_error(error as i32));
+    }
+    let mut attributes = Vec::new();
+    for name in names {
+        let mut stream = path.as_os_str().to_owned();
+        stream.push(format!(":{}", name));
+        let value = std::fs::read(&stream)?;
+        attributes.push((name.into_bytes(), value));
+    }
+    Ok(attributes)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos", windows)))]
+fn read(_path: &Path) -> std::io::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "extended attributes are not supported on this platform"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The documented layout, written out by hand
+    #[test]
+    fn attributes_are_sorted_and_length_prefixed() {
+        let attributes = vec![(b"user.tag".to_vec(), b"red".to_vec()), (b"user.a".to_vec(), Vec::new())];
+        let mut expected = Vec::new();
+        expected.extend_from_slice(&[0, 0, 0, 6]);
+        expected.extend_from_slice(b"user.a");
+        expected.extend_from_slice(&[0; 8]);
+        expected.extend_from_slice(&[0, 0, 0, 8]);
+        expected.extend_from_slice(b"user.tag");
+        expected.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 3]);
+        expected.extend_from_slice(b"red");
+        assert_eq!(serialized_digest(attributes), hex::encode(Sha256::digest(&expected)));
+        assert_eq!(serialized_digest(Vec::new()), hex::encode(Sha256::digest(b"")));
+    }
+
+    #[test]
+    fn manifest_lines_are_escaped_like_checksum_lines() {
+        let hex = "ab".repeat(32);
+        assert_eq!(manifest_line(&hex, "a.txt", false), format!("XATTRS-SHA256 (a.txt) = {}\n", hex));
+        assert_eq!(manifest_line(&hex, "x\ny\\z", false), format!("\\XATTRS-SHA256 (x\\ny\\\\z) = {}\n", hex));
+        assert_eq!(manifest_line(&hex, "x\ny", true), format!("XATTRS-SHA256 (x\ny) = {}\0", hex));
+    }
+
+    // The link's own attributes, not its target's; skipped where the file system has no user attributes
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn symlinks_are_not_followed() {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = std::env::temp_dir().join(format!("rust-hash-xattrs-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (file, link) = (dir.join("file"), dir.join("link"));
+        std::fs::write(&file, b"data").unwrap();
+        std::os::unix::fs::symlink(&file, &link).unwrap();
+        let (c_path, c_name) = (CString::new(file.as_os_str().as_bytes()).unwrap(), CString::new("user.rust-hash").unwrap());
+        let set = unsafe { libc::setxattr(c_path.as_ptr(), c_name.as_ptr(), b"red".as_ptr() as *const libc::c_void, 3, 0) };
+        let (of_file, of_link) = (digest(&file), digest(&link));
+        let _ = std::fs::remove_dir_all(&dir);
+        if set != 0 {
+            return;
+        }
+        assert_eq!(of_file.unwrap(), serialized_digest(vec![(b"user.rust-hash".to_vec(), b"red".to_vec())]));
+        assert_eq!(of_link.unwrap(), serialized_digest(Vec::new()));
+    }
+}